@@ -3,21 +3,112 @@
 
 #![cfg_attr(target_arch = "wasm32", no_main)]
 
+#[macro_use]
+mod log;
+mod metrics;
+#[cfg(feature = "simulation")]
+mod simulation;
 mod state;
 
-use snake_game::{ApplicationParameters, GameMessage, Operation, SnakeGameAbi, 
-    GameSession, LeaderboardEntry, GameState};
+use std::{cmp::Ordering, collections::{BTreeMap, BTreeSet}};
+
+use snake_game::{ApplicationParameters, GameMessage, MessageEnvelope, Operation, OperationResponse, SnakeGameAbi, 
+    GameSession, LeaderboardEntry, GameState, RankTier, SnakeGameEvent, QuestDefinition, QuestKind, Achievement, Announcement,
+    Challenge, ChallengeSide, ChallengeState, MatchmakingState, MatchmakingStatus, challenge_commitment, account_link_token, ClientKeyChange, ACCOUNT_LINK_TTL_SECS, ScoreClaim, implausible_candy_rate, is_valid_move_log_hash, move_log_hash, session_seed, MOVE_LOG_HASH_LEN,
+    ReplayVerdict, snake_engine::{self, Combo}, display_name, name_key, EVENT_SCHEMA_VERSION, STATE_SCHEMA_VERSION, RankedScore, LevelLayout, LiveSession, LiveDelta, DirectionChange, CandyDelta, LevelScore, CustomMap, map_hash, GameMode, SurvivalEntry,
+    ImprovementEntry, RisingStarEntry, DisputeMarker, LeaderboardStatus, PendingUpdate, PendingUpdateKind, PodiumChange, ShadowEntry, MAX_DISPUTE_REASON_LEN, MAX_OPEN_DISPUTES_PER_REPORTER, MAX_LEADERBOARD_SYNC, MAX_FOLLOWING, MAX_CHAT_MESSAGE_LEN, ChatMessage, ChatRecipient, Ghost, GhostRace, Avatar, avatar_format, country_code, ItemKind, PassTrack, Tournament, TournamentEntry, TournamentState, Emote, ReactionCount, PenaltyKind, PenaltyRecord, FeatureFlag, feature_default, FEATURE_BATTLE_ROYALE, SessionSummary, game_finished_admission, ResultAdmission, Team, TeamMatch, TeamMatchScore, TeamMatchState, TeamWar, TeamWarState};
 use linera_sdk::{
-    linera_base_types::{ChainId, WithContractAbi},
+    linera_base_types::{Account, AccountOwner, Amount, ChainId, Ed25519Signature, StreamUpdate, WithContractAbi},
     views::{RootView, View},
     Contract, ContractRuntime,
 };
 use async_graphql::ComplexObject;
 
-use self::state::{SnakeGameState, PlayerStats};
+use self::state::{SnakeGameState, SESSION_BUCKET_MICROS, TeamLedgerEntry, AccountLink, AccountTombstone, AchievementUnlock, BattleRoyale, CountryRanking, BattleRoyaleState, CandyTally, SessionEventKind, Dispute, LeaderboardSubscription, Notification, NotificationKind, DisputeState, SuspectSession, HallOfFameRecord, LeaderboardChange, LeaderboardChangeKind, NameChange, ProposalState, ReservedName, ResetBallot, ResetProposal, MoveLogAnchor, PersonalBest, PlayerStats, PendingReward, MarketListing, MarketSale, PrizePayout, PrizePool, QuestProgress, RewardClaim, RewardSource, ReferralRecord, ReferralState, ReferralStatus, Bracket, BracketMatch, BracketSlot, RoyaleBot, RoyaleElimination, RoyaleScore, RoyaleStatus, SeasonPass, SeasonPodium, SeasonTrophy, Sponsorship, TreasuryEntry, TreasuryFlow, QueueTicket};
 
 linera_sdk::contract!(SnakeGameContract);
 
+/// Leaderboard positions that make up the podium
+const PODIUM_SIZE: usize = 3;
+
+/// Longest game a battle royale bot plays in one round, two minutes of game time.
+/// Every bot plays in the block that closes the round, so this and `MAX_ROYALE_BOTS`
+/// together bound the work done there
+const MAX_BOT_TICKS: u64 = 1_200;
+
+/// Most bots a battle royale can be filled with
+const MAX_ROYALE_BOTS: u32 = 16;
+
+/// Most players waiting for a match at once; pairing compares every two tickets
+const MAX_MATCHMAKING_QUEUE: u64 = 256;
+
+/// Hall of fame firsts earned with a reported score, and the score each one takes
+const SCORE_FIRSTS: [(&str, u32); 2] = [("First player on the board", 0), ("First 100-candy game", 100)];
+
+/// A battle royale survivor being ranked at the end of a round
+#[derive(Clone, Copy)]
+enum RoyaleEntrant {
+    Player(ChainId),
+    Bot(usize), // Index into the event's bots
+}
+
+/// Stream carrying tier promotion and demotion events
+const TIER_STREAM_NAME: &[u8] = b"tiers";
+
+/// Stream carrying quest definitions published by the leaderboard chain
+const QUEST_STREAM_NAME: &[u8] = b"quests";
+
+/// Stream carrying admin announcements published by the leaderboard chain
+const ANNOUNCEMENT_STREAM_NAME: &[u8] = b"announcements";
+
+/// Stream carrying level layouts defined on the leaderboard chain
+const LEVEL_STREAM_NAME: &[u8] = b"levels";
+
+/// Stream carrying tournaments opened by the leaderboard chain
+const TOURNAMENT_STREAM_NAME: &[u8] = b"tournaments";
+
+/// Stream carrying live snapshots of the games played on this chain
+const LIVE_STREAM_NAME: &[u8] = b"live";
+
+/// Stream carrying game starts, candy batches and results for off-chain indexers
+const GAME_STREAM_NAME: &[u8] = b"games";
+
+/// Stream carrying leaderboard changes and season ends for off-chain indexers
+const LEADERBOARD_STREAM_NAME: &[u8] = b"leaderboard";
+
+/// Stream carrying takeovers of the top three positions, for tickers
+const PODIUM_STREAM_NAME: &[u8] = b"podium";
+
+/// Number of leaderboard positions reported in LeaderboardChanged events
+const INDEXED_TOP_SIZE: usize = 10;
+
+/// Number of announcements kept on each chain
+const MAX_ANNOUNCEMENTS: usize = 20;
+
+/// Number of row changes kept in the leaderboard change log
+const MAX_LEADERBOARD_CHANGES: usize = 1000;
+
+/// Number of podium takeovers kept in the podium history
+const MAX_PODIUM_HISTORY: usize = 100;
+
+/// Number of session summaries sent to the leaderboard chain in one message
+const HISTORY_SYNC_BATCH_SIZE: usize = 10;
+
+/// Number of synced sessions kept per player on the leaderboard chain
+const MAX_SYNCED_HISTORY: usize = 200;
+
+/// Number of previous names kept per player on the leaderboard chain
+const MAX_NAME_HISTORY: usize = 10;
+
+/// Number of finished sessions kept in the recent games queue
+const MAX_RECENT_GAMES: usize = 20;
+
+/// Number of notifications kept in a player's inbox
+const MAX_NOTIFICATIONS: usize = 50;
+
+/// Number of chat messages, sent and received, kept on a player chain
+const MAX_CHAT_MESSAGES: usize = 200;
+
 pub struct SnakeGameContract {
     state: SnakeGameState,
     runtime: ContractRuntime<Self>,
@@ -28,10 +119,10 @@ impl WithContractAbi for SnakeGameContract {
 }
 
 impl Contract for SnakeGameContract {
-    type Message = GameMessage;
+    type Message = MessageEnvelope;
     type InstantiationArgument = ();
     type Parameters = ApplicationParameters;
-    type EventValue = ();
+    type EventValue = SnakeGameEvent;
 
     async fn load(runtime: ContractRuntime<Self>) -> Self {
         let state = SnakeGameState::load(runtime.root_view_storage_context())
@@ -43,12 +134,17 @@ impl Contract for SnakeGameContract {
     async fn instantiate(&mut self, _argument: ()) {
         // Validate that the application parameters were configured correctly.
         let parameters = self.runtime.application_parameters();
+        let prize_split_total: u32 = parameters.prize_split_percent.iter().map(|&percent| u32::from(percent)).sum();
+        assert!(prize_split_total <= 100, "Prize split adds up to {}%, more than the whole fund", prize_split_total);
+        assert!(
+            parameters.royale_min_entrants <= MAX_ROYALE_BOTS,
+            "Battle royales can be filled with at most {} bots, not {}", MAX_ROYALE_BOTS, parameters.royale_min_entrants
+        );
         
-        // Initialize game state
-        self.state.session_counter.set(0);
+        self.state.schema_version.set(STATE_SCHEMA_VERSION);
         
         // Initialize leaderboard state
-        self.state.global_leaderboard.set(Vec::new());
+        self.state.global_leaderboard.clear();
         self.state.leaderboard_chain_id.set(parameters.leaderboard_chain_id);
         
         // Check if this chain is the leaderboard chain
@@ -57,25 +153,215 @@ impl Contract for SnakeGameContract {
             .unwrap_or(false);
         self.state.is_leaderboard_chain.set(is_leaderboard);
         
+        // Player chains follow the quests and announcements published by the leaderboard chain
+        if let Some(leaderboard_chain_id) = parameters.leaderboard_chain_id {
+            if !is_leaderboard {
+                self.subscribe_to_leaderboard_streams(leaderboard_chain_id);
+            }
+        }
+        
         // Initialize player-specific state
-        self.state.my_sessions.set(Vec::new());
         self.state.my_stats.set(None);
         self.state.my_current_session.set(None);
         
-        eprintln!("[INIT] Snake Game contract initialized on chain {:?}", self.runtime.chain_id());
-        eprintln!("[INIT] Is leaderboard chain: {}", is_leaderboard);
-        eprintln!("[INIT] Configured leaderboard chain: {:?}", parameters.leaderboard_chain_id);
+        log_event!(self, Info, "INIT", "Snake Game contract initialized on chain {:?}", self.runtime.chain_id());
+        log_event!(self, Debug, "INIT", "Is leaderboard chain: {}", is_leaderboard);
+        log_event!(self, Debug, "INIT", "Configured leaderboard chain: {:?}", parameters.leaderboard_chain_id);
+    }
+
+    async fn execute_operation(&mut self, operation: Operation) -> OperationResponse {
+        // Owners sharing this chain each keep their own stats, name and current game
+        if let Some(owner) = self.runtime.authenticated_signer() {
+            self.state.switch_owner(owner).await;
+        }
+        
+        match operation {
+            Operation::Batch(operations) => {
+                self.increment_metric(&metrics::operation_metric("Batch"), 1).await;
+                // Operations run in order; a panic in any of them reverts the whole batch
+                let mut responses = Vec::with_capacity(operations.len());
+                for operation in operations {
+                    responses.push(self.execute_single_operation(operation).await);
+                }
+                OperationResponse::Batch(responses)
+            }
+            operation => self.execute_single_operation(operation).await,
+        }
+    }
+
+    async fn execute_message(&mut self, envelope: Self::Message) {
+        log_event!(self, Debug, "MESSAGE", "Received message on chain {:?}", self.runtime.chain_id());
+        self.state.last_message_at.set(Some(self.runtime.system_time().micros()));
+        
+        // Check if message is bouncing
+        let is_bouncing = self
+            .runtime
+            .message_is_bouncing()
+            .expect("Message delivery status must be available when executing a message");
+
+        if is_bouncing {
+            log_event!(self, Debug, "MESSAGE", "Message is bouncing, returning");
+            self.increment_metric(metrics::MESSAGES_BOUNCED, 1).await;
+            // Gifts are the only tracked messages; one the recipient refused goes back to the balance
+            if let Ok(GameMessage::GiftCoins { amount, .. }) = envelope.open() {
+                self.state.my_coins.set(*self.state.my_coins.get() + amount);
+                log_event!(self, Info, "GIFT", "Gift of {} coins was refused, restored to this chain's balance", amount);
+            }
+            return;
+        }
+
+        // A sender on other bytecode may use a format this chain cannot read; drop the message
+        // rather than failing the block, so mixed versions keep working during an upgrade
+        let message = match envelope.open() {
+            Ok(message) => message,
+            Err(error) => {
+                log_event!(self, Warn, "MESSAGE", "Dropping message from {:?}: {}", self.runtime.message_origin_chain_id(), error);
+                self.increment_metric(metrics::MESSAGES_UNDECODABLE, 1).await;
+                return;
+            }
+        };
+        
+        // Resolve timed-out challenges and send throttled podium updates with the block time
+        // of whatever message arrives next
+        if *self.state.is_leaderboard_chain.get() {
+            self.resolve_expired_challenges().await;
+            self.run_matchmaking().await;
+            self.settle_finished_team_wars().await;
+            self.expire_proposed_team_matches().await;
+            self.finalize_closed_tournaments().await;
+            self.advance_timed_out_brackets().await;
+            self.flush_leaderboard_pushes().await;
+        }
+        
+        // A chain merged into another no longer speaks for its player on the leaderboard chain
+        if *self.state.is_leaderboard_chain.get() {
+            let origin = self.runtime.message_origin_chain_id().filter(|origin| *origin != self.runtime.chain_id());
+            if let Some(origin) = origin {
+                if let Ok(Some(tombstone)) = self.state.account_tombstones.get(&origin).await {
+                    reject_message!(self, "LINK", "{:?} was merged into {:?}, ignoring its message", origin, tombstone.merged_into);
+                    if let GameMessage::GameFinished { session_id, .. } = message {
+                        let reason = format!("This chain's history was merged into {}", tombstone.merged_into);
+                        self.send_to_chain(origin, GameMessage::ResultRejected { session_id, reason }).await;
+                    }
+                    return;
+                }
+            }
+        }
+        
+        let is_submission = matches!(message, GameMessage::GameFinished { .. } | GameMessage::CandyCollected { .. }
+            | GameMessage::TournamentScore { .. } | GameMessage::TeamWarScore { .. } | GameMessage::BracketScore { .. }
+            | GameMessage::SubmitRoyaleScore { .. });
+        if is_submission && !self.admit_submission().await {
+            // A dropped result would otherwise stay pending on the player chain
+            if let GameMessage::GameFinished { session_id, player_chain, .. } = message {
+                if self.runtime.message_origin_chain_id() == Some(player_chain) {
+                    let reason = "Too many results sent in a short time".to_string();
+                    self.send_to_chain(player_chain, GameMessage::ResultRejected { session_id, reason }).await;
+                }
+            }
+            return;
+        }
+        
+        self.handle_message(message).await;
+    }
+
+    async fn process_streams(&mut self, updates: Vec<StreamUpdate>) {
+        for update in updates {
+            for index in update.new_indices() {
+                let event = self.runtime.read_event(update.chain_id, update.stream_id.stream_name.clone(), index);
+                match event {
+                    SnakeGameEvent::QuestPublished(quest) => {
+                        log_event!(self, Info, "QUEST", "Received quest {} '{}' from {:?}", quest.quest_id, quest.title, update.chain_id);
+                        let quest_id = quest.quest_id;
+                        let _ = self.state.quests.insert(&quest_id, QuestProgress::new(quest));
+                    }
+                    SnakeGameEvent::AnnouncementPublished(announcement) => {
+                        log_event!(self, Info, "ANNOUNCEMENT", "Received announcement '{}' from {:?}", announcement.title, update.chain_id);
+                        self.store_announcement(announcement);
+                    }
+                    SnakeGameEvent::LevelPublished(level) => {
+                        log_event!(self, Info, "LEVEL", "Received level {} '{}' from {:?}", level.level_id, level.name, update.chain_id);
+                        let level_id = level.level_id;
+                        let _ = self.state.levels.insert(&level_id, level);
+                    }
+                    SnakeGameEvent::TournamentPublished(tournament) => {
+                        log_event!(self, Info, "TOURNAMENT", "Received tournament {} '{}' from {:?}", tournament.tournament_id, tournament.name, update.chain_id);
+                        let tournament_id = tournament.tournament_id;
+                        let _ = self.state.tournaments.insert(&tournament_id, tournament);
+                    }
+                    SnakeGameEvent::LeaderboardMoved { new_chain } => {
+                        // Only follow the chain we currently treat as leaderboard
+                        if *self.state.leaderboard_chain_id.get() != Some(update.chain_id) {
+                            continue;
+                        }
+                        log_event!(self, Info, "SETUP", "Leaderboard moved from {:?} to {:?}", update.chain_id, new_chain);
+                        self.unsubscribe_from_leaderboard_streams(update.chain_id);
+                        self.state.leaderboard_chain_id.set(Some(new_chain));
+                        if new_chain != self.runtime.chain_id() {
+                            self.subscribe_to_leaderboard_streams(new_chain);
+                            self.resend_pending_updates(new_chain).await;
+                        }
+                    }
+                    SnakeGameEvent::FeatureFlagSet(flag) => {
+                        // Only the leaderboard chain decides which features are on
+                        if *self.state.leaderboard_chain_id.get() != Some(update.chain_id) {
+                            continue;
+                        }
+                        log_event!(self, Info, "FEATURES", "Feature '{}' set to {} by {:?}", flag.key, flag.enabled, update.chain_id);
+                        let _ = self.state.feature_flags.insert(&flag.key, flag.enabled);
+                    }
+                    SnakeGameEvent::LiveSessionUpdated(live) => {
+                        let session_id = live.session_id.clone();
+                        let _ = self.state.live_sessions.insert(&session_id, live);
+                    }
+                    SnakeGameEvent::LiveSessionEnded { session_id } => {
+                        log_event!(self, Info, "LIVE", "Session {} on {:?} ended", session_id, update.chain_id);
+                        let _ = self.state.live_sessions.remove(&session_id);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        
+        // Drop quests that can no longer be completed
+        let timestamp = self.runtime.system_time().micros();
+        if let Ok(quest_ids) = self.state.quests.indices().await {
+            for quest_id in quest_ids {
+                if let Ok(Some(progress)) = self.state.quests.get(&quest_id).await {
+                    if timestamp >= progress.quest.expires_at {
+                        let _ = self.state.quests.remove(&quest_id);
+                    }
+                }
+            }
+        }
+    }
+
+    async fn store(mut self) {
+        let _ = self.state.save().await;
     }
+}
 
-    async fn execute_operation(&mut self, operation: Operation) -> () {
+impl SnakeGameContract {
+    /// Execute one operation that is not a batch
+    async fn execute_single_operation(&mut self, operation: Operation) -> OperationResponse {
+        self.increment_metric(&metrics::operation_metric(operation.kind()), 1).await;
+        if let Some(replacement) = operation.replacement() {
+            log_event!(self, Warn, "DEPRECATED", "{} is deprecated, use {} instead", operation.kind(), replacement);
+            return OperationResponse::Deprecated {
+                operation: operation.kind().to_string(),
+                replacement: replacement.to_string(),
+            };
+        }
         match operation {
+            Operation::Batch(_) => panic!("Batches cannot be nested"),
+            
             Operation::SetupLeaderboard { leaderboard_chain_id } => {
-                eprintln!("[SETUP] SetupLeaderboard called on chain {:?} with leaderboard_chain_id: {:?}", 
+                log_event!(self, Debug, "SETUP", "SetupLeaderboard called on chain {:?} with leaderboard_chain_id: {:?}", 
                     self.runtime.chain_id(), leaderboard_chain_id);
                 
                 // Only allow setup if not already configured
                 if self.state.leaderboard_chain_id.get().is_some() {
-                    panic!("Leaderboard already configured");
+                    panic!("Leaderboard already configured; use ReconfigureLeaderboard on the leaderboard chain to move it");
                 }
 
                 // Set the leaderboard chain ID
@@ -84,80 +370,217 @@ impl Contract for SnakeGameContract {
                 // If this chain is being designated as the leaderboard chain
                 if self.runtime.chain_id() == leaderboard_chain_id {
                     self.state.is_leaderboard_chain.set(true);
-                    eprintln!("[SETUP] This chain IS the leaderboard chain");
+                    log_event!(self, Debug, "SETUP", "This chain IS the leaderboard chain");
                 } else {
-                    eprintln!("[SETUP] This chain is NOT the leaderboard chain");
+                    log_event!(self, Debug, "SETUP", "This chain is NOT the leaderboard chain");
+                    self.subscribe_to_leaderboard_streams(leaderboard_chain_id);
                 }
             }
             
             Operation::SetPlayerName { name } => {
                 let current_chain = self.runtime.chain_id();
-                eprintln!("[SET_NAME] Setting player name '{}' for chain {:?}", name, current_chain);
+                log_event!(self, Debug, "SET_NAME", "Setting player name '{}' for chain {:?}", name, current_chain);
+                
+                let name = display_name(&name).unwrap_or_else(|reason| panic!("{}", reason));
+                let now = self.runtime.system_time().micros();
+                if let Some(changed_at) = *self.state.my_name_changed_at.get() {
+                    let allowed_at = self.runtime.application_parameters().name_change_allowed_at(changed_at);
+                    if now < allowed_at {
+                        panic!("Player name can be changed again in {} seconds", (allowed_at - now).div_ceil(1_000_000));
+                    }
+                }
                 
                 // Set the player name locally
                 self.state.my_player_name.set(Some(name.clone()));
+                self.state.my_name_changed_at.set(Some(now));
                 
                 // Send name update to leaderboard chain if this is not the leaderboard chain
                 if let Some(leaderboard_chain_id) = *self.state.leaderboard_chain_id.get() {
                     if current_chain != leaderboard_chain_id {
+                        self.state.pending_name_update.set(Some(PendingUpdate {
+                            kind: PendingUpdateKind::PlayerName,
+                            subject: name.clone(),
+                            sent_at: now,
+                        }));
                         let message = GameMessage::UpdatePlayerName {
                             player_chain: current_chain,
                             player_name: name,
                         };
-                        self.runtime.send_message(leaderboard_chain_id, message);
+                        self.runtime.send_message(leaderboard_chain_id, message.into());
                     } else {
                         // If this is the leaderboard chain, update the name mapping directly
-                        let _ = self.state.player_names.insert(&current_chain, name);
+                        if let Some(reason) = self.name_rejection(current_chain, &name).await {
+                            panic!("{}", reason);
+                        }
+                        self.record_player_name(current_chain, name).await;
                     }
                 }
             }
             
-            Operation::StartGame => {
-                let current_chain = self.runtime.chain_id();
-                let player_name = self.state.my_player_name.get().clone();
-                let timestamp = self.runtime.system_time().micros();
+            Operation::SetCountry { country } => {
+                let country = country.map(|code| country_code(&code)
+                    .unwrap_or_else(|| panic!("'{}' is not a two-letter country code", code)));
+                log_event!(self, Info, "PROFILE", "Country set to {:?}", country);
+                self.state.my_country.set(country.clone());
                 
-                // Generate unique session ID
-                let session_counter = *self.state.session_counter.get();
-                let session_id = format!("session_{}_{}", current_chain, session_counter);
-                self.state.session_counter.set(session_counter + 1);
+                if let Some(leaderboard_chain_id) = *self.state.leaderboard_chain_id.get() {
+                    let player_chain = self.runtime.chain_id();
+                    self.send_to_chain(leaderboard_chain_id, GameMessage::CountryUpdated { player_chain, country }).await;
+                }
+            }
+            
+            Operation::IssueAccountLink { new_chain } => {
+                let old_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                if new_chain == old_chain {
+                    panic!("A chain cannot be linked to itself");
+                }
+                if let Some(merged_into) = *self.state.merged_into.get() {
+                    panic!("This chain's history was already merged into {:?}", merged_into);
+                }
                 
-                // Create local game session (only stored on player's chain)
-                let session = GameSession {
-                    session_id: session_id.clone(),
-                    player: current_chain,
-                    player_name,
-                    start_time: timestamp,
-                    end_time: None,
-                    candies_collected: 0, // Start with 0 candies
-                    is_record: false,
-                    state: GameState::Playing,
-                };
+                let token = account_link_token(old_chain, new_chain, self.runtime.system_time().micros());
+                log_event!(self, Info, "LINK", "Issuing an account link to {:?}", new_chain);
+                let message = GameMessage::IssueAccountLink { old_chain, new_chain, token: token.clone() };
+                self.send_to_chain(leaderboard_chain_id, message).await;
+                self.send_to_chain(new_chain, GameMessage::AccountLinkToken { old_chain, token }).await;
+            }
+            
+            Operation::MergeAccount { old_chain } => {
+                let new_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                let token = self.state.received_link_tokens.get(&old_chain).await
+                    .ok()
+                    .flatten()
+                    .unwrap_or_else(|| panic!("No link token was received from {:?}", old_chain));
+                let _ = self.state.received_link_tokens.remove(&old_chain);
                 
-                let _ = self.state.sessions.insert(&session_id, session);
+                log_event!(self, Info, "LINK", "Asking to merge the history of {:?} into this chain", old_chain);
+                self.send_to_chain(leaderboard_chain_id, GameMessage::MergeAccounts { old_chain, new_chain, token }).await;
+            }
+            
+            Operation::RegisterClientKey { public_key, signature } => {
+                // Only the key already in charge can hand over to another one
+                if let Some(current_key) = *self.state.my_client_key.get() {
+                    let change = ClientKeyChange { chain_id: self.runtime.chain_id(), new_key: public_key };
+                    let signature = signature.expect("Replacing the client key requires a signature by the current key");
+                    if signature.check(&change, current_key).is_err() {
+                        panic!("The key change is not signed by the current client key");
+                    }
+                }
                 
-                // Add session to player's session list
-                let mut my_sessions = self.state.my_sessions.get().clone();
-                my_sessions.push(session_id.clone());
-                self.state.my_sessions.set(my_sessions);
+                log_event!(self, Info, "PROFILE", "Client key set to {:?}", public_key);
+                self.state.my_client_key.set(public_key);
+            }
+            
+            Operation::SetAvatar { blob_hash } => {
+                let avatar = blob_hash.map(|blob_hash| {
+                    let image = self.runtime.read_data_blob(blob_hash);
+                    let format = avatar_format(&image).unwrap_or_else(|reason| panic!("{}", reason));
+                    Avatar {
+                        blob_hash,
+                        format,
+                        size_bytes: image.len() as u32,
+                        set_at: self.runtime.system_time().micros(),
+                    }
+                });
+                log_event!(self, Info, "AVATAR", "Avatar set to {:?}", avatar.as_ref().map(|avatar| avatar.blob_hash));
+                self.state.my_avatar.set(avatar.clone());
                 
-                // Set as current session
-                self.state.my_current_session.set(Some(session_id.clone()));
+                if let Some(leaderboard_chain_id) = *self.state.leaderboard_chain_id.get() {
+                    let player_chain = self.runtime.chain_id();
+                    self.send_to_chain(leaderboard_chain_id, GameMessage::AvatarUpdated { player_chain, avatar }).await;
+                }
+            }
+            
+            Operation::SetHistorySync { enabled } => {
+                let leaderboard_chain = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                log_event!(self, Info, "HISTORY", "Public history sync {} for chain {:?}",
+                    if enabled { "enabled" } else { "disabled" }, self.runtime.chain_id());
                 
-                eprintln!("[START_GAME] Started new game session: {} on player chain {:?}", session_id, current_chain);
+                self.state.history_sync_enabled.set(enabled);
+                if !enabled {
+                    self.state.pending_history.clear();
+                    let player_chain = self.runtime.chain_id();
+                    self.send_to_chain(leaderboard_chain, GameMessage::HistoryOptOut { player_chain }).await;
+                }
             }
             
-            Operation::CollectCandy => {
+            Operation::SyncHistory => {
+                if !*self.state.history_sync_enabled.get() {
+                    panic!("Public history sync is not enabled");
+                }
+                self.flush_history().await;
+            }
+            
+            Operation::StartGame { level_id, map_hash, mode, match_id, royale_id, ghost, tournament_id } => {
+                let session_id = self.start_session(level_id, map_hash, mode, match_id, royale_id, ghost, tournament_id).await;
+                return OperationResponse::SessionStarted { session_id };
+            }
+            
+            Operation::RestartGame { move_log_hash, signature, level_id, map_hash, mode, match_id, royale_id, ghost, tournament_id } => {
+                if let Some(hash) = &move_log_hash {
+                    if !is_valid_move_log_hash(hash) {
+                        panic!("Move log hash must be {} hex characters", MOVE_LOG_HASH_LEN);
+                    }
+                }
+                let ended_session_id = self.state.my_current_session.get().clone();
+                if ended_session_id.is_some() {
+                    // As with EndGame, a finished game must be anchored so it can be replayed in a dispute
+                    if move_log_hash.is_none() {
+                        panic!("Ending the current game needs the hash of its move log");
+                    }
+                    self.check_score_claim(&move_log_hash, signature).await;
+                    self.finish_current_session(move_log_hash).await;
+                }
+                let session_id = self.start_session(level_id, map_hash, mode, match_id, royale_id, ghost, tournament_id).await;
+                return OperationResponse::SessionRestarted { ended_session_id, session_id };
+            }
+            
+            Operation::CollectCandy { tick } => {
                 let current_chain = self.runtime.chain_id();
                 let leaderboard_chain = self.state.leaderboard_chain_id.get().clone();
                 
                 // Get current session
                 if let Some(session_id) = self.state.my_current_session.get().clone() {
                     // Update local session to increment candy count
-                    if let Ok(Some(mut session)) = self.state.sessions.get(&session_id).await {
+                    if let Some(mut session) = self.state.session(&session_id).await {
+                        // Timed games stop counting once the session passes its tick limit
+                        let timestamp = self.runtime.system_time().micros();
+                        if !session.accepts_candy_at(tick) {
+                            log_event!(self, Warn, "COLLECT_CANDY", "Rejected candy in session {}: tick {} is past the time limit", session_id, tick);
+                            return OperationResponse::Rejected { reason: format!("Tick {} is past the time limit", tick) };
+                        }
+                        if session.lives_remaining == Some(0) {
+                            log_event!(self, Warn, "COLLECT_CANDY", "Rejected candy in session {}: the snake is out of lives", session_id);
+                            return OperationResponse::Rejected { reason: "The snake is out of lives".to_string() };
+                        }
+                        
+                        // The snake moves one cell per tick, so it can eat at most one candy per tick
+                        if session.combo.last_candy_tick.map(|last| tick <= last).unwrap_or(false) {
+                            log_event!(self, Warn, "COLLECT_CANDY", "Rejected candy in session {}: tick {} is not after the previous candy", session_id, tick);
+                            return OperationResponse::Rejected { reason: format!("Tick {} is not after the previous candy", tick) };
+                        }
+                        if tick < session.ticks {
+                            log_event!(self, Warn, "COLLECT_CANDY", "Rejected candy in session {}: tick {} is before tick {} the game already reached", session_id, tick, session.ticks);
+                            return OperationResponse::Rejected { reason: format!("Tick {} is before tick {} the game already reached", tick, session.ticks) };
+                        }
+                        
                         session.candies_collected += 1;
+                        session.ticks = session.ticks.max(tick);
+                        let multiplier = session.combo.register_candy(tick);
+                        if multiplier > 1 {
+                            log_event!(self, Debug, "COLLECT_CANDY", "Combo x{} in session {}", multiplier, session_id);
+                        }
                         let candies_collected = session.candies_collected; // Store the value before moving the session
-                        let _ = self.state.sessions.insert(&session_id, session);
+                        let started_at = session.start_time;
+                        self.state.save_session(session).await;
+                        self.state.log_session_event(&session_id, SessionEventKind::CandyEaten, tick, timestamp).await;
+                        
+                        self.advance_quests(QuestKind::CollectCandies, 1, timestamp).await;
                         
                         // Send CandyCollected message to leaderboard chain
                         match leaderboard_chain {
@@ -165,266 +588,5773 @@ impl Contract for SnakeGameContract {
                                 let message = GameMessage::CandyCollected {
                                     session_id: session_id.clone(),
                                     player_chain: current_chain,
+                                    tick,
+                                    started_at,
                                 };
-                                self.runtime.send_message(leader_chain, message);
-                                eprintln!("[COLLECT_CANDY] Sent CandyCollected to leaderboard chain {:?} for session {} (total: {})", 
+                                self.runtime.send_message(leader_chain, message.into());
+                                log_event!(self, Debug, "COLLECT_CANDY", "Sent CandyCollected to leaderboard chain {:?} for session {} (total: {})", 
                                     leader_chain, session_id, candies_collected);
                             }
                             None => {
-                                eprintln!("[ERROR] No leaderboard chain configured for collecting candy. Please use SetupLeaderboard operation first");
+                                log_event!(self, Error, "COLLECT_CANDY", "No leaderboard chain configured for collecting candy. Please use SetupLeaderboard operation first");
                             }
                         }
                         
-                        eprintln!("[COLLECT_CANDY] Collected candy in session: {} (total: {})", 
+                        log_event!(self, Debug, "COLLECT_CANDY", "Collected candy in session: {} (total: {})", 
                             session_id, candies_collected);
                     }
                 } else {
-                    eprintln!("[ERROR] No active game session found for collecting candy");
+                    log_event!(self, Error, "COLLECT_CANDY", "No active game session found for collecting candy");
                 }
             }
             
-            Operation::EndGame => {
-                let current_chain = self.runtime.chain_id();
-                let leaderboard_chain = self.state.leaderboard_chain_id.get().clone();
+            Operation::EndGame { move_log_hash, signature } => {
+                if !is_valid_move_log_hash(&move_log_hash) {
+                    panic!("Move log hash must be {} hex characters", MOVE_LOG_HASH_LEN);
+                }
+                let move_log_hash = Some(move_log_hash);
+                self.check_score_claim(&move_log_hash, signature).await;
+                self.finish_current_session(move_log_hash).await;
+            }
+            
+            Operation::ReportDeath { tick } => {
+                let session_id = self.state.my_current_session.get().clone()
+                    .expect("No active game session found");
+                let mut session = self.state.session(&session_id).await
+                    .expect("Active session is missing");
+                let lives = session.lives_remaining.expect("Deaths are only reported in Survival mode");
+                if lives == 0 {
+                    panic!("Session {} is out of lives and waits for EndGame", session_id);
+                }
+                
+                // A respawned snake can only die after its previous death
+                if session.last_death_tick.map(|last| tick <= last).unwrap_or(false) {
+                    panic!("Death at tick {} is not after the previous death", tick);
+                }
+                
+                let lives = lives.saturating_sub(1);
+                session.lives_remaining = Some(lives);
+                session.last_death_tick = Some(tick);
+                session.ticks = session.ticks.max(tick);
+                self.state.save_session(session).await;
                 let timestamp = self.runtime.system_time().micros();
+                self.state.log_session_event(&session_id, SessionEventKind::Died, tick, timestamp).await;
+                log_event!(self, Info, "SURVIVAL", "Died at tick {} in session {}, {} lives left", tick, session_id, lives);
                 
-                // Get current session
-                if let Some(session_id) = self.state.my_current_session.get().clone() {
-                    // Get the session data (we don't need to modify it here)
-                    if let Ok(Some(session)) = self.state.sessions.get(&session_id).await {
-                        let candies_collected = session.candies_collected;
-                        
-                        // Update session to mark as finished
-                        let mut updated_session = session.clone();
-                        updated_session.end_time = Some(timestamp);
-                        updated_session.state = GameState::Finished;
-                        
-                        // Check if this is a new record for this player
-                        let is_new_record = if let Some(ref stats) = *self.state.my_stats.get() {
-                            candies_collected > stats.highest_score
-                        } else {
-                            true // First game is always a record
-                        };
-                        
-                        updated_session.is_record = is_new_record;
-                        let _ = self.state.sessions.insert(&session_id, updated_session);
-                        
-                        // Only send GameFinished message to leaderboard chain if it's a new record
-                        if is_new_record {
-                            match leaderboard_chain {
-                                Some(leader_chain) => {
-                                    let message = GameMessage::GameFinished {
-                                        session_id: session_id.clone(),
-                                        player_chain: current_chain,
-                                        candies_collected,
-                                        is_new_record,
-                                    };
-                                    self.runtime.send_message(leader_chain, message);
-                                    eprintln!("[END_GAME] Sent GameFinished to leaderboard chain {:?} with {} candies (new record: {})", 
-                                        leader_chain, candies_collected, is_new_record);
-                                }
-                                None => {
-                                    eprintln!("[ERROR] No leaderboard chain configured for ending game. Please use SetupLeaderboard operation first");
-                                }
-                            }
-                        } else {
-                            eprintln!("[END_GAME] Game ended with {} candies, but not a new record. Skipping leaderboard update.", 
-                                candies_collected);
+                // The score only counts once EndGame anchors the move log, signed when a client key is registered
+                if lives == 0 {
+                    log_event!(self, Info, "SURVIVAL", "Session {} is out of lives and waits for EndGame", session_id);
+                }
+            }
+            
+            Operation::SubmitMoves { moves } => {
+                let session_id = self.state.my_current_session.get().clone()
+                    .expect("No active game session found");
+                let mut session = self.state.session(&session_id).await
+                    .expect("Active session is missing");
+                let mut engine = self.state.live_engines.get(&session_id).await
+                    .ok()
+                    .flatten()
+                    .expect("Live state is only tracked for games on levels");
+                
+                // Spectators only receive what changed: turns, candies and the death tick
+                let mut delta = LiveDelta {
+                    session_id: session_id.clone(),
+                    player: self.runtime.chain_id(),
+                    from_tick: engine.ticks,
+                    to_tick: engine.ticks,
+                    turns: Vec::new(),
+                    candies: Vec::new(),
+                    died_at: None,
+                    points: 0,
+                    elapsed_micros: 0,
+                };
+                for byte in moves {
+                    if !engine.alive {
+                        log_event!(self, Debug, "LIVE", "Snake already died at tick {} in session {}; later moves are ignored", engine.ticks, session_id);
+                        break;
+                    }
+                    let direction = snake_engine::Direction::from_byte(byte)
+                        .unwrap_or_else(|| panic!("Invalid move byte {}", byte));
+                    let previous_direction = engine.snake.direction;
+                    let outcome = engine.tick(direction);
+                    if engine.snake.direction != previous_direction {
+                        delta.turns.push(DirectionChange { tick: engine.ticks, direction: engine.snake.direction });
+                    }
+                    match outcome {
+                        snake_engine::TickOutcome::AteCandy => {
+                            delta.candies.push(CandyDelta { tick: engine.ticks, next_candy: engine.candy });
                         }
-                        
-                        // Update personal stats
-                        let mut my_stats = self.state.my_stats.get().clone().unwrap_or_else(|| PlayerStats::new(current_chain));
-                        my_stats.add_game(candies_collected, timestamp);
-                        self.state.my_stats.set(Some(my_stats));
-                        
-                        // Clear current session
-                        self.state.my_current_session.set(None);
-                        
-                        eprintln!("[END_GAME] Ended game session: {} with {} candies (record: {})", 
-                            session_id, candies_collected, is_new_record);
+                        snake_engine::TickOutcome::Died => delta.died_at = Some(engine.ticks),
+                        snake_engine::TickOutcome::Moved => {}
                     }
-                } else {
-                    eprintln!("[ERROR] No active game session found");
                 }
+                delta.to_tick = engine.ticks;
+                delta.points = engine.combo.points;
+                session.ticks = session.ticks.max(engine.ticks);
+                if !delta.candies.is_empty() {
+                    self.runtime.emit(GAME_STREAM_NAME.into(), &SnakeGameEvent::CandyBatch {
+                        version: EVENT_SCHEMA_VERSION,
+                        session_id: session_id.clone(),
+                        player: delta.player,
+                        from_tick: delta.from_tick,
+                        to_tick: delta.to_tick,
+                        candies: delta.candies.len() as u32,
+                        total_candies: engine.candies_collected,
+                    });
+                }
+                
+                let timestamp = self.runtime.system_time().micros();
+                let live = LiveSession::new(&session, &engine, timestamp);
+                delta.elapsed_micros = live.elapsed_micros;
+                let _ = self.state.live_engines.insert(&session_id, engine);
+                let _ = self.state.live_sessions.insert(&session_id, live);
+                self.state.save_session(session).await;
+                
+                if !delta.is_empty() {
+                    if let Ok(spectators) = self.state.spectators.indices().await {
+                        for spectator in spectators {
+                            self.runtime.send_message(spectator, GameMessage::LiveDelta(delta.clone()).into());
+                        }
+                    }
+                }
+            }
+            
+            Operation::Heartbeat { tick } => {
+                let session_id = self.state.my_current_session.get().clone()
+                    .expect("No active game session found");
+                let mut session = self.state.session(&session_id).await
+                    .expect("Active session is missing");
+                if tick < session.ticks {
+                    panic!("Heartbeat tick {} is behind the session at tick {}", tick, session.ticks);
+                }
+                session.ticks = tick;
+                self.state.save_session(session).await;
+            }
+            
+            Operation::ClaimGuestHistory => {
+                let current_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                let owner = self.runtime.authenticated_signer()
+                    .expect("Guest games can only be claimed by a signed operation");
+                let session_ids = self.state.guest_sessions.get().clone();
+                if session_ids.is_empty() {
+                    return OperationResponse::Rejected { reason: "There are no guest games to claim".to_string() };
+                }
+                
+                let mut sessions = Vec::new();
+                for session_id in session_ids {
+                    if let Some(mut session) = self.state.session(&session_id).await {
+                        session.guest = false;
+                        session.owner = Some(owner);
+                        self.state.save_session(session.clone()).await;
+                        sessions.push(session);
+                    }
+                }
+                sessions.sort_by_key(|session| session.start_time);
+                
+                // Replay the guest games onto the owner's stats, in the order they were played
+                let parameters = self.runtime.application_parameters();
+                let mut my_stats = self.state.my_stats.get().clone().unwrap_or_else(|| PlayerStats::new(current_chain));
+                let mut record = None;
+                let mut xp = 0;
+                for session in &sessions {
+                    let ended_at = session.end_time.unwrap_or(session.start_time);
+                    if session.has_known_seed() {
+                        my_stats.add_unranked_game(session.candies_collected, ended_at);
+                    } else if my_stats.add_game(session.candies_collected, ended_at) {
+                        my_stats.record_ticks = session.ticks;
+                        record = Some(session.clone());
+                    }
+                    my_stats.best_combo = my_stats.best_combo.max(session.combo.best);
+                    xp += parameters.xp.xp_for_game(session.candies_collected);
+                }
+                my_stats.tier = parameters.tier_thresholds.tier_for_score(my_stats.highest_score);
+                self.state.my_stats.set(Some(my_stats));
+                self.grant_xp(xp).await;
+                
+                // The best guest game counts as a record only if it beats the owner's own games
+                if let Some(mut session) = record {
+                    session.is_record = true;
+                    self.state.save_session(session.clone()).await;
+                    self.state.push_record_history(PersonalBest {
+                        score: session.candies_collected,
+                        timestamp: session.end_time.unwrap_or(session.start_time),
+                        session_id: session.session_id.clone(),
+                    });
+                    let message = GameMessage::GameFinished {
+                        session_id: session.session_id.clone(),
+                        player_chain: current_chain,
+                        candies_collected: session.candies_collected,
+                        is_new_record: true,
+                        move_log_hash: session.move_log_hash.clone(),
+                        seed: session.seed,
+                        level_id: session.level_id,
+                        map_hash: session.map_hash.clone(),
+                        points: session.combo.points,
+                        best_combo: session.combo.best,
+                        match_id: None,
+                        ticks: session.ticks,
+                        started_at: session.start_time,
+                    };
+                    self.track_pending_result(&session.session_id).await;
+                    self.send_to_chain(leaderboard_chain_id, message).await;
+                }
+                
+                // Level and mode bests were never reported while there was no leaderboard
+                for session in &sessions {
+                    let candies_collected = session.candies_collected;
+                    if session.map_hash.is_none() {
+                        let level_best = self.state.my_level_bests.get(&session.level_id).await.ok().flatten();
+                        if level_best.map(|best| candies_collected > best).unwrap_or(true) {
+                            let _ = self.state.my_level_bests.insert(&session.level_id, candies_collected);
+                            let message = GameMessage::LevelRecord { level_id: session.level_id, player_chain: current_chain, candies_collected };
+                            self.send_to_chain(leaderboard_chain_id, message).await;
+                        }
+                    }
+                    if session.mode != GameMode::Classic {
+                        let mode_best = self.state.my_mode_bests.get(&session.mode).await.ok().flatten();
+                        if mode_best.map(|best| candies_collected > best).unwrap_or(true) {
+                            let _ = self.state.my_mode_bests.insert(&session.mode, candies_collected);
+                            let message = GameMessage::ModeRecord { mode: session.mode, player_chain: current_chain, candies_collected };
+                            self.send_to_chain(leaderboard_chain_id, message).await;
+                        }
+                    }
+                }
+                
+                self.state.guest_stats.set(None);
+                self.state.guest_sessions.set(Vec::new());
+                log_event!(self, Info, "GUEST", "{:?} claimed {} guest games", owner, sessions.len());
             }
             
-            Operation::GetLeaderboard => {
-                // This operation doesn't modify state, just allows querying leaderboard
-                // The actual leaderboard can be queried through the service
+            Operation::Spectate { player_chain } => {
+                let application_id = self.runtime.application_id().forget_abi();
+                self.runtime.subscribe_to_events(player_chain, application_id, LIVE_STREAM_NAME.into());
+                let spectator = self.runtime.chain_id();
+                self.runtime.send_message(player_chain, GameMessage::SpectatorJoined { spectator }.into());
+                log_event!(self, Info, "LIVE", "Spectating games on chain {:?}", player_chain);
             }
             
-            Operation::GetMyStats => {
-                // This operation doesn't modify state, just allows querying personal stats
-                // The actual stats can be queried through the service
+            Operation::StopSpectating { player_chain } => {
+                let application_id = self.runtime.application_id().forget_abi();
+                self.runtime.unsubscribe_from_events(player_chain, application_id, LIVE_STREAM_NAME.into());
+                let spectator = self.runtime.chain_id();
+                self.runtime.send_message(player_chain, GameMessage::SpectatorLeft { spectator }.into());
+                
+                let mut watched = Vec::new();
+                if let Ok(session_ids) = self.state.live_sessions.indices().await {
+                    for session_id in session_ids {
+                        if let Ok(Some(live)) = self.state.live_sessions.get(&session_id).await {
+                            if live.player == player_chain {
+                                watched.push(session_id);
+                            }
+                        }
+                    }
+                }
+                for session_id in watched {
+                    let _ = self.state.live_sessions.remove(&session_id);
+                }
+                log_event!(self, Info, "LIVE", "Stopped spectating chain {:?}", player_chain);
             }
             
-            Operation::GetGameSession { session_id: _ } => {
-                // This operation doesn't modify state, just allows querying specific session
-                // The actual session can be queried through the service
+            Operation::GetLeaderboard | Operation::GetMyStats | Operation::GetGameSession { .. } => {
+                unreachable!("Deprecated operations are answered before dispatch");
             }
             
-            Operation::ResetLeaderboard => {
-                eprintln!("[RESET] ResetLeaderboard called on chain {:?}", self.runtime.chain_id());
+            Operation::ProposeReset => {
+                log_event!(self, Debug, "RESET", "ProposeReset called on chain {:?}", self.runtime.chain_id());
                 
-                // Only allow reset on the leaderboard chain
+                // Only allow reset proposals on the leaderboard chain
                 if !*self.state.is_leaderboard_chain.get() {
                     panic!("Reset operation can only be performed on the leaderboard chain");
                 }
+                self.assert_admin();
                 
-                // Get the list of players who were in the leaderboard before clearing
-                let mut leaderboard_players = Vec::new();
-                match self.state.leaderboard_participants.indices().await {
-                    Ok(players) => {
-                        for player in players {
-                            leaderboard_players.push(player);
-                        }
-                        eprintln!("[RESET] Found {} players who were in the leaderboard", leaderboard_players.len());
-                    }
-                    Err(e) => {
-                        eprintln!("[RESET] Error getting leaderboard participants: {:?}", e);
+                let now = self.runtime.system_time().micros();
+                if let Some(proposal) = self.state.reset_proposal.get() {
+                    if proposal.state_at(now) == ProposalState::Open {
+                        panic!("Reset proposal {} is still open", proposal.proposal_id);
                     }
                 }
                 
-                // Clear all game data on leaderboard chain
-                self.state.global_leaderboard.set(Vec::new());
-                self.state.player_stats.clear();
-                self.state.leaderboard_participants.clear();
-                self.state.session_counter.set(0);
+                let eligible_voters = self.state.leaderboard_participants.indices().await.unwrap_or_default();
+                let proposal_id = *self.state.reset_proposal_counter.get() + 1;
+                self.state.reset_proposal_counter.set(proposal_id);
+                let closes_at = now + self.runtime.application_parameters().reset_vote_window_secs * 1_000_000;
+                let mut proposal = ResetProposal {
+                    proposal_id,
+                    proposed_at: now,
+                    closes_at,
+                    eligible_voters: eligible_voters.clone(),
+                    approvals: Vec::new(),
+                    rejections: Vec::new(),
+                    state: ProposalState::Open,
+                };
                 
-                // Send LeaderboardReset message to all players who were in the leaderboard
-                for player_chain in &leaderboard_players {
-                    if *player_chain != self.runtime.chain_id() {
-                        let message = GameMessage::LeaderboardReset;
-                        self.runtime.send_message(*player_chain, message);
-                        eprintln!("[RESET] Sent LeaderboardReset message to player chain {:?}", player_chain);
-                    }
+                // Nobody to ask, so nobody to object
+                if eligible_voters.is_empty() {
+                    log_event!(self, Info, "RESET", "No participants to vote on proposal {}, resetting right away", proposal_id);
+                    proposal.state = ProposalState::Executed;
+                    self.state.reset_proposal.set(Some(proposal));
+                    self.reset_leaderboard().await;
+                    return OperationResponse::Ok;
                 }
                 
-                eprintln!("[RESET] Leaderboard reset completed successfully on leaderboard chain");
-            }
-        }
-    }
-
-    async fn execute_message(&mut self, message: Self::Message) {
-        eprintln!("[MESSAGE] Received message on chain {:?}", self.runtime.chain_id());
-        
-        // Check if message is bouncing
-        let is_bouncing = self
-            .runtime
-            .message_is_bouncing()
-            .expect("Message delivery status must be available when executing a message");
-
-        if is_bouncing {
-            eprintln!("[MESSAGE] Message is bouncing, returning");
-            return;
-        }
-
-        match message {
-            GameMessage::StartGame { .. } => {
-                // Ignore StartGame messages on all chains as sessions are only stored locally
-                eprintln!("[MESSAGE] Ignoring StartGame message - sessions are stored locally only");
+                self.state.reset_proposal.set(Some(proposal));
+                for player_chain in eligible_voters {
+                    self.send_to_chain(player_chain, GameMessage::ResetProposed { proposal_id, closes_at }).await;
+                }
+                log_event!(self, Info, "RESET", "Opened reset proposal {} until {}", proposal_id, closes_at);
             }
             
-            GameMessage::CandyCollected { session_id: _, player_chain } => {
-                eprintln!("[MESSAGE] Processing CandyCollected from player chain {:?}", player_chain);
+            Operation::VoteOnReset { proposal_id, approve } => {
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                let mut ballot = self.state.reset_ballot.get().clone()
+                    .filter(|ballot| ballot.proposal_id == proposal_id)
+                    .unwrap_or_else(|| panic!("No open reset proposal {} for this chain", proposal_id));
+                if ballot.vote.is_some() {
+                    panic!("Already voted on reset proposal {}", proposal_id);
+                }
+                if self.runtime.system_time().micros() > ballot.closes_at {
+                    panic!("Voting on reset proposal {} has closed", proposal_id);
+                }
                 
-                // Only process on leaderboard chain
-                if !*self.state.is_leaderboard_chain.get() {
-                    eprintln!("[MESSAGE] This is NOT the leaderboard chain, ignoring CandyCollected message");
-                    return;
+                ballot.vote = Some(approve);
+                self.state.reset_ballot.set(Some(ballot));
+                let voter = self.runtime.chain_id();
+                self.send_to_chain(leaderboard_chain_id, GameMessage::ResetVote { proposal_id, voter, approve }).await;
+            }
+            
+            Operation::FlagScore { chain_id, session_id, reason } => {
+                let reporter = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                if chain_id == reporter {
+                    panic!("Players cannot flag their own scores");
+                }
+                let reason = reason.trim().to_string();
+                if reason.is_empty() {
+                    panic!("A reason is required to flag a score");
+                }
+                if reason.len() > MAX_DISPUTE_REASON_LEN {
+                    panic!("Dispute reason cannot exceed {} bytes", MAX_DISPUTE_REASON_LEN);
                 }
                 
-                // In a real implementation, we might want to track candy collection on the leaderboard chain
-                // For now, we'll just log the event
-                eprintln!("[MESSAGE] Player chain {:?} collected a candy", player_chain);
+                log_event!(self, Info, "DISPUTE", "Flagging session {} of {:?}", session_id, chain_id);
+                let message = GameMessage::ScoreFlagged { reporter, player_chain: chain_id, session_id, reason };
+                self.send_to_chain(leaderboard_chain_id, message).await;
             }
             
-            GameMessage::GameFinished { session_id: _, player_chain, candies_collected, is_new_record } => {
-                eprintln!("[MESSAGE] Processing GameFinished: from {:?} with {} candies (new record: {})", 
-                    player_chain, candies_collected, is_new_record);
+            Operation::ReconfigureLeaderboard { new_chain } => {
+                log_event!(self, Debug, "SETUP", "ReconfigureLeaderboard to {:?} called on chain {:?}", new_chain, self.runtime.chain_id());
                 
-                // Only process on leaderboard chain
+                // Only the current leaderboard chain can hand over its role, by the admin
                 if !*self.state.is_leaderboard_chain.get() {
-                    eprintln!("[MESSAGE] This is NOT the leaderboard chain, ignoring GameFinished message");
-                    return;
+                    panic!("The leaderboard can only be reconfigured from the current leaderboard chain");
+                }
+                self.assert_admin();
+                if let Some(pending_chain) = *self.state.pending_leaderboard_move.get() {
+                    panic!("A move to leaderboard chain {:?} is still pending", pending_chain);
+                }
+                if new_chain == self.runtime.chain_id() {
+                    panic!("This chain is already the leaderboard chain");
                 }
                 
-                // Update leaderboard stats only (no session tracking on leaderboard chain)
-                self.update_leaderboard_stats(player_chain, candies_collected, is_new_record).await;
+                // This chain stays in charge until the new one confirms the handover
+                self.state.pending_leaderboard_move.set(Some(new_chain));
+                self.runtime.send_message(new_chain, GameMessage::LeaderboardHandover.into());
             }
             
-            GameMessage::UpdateLeaderboard { player_chain, candies_collected, is_new_record } => {
-                eprintln!("[MESSAGE] Processing UpdateLeaderboard for {:?}, candies: {}, new record: {}", 
-                    player_chain, candies_collected, is_new_record);
+            Operation::DecayInactive => {
+                log_event!(self, Debug, "DECAY", "DecayInactive called on chain {:?}", self.runtime.chain_id());
                 
-                // Only process on leaderboard chain
+                // Only allow decay on the leaderboard chain
                 if !*self.state.is_leaderboard_chain.get() {
-                    eprintln!("[MESSAGE] This is NOT the leaderboard chain, ignoring UpdateLeaderboard message");
-                    return;
+                    panic!("Decay operation can only be performed on the leaderboard chain");
                 }
                 
-                self.update_leaderboard_stats(player_chain, candies_collected, is_new_record).await;
+                // The rebuild moves stale entries out of the main board and refreshes the weekly boards
+                self.rebuild_global_leaderboard().await;
             }
             
-            GameMessage::UpdatePlayerName { player_chain, player_name } => {
-                eprintln!("[MESSAGE] Processing UpdatePlayerName for {:?}: '{}'", player_chain, player_name);
+            Operation::PublishQuest { title, kind, period, target, reward_coins, reward_xp } => {
+                log_event!(self, Debug, "QUEST", "PublishQuest called on chain {:?}", self.runtime.chain_id());
                 
-                // Only process on leaderboard chain
+                // Only allow publishing on the leaderboard chain
                 if !*self.state.is_leaderboard_chain.get() {
-                    eprintln!("[MESSAGE] This is NOT the leaderboard chain, ignoring UpdatePlayerName message");
-                    return;
+                    panic!("Quests can only be published on the leaderboard chain");
+                }
+                self.assert_admin();
+                
+                let quest_id = *self.state.quest_counter.get();
+                self.state.quest_counter.set(quest_id + 1);
+                
+                let starts_at = self.runtime.system_time().micros();
+                let quest = QuestDefinition {
+                    quest_id,
+                    title,
+                    kind,
+                    period,
+                    target,
+                    reward_coins,
+                    reward_xp,
+                    starts_at,
+                    expires_at: starts_at + period.duration_micros(),
+                };
+                
+                let _ = self.state.quest_catalog.insert(&quest_id, quest.clone());
+                self.runtime.emit(QUEST_STREAM_NAME.into(), &SnakeGameEvent::QuestPublished(quest));
+                log_event!(self, Info, "QUEST", "Published quest {} ({:?}, target {})", quest_id, kind, target);
+            }
+            
+            Operation::PublishAnnouncement { title, body, expires } => {
+                log_event!(self, Debug, "ANNOUNCEMENT", "PublishAnnouncement called on chain {:?}", self.runtime.chain_id());
+                
+                // Only allow publishing on the leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    panic!("Announcements can only be published on the leaderboard chain");
+                }
+                self.assert_admin();
+                
+                let announcement_id = *self.state.announcement_counter.get();
+                self.state.announcement_counter.set(announcement_id + 1);
+                
+                let announcement = Announcement {
+                    announcement_id,
+                    title,
+                    body,
+                    published_at: self.runtime.system_time().micros(),
+                    expires,
+                };
+                
+                self.store_announcement(announcement.clone());
+                self.runtime.emit(ANNOUNCEMENT_STREAM_NAME.into(), &SnakeGameEvent::AnnouncementPublished(announcement));
+                log_event!(self, Info, "ANNOUNCEMENT", "Published announcement {}", announcement_id);
+            }
+            
+            Operation::DefineLevel { name, width, height, obstacles } => {
+                log_event!(self, Debug, "LEVEL", "DefineLevel called on chain {:?}", self.runtime.chain_id());
+                
+                // Only allow defining levels on the leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    panic!("Levels can only be defined on the leaderboard chain");
+                }
+                self.assert_admin();
+                
+                let board = snake_engine::Board::new(width, height, obstacles);
+                if let Err(error) = board.validate() {
+                    panic!("Invalid level layout: {:?}", error);
+                }
+                
+                let level_id = *self.state.level_counter.get() + 1;
+                self.state.level_counter.set(level_id);
+                let level = LevelLayout { level_id, name, board };
+                
+                let _ = self.state.levels.insert(&level_id, level.clone());
+                self.runtime.emit(LEVEL_STREAM_NAME.into(), &SnakeGameEvent::LevelPublished(level));
+                log_event!(self, Info, "LEVEL", "Defined level {} ({}x{})", level_id, width, height);
+            }
+            
+            Operation::ReserveName { name, reserved_for } => {
+                log_event!(self, Debug, "NAMES", "ReserveName '{}' for {:?}", name, reserved_for);
+                
+                // Only allow reserving names on the leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    panic!("Names can only be reserved on the leaderboard chain");
+                }
+                self.assert_admin();
+                
+                let key = name_key(&name);
+                if key.is_empty() {
+                    panic!("Cannot reserve an empty name");
+                }
+                let reservation = ReservedName {
+                    name,
+                    reserved_for,
+                    reserved_at: self.runtime.system_time().micros(),
+                };
+                let _ = self.state.reserved_names.insert(&key, reservation);
+            }
+            
+            Operation::ReleaseName { name } => {
+                log_event!(self, Debug, "NAMES", "ReleaseName '{}'", name);
+                
+                // Only allow releasing names on the leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    panic!("Names can only be released on the leaderboard chain");
+                }
+                self.assert_admin();
+                
+                let _ = self.state.reserved_names.remove(&name_key(&name));
+            }
+            
+            Operation::RebuildNameHandles => {
+                log_event!(self, Debug, "NAMES", "RebuildNameHandles");
+                
+                // Only allow re-indexing names on the leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    panic!("Name handles can only be rebuilt on the leaderboard chain");
+                }
+                self.assert_admin();
+                
+                // Names and reservations stored before names were normalized are keyed the old way,
+                // and names from then have no handle at all
+                let mut reservations = Vec::new();
+                for key in self.state.reserved_names.indices().await.unwrap_or_default() {
+                    if let Ok(Some(reservation)) = self.state.reserved_names.get(&key).await {
+                        reservations.push(reservation);
+                    }
+                }
+                self.state.reserved_names.clear();
+                for reservation in reservations {
+                    let _ = self.state.reserved_names.insert(&name_key(&reservation.name), reservation);
+                }
+                
+                self.state.name_handles.clear();
+                for player_chain in self.state.player_names.indices().await.unwrap_or_default() {
+                    let Ok(Some(name)) = self.state.player_names.get(&player_chain).await else {
+                        continue;
+                    };
+                    // Two old names can fold into one handle; the first chain keeps it
+                    let key = name_key(&name);
+                    match self.state.name_handles.get(&key).await.ok().flatten() {
+                        Some(holder) => log_event!(self, Warn, "NAMES", "'{}' of {:?} has the same handle as the name of {:?}", name, player_chain, holder),
+                        None => {
+                            let _ = self.state.name_handles.insert(&key, player_chain);
+                        }
+                    }
+                }
+            }
+            
+            Operation::SetVerified { player_chain, verified } => {
+                log_event!(self, Debug, "NAMES", "SetVerified {:?}: {}", player_chain, verified);
+                
+                // Only allow verification on the leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    panic!("Players can only be verified on the leaderboard chain");
+                }
+                self.assert_admin();
+                
+                if verified {
+                    let _ = self.state.verified_players.insert(&player_chain);
+                } else {
+                    let _ = self.state.verified_players.remove(&player_chain);
+                }
+                
+                // Show the badge on the board right away
+                self.rebuild_global_leaderboard().await;
+            }
+            
+            Operation::SetFeatureFlag { key, enabled } => {
+                log_event!(self, Debug, "FEATURES", "SetFeatureFlag '{}': {}", key, enabled);
+                
+                // Only allow setting feature flags on the leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    panic!("Feature flags can only be set on the leaderboard chain");
+                }
+                self.assert_admin();
+                if key.trim().is_empty() {
+                    panic!("Feature flag key cannot be empty");
+                }
+                
+                let _ = self.state.feature_flags.insert(&key, enabled);
+                self.runtime.emit(ANNOUNCEMENT_STREAM_NAME.into(), &SnakeGameEvent::FeatureFlagSet(FeatureFlag { key, enabled }));
+            }
+            
+            Operation::ResolveDispute { dispute_id } => {
+                log_event!(self, Debug, "DISPUTE", "ResolveDispute {}", dispute_id);
+                
+                // Only allow moderation on the leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    panic!("Disputes can only be moderated on the leaderboard chain");
+                }
+                self.assert_admin();
+                
+                self.close_dispute(dispute_id, DisputeState::Dismissed).await;
+            }
+            
+            Operation::UpholdDispute { dispute_id } => {
+                log_event!(self, Debug, "DISPUTE", "UpholdDispute {}", dispute_id);
+                
+                // Only allow moderation on the leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    panic!("Disputes can only be moderated on the leaderboard chain");
+                }
+                self.assert_admin();
+                
+                self.close_dispute(dispute_id, DisputeState::Upheld).await;
+            }
+            
+            Operation::StrikeDispute { dispute_id } => {
+                log_event!(self, Debug, "DISPUTE", "StrikeDispute {}", dispute_id);
+                
+                // Only allow moderation on the leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    panic!("Disputes can only be moderated on the leaderboard chain");
+                }
+                self.assert_admin();
+                
+                self.close_dispute(dispute_id, DisputeState::Struck).await;
+            }
+            
+            Operation::PublishMap { name, layout } => {
+                let current_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                if let Err(error) = layout.validate() {
+                    panic!("Invalid map layout: {:?}", error);
+                }
+                
+                log_event!(self, Info, "MAP", "Publishing map '{}' with hash {}", name, map_hash(&layout));
+                let message = GameMessage::PublishMap { author: current_chain, name, board: layout };
+                self.send_to_chain(leaderboard_chain_id, message).await;
+            }
+            
+            Operation::FundTreasury { amount } => {
+                // Only the leaderboard chain holds a treasury
+                if !*self.state.is_leaderboard_chain.get() {
+                    panic!("The treasury can only be funded on the leaderboard chain");
+                }
+                
+                let funder = self.runtime.authenticated_signer()
+                    .expect("Funding the treasury requires an authenticated signer");
+                let chain_id = self.runtime.chain_id();
+                self.runtime.transfer(funder, Account { chain_id, owner: AccountOwner::CHAIN }, amount);
+                
+                self.credit_treasury(amount, Account { chain_id, owner: funder }, "Direct funding".to_string());
+            }
+            
+            Operation::WithdrawTreasury { amount, to } => {
+                log_event!(self, Info, "TREASURY", "WithdrawTreasury of {} to {:?} requested", amount, to);
+                
+                // Only allow withdrawals on the leaderboard chain, by the admin
+                if !*self.state.is_leaderboard_chain.get() {
+                    panic!("Treasury withdrawals can only be performed on the leaderboard chain");
+                }
+                self.assert_admin();
+                
+                let balance = *self.state.treasury_balance.get();
+                let balance_after = balance.try_sub(amount)
+                    .expect("Withdrawal exceeds the treasury balance");
+                
+                self.runtime.transfer(AccountOwner::CHAIN, to, amount);
+                self.state.treasury_balance.set(balance_after);
+                self.state.treasury_ledger.push(TreasuryEntry {
+                    flow: TreasuryFlow::Outflow,
+                    amount,
+                    counterparty: to,
+                    memo: "Admin withdrawal".to_string(),
+                    timestamp: self.runtime.system_time().micros(),
+                    balance_after,
+                });
+                log_event!(self, Info, "TREASURY", "Withdrew {}, balance now {}", amount, balance_after);
+            }
+            
+            Operation::DonateToPrizePool { pool, amount, sponsor_name } => {
+                let current_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                let sponsor = self.runtime.authenticated_signer()
+                    .expect("Donating requires an authenticated signer");
+                
+                // Move the tokens to the leaderboard chain, then tell it which pool they belong to
+                self.runtime.transfer(sponsor, Account { chain_id: leaderboard_chain_id, owner: AccountOwner::CHAIN }, amount);
+                if current_chain == leaderboard_chain_id {
+                    self.record_donation(pool, current_chain, sponsor_name, amount).await;
+                } else {
+                    let message = GameMessage::PrizeDonation {
+                        pool,
+                        sponsor_chain: current_chain,
+                        sponsor_name,
+                        amount,
+                    };
+                    self.runtime.send_message(leaderboard_chain_id, message.into());
+                    log_event!(self, Info, "PRIZES", "Sent donation of {} to leaderboard chain {:?}", amount, leaderboard_chain_id);
+                }
+            }
+            
+            Operation::DistributePrizes { treasury_amount } => {
+                log_event!(self, Debug, "PRIZES", "DistributePrizes called on chain {:?}", self.runtime.chain_id());
+                
+                // Only allow distribution on the leaderboard chain, by the admin
+                if !*self.state.is_leaderboard_chain.get() {
+                    panic!("Prizes can only be distributed on the leaderboard chain");
+                }
+                self.assert_admin();
+                
+                // Take the treasury contribution first
+                let mut prize_fund = Amount::ZERO;
+                if treasury_amount > Amount::ZERO {
+                    let balance_after = self.state.treasury_balance.get().try_sub(treasury_amount)
+                        .expect("Prize contribution exceeds the treasury balance");
+                    self.state.treasury_balance.set(balance_after);
+                    let chain_id = self.runtime.chain_id();
+                    self.state.treasury_ledger.push(TreasuryEntry {
+                        flow: TreasuryFlow::Outflow,
+                        amount: treasury_amount,
+                        counterparty: Account { chain_id, owner: AccountOwner::CHAIN },
+                        memo: "Prize distribution".to_string(),
+                        timestamp: self.runtime.system_time().micros(),
+                        balance_after,
+                    });
+                    prize_fund = treasury_amount;
+                }
+                
+                // Then empty every sponsored pool into the same fund
+                if let Ok(pool_names) = self.state.prize_pools.indices().await {
+                    for pool_name in pool_names {
+                        if let Ok(Some(mut pool)) = self.state.prize_pools.get(&pool_name).await {
+                            prize_fund = prize_fund.saturating_add(pool.balance);
+                            pool.balance = Amount::ZERO;
+                            let _ = self.state.prize_pools.insert(&pool_name, pool);
+                        }
+                    }
+                }
+                
+                self.pay_prizes(prize_fund).await;
+            }
+            
+            Operation::ClaimRewards => {
+                let now = self.runtime.system_time().micros();
+                let rewards = self.state.my_rewards.index_values().await.unwrap_or_default();
+                let mut claim = RewardClaim { coins: 0, reward_ids: Vec::new(), expired_coins: 0, timestamp: now };
+                for (reward_id, mut reward) in rewards {
+                    if reward.is_expired(now) {
+                        claim.expired_coins += reward.coins - reward.claimed_coins;
+                        let _ = self.state.my_rewards.remove(&reward_id);
+                        continue;
+                    }
+                    let claimable = reward.vested(now) - reward.claimed_coins;
+                    if claimable == 0 {
+                        continue;
+                    }
+                    
+                    claim.coins += claimable;
+                    claim.reward_ids.push(reward_id);
+                    reward.claimed_coins += claimable;
+                    if reward.claimed_coins == reward.coins {
+                        let _ = self.state.my_rewards.remove(&reward_id);
+                    } else {
+                        let _ = self.state.my_rewards.insert(&reward_id, reward);
+                    }
+                }
+                
+                if claim.coins == 0 && claim.expired_coins == 0 {
+                    return OperationResponse::Rejected { reason: "No vested rewards to claim".to_string() };
+                }
+                self.state.my_coins.set(*self.state.my_coins.get() + claim.coins);
+                log_event!(self, Info, "REWARDS", "Claimed {} coins from {} rewards, {} coins expired",
+                    claim.coins, claim.reward_ids.len(), claim.expired_coins);
+                self.state.reward_claims.push(claim);
+            }
+            
+            Operation::GiftCoins { to_chain, amount } => {
+                let current_chain = self.runtime.chain_id();
+                if to_chain == current_chain {
+                    panic!("Cannot gift coins to this chain");
+                }
+                if amount == 0 {
+                    panic!("Gift amount must be greater than zero");
+                }
+                self.debit_coins(amount);
+                
+                // Tracked, so the coins bounce back here if the recipient rejects the message
+                let message = GameMessage::GiftCoins {
+                    from_chain: current_chain,
+                    from_name: self.state.my_player_name.get().clone(),
+                    amount,
+                };
+                self.runtime.prepare_message(message.into()).with_tracking().send_to(to_chain);
+                log_event!(self, Info, "GIFT", "Sent {} coins to {:?}", amount, to_chain);
+            }
+            
+            Operation::ListItem { kind, item, price } => {
+                let current_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                if price == Amount::ZERO {
+                    panic!("Listings need a price above zero");
+                }
+                
+                // Skins leave this chain until they are sold or the listing is cancelled
+                if kind == ItemKind::Skin {
+                    if !self.state.my_skins.contains(&item).await.unwrap_or(false) {
+                        panic!("This chain does not own the skin '{}'", item);
+                    }
+                    let _ = self.state.my_skins.remove(&item);
+                }
+                
+                let owner = self.runtime.authenticated_signer().unwrap_or(AccountOwner::CHAIN);
+                let message = GameMessage::ListMarketItem {
+                    seller: Account { chain_id: current_chain, owner },
+                    kind,
+                    item,
+                    price,
+                };
+                self.send_to_chain(leaderboard_chain_id, message).await;
+            }
+            
+            Operation::CancelListing { listing_id } => {
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                let seller_chain = self.runtime.chain_id();
+                self.send_to_chain(leaderboard_chain_id, GameMessage::CancelMarketListing { listing_id, seller_chain }).await;
+            }
+            
+            Operation::BuyListing { listing_id, price } => {
+                let current_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                let buyer = self.runtime.authenticated_signer()
+                    .expect("Buying requires an authenticated signer");
+                
+                // The payment waits on the leaderboard chain until the sale goes through or is refunded
+                self.runtime.transfer(buyer, Account { chain_id: leaderboard_chain_id, owner: AccountOwner::CHAIN }, price);
+                let message = GameMessage::BuyMarketListing {
+                    listing_id,
+                    buyer: Account { chain_id: current_chain, owner: buyer },
+                    paid: price,
+                };
+                self.send_to_chain(leaderboard_chain_id, message).await;
+            }
+            
+            Operation::StakeForEligibility => {
+                let current_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                let coins = self.runtime.application_parameters().eligibility_stake_coins;
+                if coins == 0 {
+                    panic!("Staking for leaderboard eligibility is not offered");
+                }
+                if *self.state.my_eligibility_stake.get() > 0 {
+                    panic!("This chain has already staked for leaderboard eligibility");
+                }
+                
+                // The leaderboard chain keeps the stake; it is what a throwaway chain would lose
+                self.debit_coins(coins);
+                self.state.my_eligibility_stake.set(coins);
+                self.send_to_chain(leaderboard_chain_id, GameMessage::EligibilityStaked { player_chain: current_chain, coins }).await;
+                log_event!(self, Info, "ELIGIBILITY", "Staked {} coins for leaderboard eligibility", coins);
+            }
+            
+            Operation::RequestLeaderboardSync { limit } => {
+                let current_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                if current_chain == leaderboard_chain_id {
+                    panic!("The leaderboard chain already holds the global leaderboard");
+                }
+                if limit == 0 || limit > MAX_LEADERBOARD_SYNC {
+                    panic!("Leaderboard sync limit must be between 1 and {}", MAX_LEADERBOARD_SYNC);
+                }
+                
+                let message = GameMessage::LeaderboardSyncRequest { player_chain: current_chain, limit };
+                self.runtime.send_message(leaderboard_chain_id, message.into());
+                log_event!(self, Info, "LEADERBOARD", "Requested the top {} from leaderboard chain {:?}", limit, leaderboard_chain_id);
+            }
+            
+            Operation::ReactToEntry { chain_id, emote } => {
+                let current_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                if chain_id == current_chain {
+                    panic!("Players cannot react to their own entry");
+                }
+                
+                self.send_to_chain(leaderboard_chain_id, GameMessage::React { reactor: current_chain, target: chain_id, emote }).await;
+            }
+            
+            Operation::SendChatMessage { recipient, text } => {
+                let text = text.trim().to_string();
+                if text.is_empty() || text.chars().count() > MAX_CHAT_MESSAGE_LEN {
+                    panic!("Chat messages must be between 1 and {} characters", MAX_CHAT_MESSAGE_LEN);
+                }
+                let current_chain = self.runtime.chain_id();
+                let mut message = ChatMessage {
+                    from: current_chain,
+                    from_name: self.state.my_player_name.get().clone(),
+                    to: None,
+                    team_id: None,
+                    text,
+                    sent_at: self.runtime.system_time().micros(),
+                };
+                
+                match recipient {
+                    ChatRecipient::Player(to) => {
+                        if to == current_chain {
+                            panic!("Players cannot chat with themselves");
+                        }
+                        message.to = Some(to);
+                        self.store_chat(message.clone());
+                        self.runtime.send_message(to, GameMessage::Chat { message }.into());
+                    }
+                    ChatRecipient::Team(team_id) => {
+                        let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                            .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                        message.team_id = Some(team_id);
+                        self.store_chat(message.clone());
+                        self.send_to_chain(leaderboard_chain_id, GameMessage::TeamChat { message }).await;
+                    }
+                }
+            }
+            
+            Operation::FollowPlayer { chain_id } => {
+                let current_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                if chain_id == current_chain {
+                    panic!("Players cannot follow themselves");
+                }
+                if self.state.following.contains(&chain_id).await.unwrap_or(false) {
+                    panic!("Already following {:?}", chain_id);
+                }
+                if self.state.following.count().await.unwrap_or(0) >= MAX_FOLLOWING {
+                    panic!("A chain can follow at most {} players", MAX_FOLLOWING);
+                }
+                
+                let _ = self.state.following.insert(&chain_id);
+                self.send_to_chain(leaderboard_chain_id, GameMessage::FollowPlayer { follower: current_chain, followed: chain_id }).await;
+                log_event!(self, Info, "FOLLOW", "Following {:?}", chain_id);
+            }
+            
+            Operation::UnfollowPlayer { chain_id } => {
+                let current_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                if !self.state.following.contains(&chain_id).await.unwrap_or(false) {
+                    panic!("Not following {:?}", chain_id);
+                }
+                
+                let _ = self.state.following.remove(&chain_id);
+                self.send_to_chain(leaderboard_chain_id, GameMessage::UnfollowPlayer { follower: current_chain, followed: chain_id }).await;
+                log_event!(self, Info, "FOLLOW", "Stopped following {:?}", chain_id);
+            }
+            
+            Operation::SubscribeToLeaderboard { limit } => {
+                let current_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                if current_chain == leaderboard_chain_id {
+                    panic!("The leaderboard chain already holds the global leaderboard");
+                }
+                if limit == 0 || limit > MAX_LEADERBOARD_SYNC {
+                    panic!("Leaderboard sync limit must be between 1 and {}", MAX_LEADERBOARD_SYNC);
+                }
+                
+                self.state.my_leaderboard_subscription.set(Some(limit));
+                let message = GameMessage::LeaderboardSubscribe { player_chain: current_chain, limit };
+                self.runtime.send_message(leaderboard_chain_id, message.into());
+                log_event!(self, Info, "LEADERBOARD", "Subscribed to the top {} on leaderboard chain {:?}", limit, leaderboard_chain_id);
+            }
+            
+            Operation::UnsubscribeFromLeaderboard => {
+                let current_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                if self.state.my_leaderboard_subscription.get().is_none() {
+                    panic!("This chain is not subscribed to the leaderboard");
+                }
+                
+                self.state.my_leaderboard_subscription.set(None);
+                let message = GameMessage::LeaderboardUnsubscribe { player_chain: current_chain };
+                self.runtime.send_message(leaderboard_chain_id, message.into());
+                log_event!(self, Info, "LEADERBOARD", "Unsubscribed from leaderboard chain {:?}", leaderboard_chain_id);
+            }
+            
+            Operation::RequestReferralCode => {
+                let current_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                
+                if current_chain == leaderboard_chain_id {
+                    let code = self.mint_referral_code(current_chain).await;
+                    self.state.my_referral_code.set(Some(code));
+                } else {
+                    let message = GameMessage::RequestReferralCode { player_chain: current_chain };
+                    self.runtime.send_message(leaderboard_chain_id, message.into());
+                    log_event!(self, Info, "REFERRAL", "Requested a referral code from leaderboard chain {:?}", leaderboard_chain_id);
+                }
+            }
+            
+            Operation::RegisterReferral { code } => {
+                let current_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                
+                // Referrals are only for players who have not played yet, and only once
+                if self.state.my_referral.get().is_some() {
+                    panic!("A referral code has already been registered on this chain");
+                }
+                let games_played = self.state.my_stats.get().as_ref().map(|stats| stats.games_played).unwrap_or(0);
+                if games_played > 0 {
+                    panic!("Referral codes can only be redeemed before the first game");
+                }
+                if self.state.my_referral_code.get().as_ref() == Some(&code) {
+                    panic!("Players cannot redeem their own referral code");
+                }
+                
+                self.state.my_referral.set(Some(ReferralStatus {
+                    code: code.clone(),
+                    state: ReferralState::Pending,
+                    games_finished: 0,
+                    rejection_reason: None,
+                }));
+                
+                // The leaderboard chain owns the codes, so it validates the redemption
+                let message = GameMessage::RedeemReferral { code, referee_chain: current_chain };
+                self.send_to_chain(leaderboard_chain_id, message).await;
+            }
+            
+            Operation::CreateBattleRoyale { name, starts_at, round_duration_secs, elimination_percent } => {
+                log_event!(self, Debug, "ROYALE", "CreateBattleRoyale called on chain {:?}", self.runtime.chain_id());
+                
+                // Only allow scheduling events on the leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    panic!("Battle royales can only be created on the leaderboard chain");
+                }
+                self.assert_admin();
+                if !self.feature_enabled(FEATURE_BATTLE_ROYALE).await {
+                    panic!("Battle royales are currently disabled");
+                }
+                if elimination_percent == 0 || elimination_percent >= 100 {
+                    panic!("Elimination percent must be between 1 and 99");
+                }
+                
+                let event_id = *self.state.battle_royale_counter.get();
+                self.state.battle_royale_counter.set(event_id + 1);
+                let event = BattleRoyale {
+                    event_id,
+                    name,
+                    starts_at,
+                    round_duration_secs,
+                    elimination_percent,
+                    state: BattleRoyaleState::Registration,
+                    round: 0,
+                    round_seed: 0,
+                    round_ends_at: 0,
+                    survivors: Vec::new(),
+                    round_scores: Vec::new(),
+                    eliminated: Vec::new(),
+                    winner: None,
+                    bots: Vec::new(),
+                    winning_bot: None,
+                };
+                let _ = self.state.battle_royales.insert(&event_id, event);
+                log_event!(self, Info, "ROYALE", "Scheduled battle royale {} starting at {}", event_id, starts_at);
+            }
+            
+            Operation::JoinBattleRoyale { event_id } => {
+                let current_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                
+                let message = GameMessage::JoinBattleRoyale { event_id, player_chain: current_chain };
+                self.send_to_chain(leaderboard_chain_id, message).await;
+            }
+            
+            Operation::AdvanceBattleRoyale { event_id } => {
+                if !*self.state.is_leaderboard_chain.get() {
+                    panic!("Battle royales can only be advanced on the leaderboard chain");
+                }
+                let event = self.state.battle_royales.get(&event_id).await
+                    .ok()
+                    .flatten()
+                    .expect("Unknown battle royale");
+                self.advance_battle_royale(event).await;
+            }
+            
+            Operation::CreateTournament { name, level_id, starts_at, duration_secs, max_attempts, max_entrants, prize_coins, bracket_size, bracket_round_secs } => {
+                log_event!(self, Debug, "TOURNAMENT", "CreateTournament called on chain {:?}", self.runtime.chain_id());
+                
+                // Only allow tournaments on the leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    panic!("Tournaments can only be created on the leaderboard chain");
+                }
+                self.assert_admin();
+                if max_attempts == 0 {
+                    panic!("A tournament needs at least one attempt per entrant");
+                }
+                if bracket_size != 0 && (bracket_size < 2 || !bracket_size.is_power_of_two()) {
+                    panic!("A knockout bracket needs a power of two of at least 2 players");
+                }
+                if self.level_layout(level_id).await.is_none() {
+                    panic!("Unknown level {}", level_id);
+                }
+                
+                let tournament_id = *self.state.tournament_counter.get();
+                self.state.tournament_counter.set(tournament_id + 1);
+                let now = self.runtime.system_time().micros();
+                let starts_at = starts_at.max(now);
+                let tournament = Tournament {
+                    tournament_id,
+                    name,
+                    level_id,
+                    seed: session_seed(&format!("tournament_{}", tournament_id), now),
+                    starts_at,
+                    ends_at: starts_at + duration_secs * 1_000_000,
+                    max_attempts,
+                    max_entrants,
+                    prize_coins,
+                    bracket_size,
+                    bracket_round_secs,
+                    state: TournamentState::Open,
+                    standings: Vec::new(),
+                };
+                let _ = self.state.tournaments.insert(&tournament_id, tournament.clone());
+                let _ = self.state.open_tournaments.insert(&tournament_id);
+                self.runtime.emit(TOURNAMENT_STREAM_NAME.into(), &SnakeGameEvent::TournamentPublished(tournament));
+                log_event!(self, Info, "TOURNAMENT", "Opened tournament {} with {} attempts per entrant", tournament_id, max_attempts);
+            }
+            
+            Operation::FinalizeTournament { tournament_id } => {
+                if !*self.state.is_leaderboard_chain.get() {
+                    panic!("Tournaments can only be finalized on the leaderboard chain");
+                }
+                let tournament = self.state.tournaments.get(&tournament_id).await
+                    .ok()
+                    .flatten()
+                    .expect("Unknown tournament");
+                if tournament.state != TournamentState::Open {
+                    panic!("Tournament {} is already finalized", tournament_id);
+                }
+                if self.runtime.system_time().micros() <= tournament.ends_at {
+                    panic!("Tournament {} is still running", tournament_id);
+                }
+                self.finalize_tournament(tournament).await;
+            }
+            
+            Operation::BuySeasonPremium => {
+                let current_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                let mut pass = self.state.my_season_pass.get().clone();
+                if pass.premium {
+                    panic!("The premium track of season {} is already unlocked", pass.season);
+                }
+                let buyer = self.runtime.authenticated_signer()
+                    .expect("Buying the premium track requires an authenticated signer");
+                
+                // The price goes to the leaderboard chain's treasury
+                let price = self.runtime.application_parameters().season_pass.premium_price;
+                self.runtime.transfer(buyer, Account { chain_id: leaderboard_chain_id, owner: AccountOwner::CHAIN }, price);
+                let buyer = Account { chain_id: current_chain, owner: buyer };
+                if current_chain == leaderboard_chain_id {
+                    self.credit_treasury(price, buyer, "Season pass premium".to_string());
+                } else {
+                    let message = GameMessage::SeasonPremiumPurchased { buyer, amount: price };
+                    self.runtime.send_message(leaderboard_chain_id, message.into());
+                }
+                
+                pass.premium = true;
+                self.state.my_season_pass.set(pass);
+                log_event!(self, Info, "SEASON", "Unlocked the premium season pass track for {}", price);
+                self.report_season_pass().await;
+            }
+            
+            Operation::ClaimSeasonReward { season, track, tier } => {
+                let mut pass = self.state.my_season_pass.get().clone();
+                if season != pass.season {
+                    panic!("Season {} is over; the pass is on season {}", season, pass.season);
+                }
+                let config = self.runtime.application_parameters().season_pass;
+                let pass_tier = config.tiers.get(tier as usize)
+                    .unwrap_or_else(|| panic!("Unknown season pass tier {}", tier));
+                if pass.xp < pass_tier.xp {
+                    panic!("Tier {} needs {} season XP, only {} earned", tier, pass_tier.xp, pass.xp);
+                }
+                if track == PassTrack::Premium && !pass.premium {
+                    panic!("The premium track is locked. Please use BuySeasonPremium first");
+                }
+                if pass.claimed(track).contains(&tier) {
+                    log_event!(self, Info, "SEASON", "{:?} reward of tier {} was already claimed", track, tier);
+                    return OperationResponse::Rejected { reason: format!("{:?} reward of tier {} was already claimed", track, tier) };
+                }
+                
+                pass.claimed(track).push(tier);
+                let reward = match track {
+                    PassTrack::Free => pass_tier.free.clone(),
+                    PassTrack::Premium => pass_tier.premium.clone(),
+                };
+                self.state.my_season_pass.set(pass);
+                
+                if reward.coins > 0 {
+                    self.state.my_coins.set(*self.state.my_coins.get() + reward.coins);
+                }
+                if let Some(skin) = &reward.skin {
+                    let _ = self.state.my_skins.insert(skin);
+                }
+                if let Some(trophy) = reward.trophy.clone() {
+                    self.state.my_trophies.push(SeasonTrophy {
+                        season,
+                        name: trophy,
+                        claimed_at: self.runtime.system_time().micros(),
+                    });
+                }
+                log_event!(self, Info, "SEASON", "Claimed {:?} reward of tier {}: {:?}", track, tier, reward);
+            }
+            
+            Operation::RegisterTeam { name, teammate } => {
+                let current_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                if teammate == current_chain {
+                    panic!("A team needs two different player chains");
+                }
+                
+                let message = GameMessage::RegisterTeam { name, members: vec![current_chain, teammate] };
+                self.send_to_chain(leaderboard_chain_id, message).await;
+            }
+            
+            Operation::CreateTeamMatch { team_a, team_b } => {
+                let current_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                
+                let message = GameMessage::CreateTeamMatch { requester: current_chain, team_a, team_b };
+                self.send_to_chain(leaderboard_chain_id, message).await;
+            }
+            
+            Operation::AcceptTeam { team_id } => {
+                let current_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                if !self.state.team_invites.contains_key(&team_id).await.unwrap_or(false) {
+                    panic!("No invitation to join team {}", team_id);
+                }
+                
+                let _ = self.state.team_invites.remove(&team_id);
+                let message = GameMessage::AcceptTeam { team_id, player_chain: current_chain };
+                self.send_to_chain(leaderboard_chain_id, message).await;
+            }
+            
+            Operation::AcceptTeamMatch { match_id } => {
+                let current_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                let team_match = self.state.my_team_matches.get(&match_id).await
+                    .ok()
+                    .flatten()
+                    .unwrap_or_else(|| panic!("Unknown team match {}", match_id));
+                if team_match.state != TeamMatchState::Proposed {
+                    panic!("Team match {} is not waiting to be accepted", match_id);
+                }
+                
+                let message = GameMessage::AcceptTeamMatch { match_id, player_chain: current_chain };
+                self.send_to_chain(leaderboard_chain_id, message).await;
+            }
+            
+            Operation::ContributeToTeam { team_id, coins } => {
+                let current_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                if coins == 0 {
+                    panic!("A contribution must be at least one coin");
+                }
+                
+                // The coins come back if the leaderboard chain turns the contribution down
+                self.debit_coins(coins);
+                self.send_to_chain(leaderboard_chain_id, GameMessage::TeamContribution { team_id, player_chain: current_chain, coins }).await;
+                log_event!(self, Info, "TEAM", "Contributed {} coins to team {}", coins, team_id);
+            }
+            
+            Operation::BuyTeamPerk { team_id, perk } => {
+                let current_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                
+                self.send_to_chain(leaderboard_chain_id, GameMessage::BuyTeamPerk { team_id, leader: current_chain, perk }).await;
+            }
+            
+            Operation::ScheduleTeamWar { team_a, team_b, starts_at } => {
+                log_event!(self, Debug, "TEAM", "ScheduleTeamWar called on chain {:?}", self.runtime.chain_id());
+                
+                // Only allow scheduling wars on the leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    panic!("Team wars can only be scheduled on the leaderboard chain");
+                }
+                self.assert_admin();
+                
+                let (Ok(Some(first)), Ok(Some(second))) = (self.state.teams.get(&team_a).await, self.state.teams.get(&team_b).await) else {
+                    panic!("Unknown team {} or {}", team_a, team_b);
+                };
+                if team_a == team_b || first.members.iter().any(|member| second.members.contains(member)) {
+                    panic!("A team war needs two teams without shared members");
+                }
+                
+                let starts_at = starts_at.max(self.runtime.system_time().micros());
+                let duration = self.runtime.application_parameters().team_war_duration_secs * 1_000_000;
+                let war_id = *self.state.team_war_counter.get();
+                self.state.team_war_counter.set(war_id + 1);
+                let war = TeamWar {
+                    war_id,
+                    team_a,
+                    team_b,
+                    starts_at,
+                    ends_at: starts_at + duration,
+                    team_a_score: 0,
+                    team_b_score: 0,
+                    state: TeamWarState::Open,
+                    winner: None,
+                };
+                let _ = self.state.open_team_wars.insert(&war_id);
+                log_event!(self, Info, "TEAM", "Scheduled war {} between teams {} and {}", war_id, team_a, team_b);
+                self.publish_team_war(war).await;
+            }
+            
+            Operation::CreateChallenge { opponent, wager } => {
+                let current_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                if opponent == current_chain {
+                    panic!("Players cannot challenge themselves");
+                }
+                
+                // Stake the wager up front; the leaderboard chain holds it until settlement
+                self.debit_coins(wager);
+                let message = GameMessage::ChallengeCreated { challenger: current_chain, opponent, wager };
+                self.send_to_chain(leaderboard_chain_id, message).await;
+                log_event!(self, Info, "CHALLENGE", "Challenged {:?} for {} coins", opponent, wager);
+            }
+            
+            Operation::AcceptChallenge { challenge_id } => {
+                let current_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                let challenge = self.state.my_challenges.get(&challenge_id).await
+                    .ok()
+                    .flatten()
+                    .expect("Unknown challenge");
+                if challenge.opponent.chain_id != current_chain || challenge.state != ChallengeState::AwaitingOpponent {
+                    panic!("This challenge cannot be accepted by this chain");
+                }
+                
+                self.debit_coins(challenge.wager);
+                let message = GameMessage::ChallengeAccepted { challenge_id, player_chain: current_chain };
+                self.send_to_chain(leaderboard_chain_id, message).await;
+            }
+            
+            Operation::CommitChallengeResult { challenge_id, commitment } => {
+                let current_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                
+                let message = GameMessage::ChallengeCommit { challenge_id, player_chain: current_chain, commitment };
+                self.send_to_chain(leaderboard_chain_id, message).await;
+            }
+            
+            Operation::RevealChallengeResult { challenge_id, session_id, salt } => {
+                let current_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                
+                // The revealed score must come from a game actually finished on this chain
+                let session = self.state.session(&session_id).await
+                    .expect("Unknown session");
+                if session.player != current_chain || session.state != GameState::Finished {
+                    panic!("Only finished sessions of this chain can be revealed");
+                }
+                
+                let message = GameMessage::ChallengeReveal {
+                    challenge_id,
+                    player_chain: current_chain,
+                    session_id,
+                    started_at: session.start_time,
+                    score: session.candies_collected,
+                    salt,
+                };
+                self.send_to_chain(leaderboard_chain_id, message).await;
+            }
+            
+            Operation::ClaimChallengeTimeout { challenge_id } => {
+                let current_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                
+                let message = GameMessage::ChallengeTimeoutClaim { challenge_id, player_chain: current_chain };
+                self.send_to_chain(leaderboard_chain_id, message).await;
+            }
+            
+            Operation::JoinMatchmaking => {
+                let current_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                
+                self.send_to_chain(leaderboard_chain_id, GameMessage::JoinMatchmaking { player_chain: current_chain }).await;
+            }
+            
+            Operation::LeaveMatchmaking => {
+                let current_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                let queued = self.state.my_matchmaking.get().as_ref()
+                    .is_some_and(|status| status.state == MatchmakingState::Queued);
+                if !queued {
+                    panic!("This chain is not in the matchmaking queue");
+                }
+                
+                self.send_to_chain(leaderboard_chain_id, GameMessage::LeaveMatchmaking { player_chain: current_chain }).await;
+            }
+            
+            Operation::PublishReplay { session_id, move_log } => {
+                let mut session = self.state.session(&session_id).await
+                    .expect("Unknown game session");
+                if session.state != GameState::Finished {
+                    panic!("Replays can only be published for finished games");
+                }
+                // Without an anchored hash, nothing ties the uploaded log to the game that was played
+                let anchored_hash = session.move_log_hash.as_ref()
+                    .expect("No move log hash was anchored for this session, so its replay cannot be published");
+                if move_log_hash(&move_log) != *anchored_hash {
+                    panic!("Replay does not match the move log hash anchored at EndGame");
+                }
+                
+                // Large logs go to blob storage; the session only keeps the blob hash
+                let replay_blob = self.runtime.publish_data_blob(move_log);
+                session.replay_blob = Some(replay_blob);
+                let is_record = session.is_record;
+                self.state.save_session(session).await;
+                log_event!(self, Info, "REPLAY", "Published replay of session {}", session_id);
+                
+                // Record games are anchored on the leaderboard chain, which can then replay without a new upload
+                if is_record {
+                    let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                        .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                    let player_chain = self.runtime.chain_id();
+                    self.send_to_chain(leaderboard_chain_id, GameMessage::ReplayPublished { session_id, player_chain, replay_blob }).await;
+                }
+            }
+            
+            Operation::RequestGhost { session_id } => {
+                if self.state.ghost(&session_id).await.is_some() {
+                    panic!("The replay of session {} can already be raced", session_id);
+                }
+                let current_chain = self.runtime.chain_id();
+                let leaderboard_chain_id = self.state.leaderboard_chain_id.get()
+                    .expect("No leaderboard chain configured. Please use SetupLeaderboard operation first");
+                
+                let message = GameMessage::GhostRequest { session_id, player_chain: current_chain };
+                self.send_to_chain(leaderboard_chain_id, message).await;
+            }
+            
+            Operation::VerifyReplay { session_id, move_log } => {
+                if !*self.state.is_leaderboard_chain.get() {
+                    panic!("Replays can only be verified on the leaderboard chain");
+                }
+                let mut anchor = self.state.move_log_anchors.get(&session_id).await
+                    .ok()
+                    .flatten()
+                    .expect("No move log anchored for this session");
+                let move_log = move_log.or(anchor.replay_blob)
+                    .expect("No replay published for this session");
+                
+                // The published log must be the one anchored at EndGame before it is replayed
+                let log = self.runtime.read_data_blob(move_log);
+                let verdict = if move_log_hash(&log) != anchor.move_log_hash {
+                    log_event!(self, Warn, "REPLAY", "Published log for {} does not match its anchored hash", session_id);
+                    anchor.replayed_candies = None;
+                    ReplayVerdict::Rejected
+                } else {
+                    let board = match &anchor.map_hash {
+                        Some(hash) => self.state.custom_maps.get(hash).await.ok().flatten().map(|custom_map| custom_map.board),
+                        None => self.level_layout(anchor.level_id).await.map(|level| level.board),
+                    }
+                    .unwrap_or_default();
+                    match snake_engine::replay_candies(board, anchor.seed, &log) {
+                        Ok(candies) => {
+                            anchor.replayed_candies = Some(candies);
+                            if candies == anchor.candies_collected {
+                                ReplayVerdict::Confirmed
+                            } else {
+                                ReplayVerdict::Rejected
+                            }
+                        }
+                        Err(error) => {
+                            log_event!(self, Warn, "REPLAY", "Could not replay {}: {:?}", session_id, error);
+                            anchor.replayed_candies = None;
+                            ReplayVerdict::Rejected
+                        }
+                    }
+                };
+                log_event!(self, Info, "REPLAY", "Session {} claimed {} candies, replay verdict: {:?}",
+                    session_id, anchor.candies_collected, verdict);
+                // Failing a replay counts as a strike, once per session
+                let newly_rejected = verdict == ReplayVerdict::Rejected && anchor.verdict != ReplayVerdict::Rejected;
+                let was_confirmed = anchor.verdict == ReplayVerdict::Confirmed;
+                anchor.verdict = verdict;
+                let player_chain = anchor.player_chain;
+                let candies_collected = anchor.candies_collected;
+                let _ = self.state.move_log_anchors.insert(&session_id, anchor);
+                
+                // Flag the leaderboard entry when the replayed game is the player's standing record
+                if let Ok(Some(mut stats)) = self.state.player_stats.get(&player_chain).await {
+                    if stats.highest_score == candies_collected {
+                        stats.replay_verdict = verdict;
+                        let _ = self.state.player_stats.insert(&player_chain, stats);
+                        self.rebuild_global_leaderboard().await;
+                    }
+                }
+                
+                // Only games whose replay was confirmed count towards the activity needed to appear on the board
+                if was_confirmed != (verdict == ReplayVerdict::Confirmed) {
+                    let verified_games = self.state.verified_games.get(&player_chain).await.ok().flatten().unwrap_or_default();
+                    let verified_games = if was_confirmed { verified_games.saturating_sub(1) } else { verified_games + 1 };
+                    let _ = self.state.verified_games.insert(&player_chain, verified_games);
+                }
+                if newly_rejected {
+                    self.add_strike(player_chain, format!("Replay of session {} was rejected", session_id), session_id, candies_collected).await;
+                }
+            }
+        }
+        OperationResponse::Ok
+    }
+    
+    /// Apply a delivered, non-bouncing message
+    async fn handle_message(&mut self, message: GameMessage) {
+        self.increment_metric(metrics::MESSAGES_PROCESSED, 1).await;
+        match message {
+            GameMessage::SpectatorJoined { spectator } => {
+                let _ = self.state.spectators.insert(&spectator);
+                // Bring the new spectator up to date with the game in progress
+                if let Some(session_id) = self.state.my_current_session.get().clone() {
+                    if let Ok(Some(live)) = self.state.live_sessions.get(&session_id).await {
+                        self.runtime.send_message(spectator, GameMessage::LiveResync(live).into());
+                    }
+                }
+                log_event!(self, Info, "LIVE", "Chain {:?} is now spectating", spectator);
+            }
+            
+            GameMessage::SpectatorLeft { spectator } => {
+                let _ = self.state.spectators.remove(&spectator);
+                log_event!(self, Info, "LIVE", "Chain {:?} stopped spectating", spectator);
+            }
+            
+            GameMessage::LiveDelta(delta) => {
+                let Ok(Some(mut live)) = self.state.live_sessions.get(&delta.session_id).await else {
+                    // The start of this session never reached us
+                    let spectator = self.runtime.chain_id();
+                    self.runtime.send_message(delta.player, GameMessage::ResyncRequest { session_id: delta.session_id, spectator }.into());
+                    return;
+                };
+                if delta.to_tick <= live.ticks {
+                    // Already covered by a resync
+                    return;
+                }
+                if live.apply_delta(&delta) {
+                    let _ = self.state.live_sessions.insert(&delta.session_id, live);
+                } else {
+                    log_event!(self, Warn, "LIVE", "Gap in session {}: at tick {}, delta starts at {}; requesting resync",
+                        delta.session_id, live.ticks, delta.from_tick);
+                    let spectator = self.runtime.chain_id();
+                    self.runtime.send_message(delta.player, GameMessage::ResyncRequest { session_id: delta.session_id, spectator }.into());
+                }
+            }
+            
+            GameMessage::ResyncRequest { session_id, spectator } => {
+                match self.state.live_sessions.get(&session_id).await {
+                    Ok(Some(live)) if self.state.live_engines.contains_key(&session_id).await.unwrap_or(false) => {
+                        self.runtime.send_message(spectator, GameMessage::LiveResync(live).into());
+                    }
+                    _ => reject_message!(self, "LIVE", "Ignoring resync for session {}, which is not live on this chain", session_id),
+                }
+            }
+            
+            GameMessage::LiveResync(live) => {
+                let session_id = live.session_id.clone();
+                let _ = self.state.live_sessions.insert(&session_id, live);
+            }
+            
+            GameMessage::StartGame { .. } => {
+                // Ignore StartGame messages on all chains as sessions are only stored locally
+                reject_message!(self, "MESSAGE", "Ignoring StartGame message - sessions are stored locally only");
+            }
+            
+            GameMessage::CandyCollected { session_id, player_chain, tick, started_at } => {
+                log_event!(self, Debug, "MESSAGE", "Processing CandyCollected from player chain {:?}", player_chain);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring CandyCollected message");
+                    return;
+                }
+                
+                // Candy messages are the only signal of play between records, so use them to track activity
+                let timestamp = self.runtime.system_time().micros();
+                let _ = self.state.player_last_active.insert(&player_chain, timestamp);
+                log_event!(self, Debug, "MESSAGE", "Player chain {:?} collected a candy", player_chain);
+                
+                if self.state.suspect_sessions.contains_key(&session_id).await.unwrap_or(false) {
+                    return;
+                }
+                
+                // Keep a running count for the player's latest session only, so tallies cannot pile up
+                let mut tally = self.state.candy_tallies.get(&player_chain).await
+                    .ok()
+                    .flatten()
+                    .filter(|tally| tally.session_id == session_id)
+                    .unwrap_or_else(|| CandyTally { session_id: session_id.clone(), candies: 0, last_tick: 0 });
+                tally.candies += 1;
+                tally.last_tick = tally.last_tick.max(tick);
+                let grace_ticks = self.runtime.application_parameters().candy_rate_grace_ticks;
+                let violation = implausible_candy_rate(tally.candies, tally.last_tick, timestamp.saturating_sub(started_at), grace_ticks);
+                let _ = self.state.candy_tallies.insert(&player_chain, tally);
+                if let Some(reason) = violation {
+                    self.mark_session_suspect(session_id, player_chain, reason).await;
+                }
+            }
+            
+            GameMessage::GameFinished { session_id, player_chain, candies_collected, is_new_record, move_log_hash, seed, level_id, map_hash, points, best_combo, match_id, ticks, started_at } => {
+                log_event!(self, Debug, "MESSAGE", "Processing GameFinished: from {:?} with {} candies, {} points (new record: {})", 
+                    player_chain, candies_collected, points, is_new_record);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring GameFinished message");
+                    return;
+                }
+                
+                let elapsed_micros = self.runtime.system_time().micros().saturating_sub(started_at);
+                let grace_ticks = self.runtime.application_parameters().candy_rate_grace_ticks;
+                let admission = game_finished_admission(candies_collected, ticks, elapsed_micros, grace_ticks, is_new_record, match_id);
+                if let ResultAdmission::Implausible(reason) = &admission {
+                    self.mark_session_suspect(session_id.clone(), player_chain, reason.clone()).await;
+                }
+                
+                // A result from a merged or banned chain, or whose pace is impossible, does not count anywhere
+                if let Some(reason) = self.reported_score_rejection(player_chain, &session_id, candies_collected, ticks, started_at).await {
+                    reject_message!(self, "VALIDATION", "Ignoring result of session {} from {:?}: {}", session_id, player_chain, reason);
+                    self.send_to_chain(player_chain, GameMessage::ResultRejected { session_id, reason }).await;
+                    return;
+                }
+                
+                if let Some(match_id) = match_id {
+                    self.record_team_match_score(match_id, player_chain, candies_collected).await;
+                    if admission == ResultAdmission::MatchOnly {
+                        // Team games only reach this chain for the match; personal stats wait for a record
+                        self.send_to_chain(player_chain, GameMessage::ResultReceived { session_id }).await;
+                        return;
+                    }
+                }
+                
+                // Keep the move log hash so the record can be replayed if it is disputed
+                if let Some(move_log_hash) = move_log_hash {
+                    let anchor = MoveLogAnchor {
+                        player_chain,
+                        session_id: session_id.clone(),
+                        candies_collected,
+                        move_log_hash,
+                        seed,
+                        level_id,
+                        map_hash,
+                        anchored_at: self.runtime.system_time().micros(),
+                        verdict: ReplayVerdict::Unverified,
+                        replayed_candies: None,
+                        replay_blob: None,
+                    };
+                    let _ = self.state.move_log_anchors.insert(&session_id, anchor);
+                }
+                
+                // Update leaderboard stats only (no session tracking on leaderboard chain)
+                if let Err(reason) = self.update_leaderboard_stats(player_chain, candies_collected, is_new_record, best_combo, ticks).await {
+                    self.send_to_chain(player_chain, GameMessage::ResultRejected { session_id, reason }).await;
+                    return;
+                }
+                // Results of chains below the activity gate are kept, but only ranked once it is met
+                if self.is_eligible(player_chain).await {
+                    self.send_to_chain(player_chain, GameMessage::ResultReceived { session_id: session_id.clone() }).await;
+                } else {
+                    let reason = "Recorded, but not ranked until enough games have a confirmed replay".to_string();
+                    self.send_to_chain(player_chain, GameMessage::ResultRejected { session_id: session_id.clone(), reason }).await;
+                }
+                
+                // Tell followers about records that made it onto the player's stats
+                let stats = self.state.player_stats.get(&player_chain).await.ok().flatten();
+                if is_new_record && stats.is_some_and(|stats| stats.highest_score == candies_collected) {
+                    self.notify_followers(player_chain, candies_collected).await;
+                }
+            }
+            
+            GameMessage::UpdateLeaderboard { player_chain, candies_collected, is_new_record } => {
+                log_event!(self, Debug, "MESSAGE", "Processing UpdateLeaderboard for {:?}, candies: {}, new record: {}", 
+                    player_chain, candies_collected, is_new_record);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring UpdateLeaderboard message");
+                    return;
+                }
+                
+                let _ = self.update_leaderboard_stats(player_chain, candies_collected, is_new_record, 0, 0).await;
+            }
+            
+            GameMessage::UpdatePlayerName { player_chain, player_name } => {
+                log_event!(self, Debug, "MESSAGE", "Processing UpdatePlayerName for {:?}: '{}'", player_chain, player_name);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring UpdatePlayerName message");
+                    return;
+                }
+                
+                // Player chains on older bytecode may send names that were never normalized
+                let (name, rejection) = match display_name(&player_name) {
+                    Ok(name) => {
+                        let rejection = self.name_rejection(player_chain, &name).await;
+                        (name, rejection)
+                    }
+                    Err(reason) => (player_name.clone(), Some(reason)),
+                };
+                if let Some(reason) = rejection {
+                    reject_message!(self, "MESSAGE", "Rejected name '{}' for {:?}: {}", player_name, player_chain, reason);
+                    self.send_to_chain(player_chain, GameMessage::NameRejected { name: player_name, reason }).await;
+                    return;
+                }
+                
+                // Store the player name mapping
+                self.send_to_chain(player_chain, GameMessage::NameAccepted { name: player_name }).await;
+                self.record_player_name(player_chain, name).await;
+                log_event!(self, Info, "MESSAGE", "Updated player name for chain {:?}", player_chain);
+            }
+            
+            GameMessage::HistorySync { player_chain, summaries } => {
+                log_event!(self, Debug, "MESSAGE", "Processing HistorySync for {:?}: {} sessions", player_chain, summaries.len());
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring HistorySync message");
+                    return;
+                }
+                
+                let mut history = self.state.player_histories.get(&player_chain).await.ok().flatten().unwrap_or_default();
+                history.extend(summaries);
+                let excess = history.len().saturating_sub(MAX_SYNCED_HISTORY);
+                history.drain(..excess);
+                let _ = self.state.player_histories.insert(&player_chain, history);
+            }
+            
+            GameMessage::HistoryOptOut { player_chain } => {
+                log_event!(self, Debug, "MESSAGE", "Processing HistoryOptOut for {:?}", player_chain);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring HistoryOptOut message");
+                    return;
+                }
+                
+                let _ = self.state.player_histories.remove(&player_chain);
+            }
+            
+            GameMessage::NameRejected { name, reason } => {
+                reject_message!(self, "MESSAGE", "Player name '{}' rejected: {}", name, reason);
+                self.clear_pending_name_update(&name);
+                if self.state.my_player_name.get().as_deref() == Some(name.as_str()) {
+                    self.state.my_player_name.set(None);
+                    // A refused name does not start the cooldown
+                    self.state.my_name_changed_at.set(None);
+                }
+                for owner in self.state.owner_profiles.indices().await.unwrap_or_default() {
+                    if let Ok(Some(mut profile)) = self.state.owner_profiles.get(&owner).await {
+                        if profile.player_name.as_deref() == Some(name.as_str()) {
+                            profile.player_name = None;
+                            profile.name_changed_at = None;
+                            let _ = self.state.owner_profiles.insert(&owner, profile);
+                        }
+                    }
+                }
+            }
+            
+            GameMessage::AvatarUpdated { player_chain, avatar } => {
+                log_event!(self, Debug, "MESSAGE", "Processing AvatarUpdated for {:?}", player_chain);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring AvatarUpdated message");
+                    return;
+                }
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if origin != player_chain {
+                    reject_message!(self, "MESSAGE", "Ignoring avatar from {:?} on behalf of {:?}", origin, player_chain);
+                    return;
+                }
+                
+                if let Some(avatar) = avatar {
+                    let _ = self.state.player_avatars.insert(&player_chain, avatar);
+                } else {
+                    let _ = self.state.player_avatars.remove(&player_chain);
+                }
+            }
+            
+            GameMessage::CountryUpdated { player_chain, country } => {
+                log_event!(self, Debug, "MESSAGE", "Processing CountryUpdated for {:?}: {:?}", player_chain, country);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring CountryUpdated message");
+                    return;
+                }
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if origin != player_chain {
+                    reject_message!(self, "MESSAGE", "Ignoring country from {:?} on behalf of {:?}", origin, player_chain);
+                    return;
+                }
+                
+                match country.as_deref().and_then(country_code) {
+                    Some(country) => {
+                        let _ = self.state.player_countries.insert(&player_chain, country);
+                    }
+                    None => {
+                        let _ = self.state.player_countries.remove(&player_chain);
+                    }
+                }
+                
+                // Refresh the boards if this player is already ranked
+                if let Ok(true) = self.state.player_stats.contains_key(&player_chain).await {
+                    self.rebuild_global_leaderboard().await;
+                }
+            }
+            
+            GameMessage::IssueAccountLink { old_chain, new_chain, token } => {
+                log_event!(self, Debug, "MESSAGE", "Processing IssueAccountLink from {:?} to {:?}", old_chain, new_chain);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring IssueAccountLink message");
+                    return;
+                }
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if origin != old_chain {
+                    reject_message!(self, "LINK", "Ignoring account link from {:?} on behalf of {:?}", origin, old_chain);
+                    return;
+                }
+                if let Ok(Some(tombstone)) = self.state.account_tombstones.get(&old_chain).await {
+                    reject_message!(self, "LINK", "{:?} was already merged into {:?}", old_chain, tombstone.merged_into);
+                    return;
+                }
+                
+                // A newer link replaces any earlier one from the same chain
+                let link = AccountLink {
+                    old_chain,
+                    new_chain,
+                    token,
+                    expires_at: self.runtime.system_time().micros() + ACCOUNT_LINK_TTL_SECS * 1_000_000,
+                };
+                let _ = self.state.account_links.insert(&old_chain, link);
+            }
+            
+            GameMessage::AccountLinkToken { old_chain, token } => {
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if origin != old_chain {
+                    reject_message!(self, "LINK", "Ignoring link token from {:?} on behalf of {:?}", origin, old_chain);
+                    return;
+                }
+                log_event!(self, Info, "LINK", "Received a link token from {:?}", old_chain);
+                let _ = self.state.received_link_tokens.insert(&old_chain, token);
+            }
+            
+            GameMessage::MergeAccounts { old_chain, new_chain, token } => {
+                log_event!(self, Debug, "MESSAGE", "Processing MergeAccounts of {:?} into {:?}", old_chain, new_chain);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring MergeAccounts message");
+                    return;
+                }
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if origin != new_chain {
+                    reject_message!(self, "LINK", "Ignoring merge request from {:?} on behalf of {:?}", origin, new_chain);
+                    return;
+                }
+                let Some(link) = self.state.account_links.get(&old_chain).await.ok().flatten() else {
+                    reject_message!(self, "LINK", "{:?} has not issued an account link", old_chain);
+                    return;
+                };
+                if link.new_chain != new_chain || link.token != token {
+                    reject_message!(self, "LINK", "The link token from {:?} does not match its link", new_chain);
+                    return;
+                }
+                let now = self.runtime.system_time().micros();
+                if now > link.expires_at {
+                    let _ = self.state.account_links.remove(&old_chain);
+                    reject_message!(self, "LINK", "The account link from {:?} has expired", old_chain);
+                    return;
+                }
+                // Moving to a new chain does not shed a ban, and a merged chain cannot take in others
+                if self.is_banned(old_chain, now).await {
+                    reject_message!(self, "LINK", "{:?} is banned and cannot be merged", old_chain);
+                    return;
+                }
+                if self.state.account_tombstones.contains_key(&new_chain).await.unwrap_or(false) {
+                    reject_message!(self, "LINK", "{:?} was itself merged into another chain", new_chain);
+                    return;
+                }
+                
+                self.merge_accounts(old_chain, new_chain).await;
+            }
+            
+            GameMessage::AccountMerged { old_chain, new_chain } => {
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if Some(origin) != *self.state.leaderboard_chain_id.get() {
+                    reject_message!(self, "LINK", "Ignoring AccountMerged from {:?}, which is not the leaderboard chain", origin);
+                    return;
+                }
+                
+                if self.runtime.chain_id() == old_chain {
+                    self.state.merged_into.set(Some(new_chain));
+                    log_event!(self, Info, "LINK", "This chain's history now belongs to {:?}", new_chain);
+                } else {
+                    log_event!(self, Info, "LINK", "The history of {:?} was merged into this chain", old_chain);
+                }
+            }
+            
+            GameMessage::ResultReceived { session_id } => {
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if Some(origin) != *self.state.leaderboard_chain_id.get() {
+                    reject_message!(self, "MESSAGE", "Ignoring ResultReceived from {:?}, which is not the leaderboard chain", origin);
+                    return;
+                }
+                log_event!(self, Debug, "MESSAGE", "Leaderboard chain received the result of session {}", session_id);
+                let _ = self.state.pending_results.remove(&session_id);
+                self.set_leaderboard_status(&session_id, LeaderboardStatus::Acknowledged, None).await;
+            }
+            
+            GameMessage::ResultRejected { session_id, reason } => {
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if Some(origin) != *self.state.leaderboard_chain_id.get() {
+                    reject_message!(self, "MESSAGE", "Ignoring ResultRejected from {:?}, which is not the leaderboard chain", origin);
+                    return;
+                }
+                log_event!(self, Warn, "MESSAGE", "Leaderboard chain rejected the result of session {}: {}", session_id, reason);
+                let _ = self.state.pending_results.remove(&session_id);
+                self.set_leaderboard_status(&session_id, LeaderboardStatus::Rejected, Some(reason)).await;
+            }
+            
+            GameMessage::NameAccepted { name } => {
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if Some(origin) != *self.state.leaderboard_chain_id.get() {
+                    reject_message!(self, "MESSAGE", "Ignoring NameAccepted from {:?}, which is not the leaderboard chain", origin);
+                    return;
+                }
+                log_event!(self, Debug, "MESSAGE", "Leaderboard chain accepted the name '{}'", name);
+                self.clear_pending_name_update(&name);
+            }
+            
+            GameMessage::UpdatePlayerLevel { player_chain, level } => {
+                log_event!(self, Debug, "MESSAGE", "Processing UpdatePlayerLevel for {:?}: level {}", player_chain, level);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring UpdatePlayerLevel message");
+                    return;
+                }
+                
+                let _ = self.state.player_levels.insert(&player_chain, level);
+                
+                // Refresh the board if this player is already ranked
+                if let Ok(true) = self.state.player_stats.contains_key(&player_chain).await {
+                    self.rebuild_global_leaderboard().await;
+                }
+            }
+            
+            GameMessage::AchievementUnlocked { player_chain, achievement, unlocked_at } => {
+                log_event!(self, Debug, "MESSAGE", "Processing AchievementUnlocked for {:?}: {:?}", player_chain, achievement);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring AchievementUnlocked message");
+                    return;
+                }
+                
+                let mut achievements = self.state.player_achievements.get(&player_chain).await.ok().flatten().unwrap_or_default();
+                if achievements.iter().all(|unlock| unlock.achievement != achievement) {
+                    achievements.push(AchievementUnlock { achievement, unlocked_at });
+                    let _ = self.state.player_achievements.insert(&player_chain, achievements);
+                }
+            }
+            
+            GameMessage::PrizeDonation { pool, sponsor_chain, sponsor_name, amount } => {
+                log_event!(self, Debug, "MESSAGE", "Processing PrizeDonation of {} from {:?} to pool '{}'", amount, sponsor_chain, pool);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring PrizeDonation message");
+                    return;
+                }
+                
+                self.record_donation(pool, sponsor_chain, sponsor_name, amount).await;
+            }
+            
+            GameMessage::RequestReferralCode { player_chain } => {
+                log_event!(self, Debug, "MESSAGE", "Processing RequestReferralCode from {:?}", player_chain);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring RequestReferralCode message");
+                    return;
+                }
+                
+                let code = self.mint_referral_code(player_chain).await;
+                self.runtime.send_message(player_chain, GameMessage::ReferralCodeAssigned { code }.into());
+            }
+            
+            GameMessage::LeaderboardSyncRequest { player_chain, limit } => {
+                log_event!(self, Debug, "MESSAGE", "Processing LeaderboardSyncRequest for the top {} from {:?}", limit, player_chain);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring LeaderboardSyncRequest message");
+                    return;
+                }
+                
+                let entries: Vec<LeaderboardEntry> = self.state.leaderboard_seen_by(player_chain).await
+                    .into_iter()
+                    .take(limit.min(MAX_LEADERBOARD_SYNC) as usize)
+                    .collect();
+                let version = *self.state.leaderboard_version.get();
+                self.runtime.send_message(player_chain, GameMessage::LeaderboardSnapshot { version, entries }.into());
+            }
+            
+            GameMessage::LeaderboardSubscribe { player_chain, limit } => {
+                log_event!(self, Debug, "MESSAGE", "Processing LeaderboardSubscribe for the top {} from {:?}", limit, player_chain);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring LeaderboardSubscribe message");
+                    return;
+                }
+                
+                // Subscribers start from the current board
+                let subscription = LeaderboardSubscription { limit: limit.min(MAX_LEADERBOARD_SYNC), last_pushed_at: None };
+                let _ = self.state.leaderboard_subscribers.insert(&player_chain, subscription);
+                self.push_leaderboard(player_chain).await;
+            }
+            
+            GameMessage::LeaderboardUnsubscribe { player_chain } => {
+                log_event!(self, Debug, "MESSAGE", "Processing LeaderboardUnsubscribe from {:?}", player_chain);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring LeaderboardUnsubscribe message");
+                    return;
+                }
+                
+                let _ = self.state.leaderboard_subscribers.remove(&player_chain);
+                let _ = self.state.pending_leaderboard_pushes.remove(&player_chain);
+            }
+            
+            GameMessage::Overtaken { by, by_name, old_rank, new_rank } => {
+                log_event!(self, Info, "LEADERBOARD", "Overtaken by {:?}, down from #{} to #{}", by, old_rank, new_rank);
+                let name = by_name.unwrap_or_else(|| "Anonymous".to_string());
+                self.push_notification(
+                    NotificationKind::Overtaken,
+                    format!("{} overtook you: you dropped from #{} to #{}", name, old_rank, new_rank),
+                    Some(by),
+                    Some((old_rank, new_rank)),
+                );
+            }
+            
+            GameMessage::FollowPlayer { follower, followed } => {
+                log_event!(self, Debug, "MESSAGE", "Processing FollowPlayer: {:?} follows {:?}", follower, followed);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring FollowPlayer message");
+                    return;
+                }
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if origin != follower {
+                    reject_message!(self, "FOLLOW", "Ignoring follow from {:?} on behalf of {:?}", origin, follower);
+                    return;
+                }
+                
+                let mut followers = self.state.followers.get(&followed).await.ok().flatten().unwrap_or_default();
+                if !followers.contains(&follower) {
+                    followers.push(follower);
+                    let _ = self.state.followers.insert(&followed, followers);
+                }
+            }
+            
+            GameMessage::UnfollowPlayer { follower, followed } => {
+                log_event!(self, Debug, "MESSAGE", "Processing UnfollowPlayer: {:?} unfollows {:?}", follower, followed);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring UnfollowPlayer message");
+                    return;
+                }
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if origin != follower {
+                    reject_message!(self, "FOLLOW", "Ignoring unfollow from {:?} on behalf of {:?}", origin, follower);
+                    return;
+                }
+                
+                let mut followers = self.state.followers.get(&followed).await.ok().flatten().unwrap_or_default();
+                followers.retain(|chain| *chain != follower);
+                if followers.is_empty() {
+                    let _ = self.state.followers.remove(&followed);
+                } else {
+                    let _ = self.state.followers.insert(&followed, followers);
+                }
+            }
+            
+            GameMessage::React { reactor, target, emote } => {
+                log_event!(self, Debug, "MESSAGE", "Processing React: {:?} on {:?} from {:?}", emote, target, reactor);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring React message");
+                    return;
+                }
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if origin != reactor || reactor == target {
+                    reject_message!(self, "MESSAGE", "Ignoring reaction from {:?} on behalf of {:?}", origin, reactor);
+                    return;
+                }
+                
+                // One emote per player and entry; a new one replaces the old
+                let mut reactions = self.state.reactions.get(&target).await.ok().flatten().unwrap_or_default();
+                match emote {
+                    Some(emote) => {
+                        reactions.insert(reactor, emote);
+                    }
+                    None => {
+                        reactions.remove(&reactor);
+                    }
+                }
+                if reactions.is_empty() {
+                    let _ = self.state.reactions.remove(&target);
+                } else {
+                    let _ = self.state.reactions.insert(&target, reactions);
+                }
+                
+                // Only the reacted-to row changes, so update it in place instead of rebuilding
+                let mut entries = self.state.leaderboard_entries().await;
+                if let Some(entry) = entries.iter_mut().find(|entry| entry.chain_id == target) {
+                    entry.reactions = self.reaction_counts(target).await;
+                    self.set_global_leaderboard(entries).await;
+                }
+            }
+            
+            GameMessage::TeamChat { message } => {
+                log_event!(self, Debug, "MESSAGE", "Processing TeamChat from {:?} to team {:?}", message.from, message.team_id);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring TeamChat message");
+                    return;
+                }
+                // Handled in place when the sender is this chain, so there is no origin then
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if origin != message.from {
+                    reject_message!(self, "CHAT", "Ignoring team chat from {:?} sent on behalf of {:?}", origin, message.from);
+                    return;
+                }
+                let team = match message.team_id {
+                    Some(team_id) => self.state.teams.get(&team_id).await.ok().flatten(),
+                    None => None,
+                };
+                let Some(team) = team.filter(|team| team.members.contains(&message.from)) else {
+                    reject_message!(self, "CHAT", "{:?} is not a member of team {:?}", message.from, message.team_id);
+                    return;
+                };
+                if !self.admit_chat(message.from).await {
+                    return;
+                }
+                
+                for member in team.members {
+                    if member != message.from {
+                        self.send_to_chain(member, GameMessage::Chat { message: message.clone() }).await;
+                    }
+                }
+            }
+            
+            GameMessage::Chat { message } => {
+                // Direct messages come from their sender; team messages are relayed by the leaderboard chain
+                let origin = self.runtime.message_origin_chain_id();
+                if origin == Some(message.from) {
+                    if !self.admit_chat(message.from).await {
+                        return;
+                    }
+                } else if message.team_id.is_none() || origin != *self.state.leaderboard_chain_id.get() {
+                    reject_message!(self, "CHAT", "Ignoring chat from {:?} sent on behalf of {:?}", origin, message.from);
+                    return;
+                }
+                
+                log_event!(self, Debug, "CHAT", "Received a chat message from {:?}", message.from);
+                self.store_chat(message);
+            }
+            
+            GameMessage::FollowedRecord { player_chain, player_name, candies_collected } => {
+                log_event!(self, Info, "MESSAGE", "Followed player {:?} set a new record of {} candies", player_chain, candies_collected);
+                let name = player_name.unwrap_or_else(|| "Anonymous".to_string());
+                self.push_notification(
+                    NotificationKind::FollowedRecord,
+                    format!("{} set a new record: {} candies", name, candies_collected),
+                    Some(player_chain),
+                    None,
+                );
+            }
+            
+            GameMessage::LeaderboardSnapshot { version, entries } => {
+                // Only the configured leaderboard chain may overwrite the local copy
+                let origin = self.runtime.message_origin_chain_id();
+                if *self.state.is_leaderboard_chain.get() || origin != *self.state.leaderboard_chain_id.get() {
+                    reject_message!(self, "MESSAGE", "Ignoring LeaderboardSnapshot from {:?}, which is not the leaderboard chain", origin);
+                    return;
+                }
+                // Snapshots can cross; never go back to an older board
+                if version < *self.state.leaderboard_version.get() {
+                    log_event!(self, Debug, "MESSAGE", "Ignoring LeaderboardSnapshot version {} older than the local copy", version);
+                    return;
+                }
+                
+                log_event!(self, Info, "LEADERBOARD", "Received {} rows of leaderboard version {}", entries.len(), version);
+                let previous = self.state.leaderboard_entries().await;
+                self.state.write_leaderboard(&previous, &entries);
+                self.state.leaderboard_version.set(version);
+                let timestamp = self.runtime.system_time().micros();
+                self.state.leaderboard_synced_at.set(Some(timestamp));
+            }
+            
+            GameMessage::ReferralCodeAssigned { code } => {
+                log_event!(self, Info, "MESSAGE", "Received referral code '{}'", code);
+                self.state.my_referral_code.set(Some(code));
+            }
+            
+            GameMessage::RedeemReferral { code, referee_chain } => {
+                log_event!(self, Debug, "MESSAGE", "Processing RedeemReferral of '{}' by {:?}", code, referee_chain);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring RedeemReferral message");
+                    return;
+                }
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if origin != referee_chain {
+                    reject_message!(self, "REFERRAL", "Ignoring redemption from {:?} on behalf of {:?}", origin, referee_chain);
+                    return;
+                }
+                
+                let referrer = self.state.referral_codes.get(&code).await.ok().flatten();
+                let rejection = match referrer {
+                    None => Some("Unknown referral code".to_string()),
+                    Some(referrer) if referrer == referee_chain => Some("Players cannot refer themselves".to_string()),
+                    Some(_) if matches!(self.state.referrals.contains_key(&referee_chain).await, Ok(true)) => {
+                        Some("This player has already been referred".to_string())
+                    }
+                    Some(_) if matches!(self.state.player_stats.contains_key(&referee_chain).await, Ok(true)) => {
+                        Some("Only new players can be referred".to_string())
+                    }
+                    Some(_) => None,
+                };
+                
+                let reply = match (rejection, referrer) {
+                    (None, Some(referrer)) => {
+                        let _ = self.state.referrals.insert(&referee_chain, ReferralRecord {
+                            code: code.clone(),
+                            referrer,
+                            referee: referee_chain,
+                            rewarded: false,
+                        });
+                        log_event!(self, Info, "REFERRAL", "{:?} referred {:?} with code '{}'", referrer, referee_chain, code);
+                        GameMessage::ReferralAccepted { code }
+                    }
+                    (reason, _) => {
+                        let reason = reason.unwrap_or_default();
+                        reject_message!(self, "REFERRAL", "Rejected code '{}' for {:?}: {}", code, referee_chain, reason);
+                        GameMessage::ReferralRejected { code, reason }
+                    }
+                };
+                self.send_to_chain(referee_chain, reply).await;
+            }
+            
+            GameMessage::ReferralAccepted { code } => {
+                log_event!(self, Info, "MESSAGE", "Referral code '{}' accepted", code);
+                if let Some(mut referral) = self.state.my_referral.get().clone() {
+                    referral.state = ReferralState::Accepted;
+                    self.state.my_referral.set(Some(referral));
+                    // Games finished while the redemption was in flight still count
+                    self.advance_referral();
+                }
+            }
+            
+            GameMessage::ReferralRejected { code, reason } => {
+                reject_message!(self, "MESSAGE", "Referral code '{}' rejected: {}", code, reason);
+                if let Some(mut referral) = self.state.my_referral.get().clone() {
+                    referral.state = ReferralState::Rejected;
+                    referral.rejection_reason = Some(reason);
+                    self.state.my_referral.set(Some(referral));
+                }
+            }
+            
+            GameMessage::ReferralQualified { referee_chain } => {
+                log_event!(self, Debug, "MESSAGE", "Processing ReferralQualified for {:?}", referee_chain);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring ReferralQualified message");
+                    return;
+                }
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if origin != referee_chain {
+                    reject_message!(self, "REFERRAL", "Ignoring qualification from {:?} on behalf of {:?}", origin, referee_chain);
+                    return;
+                }
+                
+                let Ok(Some(mut referral)) = self.state.referrals.get(&referee_chain).await else {
+                    reject_message!(self, "REFERRAL", "No referral recorded for {:?}", referee_chain);
+                    return;
+                };
+                if referral.rewarded {
+                    return;
+                }
+                referral.rewarded = true;
+                let referrer = referral.referrer;
+                let _ = self.state.referrals.insert(&referee_chain, referral);
+                
+                let coins = self.runtime.application_parameters().referral_reward_coins;
+                self.send_to_chain(referrer, GameMessage::ReferralReward { coins }).await;
+                self.send_to_chain(referee_chain, GameMessage::ReferralReward { coins }).await;
+                log_event!(self, Info, "REFERRAL", "Rewarded {:?} and {:?} with {} coins each", referrer, referee_chain, coins);
+            }
+            
+            GameMessage::ReferralReward { coins } => {
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if Some(origin) != *self.state.leaderboard_chain_id.get() {
+                    reject_message!(self, "REFERRAL", "Ignoring ReferralReward from {:?}, which is not the leaderboard chain", origin);
+                    return;
+                }
+                log_event!(self, Info, "MESSAGE", "Received referral reward of {} coins", coins);
+                self.credit_reward(RewardSource::Referral, "Referral".to_string(), coins);
+            }
+            
+            GameMessage::JoinBattleRoyale { event_id, player_chain } => {
+                log_event!(self, Debug, "MESSAGE", "Processing JoinBattleRoyale: {:?} joins event {}", player_chain, event_id);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring JoinBattleRoyale message");
+                    return;
+                }
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if origin != player_chain {
+                    reject_message!(self, "ROYALE", "Ignoring registration from {:?} on behalf of {:?}", origin, player_chain);
+                    return;
+                }
+                let now = self.runtime.system_time().micros();
+                let merged = self.state.account_tombstones.contains_key(&player_chain).await.unwrap_or(false);
+                if merged || self.is_banned(player_chain, now).await {
+                    reject_message!(self, "ROYALE", "{:?} is banned or merged and cannot join event {}", player_chain, event_id);
+                    return;
+                }
+                
+                let Ok(Some(mut event)) = self.state.battle_royales.get(&event_id).await else {
+                    reject_message!(self, "ROYALE", "Ignoring registration for unknown event {}", event_id);
+                    return;
+                };
+                if event.state != BattleRoyaleState::Registration || event.survivors.contains(&player_chain) {
+                    reject_message!(self, "ROYALE", "Registration of {:?} for event {} rejected", player_chain, event_id);
+                    return;
+                }
+                event.survivors.push(player_chain);
+                let _ = self.state.battle_royales.insert(&event_id, event);
+            }
+            
+            GameMessage::SubmitRoyaleScore { event_id, round, player_chain, session_id, candies_collected, ticks, started_at } => {
+                log_event!(self, Debug, "MESSAGE", "Processing SubmitRoyaleScore: {:?} scored {} in round {} of event {}",
+                    player_chain, candies_collected, round, event_id);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring SubmitRoyaleScore message");
+                    return;
+                }
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if origin != player_chain {
+                    reject_message!(self, "ROYALE", "Ignoring royale score from {:?} on behalf of {:?}", origin, player_chain);
+                    return;
+                }
+                // Royale scores pass the same checks as leaderboard results
+                if let Some(reason) = self.reported_score_rejection(player_chain, &session_id, candies_collected, ticks, started_at).await {
+                    reject_message!(self, "ROYALE", "Ignoring royale score of session {} from {:?}: {}", session_id, player_chain, reason);
+                    return;
+                }
+                if !self.is_eligible(player_chain).await {
+                    reject_message!(self, "ROYALE", "{:?} has not played enough verified games to score in battle royales", player_chain);
+                    return;
+                }
+                
+                let Ok(Some(mut event)) = self.state.battle_royales.get(&event_id).await else {
+                    return;
+                };
+                let now = self.runtime.system_time().micros();
+                let in_round = event.state == BattleRoyaleState::Running && event.round == round && now <= event.round_ends_at;
+                if !in_round || !event.survivors.contains(&player_chain) {
+                    reject_message!(self, "ROYALE", "Score from {:?} arrived outside round {} of event {}", player_chain, round, event_id);
+                    return;
+                }
+                
+                // A survivor may play several games in a round; the best one counts
+                match event.round_scores.iter_mut().find(|score| score.player_chain == player_chain) {
+                    Some(score) => score.candies_collected = score.candies_collected.max(candies_collected),
+                    None => event.round_scores.push(RoyaleScore { player_chain, candies_collected }),
+                }
+                let _ = self.state.battle_royales.insert(&event_id, event);
+            }
+            
+            GameMessage::BattleRoyaleRound { event_id, round, round_seed, round_ends_at } => {
+                log_event!(self, Info, "MESSAGE", "Round {} of battle royale {} has started", round, event_id);
+                let status = RoyaleStatus {
+                    event_id,
+                    round,
+                    round_seed,
+                    round_ends_at,
+                    eliminated_in_round: None,
+                    won: false,
+                };
+                let _ = self.state.my_battle_royales.insert(&event_id, status);
+            }
+            
+            GameMessage::BattleRoyaleEliminated { event_id, round } => {
+                log_event!(self, Info, "MESSAGE", "Eliminated from battle royale {} in round {}", event_id, round);
+                if let Ok(Some(mut status)) = self.state.my_battle_royales.get(&event_id).await {
+                    status.eliminated_in_round = Some(round);
+                    let _ = self.state.my_battle_royales.insert(&event_id, status);
+                }
+            }
+            
+            GameMessage::BattleRoyaleWon { event_id } => {
+                log_event!(self, Info, "MESSAGE", "Won battle royale {}", event_id);
+                if let Ok(Some(mut status)) = self.state.my_battle_royales.get(&event_id).await {
+                    status.won = true;
+                    let _ = self.state.my_battle_royales.insert(&event_id, status);
+                }
+            }
+            
+            GameMessage::RegisterTeam { name, members } => {
+                log_event!(self, Debug, "MESSAGE", "Processing RegisterTeam '{}' for {:?}", name, members);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring RegisterTeam message");
+                    return;
+                }
+                
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                let (Some(&leader), Some(&teammate)) = (members.first(), members.get(1)) else {
+                    reject_message!(self, "TEAM", "Ignoring team '{}' without two members", name);
+                    return;
+                };
+                if origin != leader {
+                    reject_message!(self, "TEAM", "Ignoring team registration from {:?} on behalf of {:?}", origin, leader);
+                    return;
+                }
+                
+                let team_id = *self.state.team_counter.get();
+                self.state.team_counter.set(team_id + 1);
+                let team = Team {
+                    team_id,
+                    name,
+                    members,
+                    matches_played: 0,
+                    wins: 0,
+                    total_score: 0,
+                    war_trophies: 0,
+                    treasury: 0,
+                    perks: Vec::new(),
+                };
+                // The team only exists once the teammate agrees to join it
+                let _ = self.state.pending_teams.insert(&team_id, team.clone());
+                self.send_to_chain(teammate, GameMessage::TeamInvite { team }).await;
+                log_event!(self, Info, "TEAM", "Team {} is waiting for {:?} to accept", team_id, teammate);
+            }
+            
+            GameMessage::TeamInvite { team } => {
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if Some(origin) != *self.state.leaderboard_chain_id.get() {
+                    reject_message!(self, "TEAM", "Ignoring TeamInvite from {:?}, which is not the leaderboard chain", origin);
+                    return;
+                }
+                log_event!(self, Info, "TEAM", "Invited to join team {} '{}'", team.team_id, team.name);
+                let team_id = team.team_id;
+                let _ = self.state.team_invites.insert(&team_id, team);
+            }
+            
+            GameMessage::AcceptTeam { team_id, player_chain } => {
+                log_event!(self, Debug, "MESSAGE", "Processing AcceptTeam for team {} from {:?}", team_id, player_chain);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring AcceptTeam message");
+                    return;
+                }
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                let Some(team) = self.state.pending_teams.get(&team_id).await.ok().flatten() else {
+                    reject_message!(self, "TEAM", "Team {} is not waiting for a teammate", team_id);
+                    return;
+                };
+                if origin != player_chain || team.members.get(1) != Some(&player_chain) {
+                    reject_message!(self, "TEAM", "{:?} was not invited to team {}", origin, team_id);
+                    return;
+                }
+                
+                let _ = self.state.pending_teams.remove(&team_id);
+                let _ = self.state.teams.insert(&team_id, team);
+                log_event!(self, Info, "TEAM", "Registered team {}", team_id);
+            }
+            
+            GameMessage::CreateTeamMatch { requester, team_a, team_b } => {
+                log_event!(self, Debug, "MESSAGE", "Processing CreateTeamMatch: team {} vs team {}", team_a, team_b);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring CreateTeamMatch message");
+                    return;
+                }
+                
+                let (Ok(Some(first)), Ok(Some(second))) = (self.state.teams.get(&team_a).await, self.state.teams.get(&team_b).await) else {
+                    reject_message!(self, "TEAM", "Ignoring match between unknown teams {} and {}", team_a, team_b);
+                    return;
+                };
+                // Only a member of either team can schedule the match, and nobody can play on both sides
+                let players: Vec<ChainId> = first.members.iter().chain(second.members.iter()).copied().collect();
+                let overlapping = first.members.iter().any(|member| second.members.contains(member));
+                if !players.contains(&requester) || overlapping {
+                    reject_message!(self, "TEAM", "Rejected match between teams {} and {} requested by {:?}", team_a, team_b, requester);
+                    return;
+                }
+                
+                // The team the requester does not play for has to accept the match
+                let awaiting_team = if first.members.contains(&requester) { team_b } else { team_a };
+                let match_id = *self.state.team_match_counter.get();
+                self.state.team_match_counter.set(match_id + 1);
+                let team_match = TeamMatch {
+                    match_id,
+                    team_a,
+                    team_b,
+                    players,
+                    scores: Vec::new(),
+                    team_a_score: 0,
+                    team_b_score: 0,
+                    state: TeamMatchState::Proposed,
+                    winner: None,
+                    created_at: self.runtime.system_time().micros(),
+                    awaiting_team: Some(awaiting_team),
+                };
+                let _ = self.state.proposed_team_matches.insert(&match_id);
+                self.publish_team_match(team_match).await;
+            }
+            
+            GameMessage::AcceptTeamMatch { match_id, player_chain } => {
+                log_event!(self, Debug, "MESSAGE", "Processing AcceptTeamMatch for match {} from {:?}", match_id, player_chain);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring AcceptTeamMatch message");
+                    return;
+                }
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if origin != player_chain {
+                    reject_message!(self, "TEAM", "Ignoring match acceptance from {:?} on behalf of {:?}", origin, player_chain);
+                    return;
+                }
+                let Some(mut team_match) = self.state.team_matches.get(&match_id).await.ok().flatten() else {
+                    reject_message!(self, "TEAM", "Ignoring acceptance of unknown team match {}", match_id);
+                    return;
+                };
+                let Some(awaiting_team) = team_match.awaiting_team.filter(|_| team_match.state == TeamMatchState::Proposed) else {
+                    reject_message!(self, "TEAM", "Team match {} is not waiting to be accepted", match_id);
+                    return;
+                };
+                let is_member = self.state.teams.get(&awaiting_team).await.ok().flatten()
+                    .is_some_and(|team| team.members.contains(&player_chain));
+                if !is_member {
+                    reject_message!(self, "TEAM", "{:?} cannot accept team match {} for team {}", player_chain, match_id, awaiting_team);
+                    return;
+                }
+                
+                team_match.state = TeamMatchState::Open;
+                team_match.awaiting_team = None;
+                let _ = self.state.proposed_team_matches.remove(&match_id);
+                log_event!(self, Info, "TEAM", "Team match {} was accepted by {:?}", match_id, player_chain);
+                self.publish_team_match(team_match).await;
+            }
+            
+            GameMessage::TeamWarScore { war_id, player_chain, session_id, candies_collected, ticks, started_at } => {
+                log_event!(self, Debug, "MESSAGE", "Processing TeamWarScore for war {} from {:?}", war_id, player_chain);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring TeamWarScore message");
+                    return;
+                }
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if origin != player_chain {
+                    reject_message!(self, "TEAM", "Ignoring war score from {:?} on behalf of {:?}", origin, player_chain);
+                    return;
+                }
+                // War points pass the same checks as leaderboard results
+                if let Some(reason) = self.reported_score_rejection(player_chain, &session_id, candies_collected, ticks, started_at).await {
+                    reject_message!(self, "TEAM", "Ignoring war score of session {} from {:?}: {}", session_id, player_chain, reason);
+                    return;
+                }
+                if !self.is_eligible(player_chain).await {
+                    reject_message!(self, "TEAM", "{:?} has not played enough verified games to score in team wars", player_chain);
+                    return;
+                }
+                self.record_team_war_score(war_id, player_chain, candies_collected).await;
+            }
+            
+            GameMessage::TeamContribution { team_id, player_chain, coins } => {
+                log_event!(self, Debug, "MESSAGE", "Processing TeamContribution of {} coins to team {} from {:?}", coins, team_id, player_chain);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring TeamContribution message");
+                    return;
+                }
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if origin != player_chain {
+                    reject_message!(self, "TEAM", "Ignoring contribution from {:?} on behalf of {:?}", origin, player_chain);
+                    return;
+                }
+                let team = self.state.teams.get(&team_id).await.ok().flatten()
+                    .filter(|team| team.members.contains(&player_chain));
+                let Some(mut team) = team else {
+                    reject_message!(self, "TEAM", "{:?} is not a member of team {}, refunding {} coins", player_chain, team_id, coins);
+                    self.send_to_chain(player_chain, GameMessage::TeamContributionRefunded { team_id, coins }).await;
+                    return;
+                };
+                
+                team.treasury += coins;
+                let balance_after = team.treasury;
+                let _ = self.state.teams.insert(&team_id, team);
+                let mut contributions = self.state.team_contributions.get(&team_id).await.ok().flatten().unwrap_or_default();
+                *contributions.entry(player_chain).or_default() += coins;
+                let _ = self.state.team_contributions.insert(&team_id, contributions);
+                self.state.team_ledger.push(TeamLedgerEntry {
+                    team_id,
+                    flow: TreasuryFlow::Inflow,
+                    coins,
+                    player_chain,
+                    perk: None,
+                    balance_after,
+                    timestamp: self.runtime.system_time().micros(),
+                });
+                log_event!(self, Info, "TEAM", "Team {} treasury received {} coins from {:?}", team_id, coins, player_chain);
+            }
+            
+            GameMessage::TeamContributionRefunded { team_id, coins } => {
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if Some(origin) != *self.state.leaderboard_chain_id.get() {
+                    reject_message!(self, "TEAM", "Ignoring TeamContributionRefunded from {:?}, which is not the leaderboard chain", origin);
+                    return;
+                }
+                log_event!(self, Info, "MESSAGE", "Contribution of {} coins to team {} was refunded", coins, team_id);
+                self.state.my_coins.set(*self.state.my_coins.get() + coins);
+            }
+            
+            GameMessage::BuyTeamPerk { team_id, leader, perk } => {
+                log_event!(self, Debug, "MESSAGE", "Processing BuyTeamPerk {:?} for team {} from {:?}", perk, team_id, leader);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring BuyTeamPerk message");
+                    return;
+                }
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                let Some(mut team) = self.state.teams.get(&team_id).await.ok().flatten() else {
+                    reject_message!(self, "TEAM", "Ignoring perk purchase for unknown team {}", team_id);
+                    return;
+                };
+                if origin != leader || team.leader() != Some(leader) {
+                    reject_message!(self, "TEAM", "Only the leader of team {} can spend its treasury", team_id);
+                    return;
+                }
+                if team.perks.contains(&perk) || team.treasury < perk.cost() {
+                    reject_message!(self, "TEAM", "Team {} already has {:?} or cannot afford it ({} coins)", team_id, perk, team.treasury);
+                    return;
+                }
+                
+                team.treasury -= perk.cost();
+                team.perks.push(perk);
+                let balance_after = team.treasury;
+                let _ = self.state.teams.insert(&team_id, team);
+                self.state.team_ledger.push(TeamLedgerEntry {
+                    team_id,
+                    flow: TreasuryFlow::Outflow,
+                    coins: perk.cost(),
+                    player_chain: leader,
+                    perk: Some(perk),
+                    balance_after,
+                    timestamp: self.runtime.system_time().micros(),
+                });
+                log_event!(self, Info, "TEAM", "Team {} bought {:?} for {} coins", team_id, perk, perk.cost());
+            }
+            
+            GameMessage::GhostRequest { session_id, player_chain } => {
+                log_event!(self, Debug, "MESSAGE", "Processing GhostRequest for {} from {:?}", session_id, player_chain);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring GhostRequest message");
+                    return;
+                }
+                
+                // Only level games with a published, unrejected replay can be raced
+                let Some(anchor) = self.state.move_log_anchors.get(&session_id).await.ok().flatten() else {
+                    reject_message!(self, "GHOST", "No anchored game {} to race", session_id);
+                    return;
+                };
+                let Some(ghost) = self.raceable_ghost(anchor).await else {
+                    reject_message!(self, "GHOST", "Game {} has no replay that can be raced", session_id);
+                    return;
+                };
+                self.runtime.send_message(player_chain, GameMessage::GhostReplay { ghost }.into());
+            }
+            
+            GameMessage::GhostReplay { ghost } => {
+                // Ghosts come from the anchors on the configured leaderboard chain
+                let origin = self.runtime.message_origin_chain_id();
+                if *self.state.is_leaderboard_chain.get() || origin != *self.state.leaderboard_chain_id.get() {
+                    reject_message!(self, "MESSAGE", "Ignoring GhostReplay from {:?}, which is not the leaderboard chain", origin);
+                    return;
+                }
+                log_event!(self, Info, "GHOST", "Received the ghost of {} by {:?}", ghost.session_id, ghost.player_chain);
+                let session_id = ghost.session_id.clone();
+                let _ = self.state.ghosts.insert(&session_id, ghost);
+            }
+            
+            GameMessage::TournamentScore { tournament_id, player_chain, session_id, candies_collected, move_log_hash, seed, level_id, map_hash, ticks, started_at } => {
+                log_event!(self, Debug, "MESSAGE", "Processing TournamentScore: {:?} scored {} in tournament {}",
+                    player_chain, candies_collected, tournament_id);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring TournamentScore message");
+                    return;
+                }
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if origin != player_chain {
+                    reject_message!(self, "TOURNAMENT", "Ignoring tournament score from {:?} on behalf of {:?}", origin, player_chain);
+                    return;
+                }
+                if !is_valid_move_log_hash(&move_log_hash) {
+                    reject_message!(self, "TOURNAMENT", "Ignoring tournament score of session {} without a valid move log hash", session_id);
+                    return;
+                }
+                if let Some(reason) = self.reported_score_rejection(player_chain, &session_id, candies_collected, ticks, started_at).await {
+                    reject_message!(self, "TOURNAMENT", "Ignoring tournament score of session {} from {:?}: {}", session_id, player_chain, reason);
+                    return;
+                }
+                
+                let Some(mut tournament) = self.state.tournaments.get(&tournament_id).await.ok().flatten() else {
+                    reject_message!(self, "TOURNAMENT", "Ignoring score for unknown tournament {}", tournament_id);
+                    return;
+                };
+                let now = self.runtime.system_time().micros();
+                if !tournament.is_open(now) {
+                    reject_message!(self, "TOURNAMENT", "Score from {:?} arrived outside the window of tournament {}", player_chain, tournament_id);
+                    return;
+                }
+                let position = match tournament.standings.iter().position(|entry| entry.player_chain == player_chain) {
+                    Some(position) => position,
+                    None if tournament.max_entrants > 0 && tournament.standings.len() >= tournament.max_entrants as usize => {
+                        reject_message!(self, "TOURNAMENT", "Tournament {} is full, turning away {:?}", tournament_id, player_chain);
+                        return;
+                    }
+                    None => {
+                        tournament.standings.push(TournamentEntry::new(tournament_id, player_chain));
+                        tournament.standings.len() - 1
+                    }
+                };
+                let entry = &mut tournament.standings[position];
+                if entry.attempts >= tournament.max_attempts {
+                    reject_message!(self, "TOURNAMENT", "{:?} has no attempts left in tournament {}", player_chain, tournament_id);
+                    return;
+                }
+                entry.attempts += 1;
+                if candies_collected > entry.best_score {
+                    entry.best_score = candies_collected;
+                    entry.best_at = now;
+                }
+                let _ = self.state.tournaments.insert(&tournament_id, tournament);
+                
+                // Keep the hash so the attempt can be replayed if it is disputed, unless a record
+                // of the same game already anchored it
+                if !self.state.move_log_anchors.contains_key(&session_id).await.unwrap_or(false) {
+                    let anchor = MoveLogAnchor {
+                        player_chain,
+                        session_id: session_id.clone(),
+                        candies_collected,
+                        move_log_hash,
+                        seed,
+                        level_id,
+                        map_hash,
+                        anchored_at: now,
+                        verdict: ReplayVerdict::Unverified,
+                        replayed_candies: None,
+                        replay_blob: None,
+                    };
+                    let _ = self.state.move_log_anchors.insert(&session_id, anchor);
+                }
+            }
+            
+            GameMessage::BracketScore { tournament_id, round, player_chain, session_id, candies_collected, move_log_hash, seed, level_id, map_hash, ticks, started_at } => {
+                log_event!(self, Debug, "MESSAGE", "Processing BracketScore: {:?} scored {} in round {} of tournament {}",
+                    player_chain, candies_collected, round, tournament_id);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring BracketScore message");
+                    return;
+                }
+                // Knockout rounds pay prizes, so their games pass the same checks as tournament attempts
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if origin != player_chain {
+                    reject_message!(self, "TOURNAMENT", "Ignoring knockout score from {:?} on behalf of {:?}", origin, player_chain);
+                    return;
+                }
+                if !is_valid_move_log_hash(&move_log_hash) {
+                    reject_message!(self, "TOURNAMENT", "Ignoring knockout score of session {} without a valid move log hash", session_id);
+                    return;
+                }
+                if let Some(reason) = self.reported_score_rejection(player_chain, &session_id, candies_collected, ticks, started_at).await {
+                    reject_message!(self, "TOURNAMENT", "Ignoring knockout score of session {} from {:?}: {}", session_id, player_chain, reason);
+                    return;
+                }
+                
+                let Some(mut bracket) = self.state.brackets.get(&tournament_id).await.ok().flatten() else {
+                    reject_message!(self, "TOURNAMENT", "Ignoring knockout score for tournament {} without a bracket", tournament_id);
+                    return;
+                };
+                let now = self.runtime.system_time().micros();
+                let open_match = bracket.matches.iter_mut()
+                    .find(|bracket_match| bracket_match.round == round && bracket_match.winner.is_none()
+                        && (bracket_match.player_a == Some(player_chain) || bracket_match.player_b == Some(player_chain)));
+                let Some(bracket_match) = open_match.filter(|_| bracket.round == round && now <= bracket.round_ends_at) else {
+                    reject_message!(self, "TOURNAMENT", "Knockout score from {:?} arrived outside round {} of tournament {}", player_chain, round, tournament_id);
+                    return;
+                };
+                if bracket_match.player_a == Some(player_chain) {
+                    bracket_match.score_a = Some(candies_collected);
+                } else {
+                    bracket_match.score_b = Some(candies_collected);
+                }
+                
+                // Keep the hash so the game can be replayed if it is disputed, unless a record
+                // of the same game already anchored it
+                if !self.state.move_log_anchors.contains_key(&session_id).await.unwrap_or(false) {
+                    let anchor = MoveLogAnchor {
+                        player_chain,
+                        session_id: session_id.clone(),
+                        candies_collected,
+                        move_log_hash,
+                        seed,
+                        level_id,
+                        map_hash,
+                        anchored_at: now,
+                        verdict: ReplayVerdict::Unverified,
+                        replayed_candies: None,
+                        replay_blob: None,
+                    };
+                    let _ = self.state.move_log_anchors.insert(&session_id, anchor);
+                }
+                self.advance_bracket(bracket).await;
+            }
+            
+            GameMessage::BracketRound { tournament_id, round, round_seed, round_ends_at, opponent } => {
+                log_event!(self, Info, "MESSAGE", "Round {} of tournament {} against {:?}", round, tournament_id, opponent);
+                let slot = BracketSlot {
+                    tournament_id,
+                    round,
+                    round_seed,
+                    round_ends_at,
+                    opponent,
+                    played: false,
+                    eliminated_in_round: None,
+                    champion: false,
+                };
+                let _ = self.state.my_brackets.insert(&tournament_id, slot);
+            }
+            
+            GameMessage::BracketEliminated { tournament_id, round, prize_coins } => {
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if Some(origin) != *self.state.leaderboard_chain_id.get() {
+                    reject_message!(self, "TOURNAMENT", "Ignoring BracketEliminated from {:?}, which is not the leaderboard chain", origin);
+                    return;
+                }
+                log_event!(self, Info, "MESSAGE", "Knocked out of tournament {} in round {}", tournament_id, round);
+                self.credit_reward(RewardSource::BracketPrize, format!("Tournament {} finals", tournament_id), prize_coins);
+                if let Ok(Some(mut slot)) = self.state.my_brackets.get(&tournament_id).await {
+                    slot.eliminated_in_round = Some(round);
+                    let _ = self.state.my_brackets.insert(&tournament_id, slot);
+                }
+            }
+            
+            GameMessage::BracketWon { tournament_id, prize_coins } => {
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if Some(origin) != *self.state.leaderboard_chain_id.get() {
+                    reject_message!(self, "TOURNAMENT", "Ignoring BracketWon from {:?}, which is not the leaderboard chain", origin);
+                    return;
+                }
+                log_event!(self, Info, "MESSAGE", "Won tournament {}", tournament_id);
+                self.credit_reward(RewardSource::BracketPrize, format!("Tournament {} champion", tournament_id), prize_coins);
+                if let Ok(Some(mut slot)) = self.state.my_brackets.get(&tournament_id).await {
+                    slot.champion = true;
+                    let _ = self.state.my_brackets.insert(&tournament_id, slot);
+                }
+            }
+            
+            GameMessage::SeasonPassUpdated { player_chain, progress } => {
+                log_event!(self, Debug, "MESSAGE", "Processing SeasonPassUpdated for {:?}: {} tiers", player_chain, progress.tiers_unlocked);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring SeasonPassUpdated message");
+                    return;
+                }
+                // Progress is tracked on the player's chain, so only that chain can report it
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if origin != player_chain {
+                    reject_message!(self, "SEASON", "Ignoring season pass update from {:?} on behalf of {:?}", origin, player_chain);
+                    return;
+                }
+                
+                let _ = self.state.season_passes.insert(&player_chain, progress);
+            }
+            
+            GameMessage::SeasonPremiumPurchased { buyer, amount } => {
+                log_event!(self, Debug, "MESSAGE", "Processing SeasonPremiumPurchased of {} from {:?}", amount, buyer);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring SeasonPremiumPurchased message");
+                    return;
+                }
+                
+                self.credit_treasury(amount, buyer, "Season pass premium".to_string());
+            }
+            
+            GameMessage::ListMarketItem { seller, kind, item, price } => {
+                log_event!(self, Debug, "MESSAGE", "Processing ListMarketItem of {:?} '{}' from {:?}", kind, item, seller);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring ListMarketItem message");
+                    return;
+                }
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if origin != seller.chain_id {
+                    reject_message!(self, "MARKET", "Ignoring listing from {:?} on behalf of {:?}", origin, seller.chain_id);
+                    return;
+                }
+                
+                // A listed name is reserved for nobody until it is sold or the listing is cancelled
+                if kind == ItemKind::NameToken {
+                    let key = name_key(&item);
+                    match self.state.reserved_names.get(&key).await.ok().flatten() {
+                        Some(mut reservation) if reservation.reserved_for == Some(seller.chain_id) => {
+                            reservation.reserved_for = None;
+                            let _ = self.state.reserved_names.insert(&key, reservation);
+                        }
+                        _ => {
+                            reject_message!(self, "MARKET", "{:?} does not hold the name token '{}'", seller.chain_id, item);
+                            return;
+                        }
+                    }
+                }
+                
+                let listing_id = *self.state.listing_counter.get() + 1;
+                self.state.listing_counter.set(listing_id);
+                log_event!(self, Info, "MARKET", "Listed {:?} '{}' for {} as listing {}", kind, item, price, listing_id);
+                let _ = self.state.market_listings.insert(&listing_id, MarketListing {
+                    listing_id,
+                    seller,
+                    kind,
+                    item,
+                    price,
+                    listed_at: self.runtime.system_time().micros(),
+                });
+            }
+            
+            GameMessage::CancelMarketListing { listing_id, seller_chain } => {
+                log_event!(self, Debug, "MESSAGE", "Processing CancelMarketListing {} from {:?}", listing_id, seller_chain);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring CancelMarketListing message");
+                    return;
+                }
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                let listing = match self.state.market_listings.get(&listing_id).await.ok().flatten() {
+                    Some(listing) if listing.seller.chain_id == seller_chain && origin == seller_chain => listing,
+                    _ => {
+                        reject_message!(self, "MARKET", "{:?} cannot cancel listing {}", origin, listing_id);
+                        return;
+                    }
+                };
+                
+                let _ = self.state.market_listings.remove(&listing_id);
+                log_event!(self, Info, "MARKET", "Listing {} cancelled", listing_id);
+                self.deliver_market_item(&listing, seller_chain).await;
+            }
+            
+            GameMessage::BuyMarketListing { listing_id, buyer, paid } => {
+                log_event!(self, Debug, "MESSAGE", "Processing BuyMarketListing {} from {:?}, paid {}", listing_id, buyer, paid);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring BuyMarketListing message");
+                    return;
+                }
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if origin != buyer.chain_id {
+                    reject_message!(self, "MARKET", "Ignoring purchase from {:?} on behalf of {:?}", origin, buyer.chain_id);
+                    return;
+                }
+                let listing = match self.state.market_listings.get(&listing_id).await.ok().flatten() {
+                    Some(listing) if paid >= listing.price && listing.seller.chain_id != buyer.chain_id => listing,
+                    _ => {
+                        self.runtime.transfer(AccountOwner::CHAIN, buyer, paid);
+                        reject_message!(self, "MARKET", "Refunded {} to {:?}: listing {} is not for sale to them at that price", paid, buyer, listing_id);
+                        return;
+                    }
+                };
+                let _ = self.state.market_listings.remove(&listing_id);
+                
+                // The seller gets the price minus the fee, the buyer any overpayment
+                let fee_percent = self.runtime.application_parameters().market_fee_percent.min(100);
+                let fee = Amount::from_attos(u128::from(listing.price) * fee_percent as u128 / 100);
+                self.runtime.transfer(AccountOwner::CHAIN, listing.seller, listing.price.saturating_sub(fee));
+                let change = paid.saturating_sub(listing.price);
+                if change > Amount::ZERO {
+                    self.runtime.transfer(AccountOwner::CHAIN, buyer, change);
+                }
+                if fee > Amount::ZERO {
+                    self.credit_treasury(fee, buyer, format!("Market fee for listing {}", listing_id));
+                }
+                
+                // A sold name can no longer be shown by its seller
+                let seller_chain = listing.seller.chain_id;
+                if listing.kind == ItemKind::NameToken {
+                    let seller_name = self.state.player_names.get(&seller_chain).await.ok().flatten();
+                    if seller_name.is_some_and(|name| name_key(&name) == name_key(&listing.item)) {
+                        let _ = self.state.player_names.remove(&seller_chain);
+                        let _ = self.state.name_handles.remove(&name_key(&listing.item));
+                        let reason = "The name was sold on the market".to_string();
+                        self.send_to_chain(seller_chain, GameMessage::NameRejected { name: listing.item.clone(), reason }).await;
+                    }
+                }
+                
+                let now = self.runtime.system_time().micros();
+                self.state.market_sales.push(MarketSale {
+                    listing_id,
+                    kind: listing.kind,
+                    item: listing.item.clone(),
+                    price: listing.price,
+                    fee,
+                    seller: seller_chain,
+                    buyer: buyer.chain_id,
+                    sold_at: now,
+                });
+                log_event!(self, Info, "MARKET", "Listing {} sold to {:?} for {}", listing_id, buyer.chain_id, listing.price);
+                self.deliver_market_item(&listing, buyer.chain_id).await;
+            }
+            
+            GameMessage::MarketItemDelivered { listing_id, kind, item } => {
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if Some(origin) != *self.state.leaderboard_chain_id.get() {
+                    reject_message!(self, "MARKET", "Ignoring market item from {:?}, which is not the leaderboard chain", origin);
+                    return;
+                }
+                
+                log_event!(self, Info, "MESSAGE", "Received {:?} '{}' from listing {}", kind, item, listing_id);
+                if kind == ItemKind::Skin {
+                    let _ = self.state.my_skins.insert(&item);
+                }
+            }
+            
+            GameMessage::GiftCoins { from_chain, from_name, amount } => {
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if origin != from_chain {
+                    reject_message!(self, "GIFT", "Ignoring gift from {:?} on behalf of {:?}", origin, from_chain);
+                    return;
+                }
+                
+                log_event!(self, Info, "MESSAGE", "Received {} coins from {:?}", amount, from_chain);
+                self.state.my_coins.set(*self.state.my_coins.get() + amount);
+                let name = from_name.unwrap_or_else(|| "Anonymous".to_string());
+                self.push_notification(
+                    NotificationKind::GiftReceived,
+                    format!("{} sent you {} coins", name, amount),
+                    Some(from_chain),
+                    None,
+                );
+            }
+            
+            GameMessage::TournamentResult { tournament_id, entry } => {
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if Some(origin) != *self.state.leaderboard_chain_id.get() {
+                    reject_message!(self, "TOURNAMENT", "Ignoring TournamentResult from {:?}, which is not the leaderboard chain", origin);
+                    return;
+                }
+                log_event!(self, Info, "MESSAGE", "Finished tournament {} at rank {:?}", tournament_id, entry.rank);
+                self.credit_reward(RewardSource::TournamentPrize, format!("Tournament {}", tournament_id), entry.prize_coins);
+                let _ = self.state.my_tournaments.insert(&tournament_id, entry);
+            }
+            
+            GameMessage::TeamWarUpdated { war } => {
+                log_event!(self, Info, "MESSAGE", "Team war {} is now {:?}", war.war_id, war.state);
+                let war_id = war.war_id;
+                let _ = self.state.my_team_wars.insert(&war_id, war);
+            }
+            
+            GameMessage::TeamMatchUpdated { team_match } => {
+                log_event!(self, Info, "MESSAGE", "Team match {} is now {:?}", team_match.match_id, team_match.state);
+                let match_id = team_match.match_id;
+                let _ = self.state.my_team_matches.insert(&match_id, team_match);
+            }
+            
+            GameMessage::ChallengeCreated { challenger, opponent, wager } => {
+                log_event!(self, Debug, "MESSAGE", "Processing ChallengeCreated: {:?} vs {:?} for {} coins", challenger, opponent, wager);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring ChallengeCreated message");
+                    return;
+                }
+                // The challenger's wager was taken on its own chain, so only it can open the challenge
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if origin != challenger {
+                    reject_message!(self, "CHALLENGE", "Ignoring challenge from {:?} on behalf of {:?}", origin, challenger);
+                    return;
+                }
+                
+                let challenge_id = *self.state.challenge_counter.get();
+                self.state.challenge_counter.set(challenge_id + 1);
+                let now = self.runtime.system_time().micros();
+                let window = self.runtime.application_parameters().challenge_accept_window_secs;
+                let challenge = Challenge {
+                    challenge_id,
+                    challenger: ChallengeSide::new(challenger),
+                    opponent: ChallengeSide::new(opponent),
+                    wager,
+                    state: ChallengeState::AwaitingOpponent,
+                    created_at: now,
+                    accepted_at: None,
+                    deadline: Some(now + window * 1_000_000),
+                    winner: None,
+                };
+                let _ = self.state.open_challenges.insert(&challenge_id);
+                self.publish_challenge(challenge).await;
+            }
+            
+            GameMessage::ChallengeAccepted { challenge_id, player_chain } => {
+                let Some(mut challenge) = self.load_challenge(challenge_id).await else {
+                    return;
+                };
+                if challenge.state != ChallengeState::AwaitingOpponent || challenge.opponent.chain_id != player_chain {
+                    reject_message!(self, "CHALLENGE", "Ignoring acceptance of challenge {} by {:?}", challenge_id, player_chain);
+                    return;
+                }
+                
+                let window = self.runtime.application_parameters().challenge_commit_window_secs;
+                let now = self.runtime.system_time().micros();
+                challenge.state = ChallengeState::Committing;
+                challenge.accepted_at = Some(now);
+                challenge.deadline = Some(now + window * 1_000_000);
+                self.publish_challenge(challenge).await;
+            }
+            
+            GameMessage::ChallengeCommit { challenge_id, player_chain, commitment } => {
+                let Some(mut challenge) = self.load_challenge(challenge_id).await else {
+                    return;
+                };
+                if challenge.state != ChallengeState::Committing {
+                    reject_message!(self, "CHALLENGE", "Challenge {} is not accepting commitments", challenge_id);
+                    return;
+                }
+                match challenge.side_mut(player_chain) {
+                    Some(side) if side.commitment.is_none() => side.commitment = Some(commitment),
+                    _ => {
+                        reject_message!(self, "CHALLENGE", "Ignoring commitment from {:?} for challenge {}", player_chain, challenge_id);
+                        return;
+                    }
+                }
+                
+                // Once both results are locked in, open the reveal window
+                if challenge.challenger.commitment.is_some() && challenge.opponent.commitment.is_some() {
+                    let window = self.runtime.application_parameters().challenge_reveal_window_secs;
+                    challenge.state = ChallengeState::Revealing;
+                    challenge.deadline = Some(self.runtime.system_time().micros() + window * 1_000_000);
+                }
+                self.publish_challenge(challenge).await;
+            }
+            
+            GameMessage::ChallengeReveal { challenge_id, player_chain, session_id, started_at, score, salt } => {
+                let Some(mut challenge) = self.load_challenge(challenge_id).await else {
+                    return;
+                };
+                if challenge.state != ChallengeState::Revealing {
+                    reject_message!(self, "CHALLENGE", "Challenge {} is not accepting reveals", challenge_id);
+                    return;
+                }
+                
+                let expected = challenge_commitment(challenge_id, player_chain, &session_id, score, &salt);
+                let played_during_challenge = challenge.accepted_at.is_some_and(|accepted_at| started_at >= accepted_at);
+                let Some(side) = challenge.side_mut(player_chain) else {
+                    return;
+                };
+                if side.revealed_score.is_some() {
+                    return;
+                }
+                
+                // A reveal that does not match its commitment, or of a game played before the
+                // challenge was accepted, forfeits the stake
+                if side.commitment.as_deref() != Some(expected.as_str()) || !played_during_challenge {
+                    reject_message!(self, "CHALLENGE", "Reveal of session {} from {:?} does not match its commitment or predates the challenge",
+                        session_id, player_chain);
+                    let winner = if challenge.challenger.chain_id == player_chain {
+                        challenge.opponent.chain_id
+                    } else {
+                        challenge.challenger.chain_id
+                    };
+                    self.settle_challenge(challenge, Some(winner)).await;
+                    return;
+                }
+                side.revealed_score = Some(score);
+                
+                match (challenge.challenger.revealed_score, challenge.opponent.revealed_score) {
+                    (Some(challenger_score), Some(opponent_score)) => {
+                        let winner = if challenger_score > opponent_score {
+                            Some(challenge.challenger.chain_id)
+                        } else if opponent_score > challenger_score {
+                            Some(challenge.opponent.chain_id)
+                        } else {
+                            None
+                        };
+                        self.settle_challenge(challenge, winner).await;
+                    }
+                    _ => self.publish_challenge(challenge).await,
+                }
+            }
+            
+            GameMessage::JoinMatchmaking { player_chain } => {
+                log_event!(self, Debug, "MESSAGE", "Processing JoinMatchmaking from {:?}", player_chain);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring JoinMatchmaking message");
+                    return;
+                }
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if origin != player_chain {
+                    reject_message!(self, "MATCHMAKING", "Ignoring queue entry from {:?} on behalf of {:?}", origin, player_chain);
+                    return;
+                }
+                
+                // Queued tickets were matched or timed out before this message was handled, so a
+                // ticket still here is simply waiting
+                if let Ok(Some(ticket)) = self.state.matchmaking_queue.get(&player_chain).await {
+                    self.send_to_chain(player_chain, GameMessage::MatchmakingUpdate { status: queue_status(&ticket, MatchmakingState::Queued) }).await;
+                    return;
+                }
+                let now = self.runtime.system_time().micros();
+                let merged = self.state.account_tombstones.contains_key(&player_chain).await.unwrap_or(false);
+                if merged || self.is_banned(player_chain, now).await {
+                    reject_message!(self, "MATCHMAKING", "{:?} is banned or merged and cannot queue", player_chain);
+                    return;
+                }
+                if self.state.matchmaking_queue.count().await.unwrap_or(0) as u64 >= MAX_MATCHMAKING_QUEUE {
+                    reject_message!(self, "MATCHMAKING", "The matchmaking queue is full, {:?} was not queued", player_chain);
+                    return;
+                }
+                
+                let rating = self.state.player_stats.get(&player_chain).await
+                    .ok()
+                    .flatten()
+                    .map(|stats| stats.highest_score)
+                    .unwrap_or(0);
+                let ticket = QueueTicket { player_chain, rating, queued_at: now };
+                let _ = self.state.matchmaking_queue.insert(&player_chain, ticket.clone());
+                log_event!(self, Info, "MATCHMAKING", "{:?} joined the queue with rating {}", player_chain, rating);
+                self.send_to_chain(player_chain, GameMessage::MatchmakingUpdate { status: queue_status(&ticket, MatchmakingState::Queued) }).await;
+                self.run_matchmaking().await;
+            }
+            
+            GameMessage::LeaveMatchmaking { player_chain } => {
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring LeaveMatchmaking message");
+                    return;
+                }
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if origin != player_chain {
+                    reject_message!(self, "MATCHMAKING", "Ignoring queue exit from {:?} on behalf of {:?}", origin, player_chain);
+                    return;
+                }
+                let Ok(Some(ticket)) = self.state.matchmaking_queue.get(&player_chain).await else {
+                    reject_message!(self, "MATCHMAKING", "{:?} is not in the matchmaking queue", player_chain);
+                    return;
+                };
+                
+                let _ = self.state.matchmaking_queue.remove(&player_chain);
+                log_event!(self, Info, "MATCHMAKING", "{:?} left the queue", player_chain);
+                self.send_to_chain(player_chain, GameMessage::MatchmakingUpdate { status: queue_status(&ticket, MatchmakingState::Left) }).await;
+            }
+            
+            GameMessage::ChallengeTimeoutClaim { challenge_id, player_chain } => {
+                let Some(challenge) = self.load_challenge(challenge_id).await else {
+                    return;
+                };
+                let now = self.runtime.system_time().micros();
+                if !challenge.is_expired(now) {
+                    reject_message!(self, "CHALLENGE", "Timeout claim by {:?} on challenge {} is premature", player_chain, challenge_id);
+                    return;
+                }
+                
+                self.expire_challenge(challenge).await;
+            }
+            
+            GameMessage::ChallengeUpdated { challenge } => {
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if Some(origin) != *self.state.leaderboard_chain_id.get() {
+                    reject_message!(self, "CHALLENGE", "Ignoring ChallengeUpdated from {:?}, which is not the leaderboard chain", origin);
+                    return;
+                }
+                log_event!(self, Info, "MESSAGE", "Challenge {} is now {:?}", challenge.challenge_id, challenge.state);
+                let challenge_id = challenge.challenge_id;
+                let _ = self.state.my_challenges.insert(&challenge_id, challenge);
+            }
+            
+            GameMessage::MatchmakingUpdate { status } => {
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if Some(origin) != *self.state.leaderboard_chain_id.get() {
+                    reject_message!(self, "MATCHMAKING", "Ignoring MatchmakingUpdate from {:?}, which is not the leaderboard chain", origin);
+                    return;
+                }
+                log_event!(self, Info, "MATCHMAKING", "Matchmaking ticket is now {:?}", status.state);
+                self.state.my_matchmaking.set(Some(status));
+            }
+            
+            GameMessage::ChallengePayout { challenge_id, coins } => {
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if Some(origin) != *self.state.leaderboard_chain_id.get() {
+                    reject_message!(self, "CHALLENGE", "Ignoring ChallengePayout from {:?}, which is not the leaderboard chain", origin);
+                    return;
+                }
+                log_event!(self, Info, "MESSAGE", "Received {} coins from challenge {}", coins, challenge_id);
+                self.state.my_coins.set(*self.state.my_coins.get() + coins);
+            }
+            
+            GameMessage::LevelRecord { level_id, player_chain, candies_collected } => {
+                log_event!(self, Debug, "MESSAGE", "Processing LevelRecord: {:?} scored {} on level {}", player_chain, candies_collected, level_id);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring LevelRecord message");
+                    return;
+                }
+                if self.level_layout(level_id).await.is_none() {
+                    reject_message!(self, "LEVEL", "Ignoring score for unknown level {}", level_id);
+                    return;
+                }
+                
+                let player_name = self.state.player_names.get(&player_chain).await.ok().flatten();
+                let record_timestamp = self.runtime.system_time().micros();
+                let mut scores = self.state.level_leaderboards.get(&level_id).await
+                    .ok()
+                    .flatten()
+                    .unwrap_or_default();
+                if record_best_score(&mut scores, player_chain, player_name, candies_collected, record_timestamp) {
+                    let _ = self.state.level_leaderboards.insert(&level_id, scores);
+                }
+            }
+            
+            GameMessage::ModeRecord { mode, player_chain, candies_collected } => {
+                log_event!(self, Debug, "MESSAGE", "Processing ModeRecord: {:?} scored {} in {:?} mode", player_chain, candies_collected, mode);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring ModeRecord message");
+                    return;
+                }
+                
+                let player_name = self.state.player_names.get(&player_chain).await.ok().flatten();
+                let record_timestamp = self.runtime.system_time().micros();
+                let mut scores = self.state.mode_leaderboards.get(&mode).await
+                    .ok()
+                    .flatten()
+                    .unwrap_or_default();
+                if record_best_score(&mut scores, player_chain, player_name, candies_collected, record_timestamp) {
+                    let _ = self.state.mode_leaderboards.insert(&mode, scores);
+                }
+            }
+            
+            GameMessage::ReplayPublished { session_id, player_chain, replay_blob } => {
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring ReplayPublished message");
+                    return;
+                }
+                
+                match self.state.move_log_anchors.get(&session_id).await {
+                    Ok(Some(mut anchor)) if anchor.player_chain == player_chain => {
+                        anchor.replay_blob = Some(replay_blob);
+                        let _ = self.state.move_log_anchors.insert(&session_id, anchor);
+                        log_event!(self, Info, "REPLAY", "Attached published replay to session {}", session_id);
+                    }
+                    _ => reject_message!(self, "REPLAY", "No record of {:?} anchored for session {}, ignoring replay", player_chain, session_id),
+                }
+            }
+            
+            GameMessage::SurvivalRecord { player_chain, survival_ticks } => {
+                log_event!(self, Debug, "MESSAGE", "Processing SurvivalRecord: {:?} survived {} ticks", player_chain, survival_ticks);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring SurvivalRecord message");
+                    return;
+                }
+                
+                let player_name = self.state.player_names.get(&player_chain).await.ok().flatten();
+                let record_timestamp = self.runtime.system_time().micros();
+                let mut entries = self.state.survival_leaderboard.get().clone();
+                match entries.iter_mut().find(|entry| entry.chain_id == player_chain) {
+                    Some(entry) if entry.longest_survival_ticks >= survival_ticks => return,
+                    Some(entry) => {
+                        entry.longest_survival_ticks = survival_ticks;
+                        entry.record_timestamp = record_timestamp;
+                        entry.player_name = player_name;
+                    }
+                    None => entries.push(SurvivalEntry {
+                        chain_id: player_chain,
+                        player_name,
+                        longest_survival_ticks: survival_ticks,
+                        record_timestamp,
+                    }),
+                }
+                
+                // Longest first; whoever got there first wins a tie
+                entries.sort_by(|a, b| b.longest_survival_ticks.cmp(&a.longest_survival_ticks)
+                    .then(a.record_timestamp.cmp(&b.record_timestamp)));
+                self.state.survival_leaderboard.set(entries);
+                
+                if let Ok(Some(mut stats)) = self.state.player_stats.get(&player_chain).await {
+                    stats.longest_survival_ticks = survival_ticks;
+                    let _ = self.state.player_stats.insert(&player_chain, stats);
+                }
+            }
+            
+            GameMessage::PublishMap { author, name, board } => {
+                log_event!(self, Debug, "MESSAGE", "Processing PublishMap '{}' from {:?}", name, author);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring PublishMap message");
+                    return;
+                }
+                if board.validate().is_err() {
+                    reject_message!(self, "MAP", "Ignoring invalid map '{}' from {:?}", name, author);
+                    return;
+                }
+                
+                // The same layout always maps to the same hash, so republishing keeps the original
+                let hash = map_hash(&board);
+                if let Ok(true) = self.state.custom_maps.contains_key(&hash).await {
+                    reject_message!(self, "MAP", "Map {} is already published", hash);
+                    return;
+                }
+                let custom_map = CustomMap {
+                    map_hash: hash.clone(),
+                    name,
+                    author,
+                    board,
+                    published_at: self.runtime.system_time().micros(),
+                    plays: 0,
+                };
+                let _ = self.state.custom_maps.insert(&hash, custom_map);
+                log_event!(self, Info, "MAP", "Published map {}", hash);
+            }
+            
+            GameMessage::MapPlayed { map_hash, player_chain, candies_collected } => {
+                log_event!(self, Debug, "MESSAGE", "Processing MapPlayed: {:?} scored {} on map {}", player_chain, candies_collected, map_hash);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring MapPlayed message");
+                    return;
+                }
+                let Ok(Some(mut custom_map)) = self.state.custom_maps.get(&map_hash).await else {
+                    reject_message!(self, "MAP", "Ignoring score for unknown map {}", map_hash);
+                    return;
+                };
+                custom_map.plays += 1;
+                let _ = self.state.custom_maps.insert(&map_hash, custom_map);
+                
+                let player_name = self.state.player_names.get(&player_chain).await.ok().flatten();
+                let record_timestamp = self.runtime.system_time().micros();
+                let mut scores = self.state.map_leaderboards.get(&map_hash).await
+                    .ok()
+                    .flatten()
+                    .unwrap_or_default();
+                if record_best_score(&mut scores, player_chain, player_name, candies_collected, record_timestamp) {
+                    let _ = self.state.map_leaderboards.insert(&map_hash, scores);
+                }
+            }
+            
+            GameMessage::LeaderboardHandover => {
+                let Some(previous_chain) = self.runtime.message_origin_chain_id() else {
+                    return;
+                };
+                log_event!(self, Debug, "MESSAGE", "Processing LeaderboardHandover from {:?}", previous_chain);
+                
+                // Only the chain this one currently follows can hand over the role
+                if let Some(leaderboard_chain_id) = *self.state.leaderboard_chain_id.get() {
+                    if leaderboard_chain_id != previous_chain {
+                        reject_message!(self, "MESSAGE", "{:?} is not our leaderboard chain, ignoring LeaderboardHandover message", previous_chain);
+                        return;
+                    }
+                    self.unsubscribe_from_leaderboard_streams(leaderboard_chain_id);
+                }
+                
+                let current_chain = self.runtime.chain_id();
+                self.state.leaderboard_chain_id.set(Some(current_chain));
+                self.state.is_leaderboard_chain.set(true);
+                self.runtime.send_message(previous_chain, GameMessage::LeaderboardHandoverAccepted.into());
+                log_event!(self, Debug, "SETUP", "This chain IS now the leaderboard chain");
+            }
+            
+            GameMessage::LeaderboardHandoverAccepted => {
+                let origin = self.runtime.message_origin_chain_id();
+                let Some(new_chain) = origin.filter(|origin| Some(*origin) == *self.state.pending_leaderboard_move.get()) else {
+                    reject_message!(self, "MESSAGE", "Unexpected LeaderboardHandoverAccepted from {:?}, ignoring", origin);
+                    return;
+                };
+                log_event!(self, Info, "MESSAGE", "Leaderboard chain {:?} accepted the handover", new_chain);
+                
+                self.state.pending_leaderboard_move.set(None);
+                self.state.is_leaderboard_chain.set(false);
+                self.state.leaderboard_chain_id.set(Some(new_chain));
+                self.subscribe_to_leaderboard_streams(new_chain);
+                
+                // Every player chain follows the announcements stream of this chain
+                self.runtime.emit(ANNOUNCEMENT_STREAM_NAME.into(), &SnakeGameEvent::LeaderboardMoved { new_chain });
+            }
+            
+            GameMessage::ResetProposed { proposal_id, closes_at } => {
+                let origin = self.runtime.message_origin_chain_id().unwrap_or_else(|| self.runtime.chain_id());
+                if Some(origin) != *self.state.leaderboard_chain_id.get() {
+                    reject_message!(self, "RESET", "Ignoring reset proposal from {:?}, which is not the leaderboard chain", origin);
+                    return;
+                }
+                log_event!(self, Info, "MESSAGE", "Reset proposal {} is open for votes until {}", proposal_id, closes_at);
+                self.state.reset_ballot.set(Some(ResetBallot { proposal_id, closes_at, vote: None }));
+            }
+            
+            GameMessage::ResetVote { proposal_id, voter, approve } => {
+                log_event!(self, Debug, "MESSAGE", "Processing ResetVote from {:?} on proposal {}: {}", voter, proposal_id, approve);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring ResetVote message");
+                    return;
+                }
+                
+                let now = self.runtime.system_time().micros();
+                let Some(mut proposal) = self.state.reset_proposal.get().clone() else {
+                    return;
+                };
+                if proposal.proposal_id != proposal_id || proposal.state_at(now) != ProposalState::Open {
+                    reject_message!(self, "RESET", "Proposal {} is not open, ignoring vote", proposal_id);
+                    return;
+                }
+                if !proposal.eligible_voters.contains(&voter) || proposal.has_voted(&voter) {
+                    reject_message!(self, "RESET", "{:?} may not vote on proposal {}, ignoring vote", voter, proposal_id);
+                    return;
+                }
+                
+                if approve {
+                    proposal.approvals.push(voter);
+                } else {
+                    proposal.rejections.push(voter);
+                }
+                
+                let quorum_percent = self.runtime.application_parameters().reset_quorum_percent;
+                if proposal.quorum_reached(quorum_percent) {
+                    log_event!(self, Info, "RESET", "Proposal {} approved by {} of {} participants", proposal_id,
+                        proposal.approvals.len(), proposal.eligible_voters.len());
+                    proposal.state = ProposalState::Executed;
+                    self.state.reset_proposal.set(Some(proposal));
+                    self.reset_leaderboard().await;
+                    return;
+                }
+                if proposal.quorum_unreachable(quorum_percent) {
+                    log_event!(self, Info, "RESET", "Proposal {} can no longer reach the quorum", proposal_id);
+                    proposal.state = ProposalState::Rejected;
+                }
+                self.state.reset_proposal.set(Some(proposal));
+            }
+            
+            GameMessage::EligibilityStaked { player_chain, coins } => {
+                log_event!(self, Debug, "MESSAGE", "Processing EligibilityStaked: {:?} staked {} coins", player_chain, coins);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring EligibilityStaked message");
+                    return;
+                }
+                
+                let staked = self.state.eligibility_stakes.get(&player_chain).await.ok().flatten().unwrap_or_default();
+                let _ = self.state.eligibility_stakes.insert(&player_chain, staked + coins);
+                log_event!(self, Info, "ELIGIBILITY", "{:?} staked {} coins for leaderboard eligibility", player_chain, coins);
+                
+                // A player who already has stats appears on the board right away
+                self.rebuild_global_leaderboard().await;
+            }
+            
+            GameMessage::PenaltyApplied { penalty, session_id } => {
+                log_event!(self, Warn, "PENALTY", "Received strike {}: {:?} ({})", penalty.strike, penalty.kind, penalty.reason);
+                // Void the score here too, in the stats of the owner who played the game, so the
+                // local best matches the board again
+                let origin = self.runtime.message_origin_chain_id();
+                if origin.is_none() || origin != *self.state.leaderboard_chain_id.get() {
+                    reject_message!(self, "PENALTY", "Ignoring penalty from {:?}, which is not the leaderboard chain", origin);
+                    return;
+                }
+                let owner = self.state.session(&session_id).await.and_then(|session| session.owner);
+                let stats = match owner {
+                    Some(owner) => self.state.owner_profile(owner).await.stats,
+                    None => self.state.my_stats.get().clone(),
+                };
+                if let Some(mut stats) = stats {
+                    let weights = self.runtime.application_parameters().composite_weights;
+                    stats.void_record(penalty.voided_score, &weights);
+                    self.state.set_owner_stats(owner, Some(stats)).await;
+                }
+                self.state.my_penalties.push(penalty);
+            }
+            
+            GameMessage::ScoreFlagged { reporter, player_chain, session_id, reason } => {
+                log_event!(self, Debug, "MESSAGE", "Processing ScoreFlagged from {:?} against {:?}", reporter, player_chain);
+                
+                // Only process on leaderboard chain
+                if !*self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is NOT the leaderboard chain, ignoring ScoreFlagged message");
+                    return;
+                }
+                
+                // Only players on the board can flag, and only entries that are on it
+                let reporter_participates = self.state.leaderboard_participants.contains(&reporter).await.unwrap_or(false);
+                if !reporter_participates {
+                    reject_message!(self, "DISPUTE", "{:?} has not played on this leaderboard, ignoring flag", reporter);
+                    return;
+                }
+                if self.state.player_stats.get(&player_chain).await.ok().flatten().is_none() {
+                    reject_message!(self, "DISPUTE", "{:?} has no leaderboard entry, ignoring flag", player_chain);
+                    return;
+                }
+                
+                // Only record games anchored at EndGame by the flagged player can be disputed
+                let anchor = match self.state.move_log_anchors.get(&session_id).await.ok().flatten() {
+                    Some(anchor) if anchor.player_chain == player_chain => anchor,
+                    _ => {
+                        reject_message!(self, "DISPUTE", "Session {} is not an anchored game of {:?}, ignoring flag", session_id, player_chain);
+                        return;
+                    }
+                };
+                let pending = self.state.review_queue.indices().await.unwrap_or_default();
+                let mut open_by_reporter = 0;
+                for dispute_id in pending {
+                    if let Ok(Some(dispute)) = self.state.disputes.get(&dispute_id).await {
+                        if dispute.reporter != reporter {
+                            continue;
+                        }
+                        if dispute.session_id == session_id {
+                            reject_message!(self, "DISPUTE", "{:?} already flagged session {}", reporter, session_id);
+                            return;
+                        }
+                        open_by_reporter += 1;
+                    }
+                }
+                if open_by_reporter >= MAX_OPEN_DISPUTES_PER_REPORTER {
+                    reject_message!(self, "DISPUTE", "{:?} already has {} flags waiting for a moderator", reporter, open_by_reporter);
+                    return;
+                }
+                
+                let dispute_id = *self.state.dispute_counter.get() + 1;
+                self.state.dispute_counter.set(dispute_id);
+                let dispute = Dispute {
+                    dispute_id,
+                    player_chain,
+                    session_id,
+                    reporter,
+                    reason,
+                    flagged_score: Some(anchor.candies_collected),
+                    state: DisputeState::Pending,
+                    flagged_at: self.runtime.system_time().micros(),
+                    resolved_at: None,
+                };
+                let _ = self.state.disputes.insert(&dispute_id, dispute);
+                let _ = self.state.review_queue.insert(&dispute_id);
+                log_event!(self, Info, "DISPUTE", "Dispute {} opened against {:?}", dispute_id, player_chain);
+                
+                // Mark the entry as under review right away
+                self.rebuild_global_leaderboard().await;
+            }
+            
+            GameMessage::LeaderboardReset => {
+                log_event!(self, Debug, "MESSAGE", "Processing LeaderboardReset notification on chain {:?}", self.runtime.chain_id());
+                
+                // Clear local leaderboard data on player chains
+                // On the leaderboard chain, this would be redundant, but we'll handle it gracefully
+                if *self.state.is_leaderboard_chain.get() {
+                    reject_message!(self, "MESSAGE", "This is the leaderboard chain, ignoring LeaderboardReset message");
+                    return;
+                }
+                
+                // Clear local player stats when leaderboard is reset
+                // This will reset the highest score and all other player statistics
+                if let Some(mut stats) = self.state.my_stats.get().clone() {
+                    stats.clear_for_reset();
+                    self.state.my_stats.set(Some(stats));
+                    log_event!(self, Info, "MESSAGE", "Player chain {:?} cleared local stats due to leaderboard reset", 
+                        self.runtime.chain_id());
+                } else {
+                    log_event!(self, Info, "MESSAGE", "Player chain {:?} had no local stats to clear", 
+                        self.runtime.chain_id());
+                }
+                
+                // Owners who are not playing right now start over too
+                for owner in self.state.owner_profiles.indices().await.unwrap_or_default() {
+                    if let Ok(Some(mut profile)) = self.state.owner_profiles.get(&owner).await {
+                        if let Some(stats) = profile.stats.as_mut() {
+                            stats.clear_for_reset();
+                            let _ = self.state.owner_profiles.insert(&owner, profile);
+                        }
+                    }
+                }
+                
+                // Also clear the global leaderboard on this player chain if it exists
+                self.state.global_leaderboard.clear();
+                self.state.my_level_bests.clear();
+                self.state.my_mode_bests.clear();
+                
+                // A new season starts a new pass; claimed skins and trophies stay
+                let season = self.state.my_season_pass.get().season + 1;
+                self.state.my_season_pass.set(SeasonPass { season, ..SeasonPass::default() });
+                log_event!(self, Info, "MESSAGE", "Player chain {:?} cleared local leaderboard data", 
+                    self.runtime.chain_id());
+            }
+        }
+    }
+    
+    /// Panic unless the operation was signed by the configured admin
+    fn assert_admin(&mut self) {
+        let admin = self.runtime.application_parameters().admin
+            .expect("No admin is configured for this application");
+        let signer = self.runtime.authenticated_signer();
+        assert_eq!(signer, Some(admin), "Only the admin can perform this operation");
+    }
+    
+    /// Record tokens that have been moved into the treasury's chain account
+    fn credit_treasury(&mut self, amount: Amount, from: Account, memo: String) {
+        let balance_after = self.state.treasury_balance.get().saturating_add(amount);
+        self.state.treasury_balance.set(balance_after);
+        self.state.treasury_ledger.push(TreasuryEntry {
+            flow: TreasuryFlow::Inflow,
+            amount,
+            counterparty: from,
+            memo,
+            timestamp: self.runtime.system_time().micros(),
+            balance_after,
+        });
+        log_event!(self, Info, "TREASURY", "Received {} from {:?}, balance now {}", amount, from, balance_after);
+    }
+    
+    /// Hand a listed item to `owner`: name tokens are reserved for them here, skins go to their chain
+    async fn deliver_market_item(&mut self, listing: &MarketListing, owner: ChainId) {
+        if listing.kind == ItemKind::NameToken {
+            let _ = self.state.reserved_names.insert(&name_key(&listing.item), ReservedName {
+                name: listing.item.clone(),
+                reserved_for: Some(owner),
+                reserved_at: self.runtime.system_time().micros(),
+            });
+        }
+        let message = GameMessage::MarketItemDelivered {
+            listing_id: listing.listing_id,
+            kind: listing.kind,
+            item: listing.item.clone(),
+        };
+        self.send_to_chain(owner, message).await;
+    }
+    
+    /// Add coins to this player's reward ledger, claimable as they vest
+    fn credit_reward(&mut self, source: RewardSource, memo: String, coins: u64) {
+        if coins == 0 {
+            return;
+        }
+        
+        let parameters = self.runtime.application_parameters();
+        let credited_at = self.runtime.system_time().micros();
+        let vests_until = credited_at.saturating_add(parameters.reward_vesting_secs.saturating_mul(1_000_000));
+        let expires_at = (parameters.reward_expiry_secs > 0)
+            .then(|| vests_until.saturating_add(parameters.reward_expiry_secs.saturating_mul(1_000_000)));
+        let reward_id = *self.state.reward_counter.get() + 1;
+        self.state.reward_counter.set(reward_id);
+        log_event!(self, Info, "REWARDS", "Credited {} coins as reward {} ({})", coins, reward_id, memo);
+        let _ = self.state.my_rewards.insert(&reward_id, PendingReward {
+            reward_id,
+            source,
+            memo,
+            coins,
+            claimed_coins: 0,
+            credited_at,
+            vests_until,
+            expires_at,
+        });
+    }
+    
+    /// Return the referral code of a player chain, minting one on first request
+    async fn mint_referral_code(&mut self, player_chain: ChainId) -> String {
+        if let Ok(Some(code)) = self.state.referral_code_of.get(&player_chain).await {
+            return code;
+        }
+        
+        let counter = *self.state.referral_counter.get();
+        self.state.referral_counter.set(counter + 1);
+        let code = format!("SNAKE{:05}", counter + 1);
+        let _ = self.state.referral_codes.insert(&code, player_chain);
+        let _ = self.state.referral_code_of.insert(&player_chain, code.clone());
+        log_event!(self, Info, "REFERRAL", "Minted code '{}' for {:?}", code, player_chain);
+        code
+    }
+    
+    /// Count a finished game towards an accepted referral, reporting it once it qualifies
+    fn advance_referral(&mut self) {
+        let Some(mut referral) = self.state.my_referral.get().clone() else {
+            return;
+        };
+        
+        // Games count from the moment the code is registered, even before the leaderboard chain answers
+        if matches!(referral.state, ReferralState::Pending | ReferralState::Accepted) {
+            referral.games_finished = self.state.my_stats.get().as_ref().map(|stats| stats.games_played).unwrap_or(0);
+        }
+        
+        let required = self.runtime.application_parameters().referral_games_required;
+        if referral.state == ReferralState::Accepted && referral.games_finished >= required {
+            referral.state = ReferralState::Qualified;
+            let current_chain = self.runtime.chain_id();
+            if let Some(leaderboard_chain_id) = *self.state.leaderboard_chain_id.get() {
+                self.runtime.send_message(leaderboard_chain_id, GameMessage::ReferralQualified { referee_chain: current_chain }.into());
+                log_event!(self, Info, "REFERRAL", "Referral qualified after {} games", referral.games_finished);
+            }
+        }
+        
+        self.state.my_referral.set(Some(referral));
+    }
+    
+    /// Create a session on this chain and make it the current one, returning its ID
+    async fn start_session(
+        &mut self,
+        mut level_id: u32,
+        map_hash: Option<String>,
+        mode: GameMode,
+        match_id: Option<u64>,
+        royale_id: Option<u64>,
+        ghost: Option<String>,
+        tournament_id: Option<u64>,
+    ) -> String {
+        if let Some(flag) = mode.feature_flag() {
+            if !self.feature_enabled(flag).await {
+                panic!("Game mode {:?} is currently disabled", mode);
+            }
+        }
+        if royale_id.is_some() && !self.feature_enabled(FEATURE_BATTLE_ROYALE).await {
+            panic!("Battle royales are currently disabled");
+        }
+        // A ghost race is played on the ghost's level and candy schedule
+        let ghost = match ghost {
+            Some(session_id) => {
+                if map_hash.is_some() || match_id.is_some() || royale_id.is_some() {
+                    panic!("Ghost races are played on their own, on the ghost's level");
+                }
+                let ghost = self.state.ghost(&session_id).await
+                    .unwrap_or_else(|| panic!("No published replay to race for session {}", session_id));
+                level_id = ghost.level_id;
+                Some(ghost)
+            }
+            None => None,
+        };
+        // A tournament game is played on the tournament's level: a knockout game on the round's
+        // seed once this chain has a match, otherwise a qualifier attempt, spent when it starts
+        let mut bracket_round = None;
+        let tournament_seed = match tournament_id {
+            Some(tournament_id) => {
+                if map_hash.is_some() || match_id.is_some() || royale_id.is_some() || ghost.is_some() {
+                    panic!("Tournament attempts are played on their own, on the tournament's level");
+                }
+                let tournament = self.state.tournaments.get(&tournament_id).await
+                    .ok()
+                    .flatten()
+                    .unwrap_or_else(|| panic!("Unknown tournament {}", tournament_id));
+                let now = self.runtime.system_time().micros();
+                level_id = tournament.level_id;
+                let knockout = self.state.my_brackets.get(&tournament_id).await
+                    .ok()
+                    .flatten()
+                    .filter(|slot| !slot.played && slot.eliminated_in_round.is_none() && now <= slot.round_ends_at);
+                if let Some(mut slot) = knockout {
+                    slot.played = true;
+                    bracket_round = Some(slot.round);
+                    let round_seed = slot.round_seed;
+                    let _ = self.state.my_brackets.insert(&tournament_id, slot);
+                    Some(round_seed)
+                } else {
+                    if !tournament.is_open(now) {
+                        panic!("Tournament {} is not open", tournament_id);
+                    }
+                    let mut entry = self.state.my_tournaments.get(&tournament_id).await
+                        .ok()
+                        .flatten()
+                        .unwrap_or_else(|| TournamentEntry::new(tournament_id, self.runtime.chain_id()));
+                    if entry.attempts >= tournament.max_attempts {
+                        panic!("All {} attempts at tournament {} are used", tournament.max_attempts, tournament_id);
+                    }
+                    entry.attempts += 1;
+                    let _ = self.state.my_tournaments.insert(&tournament_id, entry);
+                    Some(tournament.seed)
+                }
+            }
+            None => None,
+        };
+        let level = self.level_layout(level_id).await
+            .unwrap_or_else(|| panic!("Unknown level {}", level_id));
+        if let Some(match_id) = match_id {
+            let team_match = self.state.my_team_matches.get(&match_id).await
+                .ok()
+                .flatten()
+                .expect("Unknown team match");
+            if team_match.state != TeamMatchState::Open {
+                panic!("Team match {} is not open to play ({:?})", match_id, team_match.state);
+            }
+        }
+        // The leaderboard chain holds the maps, so unknown hashes are only caught when the score arrives
+        if map_hash.is_some() && level_id != LevelLayout::OPEN_LEVEL_ID {
+            panic!("A custom map is played instead of a level, not on top of one");
+        }
+        let current_chain = self.runtime.chain_id();
+        let player_name = self.state.my_player_name.get().clone();
+        let timestamp = self.runtime.system_time().micros();
+        
+        // Battle royale rounds are played on the round's shared seed
+        let royale_round = match royale_id {
+            Some(event_id) => {
+                let status = self.state.my_battle_royales.get(&event_id).await
+                    .ok()
+                    .flatten()
+                    .expect("This chain has no round to play in that battle royale");
+                if status.eliminated_in_round.is_some() || status.won || timestamp > status.round_ends_at {
+                    panic!("No open battle royale round for this chain");
+                }
+                Some((status.round, status.round_seed))
+            }
+            None => None,
+        };
+        
+        // Session IDs come from where the session started, so they survive resets without colliding
+        let block_height = self.runtime.block_height().0;
+        let index = match *self.state.session_block.get() {
+            (height, started) if height == block_height => started,
+            _ => 0,
+        };
+        self.state.session_block.set((block_height, index + 1));
+        let session_id = snake_game::session_id(current_chain, block_height, index);
+        
+        // Create local game session (only stored on player's chain)
+        let session = GameSession {
+            session_id: session_id.clone(),
+            player: current_chain,
+            player_name,
+            start_time: timestamp,
+            end_time: None,
+            candies_collected: 0, // Start with 0 candies
+            is_record: false,
+            state: GameState::Playing,
+            move_log_hash: None,
+            seed: royale_round.map(|(_, seed)| seed)
+                .or(ghost.as_ref().map(|ghost| ghost.seed))
+                .or(tournament_seed)
+                .unwrap_or_else(|| session_seed(&session_id, timestamp)),
+            level_id,
+            map_hash,
+            mode,
+            ticks: 0,
+            tick_limit: match mode {
+                GameMode::Timed => Some(self.runtime.application_parameters().timed_mode_limit_secs * snake_engine::TICKS_PER_SECOND),
+                _ => None,
+            },
+            lives_remaining: match mode {
+                GameMode::Survival => Some(self.runtime.application_parameters().survival_lives),
+                _ => None,
+            },
+            last_death_tick: None,
+            combo: Combo::default(),
+            match_id,
+            royale_id,
+            royale_round: royale_round.map(|(round, _)| round),
+            replay_blob: None,
+            ghost: ghost.map(|ghost| GhostRace { ghost, ghost_candies: None, beaten: false }),
+            tournament_id,
+            bracket_round,
+            owner: self.runtime.authenticated_signer(),
+            guest: self.state.leaderboard_chain_id.get().is_none(),
+            leaderboard_status: LeaderboardStatus::NotApplicable,
+            leaderboard_rejection: None,
+        };
+        
+        // Custom map layouts live on the leaderboard chain, so only level games can be watched
+        if session.map_hash.is_none() {
+            let engine = snake_engine::Engine::with_board(level.board, session.seed);
+            self.publish_live_session(&session, engine);
+        }
+        
+        self.runtime.emit(GAME_STREAM_NAME.into(), &SnakeGameEvent::GameStarted {
+            version: EVENT_SCHEMA_VERSION,
+            session_id: session_id.clone(),
+            player: current_chain,
+            mode,
+            level_id,
+            map_hash: session.map_hash.clone(),
+            started_at: timestamp,
+        });
+        self.state.save_session(session).await;
+        self.state.log_session_event(&session_id, SessionEventKind::Started, 0, timestamp).await;
+        
+        // Index the session by the day it started
+        let bucket = timestamp / SESSION_BUCKET_MICROS;
+        let mut bucket_sessions = self.state.sessions_by_start.get(&bucket).await.ok().flatten().unwrap_or_default();
+        bucket_sessions.push(session_id.clone());
+        let _ = self.state.sessions_by_start.insert(&bucket, bucket_sessions);
+        
+        // Set as current session
+        self.state.my_current_session.set(Some(session_id.clone()));
+        
+        log_event!(self, Info, "START_GAME", "Started new game session: {} on player chain {:?}", session_id, current_chain);
+        session_id
+    }
+    
+    /// Check that the registered client key signed the score the current session is about to end with.
+    /// Chains without a client key accept unsigned results
+    async fn check_score_claim(&mut self, move_log_hash: &Option<String>, signature: Option<Ed25519Signature>) {
+        let Some(client_key) = *self.state.my_client_key.get() else {
+            return;
+        };
+        let session_id = self.state.my_current_session.get().clone()
+            .expect("No active game session found");
+        let session = self.state.session(&session_id).await
+            .expect("Active session is missing");
+        let claim = ScoreClaim {
+            chain_id: self.runtime.chain_id(),
+            session_id,
+            candies_collected: session.candies_collected,
+            move_log_hash: move_log_hash.clone(),
+        };
+        let signature = signature.expect("This chain only accepts scores signed by its client key");
+        if signature.check(&claim, client_key).is_err() {
+            panic!("The score claim for session {} is not signed by the client key", claim.session_id);
+        }
+    }
+    
+    /// Finish the active session: record the result locally and report it to the leaderboard chain
+    async fn finish_current_session(&mut self, move_log_hash: Option<String>) {
+        let current_chain = self.runtime.chain_id();
+        let leaderboard_chain = self.state.leaderboard_chain_id.get().clone();
+        let timestamp = self.runtime.system_time().micros();
+        
+        // Get current session
+        if let Some(session_id) = self.state.my_current_session.get().clone() {
+            // Get the session data (we don't need to modify it here)
+            if let Some(session) = self.state.session(&session_id).await {
+                let candies_collected = session.candies_collected;
+                
+                // Update session to mark as finished
+                let mut updated_session = session.clone();
+                updated_session.end_time = Some(timestamp);
+                if session.tick_limit.map(|limit| session.ticks > limit).unwrap_or(false) {
+                    // Late candies were already rejected, so the score stands at the last valid collection
+                    log_event!(self, Info, "END_GAME", "Session {} ended after its time limit; score capped at {} candies",
+                        session_id, candies_collected);
+                }
+                updated_session.state = GameState::Finished;
+                updated_session.move_log_hash = move_log_hash.clone();
+                
+                // The ghost plays out its whole stored game on the same board and seed
+                if let Some(race) = updated_session.ghost.as_mut() {
+                    let log = self.runtime.read_data_blob(race.ghost.replay_blob);
+                    let board = self.level_layout(session.level_id).await.map(|level| level.board).unwrap_or_default();
+                    match snake_engine::replay(board, session.seed, &log) {
+                        Ok(ghost) => {
+                            let sooner = matches!((session.combo.last_candy_tick, ghost.combo.last_candy_tick),
+                                (Some(mine), Some(its)) if mine < its);
+                            race.ghost_candies = Some(ghost.candies_collected);
+                            race.beaten = candies_collected > ghost.candies_collected
+                                || (candies_collected == ghost.candies_collected && sooner);
+                            log_event!(self, Info, "GHOST", "Session {} scored {} against the ghost of {}'s {}",
+                                session_id, candies_collected, race.ghost.session_id, ghost.candies_collected);
+                        }
+                        Err(error) => {
+                            log_event!(self, Warn, "GHOST", "Could not replay the ghost of {}: {:?}", race.ghost.session_id, error);
+                        }
+                    }
+                }
+                let beat_other_ghost = updated_session.ghost.as_ref()
+                    .is_some_and(|race| race.beaten && race.ghost.player_chain != current_chain);
+                
+                let known_seed = session.has_known_seed();
+                
+                // Check if this is a new record for this player, or for the guest profile
+                let stats = if session.guest { self.state.guest_stats.get() } else { self.state.my_stats.get() };
+                let is_new_record = if known_seed {
+                    false
+                } else if let Some(ref stats) = *stats {
+                    candies_collected > stats.highest_score
+                } else {
+                    true // First game is always a record
+                };
+                
+                updated_session.is_record = is_new_record;
+                let summary = SessionSummary::from(&updated_session);
+                self.state.save_session(updated_session.clone()).await;
+                self.state.log_session_event(&session_id, SessionEventKind::Finished, session.ticks, timestamp).await;
+                self.state.recent_games.push_back(session_id.clone());
+                while self.state.recent_games.count() > MAX_RECENT_GAMES {
+                    self.state.recent_games.delete_front();
+                }
+                
+                // Opted-in players publish their history to the leaderboard chain in batches
+                if *self.state.history_sync_enabled.get() {
+                    self.state.pending_history.push_back(summary);
+                    if self.state.pending_history.count() >= HISTORY_SYNC_BATCH_SIZE {
+                        self.flush_history().await;
+                    }
+                }
+                
+                // Spectators stop watching once the game is over
+                if self.state.live_engines.contains_key(&session_id).await.unwrap_or(false) {
+                    let _ = self.state.live_engines.remove(&session_id);
+                    let _ = self.state.live_sessions.remove(&session_id);
+                    self.runtime.emit(LIVE_STREAM_NAME.into(), &SnakeGameEvent::LiveSessionEnded { session_id: session_id.clone() });
+                }
+                
+                self.runtime.emit(GAME_STREAM_NAME.into(), &SnakeGameEvent::GameFinished {
+                    version: EVENT_SCHEMA_VERSION,
+                    session_id: session_id.clone(),
+                    player: current_chain,
+                    candies_collected,
+                    points: session.combo.points,
+                    best_combo: session.combo.best,
+                    ticks: session.ticks,
+                    is_record: is_new_record,
+                    ended_at: timestamp,
+                });
+                
+                // Guest games wait under the guest profile until an owner claims them
+                if session.guest {
+                    let mut guest_stats = self.state.guest_stats.get().clone().unwrap_or_else(|| PlayerStats::new(current_chain));
+                    if known_seed {
+                        guest_stats.add_unranked_game(candies_collected, timestamp);
+                    } else {
+                        guest_stats.add_game(candies_collected, timestamp);
+                    }
+                    guest_stats.best_combo = guest_stats.best_combo.max(session.combo.best);
+                    self.state.guest_stats.set(Some(guest_stats));
+                    let mut guest_sessions = self.state.guest_sessions.get().clone();
+                    guest_sessions.push(session_id.clone());
+                    self.state.guest_sessions.set(guest_sessions);
+                    self.state.my_current_session.set(None);
+                    log_event!(self, Info, "GUEST", "Guest game {} ended with {} candies, kept until it is claimed", session_id, candies_collected);
+                    return;
+                }
+                
+                if beat_other_ghost {
+                    self.unlock_achievement(Achievement::GhostBuster, timestamp).await;
+                }
+                
+                // Remember the new personal best for the progression history
+                if is_new_record {
+                    self.state.push_record_history(PersonalBest {
+                        score: candies_collected,
+                        timestamp,
+                        session_id: session_id.clone(),
+                    });
+                }
+                
+                // Only send GameFinished message to leaderboard chain if it's a new record,
+                // or if a team match is waiting for this game's score
+                if is_new_record || session.match_id.is_some() {
+                    match leaderboard_chain {
+                        Some(leader_chain) => {
+                            let message = Self::game_finished_message(&updated_session, current_chain);
+                            self.track_pending_result(&session_id).await;
+                            self.runtime.send_message(leader_chain, message.into());
+                            log_event!(self, Info, "END_GAME", "Sent GameFinished to leaderboard chain {:?} with {} candies (new record: {})", 
+                                leader_chain, candies_collected, is_new_record);
+                        }
+                        None => {
+                            log_event!(self, Error, "END_GAME", "No leaderboard chain configured for ending game. Please use SetupLeaderboard operation first");
+                        }
+                    }
+                } else {
+                    log_event!(self, Info, "END_GAME", "Game ended with {} candies, but not a new record. Skipping leaderboard update.", 
+                        candies_collected);
+                }
+                
+                // Knockout games decide their match; every qualifier attempt is reported, so the
+                // leaderboard chain counts attempts too
+                if let (Some(tournament_id), Some(round), Some(leader_chain)) = (session.tournament_id, session.bracket_round, leaderboard_chain) {
+                    match move_log_hash {
+                        Some(move_log_hash) => {
+                            let message = GameMessage::BracketScore {
+                                tournament_id,
+                                round,
+                                player_chain: current_chain,
+                                session_id: session_id.clone(),
+                                candies_collected,
+                                move_log_hash,
+                                seed: session.seed,
+                                level_id: session.level_id,
+                                map_hash: session.map_hash.clone(),
+                                ticks: session.ticks,
+                                started_at: session.start_time,
+                            };
+                            self.send_to_chain(leader_chain, message).await;
+                        }
+                        None => {
+                            log_event!(self, Warn, "TOURNAMENT", "Session {} has no move log hash, so its knockout score is not reported", session_id);
+                        }
+                    }
+                } else if let Some(tournament_id) = session.tournament_id {
+                    if let Ok(Some(mut entry)) = self.state.my_tournaments.get(&tournament_id).await {
+                        if candies_collected > entry.best_score {
+                            entry.best_score = candies_collected;
+                            entry.best_at = timestamp;
+                        }
+                        let _ = self.state.my_tournaments.insert(&tournament_id, entry);
+                    }
+                    match (leaderboard_chain, move_log_hash) {
+                        (Some(leader_chain), Some(move_log_hash)) => {
+                            let message = GameMessage::TournamentScore {
+                                tournament_id,
+                                player_chain: current_chain,
+                                session_id: session_id.clone(),
+                                candies_collected,
+                                move_log_hash,
+                                seed: session.seed,
+                                level_id: session.level_id,
+                                map_hash: session.map_hash.clone(),
+                                ticks: session.ticks,
+                                started_at: session.start_time,
+                            };
+                            self.send_to_chain(leader_chain, message).await;
+                        }
+                        (Some(_), None) => {
+                            log_event!(self, Warn, "TOURNAMENT", "Session {} has no move log hash, so its attempt is not reported", session_id);
+                        }
+                        (None, _) => {}
+                    }
+                }
+                
+                // Every game finished during a war of this chain's team counts towards it
+                if let Some(leader_chain) = leaderboard_chain {
+                    let now = self.runtime.system_time().micros();
+                    let wars = self.state.my_team_wars.index_values().await.unwrap_or_default();
+                    for (war_id, war) in wars {
+                        if war.is_running(now) {
+                            let message = GameMessage::TeamWarScore {
+                                war_id,
+                                player_chain: current_chain,
+                                session_id: session_id.clone(),
+                                candies_collected,
+                                ticks: session.ticks,
+                                started_at: session.start_time,
+                            };
+                            self.send_to_chain(leader_chain, message).await;
+                        }
+                    }
+                }
+                
+                if let Some(map_hash) = session.map_hash.clone() {
+                    // Every custom map game counts towards the map's popularity
+                    if let Some(leader_chain) = leaderboard_chain {
+                        let message = GameMessage::MapPlayed {
+                            map_hash,
+                            player_chain: current_chain,
+                            candies_collected,
+                        };
+                        self.send_to_chain(leader_chain, message).await;
+                    }
+                } else if !known_seed {
+                    // Per-level boards only need this chain's best on each level
+                    let level_best = self.state.my_level_bests.get(&session.level_id).await.ok().flatten();
+                    if level_best.map(|best| candies_collected > best).unwrap_or(true) {
+                        let _ = self.state.my_level_bests.insert(&session.level_id, candies_collected);
+                        if let Some(leader_chain) = leaderboard_chain {
+                            let message = GameMessage::LevelRecord {
+                                level_id: session.level_id,
+                                player_chain: current_chain,
+                                candies_collected,
+                            };
+                            self.send_to_chain(leader_chain, message).await;
+                        }
+                    }
+                }
+                
+                if let (Some(event_id), Some(round), Some(leader_chain)) = (session.royale_id, session.royale_round, leaderboard_chain) {
+                    let message = GameMessage::SubmitRoyaleScore {
+                        event_id,
+                        round,
+                        player_chain: current_chain,
+                        session_id: session_id.clone(),
+                        candies_collected,
+                        ticks: session.ticks,
+                        started_at: session.start_time,
+                    };
+                    self.send_to_chain(leader_chain, message).await;
+                }
+                
+                // Modes other than Classic keep a board of their own
+                if session.mode != GameMode::Classic && !known_seed {
+                    let mode_best = self.state.my_mode_bests.get(&session.mode).await.ok().flatten();
+                    if mode_best.map(|best| candies_collected > best).unwrap_or(true) {
+                        let _ = self.state.my_mode_bests.insert(&session.mode, candies_collected);
+                        if let Some(leader_chain) = leaderboard_chain {
+                            let message = GameMessage::ModeRecord {
+                                mode: session.mode,
+                                player_chain: current_chain,
+                                candies_collected,
+                            };
+                            self.send_to_chain(leader_chain, message).await;
+                        }
+                    }
+                }
+                
+                // Update personal stats
+                let mut my_stats = self.state.my_stats.get().clone().unwrap_or_else(|| PlayerStats::new(current_chain));
+                if known_seed {
+                    my_stats.add_unranked_game(candies_collected, timestamp);
+                } else if my_stats.add_game(candies_collected, timestamp) {
+                    my_stats.record_ticks = session.ticks;
+                }
+                my_stats.best_combo = my_stats.best_combo.max(session.combo.best);
+                // Score-only estimate; the leaderboard chain holds the authoritative tier
+                my_stats.tier = self.runtime.application_parameters().tier_thresholds.tier_for_score(my_stats.highest_score);
+                let earned_achievements = my_stats.earned_achievements(candies_collected);
+                
+                // Survival runs are ranked by how long the snake lasted, not by candies
+                if session.mode == GameMode::Survival {
+                    let survival_ticks = session.last_death_tick.unwrap_or(0);
+                    if survival_ticks > my_stats.longest_survival_ticks {
+                        my_stats.longest_survival_ticks = survival_ticks;
+                        if let Some(leader_chain) = leaderboard_chain {
+                            let message = GameMessage::SurvivalRecord { player_chain: current_chain, survival_ticks };
+                            self.send_to_chain(leader_chain, message).await;
+                        }
+                    }
+                }
+                self.state.my_stats.set(Some(my_stats));
+                
+                // Award XP for the game and any newly reached milestones
+                let game_xp = self.runtime.application_parameters().xp.xp_for_game(candies_collected);
+                self.grant_xp(game_xp).await;
+                for achievement in earned_achievements {
+                    self.unlock_achievement(achievement, timestamp).await;
+                }
+                
+                // Count the finished game towards open quests
+                self.advance_quests(QuestKind::FinishGames, 1, timestamp).await;
+                self.advance_quests(QuestKind::ReachScore, candies_collected, timestamp).await;
+                self.advance_referral();
+                
+                // Clear current session
+                self.state.my_current_session.set(None);
+                
+                log_event!(self, Info, "END_GAME", "Ended game session: {} with {} candies (record: {})", 
+                    session_id, candies_collected, is_new_record);
+            }
+        } else {
+            log_event!(self, Error, "END_GAME", "No active game session found");
+        }
+    }
+    
+    /// Look up a level, treating level 0 as the built-in open field
+    async fn level_layout(&self, level_id: u32) -> Option<LevelLayout> {
+        if level_id == LevelLayout::OPEN_LEVEL_ID {
+            return Some(LevelLayout::open());
+        }
+        self.state.levels.get(&level_id).await.ok().flatten()
+    }
+    
+    /// Move a battle royale forward: open the first round once it starts, or close the
+    /// current round by eliminating the lowest scores
+    async fn advance_battle_royale(&mut self, mut event: BattleRoyale) {
+        let now = self.runtime.system_time().micros();
+        match event.state {
+            BattleRoyaleState::Registration => {
+                if now < event.starts_at {
+                    panic!("Battle royale {} has not started yet", event.event_id);
+                }
+                
+                // Fill a thin field with bots so the players who came still get a contest
+                let min_entrants = self.runtime.application_parameters().royale_min_entrants as usize;
+                if !event.survivors.is_empty() {
+                    for bot_id in 0..min_entrants.saturating_sub(event.survivors.len()) {
+                        event.bots.push(RoyaleBot::new(bot_id as u32));
+                    }
+                }
+            }
+            BattleRoyaleState::Running => {
+                if now <= event.round_ends_at {
+                    panic!("Round {} of battle royale {} is still running", event.round, event.event_id);
+                }
+                
+                // Bots play the round's candy schedule once it is over, for as long as the round lasted
+                let bot_ticks = (event.round_duration_secs * snake_engine::TICKS_PER_SECOND).min(MAX_BOT_TICKS);
+                let round_seed = event.round_seed;
+                for bot in event.bots.iter_mut().filter(|bot| bot.eliminated_in_round.is_none()) {
+                    bot.play_round(round_seed, bot_ticks);
+                }
+                
+                // Rank survivors by their best game this round; registration order breaks ties,
+                // and players come before bots
+                let score_of = |player: &ChainId| event.round_scores.iter()
+                    .find(|score| score.player_chain == *player)
+                    .map(|score| score.candies_collected)
+                    .unwrap_or(0);
+                let mut ranked: Vec<(RoyaleEntrant, u32)> = event.survivors.iter()
+                    .map(|player| (RoyaleEntrant::Player(*player), score_of(player)))
+                    .chain(event.bots.iter().enumerate()
+                        .filter(|(_, bot)| bot.eliminated_in_round.is_none())
+                        .map(|(index, bot)| (RoyaleEntrant::Bot(index), bot.candies_collected)))
+                    .collect();
+                ranked.sort_by_key(|(_, candies_collected)| std::cmp::Reverse(*candies_collected));
+                let knocked_out = ranked.split_off(ranked.len() - event.elimination_count());
+                
+                let mut eliminated_players = Vec::new();
+                for (entrant, candies_collected) in knocked_out {
+                    match entrant {
+                        RoyaleEntrant::Player(player_chain) => {
+                            event.eliminated.push(RoyaleElimination { player_chain, round: event.round, candies_collected });
+                            eliminated_players.push(player_chain);
+                        }
+                        RoyaleEntrant::Bot(index) => event.bots[index].eliminated_in_round = Some(event.round),
+                    }
+                }
+                event.survivors.retain(|player| !eliminated_players.contains(player));
+                for player_chain in eliminated_players {
+                    self.send_to_chain(player_chain, GameMessage::BattleRoyaleEliminated { event_id: event.event_id, round: event.round }).await;
+                }
+            }
+            BattleRoyaleState::Finished => panic!("Battle royale {} is already finished", event.event_id),
+        }
+        
+        // Once every player is out there is nobody left to play for
+        if event.survivors.is_empty() || event.entrants_left() <= 1 {
+            self.crown_battle_royale_winner(event, now).await;
+            return;
+        }
+        
+        event.round += 1;
+        event.state = BattleRoyaleState::Running;
+        event.round_seed = session_seed(&format!("royale_{}_{}", event.event_id, event.round), now);
+        event.round_ends_at = now + event.round_duration_secs * 1_000_000;
+        event.round_scores.clear();
+        log_event!(self, Info, "ROYALE", "Round {} of battle royale {} started with {} survivors",
+            event.round, event.event_id, event.survivors.len());
+        
+        let _ = self.state.battle_royales.insert(&event.event_id, event.clone());
+        for player_chain in event.survivors.clone() {
+            let message = GameMessage::BattleRoyaleRound {
+                event_id: event.event_id,
+                round: event.round,
+                round_seed: event.round_seed,
+                round_ends_at: event.round_ends_at,
+            };
+            self.send_to_chain(player_chain, message).await;
+        }
+    }
+    
+    /// Finish a battle royale, recording its last survivor in the hall of fame
+    async fn crown_battle_royale_winner(&mut self, mut event: BattleRoyale, timestamp: u64) {
+        event.state = BattleRoyaleState::Finished;
+        event.winner = event.survivors.first().copied();
+        if event.winner.is_none() {
+            // The best bot of the last round; the earliest added wins a tie
+            event.winning_bot = event.bots.iter()
+                .filter(|bot| bot.eliminated_in_round.is_none())
+                .min_by_key(|bot| std::cmp::Reverse(bot.candies_collected))
+                .map(|bot| bot.bot_id);
+        }
+        
+        if let Some(winner) = event.winner {
+            let score = event.round_scores.iter()
+                .find(|score| score.player_chain == winner)
+                .map(|score| score.candies_collected)
+                .unwrap_or(0);
+            let mut hall_of_fame = self.state.hall_of_fame.get().clone();
+            hall_of_fame.battle_royale_winners.push(HallOfFameRecord {
+                title: event.name.clone(),
+                chain_id: winner,
+                player_name: self.state.player_names.get(&winner).await.ok().flatten(),
+                score,
+                timestamp,
+            });
+            self.state.hall_of_fame.set(hall_of_fame);
+            self.send_to_chain(winner, GameMessage::BattleRoyaleWon { event_id: event.event_id }).await;
+            log_event!(self, Info, "ROYALE", "Battle royale {} won by {:?}", event.event_id, winner);
+        } else if let Some(bot_id) = event.winning_bot {
+            // Bots are not players, so their wins stay out of the hall of fame
+            log_event!(self, Info, "ROYALE", "Battle royale {} won by bot {}", event.event_id, bot_id);
+        } else {
+            log_event!(self, Info, "ROYALE", "Battle royale {} ended without players", event.event_id);
+        }
+        
+        let event_id = event.event_id;
+        let _ = self.state.battle_royales.insert(&event_id, event);
+    }
+    
+    /// Store a team match and push the new state to all four players
+    async fn publish_team_match(&mut self, team_match: TeamMatch) {
+        let _ = self.state.team_matches.insert(&team_match.match_id, team_match.clone());
+        for player in team_match.players.clone() {
+            self.send_to_chain(player, GameMessage::TeamMatchUpdated { team_match: team_match.clone() }).await;
+        }
+    }
+    
+    /// Expire the matches the other team did not accept within the window
+    async fn expire_proposed_team_matches(&mut self) {
+        let now = self.runtime.system_time().micros();
+        let window = self.runtime.application_parameters().team_match_accept_window_secs * 1_000_000;
+        for match_id in self.state.proposed_team_matches.indices().await.unwrap_or_default() {
+            let Some(mut team_match) = self.state.team_matches.get(&match_id).await.ok().flatten() else {
+                let _ = self.state.proposed_team_matches.remove(&match_id);
+                continue;
+            };
+            if now <= team_match.created_at + window {
+                continue;
+            }
+            team_match.state = TeamMatchState::Expired;
+            team_match.awaiting_team = None;
+            let _ = self.state.proposed_team_matches.remove(&match_id);
+            log_event!(self, Info, "TEAM", "Team match {} expired before it was accepted", match_id);
+            self.publish_team_match(team_match).await;
+        }
+    }
+    
+    /// Store a team war and send its latest state to the members of both teams
+    async fn publish_team_war(&mut self, war: TeamWar) {
+        let _ = self.state.team_wars.insert(&war.war_id, war.clone());
+        for team_id in [war.team_a, war.team_b] {
+            let members = self.state.teams.get(&team_id).await.ok().flatten().map(|team| team.members).unwrap_or_default();
+            for member in members {
+                self.send_to_chain(member, GameMessage::TeamWarUpdated { war: war.clone() }).await;
+            }
+        }
+    }
+    
+    /// Add a member's game to their team's total in a running war
+    async fn record_team_war_score(&mut self, war_id: u64, player_chain: ChainId, candies_collected: u32) {
+        let now = self.runtime.system_time().micros();
+        let Some(mut war) = self.state.team_wars.get(&war_id).await.ok().flatten().filter(|war| war.is_running(now)) else {
+            reject_message!(self, "TEAM", "Ignoring score from {:?} for team war {}, which is not running", player_chain, war_id);
+            return;
+        };
+        
+        let lead_before = war.team_a_score.cmp(&war.team_b_score);
+        let mut counted = false;
+        for (team_id, score) in [(war.team_a, &mut war.team_a_score), (war.team_b, &mut war.team_b_score)] {
+            let is_member = self.state.teams.get(&team_id).await.ok().flatten()
+                .is_some_and(|team| team.members.contains(&player_chain));
+            if is_member {
+                *score += candies_collected as u64;
+                counted = true;
+            }
+        }
+        if !counted {
+            reject_message!(self, "TEAM", "{:?} fights on neither side of team war {}", player_chain, war_id);
+            return;
+        }
+        // Members hear about the totals when the lead changes and when the war settles, rather
+        // than on every game
+        if war.team_a_score.cmp(&war.team_b_score) == lead_before {
+            let _ = self.state.team_wars.insert(&war_id, war);
+        } else {
+            self.publish_team_war(war).await;
+        }
+    }
+    
+    /// Settle the wars whose window has closed, awarding a trophy to the winning team
+    async fn settle_finished_team_wars(&mut self) {
+        let now = self.runtime.system_time().micros();
+        let war_ids = self.state.open_team_wars.indices().await.unwrap_or_default();
+        for war_id in war_ids {
+            let Some(mut war) = self.state.team_wars.get(&war_id).await.ok().flatten() else {
+                continue;
+            };
+            if now < war.ends_at {
+                continue;
+            }
+            
+            war.state = TeamWarState::Settled;
+            war.winner = match war.team_a_score.cmp(&war.team_b_score) {
+                Ordering::Greater => Some(war.team_a),
+                Ordering::Less => Some(war.team_b),
+                Ordering::Equal => None,
+            };
+            if let Some(winner) = war.winner {
+                if let Ok(Some(mut team)) = self.state.teams.get(&winner).await {
+                    team.war_trophies += 1;
+                    let _ = self.state.teams.insert(&winner, team);
+                }
+            }
+            let _ = self.state.open_team_wars.remove(&war_id);
+            log_event!(self, Info, "TEAM", "Team war {} settled {}-{}", war_id, war.team_a_score, war.team_b_score);
+            self.publish_team_war(war).await;
+        }
+    }
+    
+    /// Finalize every tournament whose window has closed
+    async fn finalize_closed_tournaments(&mut self) {
+        let now = self.runtime.system_time().micros();
+        let tournament_ids = self.state.open_tournaments.indices().await.unwrap_or_default();
+        for tournament_id in tournament_ids {
+            let Some(tournament) = self.state.tournaments.get(&tournament_id).await.ok().flatten() else {
+                continue;
+            };
+            if now > tournament.ends_at {
+                self.finalize_tournament(tournament).await;
+            }
+        }
+    }
+    
+    /// Rank a closed tournament by best attempt and send every entrant its result. Without a
+    /// bracket the prize is split across the top entrants and the winner enters the hall of
+    /// fame; with one, the top qualifiers are seeded into the knockout finals instead
+    async fn finalize_tournament(&mut self, mut tournament: Tournament) {
+        // The earlier of two equal scores ranks higher
+        tournament.standings.sort_by(|a, b| b.best_score.cmp(&a.best_score).then(a.best_at.cmp(&b.best_at)));
+        let split = self.runtime.application_parameters().prize_split_percent;
+        let has_bracket = tournament.bracket_size > 0;
+        for (position, entry) in tournament.standings.iter_mut().enumerate() {
+            entry.rank = Some(position as u32 + 1);
+            entry.prize_coins = split.get(position)
+                .filter(|_| !has_bracket)
+                .map(|percent| tournament.prize_coins * *percent as u64 / 100)
+                .unwrap_or(0);
+        }
+        tournament.state = TournamentState::Finalized;
+        
+        if let Some(winner) = tournament.standings.first().cloned().filter(|_| !has_bracket) {
+            self.record_tournament_winner(&tournament.name, winner.player_chain, winner.best_score).await;
+        }
+        for entry in tournament.standings.clone() {
+            let message = GameMessage::TournamentResult { tournament_id: tournament.tournament_id, entry: entry.clone() };
+            self.send_to_chain(entry.player_chain, message).await;
+        }
+        log_event!(self, Info, "TOURNAMENT", "Tournament {} finalized with {} entrants", tournament.tournament_id, tournament.standings.len());
+        
+        let tournament_id = tournament.tournament_id;
+        let _ = self.state.open_tournaments.remove(&tournament_id);
+        let _ = self.state.tournaments.insert(&tournament_id, tournament.clone());
+        if has_bracket && !tournament.standings.is_empty() {
+            self.start_bracket(&tournament).await;
+        }
+    }
+    
+    /// Add a tournament champion to the hall of fame
+    async fn record_tournament_winner(&mut self, title: &str, chain_id: ChainId, score: u32) {
+        let mut hall_of_fame = self.state.hall_of_fame.get().clone();
+        hall_of_fame.tournament_winners.push(HallOfFameRecord {
+            title: title.to_string(),
+            chain_id,
+            player_name: self.state.player_names.get(&chain_id).await.ok().flatten(),
+            score,
+            timestamp: self.runtime.system_time().micros(),
+        });
+        self.state.hall_of_fame.set(hall_of_fame);
+    }
+    
+    /// Seed the top qualifiers into a knockout bracket: the first seed meets the last, the
+    /// second the second to last, and so on; missing seeds are byes. With fewer qualifiers than
+    /// places the bracket shrinks to the smallest power of two that holds them all, so every
+    /// first-round match has at least one player
+    async fn start_bracket(&mut self, tournament: &Tournament) {
+        let size = tournament.standings.len()
+            .next_power_of_two()
+            .clamp(2, tournament.bracket_size as usize);
+        let seeds: Vec<ChainId> = tournament.standings.iter().take(size).map(|entry| entry.player_chain).collect();
+        let players = (0..size / 2)
+            .flat_map(|index| [seeds.get(index).copied(), seeds.get(size - 1 - index).copied()])
+            .collect();
+        let mut bracket = Bracket {
+            tournament_id: tournament.tournament_id,
+            round: 0,
+            round_seed: 0,
+            round_ends_at: 0,
+            matches: Vec::new(),
+            champion: None,
+        };
+        self.open_bracket_round(&mut bracket, tournament.bracket_round_secs, players).await;
+        let _ = self.state.open_brackets.insert(&tournament.tournament_id);
+        self.advance_bracket(bracket).await;
+    }
+    
+    /// Pair `players` in order into the next round of a bracket and tell each player its opponent
+    async fn open_bracket_round(&mut self, bracket: &mut Bracket, round_secs: u64, players: Vec<Option<ChainId>>) {
+        let now = self.runtime.system_time().micros();
+        bracket.round += 1;
+        bracket.round_seed = session_seed(&format!("bracket_{}_{}", bracket.tournament_id, bracket.round), now);
+        bracket.round_ends_at = now + round_secs * 1_000_000;
+        
+        for pair in players.chunks(2) {
+            let mut bracket_match = BracketMatch {
+                round: bracket.round,
+                player_a: pair[0],
+                player_b: pair.get(1).copied().flatten(),
+                score_a: None,
+                score_b: None,
+                winner: None,
+            };
+            bracket_match.winner = bracket_match.decide(false);
+            if let (Some(player_a), Some(player_b)) = (bracket_match.player_a, bracket_match.player_b) {
+                for (player_chain, opponent) in [(player_a, player_b), (player_b, player_a)] {
+                    let message = GameMessage::BracketRound {
+                        tournament_id: bracket.tournament_id,
+                        round: bracket.round,
+                        round_seed: bracket.round_seed,
+                        round_ends_at: bracket.round_ends_at,
+                        opponent,
+                    };
+                    self.send_to_chain(player_chain, message).await;
+                }
+            }
+            bracket.matches.push(bracket_match);
+        }
+        log_event!(self, Info, "TOURNAMENT", "Round {} of the bracket of tournament {} opened", bracket.round, bracket.tournament_id);
+    }
+    
+    /// Decide the matches of the current round that can be decided, and once all are, knock out
+    /// the losers and open the next round; the last winner standing is the champion
+    async fn advance_bracket(&mut self, mut bracket: Bracket) {
+        let Some(tournament) = self.state.tournaments.get(&bracket.tournament_id).await.ok().flatten() else {
+            return;
+        };
+        let split = self.runtime.application_parameters().prize_split_percent;
+        let share = |position: usize| split.get(position).map(|percent| tournament.prize_coins * *percent as u64 / 100).unwrap_or(0);
+        let now = self.runtime.system_time().micros();
+        
+        loop {
+            let round = bracket.round;
+            let round_over = now > bracket.round_ends_at;
+            for bracket_match in bracket.matches.iter_mut().filter(|bracket_match| bracket_match.round == round && bracket_match.winner.is_none()) {
+                bracket_match.winner = bracket_match.decide(round_over);
+            }
+            let current: Vec<BracketMatch> = bracket.matches.iter().filter(|bracket_match| bracket_match.round == round).cloned().collect();
+            if current.iter().any(|bracket_match| bracket_match.winner.is_none()) {
+                break;
+            }
+            
+            let winners: Vec<Option<ChainId>> = current.iter().map(|bracket_match| bracket_match.winner).collect();
+            let is_final = winners.len() == 1;
+            for loser in current.iter().filter_map(|bracket_match| bracket_match.loser()) {
+                let prize_coins = if is_final { share(1) } else { 0 };
+                self.send_to_chain(loser, GameMessage::BracketEliminated { tournament_id: bracket.tournament_id, round, prize_coins }).await;
+            }
+            if is_final {
+                let champion = winners[0].expect("a decided match has a winner");
+                bracket.champion = Some(champion);
+                let score = current[0].score_a.max(current[0].score_b).unwrap_or(0);
+                self.record_tournament_winner(&tournament.name, champion, score).await;
+                self.send_to_chain(champion, GameMessage::BracketWon { tournament_id: bracket.tournament_id, prize_coins: share(0) }).await;
+                let _ = self.state.open_brackets.remove(&bracket.tournament_id);
+                log_event!(self, Info, "TOURNAMENT", "Tournament {} won by {:?}", bracket.tournament_id, champion);
+                break;
+            }
+            // Byes decide their matches at once, so keep going until a round needs games
+            self.open_bracket_round(&mut bracket, tournament.bracket_round_secs, winners).await;
+        }
+        
+        let tournament_id = bracket.tournament_id;
+        let _ = self.state.brackets.insert(&tournament_id, bracket);
+    }
+    
+    /// Close the knockout rounds whose time ran out
+    async fn advance_timed_out_brackets(&mut self) {
+        let now = self.runtime.system_time().micros();
+        let tournament_ids = self.state.open_brackets.indices().await.unwrap_or_default();
+        for tournament_id in tournament_ids {
+            if let Some(bracket) = self.state.brackets.get(&tournament_id).await.ok().flatten() {
+                if now > bracket.round_ends_at {
+                    self.advance_bracket(bracket).await;
+                }
+            }
+        }
+    }
+    
+    /// Count a member's game towards an open team match, settling it once all four have played
+    async fn record_team_match_score(&mut self, match_id: u64, player_chain: ChainId, candies_collected: u32) {
+        let Ok(Some(mut team_match)) = self.state.team_matches.get(&match_id).await else {
+            log_event!(self, Warn, "TEAM", "Ignoring score for unknown team match {}", match_id);
+            return;
+        };
+        let already_played = team_match.scores.iter().any(|score| score.player_chain == player_chain);
+        if team_match.state != TeamMatchState::Open || !team_match.players.contains(&player_chain) || already_played {
+            log_event!(self, Warn, "TEAM", "Ignoring score from {:?} for team match {}", player_chain, match_id);
+            return;
+        }
+        team_match.scores.push(TeamMatchScore { player_chain, candies_collected });
+        
+        // The first two players belong to team A, the last two to team B
+        let team_a_members = team_match.players[..2].to_vec();
+        let (team_a_score, team_b_score) = team_match.scores.iter().fold((0u64, 0u64), |(a, b), score| {
+            if team_a_members.contains(&score.player_chain) {
+                (a + score.candies_collected as u64, b)
+            } else {
+                (a, b + score.candies_collected as u64)
+            }
+        });
+        team_match.team_a_score = team_a_score;
+        team_match.team_b_score = team_b_score;
+        
+        if team_match.scores.len() == team_match.players.len() {
+            team_match.state = TeamMatchState::Completed;
+            team_match.winner = match team_a_score.cmp(&team_b_score) {
+                Ordering::Greater => Some(team_match.team_a),
+                Ordering::Less => Some(team_match.team_b),
+                Ordering::Equal => None,
+            };
+            for (team_id, score) in [(team_match.team_a, team_a_score), (team_match.team_b, team_b_score)] {
+                if let Ok(Some(mut team)) = self.state.teams.get(&team_id).await {
+                    team.matches_played += 1;
+                    team.total_score += score;
+                    if team_match.winner == Some(team_id) {
+                        team.wins += 1;
+                    }
+                    let _ = self.state.teams.insert(&team_id, team);
+                }
+            }
+            log_event!(self, Info, "TEAM", "Team match {} completed {}-{}", match_id, team_a_score, team_b_score);
+        }
+        
+        self.publish_team_match(team_match).await;
+    }
+    
+    /// Remove coins from this player's balance, panicking if they cannot afford it
+    fn debit_coins(&mut self, amount: u64) {
+        let balance = *self.state.my_coins.get();
+        if balance < amount {
+            panic!("Insufficient coins: {} available, {} required", balance, amount);
+        }
+        self.state.my_coins.set(balance - amount);
+    }
+    
+    /// Load a challenge on the leaderboard chain, logging why it is unavailable
+    async fn load_challenge(&mut self, challenge_id: u64) -> Option<Challenge> {
+        if !*self.state.is_leaderboard_chain.get() {
+            log_event!(self, Warn, "MESSAGE", "This is NOT the leaderboard chain, ignoring challenge message");
+            return None;
+        }
+        
+        let challenge = self.state.challenges.get(&challenge_id).await.ok().flatten();
+        if challenge.is_none() {
+            log_event!(self, Warn, "CHALLENGE", "Unknown challenge {}", challenge_id);
+        }
+        challenge
+    }
+    
+    /// Store a challenge and push the new state to both participants
+    async fn publish_challenge(&mut self, challenge: Challenge) {
+        let _ = self.state.challenges.insert(&challenge.challenge_id, challenge.clone());
+        let participants = [challenge.challenger.chain_id, challenge.opponent.chain_id];
+        for participant in participants {
+            self.send_to_chain(participant, GameMessage::ChallengeUpdated { challenge: challenge.clone() }).await;
+        }
+    }
+    
+    /// Close a challenge, paying the pot to the winner or returning both stakes
+    async fn settle_challenge(&mut self, mut challenge: Challenge, winner: Option<ChainId>) {
+        let challenge_id = challenge.challenge_id;
+        challenge.state = ChallengeState::Settled;
+        challenge.deadline = None;
+        challenge.winner = winner;
+        let _ = self.state.open_challenges.remove(&challenge_id);
+        
+        match winner {
+            Some(winner) => {
+                let coins = challenge.wager * 2;
+                self.send_to_chain(winner, GameMessage::ChallengePayout { challenge_id, coins }).await;
+                log_event!(self, Info, "CHALLENGE", "Challenge {} won by {:?} ({} coins)", challenge_id, winner, coins);
+            }
+            None => {
+                let coins = challenge.wager;
+                let participants = [challenge.challenger.chain_id, challenge.opponent.chain_id];
+                for participant in participants {
+                    self.send_to_chain(participant, GameMessage::ChallengePayout { challenge_id, coins }).await;
+                }
+                log_event!(self, Info, "CHALLENGE", "Challenge {} ended without a winner, stakes returned", challenge_id);
+            }
+        }
+        
+        self.publish_challenge(challenge).await;
+    }
+    
+    /// Close a challenge whose current stage timed out. An unaccepted challenge is voided and
+    /// the challenger's stake returned; otherwise the side that kept up wins by forfeit, and
+    /// if neither did both stakes are returned.
+    async fn expire_challenge(&mut self, mut challenge: Challenge) {
+        let challenge_id = challenge.challenge_id;
+        if challenge.state != ChallengeState::AwaitingOpponent {
+            log_event!(self, Info, "CHALLENGE", "Challenge {} timed out while {:?}", challenge_id, challenge.state);
+            let winner = challenge.forfeit_winner();
+            self.settle_challenge(challenge, winner).await;
+            return;
+        }
+        
+        challenge.state = ChallengeState::Voided;
+        challenge.deadline = None;
+        let _ = self.state.open_challenges.remove(&challenge_id);
+        let challenger = challenge.challenger.chain_id;
+        let coins = challenge.wager;
+        self.send_to_chain(challenger, GameMessage::ChallengePayout { challenge_id, coins }).await;
+        log_event!(self, Info, "CHALLENGE", "Challenge {} was never accepted, {} coins returned to {:?}", challenge_id, coins, challenger);
+        self.publish_challenge(challenge).await;
+    }
+    
+    /// Time out every open challenge whose current stage has passed its deadline
+    async fn resolve_expired_challenges(&mut self) {
+        let now = self.runtime.system_time().micros();
+        let challenge_ids = self.state.open_challenges.indices().await.unwrap_or_default();
+        for challenge_id in challenge_ids {
+            let Some(challenge) = self.state.challenges.get(&challenge_id).await.ok().flatten() else {
+                continue;
+            };
+            if challenge.is_expired(now) {
+                self.expire_challenge(challenge).await;
+            }
+        }
+    }
+    
+    /// Time out the tickets that waited too long, then pair the others, oldest first, with the
+    /// closest rating inside the older ticket's band, which has widened the most
+    async fn run_matchmaking(&mut self) {
+        let mut tickets: Vec<QueueTicket> = self.state.matchmaking_queue.index_values().await.unwrap_or_default()
+            .into_iter()
+            .map(|(_, ticket)| ticket)
+            .collect();
+        if tickets.is_empty() {
+            return;
+        }
+        let now = self.runtime.system_time().micros();
+        let config = self.runtime.application_parameters().matchmaking;
+        tickets.sort_by_key(|ticket| ticket.queued_at);
+        
+        let mut waiting = Vec::new();
+        for ticket in tickets {
+            if config.timed_out(ticket.queued_at, now) {
+                self.time_out_matchmaking(ticket).await;
+            } else {
+                waiting.push(ticket);
+            }
+        }
+        
+        let mut index = 0;
+        while index < waiting.len() {
+            let rating = waiting[index].rating;
+            let band = config.band_at(waiting[index].queued_at, now);
+            let partner = waiting.iter()
+                .enumerate()
+                .skip(index + 1)
+                .filter(|(_, other)| other.rating.abs_diff(rating) <= band)
+                .min_by_key(|(_, other)| other.rating.abs_diff(rating))
+                .map(|(position, _)| position);
+            match partner {
+                Some(position) => {
+                    let opponent = waiting.remove(position);
+                    let ticket = waiting.remove(index);
+                    self.open_matched_challenge(ticket, opponent).await;
+                }
+                None => index += 1,
+            }
+        }
+    }
+    
+    /// Pair two queued players in a challenge without a wager, already accepted by both
+    async fn open_matched_challenge(&mut self, first: QueueTicket, second: QueueTicket) {
+        let _ = self.state.matchmaking_queue.remove(&first.player_chain);
+        let _ = self.state.matchmaking_queue.remove(&second.player_chain);
+        
+        let challenge_id = *self.state.challenge_counter.get();
+        self.state.challenge_counter.set(challenge_id + 1);
+        let now = self.runtime.system_time().micros();
+        let window = self.runtime.application_parameters().challenge_commit_window_secs;
+        let challenge = Challenge {
+            challenge_id,
+            challenger: ChallengeSide::new(first.player_chain),
+            opponent: ChallengeSide::new(second.player_chain),
+            wager: 0,
+            state: ChallengeState::Committing,
+            created_at: now,
+            accepted_at: Some(now),
+            deadline: Some(now + window * 1_000_000),
+            winner: None,
+        };
+        let _ = self.state.open_challenges.insert(&challenge_id);
+        log_event!(self, Info, "MATCHMAKING", "Matched {:?} ({}) with {:?} ({}) in challenge {}",
+            first.player_chain, first.rating, second.player_chain, second.rating, challenge_id);
+        self.publish_challenge(challenge).await;
+        
+        for (ticket, opponent) in [(&first, second.player_chain), (&second, first.player_chain)] {
+            let status = MatchmakingStatus {
+                challenge_id: Some(challenge_id),
+                opponent: Some(opponent),
+                ..queue_status(ticket, MatchmakingState::Matched)
+            };
+            self.send_to_chain(ticket.player_chain, GameMessage::MatchmakingUpdate { status }).await;
+        }
+    }
+    
+    /// Take a ticket that waited too long out of the queue and send the player the ghost of the
+    /// raceable record closest to their rating; with no ghost to race, they play a solo run
+    async fn time_out_matchmaking(&mut self, ticket: QueueTicket) {
+        let _ = self.state.matchmaking_queue.remove(&ticket.player_chain);
+        
+        let mut ghost: Option<Ghost> = None;
+        for (_, anchor) in self.state.move_log_anchors.index_values().await.unwrap_or_default() {
+            if anchor.player_chain == ticket.player_chain {
+                continue;
+            }
+            let closer = ghost.as_ref()
+                .is_none_or(|ghost| anchor.candies_collected.abs_diff(ticket.rating) < ghost.candies_collected.abs_diff(ticket.rating));
+            if closer {
+                if let Some(candidate) = self.raceable_ghost(anchor).await {
+                    ghost = Some(candidate);
+                }
+            }
+        }
+        
+        log_event!(self, Info, "MATCHMAKING", "{:?} was not matched in time, racing {:?}", ticket.player_chain,
+            ghost.as_ref().map(|ghost| &ghost.session_id));
+        let status = MatchmakingStatus {
+            ghost: ghost.as_ref().map(|ghost| ghost.session_id.clone()),
+            ..queue_status(&ticket, MatchmakingState::TimedOut)
+        };
+        if let Some(ghost) = ghost {
+            self.runtime.send_message(ticket.player_chain, GameMessage::GhostReplay { ghost }.into());
+        }
+        self.send_to_chain(ticket.player_chain, GameMessage::MatchmakingUpdate { status }).await;
+    }
+    
+    /// The ghost of an anchored level game, if its replay is published and was not rejected
+    async fn raceable_ghost(&self, anchor: MoveLogAnchor) -> Option<Ghost> {
+        let replay_blob = anchor.replay_blob.filter(|_| anchor.map_hash.is_none() && anchor.verdict != ReplayVerdict::Rejected)?;
+        Some(Ghost {
+            player_name: self.state.player_names.get(&anchor.player_chain).await.ok().flatten(),
+            session_id: anchor.session_id,
+            player_chain: anchor.player_chain,
+            candies_collected: anchor.candies_collected,
+            seed: anchor.seed,
+            level_id: anchor.level_id,
+            replay_blob,
+        })
+    }
+    
+    /// How many players left each emote on a player's entry, most used first
+    async fn reaction_counts(&self, player_chain: ChainId) -> Vec<ReactionCount> {
+        let reactions = self.state.reactions.get(&player_chain).await.ok().flatten().unwrap_or_default();
+        let mut counts: BTreeMap<Emote, u32> = BTreeMap::new();
+        for emote in reactions.into_values() {
+            *counts.entry(emote).or_default() += 1;
+        }
+        let mut counts: Vec<ReactionCount> = counts.into_iter()
+            .map(|(emote, count)| ReactionCount { emote, count })
+            .collect();
+        counts.sort_by(|a, b| b.count.cmp(&a.count));
+        counts
+    }
+    
+    /// Add a notification to this chain's inbox, dropping the oldest beyond the limit
+    fn push_notification(&mut self, kind: NotificationKind, message: String, other_player: Option<ChainId>, ranks: Option<(u32, u32)>) {
+        self.state.notifications.push_back(Notification {
+            kind,
+            message,
+            other_player,
+            old_rank: ranks.map(|(old_rank, _)| old_rank),
+            new_rank: ranks.map(|(_, new_rank)| new_rank),
+            received_at: self.runtime.system_time().micros(),
+        });
+        while self.state.notifications.count() > MAX_NOTIFICATIONS {
+            self.state.notifications.delete_front();
+        }
+    }
+    
+    /// Forward a player's new record to everyone following them
+    async fn notify_followers(&mut self, player_chain: ChainId, candies_collected: u32) {
+        let followers = self.state.followers.get(&player_chain).await.ok().flatten().unwrap_or_default();
+        if followers.is_empty() {
+            return;
+        }
+        let player_name = self.state.player_names.get(&player_chain).await.ok().flatten();
+        for follower in followers {
+            let message = GameMessage::FollowedRecord { player_chain, player_name: player_name.clone(), candies_collected };
+            self.send_to_chain(follower, message).await;
+        }
+    }
+    
+    /// Tell each player pushed down from the top of the board who moved ahead of them
+    /// Only the top `INDEXED_TOP_SIZE` positions are notified, so one new entry near the top
+    /// sends a bounded number of messages rather than one to every row below it
+    async fn notify_overtaken_players(&mut self, changes: &[LeaderboardChange]) {
+        for displaced in changes {
+            let (Some(old_rank), Some(new_rank)) = (displaced.previous_position, displaced.position) else {
+                continue;
+            };
+            if new_rank <= old_rank || old_rank as usize > INDEXED_TOP_SIZE {
+                continue;
+            }
+            // Whoever is now above them but was below them, or not on the board, overtook them
+            let overtaker = changes.iter().find(|change| {
+                change.position.is_some_and(|position| position < new_rank)
+                    && change.previous_position.is_none_or(|position| position > old_rank)
+            });
+            let Some(overtaker) = overtaker else {
+                continue;
+            };
+            
+            let message = GameMessage::Overtaken {
+                by: overtaker.chain_id,
+                by_name: overtaker.entry.as_ref().and_then(|entry| entry.player_name.clone()),
+                old_rank,
+                new_rank,
+            };
+            self.send_to_chain(displaced.chain_id, message).await;
+        }
+    }
+    
+    /// Send a subscriber the top of the board it asked for
+    async fn push_leaderboard(&mut self, subscriber: ChainId) {
+        let Some(mut subscription) = self.state.leaderboard_subscribers.get(&subscriber).await.ok().flatten() else {
+            return;
+        };
+        let entries: Vec<LeaderboardEntry> = self.state.leaderboard_seen_by(subscriber).await
+            .into_iter()
+            .take(subscription.limit as usize)
+            .collect();
+        let version = *self.state.leaderboard_version.get();
+        self.runtime.send_message(subscriber, GameMessage::LeaderboardSnapshot { version, entries }.into());
+        
+        subscription.last_pushed_at = Some(self.runtime.system_time().micros());
+        let _ = self.state.leaderboard_subscribers.insert(&subscriber, subscription);
+        let _ = self.state.pending_leaderboard_pushes.remove(&subscriber);
+    }
+    
+    /// Whether a subscriber's throttling window has passed since its last push
+    async fn push_window_open(&mut self, subscriber: ChainId) -> bool {
+        let interval = self.runtime.application_parameters().leaderboard_push_interval_secs * 1_000_000;
+        let now = self.runtime.system_time().micros();
+        self.state.leaderboard_subscribers.get(&subscriber).await
+            .ok()
+            .flatten()
+            .and_then(|subscription| subscription.last_pushed_at)
+            .is_none_or(|last_pushed_at| now >= last_pushed_at + interval)
+    }
+    
+    /// Push the new podium to every subscriber, holding it back for those pushed to recently
+    async fn notify_leaderboard_subscribers(&mut self) {
+        let subscribers = self.state.leaderboard_subscribers.indices().await.unwrap_or_default();
+        for subscriber in subscribers {
+            if self.push_window_open(subscriber).await {
+                self.push_leaderboard(subscriber).await;
+            } else {
+                let _ = self.state.pending_leaderboard_pushes.insert(&subscriber);
+            }
+        }
+    }
+    
+    /// Send the podium updates that were held back once their throttling window has passed
+    async fn flush_leaderboard_pushes(&mut self) {
+        let pending = self.state.pending_leaderboard_pushes.indices().await.unwrap_or_default();
+        for subscriber in pending {
+            if self.push_window_open(subscriber).await {
+                self.push_leaderboard(subscriber).await;
+            }
+        }
+    }
+    
+    /// Take a dispute out of the review queue with the moderator's decision
+    async fn close_dispute(&mut self, dispute_id: u64, outcome: DisputeState) {
+        let mut dispute = self.state.disputes.get(&dispute_id).await
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| panic!("Unknown dispute {}", dispute_id));
+        if dispute.state != DisputeState::Pending {
+            panic!("Dispute {} was already closed as {:?}", dispute_id, dispute.state);
+        }
+        
+        let player_chain = dispute.player_chain;
+        let flagged_score = dispute.flagged_score.unwrap_or_default();
+        let session_id = dispute.session_id.clone();
+        dispute.state = outcome;
+        dispute.resolved_at = Some(self.runtime.system_time().micros());
+        let _ = self.state.disputes.insert(&dispute_id, dispute);
+        let _ = self.state.review_queue.remove(&dispute_id);
+        
+        if outcome != DisputeState::Dismissed {
+            let _ = self.state.upheld_players.insert(&player_chain);
+        }
+        log_event!(self, Info, "DISPUTE", "Dispute {} against {:?} closed as {:?}", dispute_id, player_chain, outcome);
+        
+        if outcome == DisputeState::Struck {
+            self.add_strike(player_chain, format!("Dispute {} was upheld", dispute_id), session_id, flagged_score).await;
+        } else {
+            self.rebuild_global_leaderboard().await;
+        }
+    }
+    
+    /// Record a strike against a player and apply the penalty their strike count has reached.
+    /// Every penalty voids the offending game's score; repeat offenders are also banned from the board.
+    async fn add_strike(&mut self, player_chain: ChainId, reason: String, session_id: String, score: u32) {
+        let strikes = self.state.player_strikes.get(&player_chain).await.ok().flatten().unwrap_or_default() + 1;
+        let _ = self.state.player_strikes.insert(&player_chain, strikes);
+        
+        let parameters = self.runtime.application_parameters();
+        let kind = parameters.strike_policy.penalty_for(strikes);
+        let now = self.runtime.system_time().micros();
+        let mut voided_score = 0;
+        if let Ok(Some(mut stats)) = self.state.player_stats.get(&player_chain).await {
+            voided_score = stats.void_record(score, &parameters.composite_weights);
+            let _ = self.state.player_stats.insert(&player_chain, stats);
+        }
+        
+        let penalty = PenaltyRecord {
+            strike: strikes,
+            kind,
+            reason,
+            voided_score,
+            applied_at: now,
+            expires_at: (kind == PenaltyKind::TemporaryBan)
+                .then(|| now + parameters.strike_policy.temporary_ban_secs * 1_000_000),
+        };
+        if kind != PenaltyKind::ScoreVoided {
+            let _ = self.state.active_bans.insert(&player_chain, penalty.clone());
+        }
+        let mut history = self.state.penalty_history.get(&player_chain).await.ok().flatten().unwrap_or_default();
+        history.push(penalty.clone());
+        let _ = self.state.penalty_history.insert(&player_chain, history);
+        log_event!(self, Warn, "PENALTY", "Strike {} for {:?} ({}): {:?}, voided score {}", strikes, player_chain, penalty.reason, kind, voided_score);
+        
+        self.send_to_chain(player_chain, GameMessage::PenaltyApplied { penalty, session_id }).await;
+        self.rebuild_global_leaderboard().await;
+    }
+    
+    /// Record that a session's pace is not humanly possible, once
+    async fn mark_session_suspect(&mut self, session_id: String, player_chain: ChainId, reason: String) {
+        if self.state.suspect_sessions.contains_key(&session_id).await.unwrap_or(false) {
+            return;
+        }
+        log_event!(self, Warn, "VALIDATION", "Session {} of {:?} is suspect: {}", session_id, player_chain, reason);
+        let suspect = SuspectSession {
+            session_id: session_id.clone(),
+            player_chain,
+            reason,
+            flagged_at: self.runtime.system_time().micros(),
+        };
+        let _ = self.state.suspect_sessions.insert(&session_id, suspect);
+    }
+    
+    /// Count a result against its origin chain's rate limit on the leaderboard chain,
+    /// returning whether it is within the limit and should be handled
+    async fn admit_submission(&mut self) -> bool {
+        let limit = self.runtime.application_parameters().submission_rate_limit;
+        if !*self.state.is_leaderboard_chain.get() || limit.max_messages == 0 {
+            return true;
+        }
+        let Some(origin) = self.runtime.message_origin_chain_id() else {
+            return true;
+        };
+        
+        let now = self.runtime.system_time().micros();
+        let mut window = self.state.submission_windows.get(&origin).await.ok().flatten().unwrap_or_default();
+        let admitted = window.admit(now, &limit);
+        let _ = self.state.submission_windows.insert(&origin, window);
+        
+        if !admitted {
+            log_event!(self, Warn, "RATE_LIMIT", "{:?} sent more than {} results in {}s, dropping", origin, limit.max_messages, limit.window_secs);
+            self.increment_metric(metrics::MESSAGES_RATE_LIMITED, 1).await;
+        }
+        admitted
+    }
+    
+    /// Why a score reported by a player chain must not count: the chain was merged into another,
+    /// its player is banned, or the session is suspect or its pace impossible
+    async fn reported_score_rejection(&mut self, player_chain: ChainId, session_id: &str, candies_collected: u32, ticks: u64, started_at: u64) -> Option<String> {
+        if let Ok(Some(tombstone)) = self.state.account_tombstones.get(&player_chain).await {
+            return Some(format!("This chain's history was merged into {}", tombstone.merged_into));
+        }
+        let now = self.runtime.system_time().micros();
+        if self.is_banned(player_chain, now).await {
+            return Some("This chain is banned".to_string());
+        }
+        let grace_ticks = self.runtime.application_parameters().candy_rate_grace_ticks;
+        if let Some(reason) = implausible_candy_rate(candies_collected, ticks, now.saturating_sub(started_at), grace_ticks) {
+            self.mark_session_suspect(session_id.to_string(), player_chain, reason).await;
+        }
+        if self.state.suspect_sessions.contains_key(&session_id.to_string()).await.unwrap_or(false) {
+            return Some("The session's pace is implausible".to_string());
+        }
+        None
+    }
+    
+    /// Count a chat message against its sender's rate limit, returning whether to deliver it
+    async fn admit_chat(&mut self, sender: ChainId) -> bool {
+        let limit = self.runtime.application_parameters().chat_rate_limit;
+        let now = self.runtime.system_time().micros();
+        let mut window = self.state.chat_windows.get(&sender).await.ok().flatten().unwrap_or_default();
+        let admitted = window.admit(now, &limit);
+        let _ = self.state.chat_windows.insert(&sender, window);
+        
+        if !admitted {
+            log_event!(self, Warn, "RATE_LIMIT", "{:?} sent more than {} chat messages in {}s, dropping", sender, limit.max_messages, limit.window_secs);
+            self.increment_metric(metrics::MESSAGES_RATE_LIMITED, 1).await;
+        }
+        admitted
+    }
+    
+    /// Keep a chat message in this chain's inbox, dropping the oldest beyond the limit
+    fn store_chat(&mut self, message: ChatMessage) {
+        self.state.chat_inbox.push_back(message);
+        while self.state.chat_inbox.count() > MAX_CHAT_MESSAGES {
+            self.state.chat_inbox.delete_front();
+        }
+    }
+    
+    /// Whether a chain has played enough verified games, or staked enough coins, to be ranked
+    async fn is_eligible(&mut self, player_chain: ChainId) -> bool {
+        let parameters = self.runtime.application_parameters();
+        let verified_games = self.state.verified_games.get(&player_chain).await.ok().flatten().unwrap_or_default();
+        if verified_games >= parameters.min_verified_games {
+            return true;
+        }
+        let staked = self.state.eligibility_stakes.get(&player_chain).await.ok().flatten().unwrap_or_default();
+        parameters.eligibility_stake_coins > 0 && staked >= parameters.eligibility_stake_coins
+    }
+    
+    /// Whether a player is serving a ban at `now`
+    async fn is_banned(&self, player_chain: ChainId, now: u64) -> bool {
+        self.state.active_bans.get(&player_chain).await
+            .ok()
+            .flatten()
+            .map(|ban| ban.bans_at(now))
+            .unwrap_or(false)
+    }
+    
+    /// Send a message, handling it in place when the destination is this chain
+    async fn send_to_chain(&mut self, destination: ChainId, message: GameMessage) {
+        if destination == self.runtime.chain_id() {
+            Box::pin(self.handle_message(message)).await;
+        } else {
+            self.runtime.send_message(destination, message.into());
+        }
+    }
+    
+    /// Remember a result sent to the leaderboard chain until it confirms receipt
+    async fn track_pending_result(&mut self, session_id: &str) {
+        let session_id = session_id.to_string();
+        let update = PendingUpdate {
+            kind: PendingUpdateKind::GameResult,
+            subject: session_id.clone(),
+            sent_at: self.runtime.system_time().micros(),
+        };
+        let _ = self.state.pending_results.insert(&session_id, update);
+        self.set_leaderboard_status(&session_id, LeaderboardStatus::Sent, None).await;
+    }
+    
+    /// The GameFinished report of a finished session, as it is sent to the leaderboard chain
+    fn game_finished_message(session: &GameSession, player_chain: ChainId) -> GameMessage {
+        GameMessage::GameFinished {
+            session_id: session.session_id.clone(),
+            player_chain,
+            candies_collected: session.candies_collected,
+            is_new_record: session.is_record,
+            move_log_hash: session.move_log_hash.clone(),
+            seed: session.seed,
+            level_id: session.level_id,
+            map_hash: session.map_hash.clone(),
+            points: session.combo.points,
+            best_combo: session.combo.best,
+            match_id: session.match_id,
+            ticks: session.ticks,
+            started_at: session.start_time,
+        }
+    }
+    
+    /// Send the results and name update the previous leaderboard chain never answered to the new
+    /// one, which would otherwise stay pending after a move
+    async fn resend_pending_updates(&mut self, new_chain: ChainId) {
+        let current_chain = self.runtime.chain_id();
+        for session_id in self.state.pending_results.indices().await.unwrap_or_default() {
+            if let Some(session) = self.state.session(&session_id).await {
+                log_event!(self, Info, "SETUP", "Re-sending the result of session {} to {:?}", session_id, new_chain);
+                self.runtime.send_message(new_chain, Self::game_finished_message(&session, current_chain).into());
+                self.track_pending_result(&session_id).await;
+            }
+        }
+        if self.state.pending_name_update.get().is_some() {
+            if let Some(player_name) = self.state.my_player_name.get().clone() {
+                let message = GameMessage::UpdatePlayerName { player_chain: current_chain, player_name };
+                self.runtime.send_message(new_chain, message.into());
+            }
+        }
+    }
+    
+    /// Record where a game's result stands with the leaderboard chain
+    async fn set_leaderboard_status(&mut self, session_id: &str, status: LeaderboardStatus, rejection: Option<String>) {
+        if let Some(mut session) = self.state.session(session_id).await {
+            session.leaderboard_status = status;
+            session.leaderboard_rejection = rejection;
+            self.state.save_session(session).await;
+        }
+    }
+    
+    /// Stop waiting on a name update once the leaderboard chain answered for that name
+    fn clear_pending_name_update(&mut self, name: &str) {
+        if self.state.pending_name_update.get().as_ref().is_some_and(|update| update.subject == name) {
+            self.state.pending_name_update.set(None);
+        }
+    }
+    
+    /// Whether a feature is switched on, falling back to its default until the admin sets it
+    async fn feature_enabled(&self, key: &str) -> bool {
+        self.state.feature_flags.get(&key.to_string()).await.ok().flatten().unwrap_or_else(|| feature_default(key))
+    }
+    
+    /// Why a player may not use a name, if it is reserved for or used by someone else
+    async fn name_rejection(&self, player_chain: ChainId, name: &str) -> Option<String> {
+        let key = name_key(name);
+        if let Ok(Some(owner)) = self.state.name_handles.get(&key).await {
+            if owner != player_chain {
+                return Some(format!("The name '{}' is taken", name));
+            }
+        }
+        let reservation = self.state.reserved_names.get(&key).await.ok().flatten()?;
+        reservation.blocks(player_chain).then(|| format!("The name '{}' is reserved", reservation.name))
+    }
+    
+    /// Store a player's name, keeping the name it replaces in the rename history
+    async fn record_player_name(&mut self, player_chain: ChainId, name: String) {
+        if let Ok(Some(previous_name)) = self.state.player_names.get(&player_chain).await {
+            let _ = self.state.name_handles.remove(&name_key(&previous_name));
+            if previous_name != name {
+                let mut history = self.state.name_history.get(&player_chain).await.ok().flatten().unwrap_or_default();
+                history.push(NameChange {
+                    previous_name,
+                    changed_at: self.runtime.system_time().micros(),
+                });
+                let excess = history.len().saturating_sub(MAX_NAME_HISTORY);
+                history.drain(..excess);
+                let _ = self.state.name_history.insert(&player_chain, history);
+            }
+        }
+        let _ = self.state.name_handles.insert(&name_key(&name), player_chain);
+        let _ = self.state.player_names.insert(&player_chain, name);
+    }
+    
+    /// Move everything the leaderboard chain knows about `old_chain` over to `new_chain`,
+    /// leaving a tombstone so the old chain's results are no longer counted
+    async fn merge_accounts(&mut self, old_chain: ChainId, new_chain: ChainId) {
+        let now = self.runtime.system_time().micros();
+        let weights = self.runtime.application_parameters().composite_weights;
+        
+        if let Ok(Some(old_stats)) = self.state.player_stats.get(&old_chain).await {
+            let mut stats = self.state.player_stats.get(&new_chain).await
+                .ok()
+                .flatten()
+                .unwrap_or_else(|| PlayerStats::new(new_chain));
+            stats.merge(old_stats, &weights);
+            let _ = self.state.player_stats.insert(&new_chain, stats);
+            let _ = self.state.player_stats.remove(&old_chain);
+        }
+        
+        // Each achievement keeps its earliest unlock
+        let old_achievements = self.state.player_achievements.get(&old_chain).await.ok().flatten().unwrap_or_default();
+        if !old_achievements.is_empty() {
+            let mut achievements = self.state.player_achievements.get(&new_chain).await.ok().flatten().unwrap_or_default();
+            for unlock in old_achievements {
+                match achievements.iter_mut().find(|existing| existing.achievement == unlock.achievement) {
+                    Some(existing) => existing.unlocked_at = existing.unlocked_at.min(unlock.unlocked_at),
+                    None => achievements.push(unlock),
                 }
-                
-                // Store the player name mapping
-                let _ = self.state.player_names.insert(&player_chain, player_name);
-                eprintln!("[MESSAGE] Updated player name for chain {:?}", player_chain);
             }
+            achievements.sort_by_key(|unlock| unlock.unlocked_at);
+            let _ = self.state.player_achievements.insert(&new_chain, achievements);
+            let _ = self.state.player_achievements.remove(&old_chain);
+        }
+        
+        if let Ok(Some(old_level)) = self.state.player_levels.get(&old_chain).await {
+            let level = self.state.player_levels.get(&new_chain).await.ok().flatten().unwrap_or(1);
+            let _ = self.state.player_levels.insert(&new_chain, level.max(old_level));
+            let _ = self.state.player_levels.remove(&old_chain);
+        }
+        if let Ok(Some(old_games)) = self.state.verified_games.get(&old_chain).await {
+            let games = self.state.verified_games.get(&new_chain).await.ok().flatten().unwrap_or_default();
+            let _ = self.state.verified_games.insert(&new_chain, games + old_games);
+            let _ = self.state.verified_games.remove(&old_chain);
+        }
+        // Strikes follow the player, so a fresh chain does not start with a clean record
+        if let Ok(Some(old_strikes)) = self.state.player_strikes.get(&old_chain).await {
+            let strikes = self.state.player_strikes.get(&new_chain).await.ok().flatten().unwrap_or_default();
+            let _ = self.state.player_strikes.insert(&new_chain, strikes + old_strikes);
+        }
+        if self.state.upheld_players.contains(&old_chain).await.unwrap_or(false) {
+            let _ = self.state.upheld_players.insert(&new_chain);
+        }
+        if let Ok(Some(last_active)) = self.state.player_last_active.get(&old_chain).await {
+            let new_last_active = self.state.player_last_active.get(&new_chain).await.ok().flatten().unwrap_or_default();
+            let _ = self.state.player_last_active.insert(&new_chain, new_last_active.max(last_active));
+        }
+        
+        // The old name goes with the history unless the new chain already has one
+        if let Ok(Some(name)) = self.state.player_names.get(&old_chain).await {
+            let _ = self.state.name_handles.remove(&name_key(&name));
+            let _ = self.state.player_names.remove(&old_chain);
+            if self.state.player_names.get(&new_chain).await.ok().flatten().is_none() {
+                self.record_player_name(new_chain, name).await;
+            }
+        }
+        
+        let _ = self.state.leaderboard_participants.insert(&new_chain);
+        let _ = self.state.account_links.remove(&old_chain);
+        let _ = self.state.account_tombstones.insert(&old_chain, AccountTombstone {
+            old_chain,
+            merged_into: new_chain,
+            merged_at: now,
+        });
+        log_event!(self, Info, "LINK", "Merged the history of {:?} into {:?}", old_chain, new_chain);
+        
+        self.send_to_chain(old_chain, GameMessage::AccountMerged { old_chain, new_chain }).await;
+        self.send_to_chain(new_chain, GameMessage::AccountMerged { old_chain, new_chain }).await;
+        self.rebuild_global_leaderboard().await;
+    }
+    
+    /// Send the pending session summaries to the leaderboard chain in one message
+    async fn flush_history(&mut self) {
+        let summaries = self.state.pending_history.elements().await.unwrap_or_default();
+        if summaries.is_empty() {
+            return;
+        }
+        let Some(leaderboard_chain) = *self.state.leaderboard_chain_id.get() else {
+            log_event!(self, Warn, "HISTORY", "No leaderboard chain configured, keeping {} summaries pending", summaries.len());
+            return;
+        };
+        
+        self.state.pending_history.clear();
+        let player_chain = self.runtime.chain_id();
+        self.send_to_chain(leaderboard_chain, GameMessage::HistorySync { player_chain, summaries }).await;
+    }
+    
+    /// Add a donation to its prize pool and the sponsor list
+    async fn record_donation(&mut self, pool: String, sponsor_chain: ChainId, sponsor_name: Option<String>, amount: Amount) {
+        let mut prize_pool = self.state.prize_pools.get(&pool).await
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| PrizePool { name: pool.clone(), ..PrizePool::default() });
+        prize_pool.balance = prize_pool.balance.saturating_add(amount);
+        prize_pool.total_donated = prize_pool.total_donated.saturating_add(amount);
+        let _ = self.state.prize_pools.insert(&pool, prize_pool);
+        
+        self.state.sponsorships.push(Sponsorship {
+            pool: pool.clone(),
+            sponsor_chain,
+            sponsor_name,
+            amount,
+            timestamp: self.runtime.system_time().micros(),
+        });
+        log_event!(self, Info, "PRIZES", "Pool '{}' received {} from {:?}", pool, amount, sponsor_chain);
+    }
+    
+    /// Split a prize fund across the top of the leaderboard using the configured percentages
+    async fn pay_prizes(&mut self, prize_fund: Amount) {
+        let split = self.runtime.application_parameters().prize_split_percent;
+        let leaderboard = self.state.leaderboard_entries().await;
+        let timestamp = self.runtime.system_time().micros();
+        let total = u128::from(prize_fund);
+        let mut paid = Amount::ZERO;
+        
+        for (position, (entry, percent)) in leaderboard.iter().zip(split).enumerate() {
+            let amount = Amount::from_attos(total * percent as u128 / 100);
+            if amount == Amount::ZERO {
+                continue;
+            }
+            paid = paid.saturating_add(amount);
             
-            GameMessage::LeaderboardReset => {
-                eprintln!("[MESSAGE] Processing LeaderboardReset notification on chain {:?}", self.runtime.chain_id());
-                
-                // Clear local leaderboard data on player chains
-                // On the leaderboard chain, this would be redundant, but we'll handle it gracefully
-                if *self.state.is_leaderboard_chain.get() {
-                    eprintln!("[MESSAGE] This is the leaderboard chain, ignoring LeaderboardReset message");
-                    return;
-                }
-                
-                // Clear local player stats when leaderboard is reset
-                // This will reset the highest score and all other player statistics
-                if let Some(mut stats) = self.state.my_stats.get().clone() {
-                    stats.highest_score = 0;
-                    stats.games_played = 0;
-                    stats.total_candies = 0;
-                    stats.current_streak = 0;
-                    stats.best_streak = 0;
-                    self.state.my_stats.set(Some(stats));
-                    eprintln!("[MESSAGE] Player chain {:?} cleared local stats due to leaderboard reset", 
-                        self.runtime.chain_id());
-                } else {
-                    eprintln!("[MESSAGE] Player chain {:?} had no local stats to clear", 
-                        self.runtime.chain_id());
+            self.runtime.transfer(AccountOwner::CHAIN, Account { chain_id: entry.chain_id, owner: AccountOwner::CHAIN }, amount);
+            self.state.prize_payouts.push(PrizePayout {
+                chain_id: entry.chain_id,
+                position: position as u32 + 1,
+                amount,
+                timestamp,
+            });
+            log_event!(self, Debug, "PRIZES", "Paid {} to #{} {:?}", amount, position + 1, entry.chain_id);
+        }
+        
+        // Shares of empty positions and rounding stay on this chain, so they go back to the treasury
+        let remainder = prize_fund.saturating_sub(paid);
+        if remainder > Amount::ZERO {
+            let chain_id = self.runtime.chain_id();
+            self.credit_treasury(remainder, Account { chain_id, owner: AccountOwner::CHAIN }, "Unpaid prize remainder".to_string());
+        }
+    }
+    
+    /// Store the live state of a new session and announce it to spectators
+    fn publish_live_session(&mut self, session: &GameSession, engine: snake_engine::Engine) {
+        let timestamp = self.runtime.system_time().micros();
+        let live = LiveSession::new(session, &engine, timestamp);
+        let _ = self.state.live_engines.insert(&session.session_id, engine);
+        let _ = self.state.live_sessions.insert(&session.session_id, live.clone());
+        self.runtime.emit(LIVE_STREAM_NAME.into(), &SnakeGameEvent::LiveSessionUpdated(live));
+    }
+    
+    /// Subscribe this player chain to the streams published by the leaderboard chain
+    fn subscribe_to_leaderboard_streams(&mut self, leaderboard_chain_id: ChainId) {
+        let application_id = self.runtime.application_id().forget_abi();
+        for stream_name in [QUEST_STREAM_NAME, ANNOUNCEMENT_STREAM_NAME, LEVEL_STREAM_NAME, TOURNAMENT_STREAM_NAME] {
+            self.runtime.subscribe_to_events(leaderboard_chain_id, application_id, stream_name.into());
+        }
+        log_event!(self, Info, "SETUP", "Subscribed to quests, announcements and levels from leaderboard chain {:?}", leaderboard_chain_id);
+    }
+    
+    /// Archive the season and clear every leaderboard, telling the participants to clear their copies
+    async fn reset_leaderboard(&mut self) {
+        // Get the list of players who were in the leaderboard before clearing
+        let mut leaderboard_players = Vec::new();
+        match self.state.leaderboard_participants.indices().await {
+            Ok(players) => {
+                for player in players {
+                    leaderboard_players.push(player);
                 }
-                
-                // Also clear the global leaderboard on this player chain if it exists
-                self.state.global_leaderboard.set(Vec::new());
-                eprintln!("[MESSAGE] Player chain {:?} cleared local leaderboard data", 
-                    self.runtime.chain_id());
+                log_event!(self, Debug, "RESET", "Found {} players who were in the leaderboard", leaderboard_players.len());
+            }
+            Err(e) => {
+                log_event!(self, Error, "RESET", "Error getting leaderboard participants: {:?}", e);
+            }
+        }
+        
+        // Archive the season podium before clearing the board
+        let mut hall_of_fame = self.state.hall_of_fame.get().clone();
+        let season = hall_of_fame.seasons.len() as u32 + 1;
+        let podium: Vec<LeaderboardEntry> = self.state.leaderboard_entries().await.into_iter().take(PODIUM_SIZE).collect();
+        let ended_at = self.runtime.system_time().micros();
+        self.runtime.emit(LEADERBOARD_STREAM_NAME.into(), &SnakeGameEvent::SeasonEnded {
+            version: EVENT_SCHEMA_VERSION,
+            season,
+            ended_at,
+            podium: podium.iter().map(RankedScore::from).collect(),
+        });
+        hall_of_fame.seasons.push(SeasonPodium {
+            season,
+            ended_at,
+            podium,
+        });
+        self.state.hall_of_fame.set(hall_of_fame);
+        log_event!(self, Info, "RESET", "Archived podium of season {} to the hall of fame", season);
+        
+        // Clear all game data on leaderboard chain
+        self.set_global_leaderboard(Vec::new()).await;
+        self.state.inactive_leaderboard.set(Vec::new());
+        self.state.shadow_leaderboard.set(Vec::new());
+        self.state.player_last_active.clear();
+        self.state.player_stats.clear();
+        self.state.season_passes.clear();
+        self.state.country_leaderboards.clear();
+        self.state.country_rankings.set(Vec::new());
+        self.state.leaderboard_participants.clear();
+        self.state.upheld_players.clear();
+        self.state.reactions.clear();
+        self.state.level_leaderboards.clear();
+        self.state.map_leaderboards.clear();
+        self.state.mode_leaderboards.clear();
+        self.state.survival_leaderboard.set(Vec::new());
+        self.state.most_improved.set(Vec::new());
+        self.state.rising_stars.set(Vec::new());
+        
+        // Send LeaderboardReset message to all players who were in the leaderboard
+        for player_chain in &leaderboard_players {
+            if *player_chain != self.runtime.chain_id() {
+                let message = GameMessage::LeaderboardReset;
+                self.runtime.send_message(*player_chain, message.into());
+                log_event!(self, Debug, "RESET", "Sent LeaderboardReset message to player chain {:?}", player_chain);
             }
         }
+        
+        log_event!(self, Info, "RESET", "Leaderboard reset completed successfully on leaderboard chain");
     }
-
-    async fn store(mut self) {
-        let _ = self.state.save().await;
+    
+    /// Stop following the streams of a chain that is no longer the leaderboard chain
+    fn unsubscribe_from_leaderboard_streams(&mut self, leaderboard_chain_id: ChainId) {
+        let application_id = self.runtime.application_id().forget_abi();
+        for stream_name in [QUEST_STREAM_NAME, ANNOUNCEMENT_STREAM_NAME, LEVEL_STREAM_NAME, TOURNAMENT_STREAM_NAME] {
+            self.runtime.unsubscribe_from_events(leaderboard_chain_id, application_id, stream_name.into());
+        }
+        log_event!(self, Info, "SETUP", "Unsubscribed from leaderboard chain {:?}", leaderboard_chain_id);
     }
-}
-
-impl SnakeGameContract {
-    async fn update_leaderboard_stats(&mut self, player_chain: ChainId, candies_collected: u32, is_new_record: bool) {
-        eprintln!("[LEADERBOARD] Updating stats for {:?}, candies: {}, new record: {}", 
+    
+    /// Append an announcement, dropping the oldest ones beyond the queue bound
+    fn store_announcement(&mut self, announcement: Announcement) {
+        self.state.announcements.push_back(announcement);
+        while self.state.announcements.count() > MAX_ANNOUNCEMENTS {
+            self.state.announcements.delete_front();
+        }
+    }
+    
+    /// Record play activity against every open quest and pay out rewards for completed ones
+    async fn advance_quests(&mut self, kind: QuestKind, amount: u32, timestamp: u64) {
+        let quest_ids = match self.state.quests.indices().await {
+            Ok(quest_ids) => quest_ids,
+            Err(_) => return,
+        };
+        
+        for quest_id in quest_ids {
+            let Ok(Some(mut progress)) = self.state.quests.get(&quest_id).await else {
+                continue;
+            };
+            if !progress.quest.is_active(timestamp) {
+                continue;
+            }
+            
+            let completed = progress.record(kind, amount);
+            if completed {
+                let reward_coins = progress.quest.reward_coins;
+                let reward_xp = progress.quest.reward_xp;
+                self.credit_reward(RewardSource::Quest, format!("Quest '{}'", progress.quest.title), reward_coins);
+                self.grant_xp(reward_xp).await;
+                self.unlock_achievement(Achievement::QuestSolver, timestamp).await;
+                
+                log_event!(self, Info, "QUEST", "Completed quest {} '{}': +{} coins, +{} XP", 
+                    quest_id, progress.quest.title, reward_coins, reward_xp);
+            }
+            let _ = self.state.quests.insert(&quest_id, progress);
+        }
+    }
+    
+    async fn update_leaderboard_stats(&mut self, player_chain: ChainId, candies_collected: u32, is_new_record: bool, best_combo: u32, ticks: u64) -> Result<(), String> {
+        log_event!(self, Debug, "LEADERBOARD", "Updating stats for {:?}, candies: {}, new record: {}", 
             player_chain, candies_collected, is_new_record);
         
         let timestamp = self.runtime.system_time().micros();
+        if self.is_banned(player_chain, timestamp).await {
+            reject_message!(self, "PENALTY", "{:?} is banned, ignoring their result", player_chain);
+            return Err("This chain is banned".to_string());
+        }
         
         // Get or create player stats
         let mut stats = match self.state.player_stats.get(&player_chain).await {
@@ -433,85 +6363,608 @@ impl SnakeGameContract {
         };
         
         // Update stats
-        let _was_record = stats.add_game(candies_collected, timestamp); // Prefix with underscore to indicate intentional omission
+        let weights = self.runtime.application_parameters().composite_weights;
+        stats.record_reported_game(candies_collected, best_combo, ticks, timestamp, &weights);
         
         // Save updated stats
         let _ = self.state.player_stats.insert(&player_chain, stats.clone());
         
+        // Only scores that count towards the board can enter the hall of fame
+        let upheld = self.state.upheld_players.contains(&player_chain).await.unwrap_or(false);
+        if !upheld && self.is_eligible(player_chain).await {
+            self.update_hall_of_fame(player_chain, candies_collected, timestamp).await;
+        }
+        
         // Add player to leaderboard participants set
         let _ = self.state.leaderboard_participants.insert(&player_chain);
+        let _ = self.state.player_last_active.insert(&player_chain, timestamp);
         
         // Rebuild global leaderboard
         self.rebuild_global_leaderboard().await;
         
-        eprintln!("[LEADERBOARD] Updated stats for {:?}: games={}, highest={}, total_candies={}, avg={:.2}", 
+        log_event!(self, Debug, "LEADERBOARD", "Updated stats for {:?}: games={}, highest={}, total_candies={}, avg={:.2}", 
             player_chain, stats.games_played, stats.highest_score, stats.total_candies, stats.average_candies());
+        Ok(())
+    }
+    
+    /// Check a reported score against the all-time record and the notable firsts
+    async fn update_hall_of_fame(&mut self, player_chain: ChainId, score: u32, timestamp: u64) {
+        let player_name = self.state.player_names.get(&player_chain).await.ok().flatten();
+        let mut hall_of_fame = self.state.hall_of_fame.get().clone();
+        
+        let beats_record = hall_of_fame.all_time_record.as_ref()
+            .map(|record| score > record.score)
+            .unwrap_or(true);
+        if beats_record {
+            hall_of_fame.all_time_record = Some(HallOfFameRecord {
+                title: "All-time record".to_string(),
+                chain_id: player_chain,
+                player_name: player_name.clone(),
+                score,
+                timestamp,
+            });
+            log_event!(self, Info, "HALL_OF_FAME", "New all-time record by {:?}: {} candies", player_chain, score);
+        }
+        
+        for (title, min_score) in SCORE_FIRSTS {
+            if score >= min_score {
+                hall_of_fame.record_first(HallOfFameRecord {
+                    title: title.to_string(),
+                    chain_id: player_chain,
+                    player_name: player_name.clone(),
+                    score,
+                    timestamp,
+                });
+            }
+        }
+        
+        self.state.hall_of_fame.set(hall_of_fame);
+    }
+    
+    /// Recompute the score-based hall of fame entries from the scores still on the boards, so voided
+    /// scores and players who lost eligibility or had a flag upheld drop out. A first stays with its
+    /// holder while their score still qualifies, and otherwise passes to the earliest qualifying record
+    fn refresh_hall_of_fame(&mut self, entries: &[&LeaderboardEntry]) {
+        let standing: Vec<&LeaderboardEntry> = entries.iter()
+            .copied()
+            .filter(|entry| entry.dispute != DisputeMarker::Upheld)
+            .collect();
+        let mut hall_of_fame = self.state.hall_of_fame.get().clone();
+        
+        let record = standing.iter()
+            .filter(|entry| entry.highest_score > 0)
+            .min_by_key(|entry| (std::cmp::Reverse(entry.highest_score), entry.record_timestamp));
+        let previous_record = hall_of_fame.all_time_record.as_ref().map(|record| (record.chain_id, record.score));
+        if record.map(|entry| (entry.chain_id, entry.highest_score)) != previous_record {
+            log_event!(self, Info, "HALL_OF_FAME", "All-time record is now {:?}", record.map(|entry| (entry.chain_id, entry.highest_score)));
+            hall_of_fame.all_time_record = record.map(|entry| HallOfFameRecord {
+                title: "All-time record".to_string(),
+                chain_id: entry.chain_id,
+                player_name: entry.player_name.clone(),
+                score: entry.highest_score,
+                timestamp: entry.record_timestamp,
+            });
+        }
+        
+        for (title, min_score) in SCORE_FIRSTS {
+            let holder_stands = hall_of_fame.notable_firsts.iter()
+                .find(|first| first.title == title)
+                .map(|first| standing.iter().any(|entry| entry.chain_id == first.chain_id && entry.highest_score >= min_score));
+            if holder_stands != Some(false) {
+                continue;
+            }
+            hall_of_fame.notable_firsts.retain(|first| first.title != title);
+            let successor = standing.iter()
+                .filter(|entry| entry.highest_score >= min_score)
+                .min_by_key(|entry| entry.record_timestamp);
+            if let Some(entry) = successor {
+                hall_of_fame.record_first(HallOfFameRecord {
+                    title: title.to_string(),
+                    chain_id: entry.chain_id,
+                    player_name: entry.player_name.clone(),
+                    score: entry.highest_score,
+                    timestamp: entry.record_timestamp,
+                });
+            }
+            log_event!(self, Info, "HALL_OF_FAME", "'{}' passed to {:?}", title, successor.map(|entry| entry.chain_id));
+        }
+        
+        self.state.hall_of_fame.set(hall_of_fame);
+    }
+    
+    /// Add XP to this player's stats and report level-ups to the leaderboard chain
+    async fn grant_xp(&mut self, amount: u64) {
+        if amount == 0 {
+            return;
+        }
+        
+        let current_chain = self.runtime.chain_id();
+        let xp_config = self.runtime.application_parameters().xp;
+        let mut my_stats = self.state.my_stats.get().clone().unwrap_or_else(|| PlayerStats::new(current_chain));
+        my_stats.xp += amount;
+        let new_level = xp_config.level_for_xp(my_stats.xp);
+        let leveled_up = new_level > my_stats.level;
+        my_stats.level = new_level;
+        self.state.my_stats.set(Some(my_stats));
+        
+        // Season XP counts towards the pass; profiles only change when a tier unlocks
+        let pass_config = self.runtime.application_parameters().season_pass;
+        let mut pass = self.state.my_season_pass.get().clone();
+        let tiers_before = pass_config.tiers_unlocked(pass.xp);
+        pass.xp += amount;
+        let tier_unlocked = pass_config.tiers_unlocked(pass.xp) > tiers_before;
+        self.state.my_season_pass.set(pass);
+        if tier_unlocked {
+            self.report_season_pass().await;
+        }
+        
+        if !leveled_up {
+            return;
+        }
+        log_event!(self, Info, "XP", "Chain {:?} reached level {}", current_chain, new_level);
+        
+        if let Some(leaderboard_chain_id) = *self.state.leaderboard_chain_id.get() {
+            if current_chain != leaderboard_chain_id {
+                let message = GameMessage::UpdatePlayerLevel {
+                    player_chain: current_chain,
+                    level: new_level,
+                };
+                self.runtime.send_message(leaderboard_chain_id, message.into());
+            } else {
+                let _ = self.state.player_levels.insert(&current_chain, new_level);
+            }
+        }
+    }
+    
+    /// Show this player's season pass on their profile on the leaderboard chain
+    async fn report_season_pass(&mut self) {
+        let Some(leaderboard_chain_id) = *self.state.leaderboard_chain_id.get() else {
+            return;
+        };
+        let current_chain = self.runtime.chain_id();
+        let progress = self.state.my_season_pass.get()
+            .progress(&self.runtime.application_parameters().season_pass);
+        if current_chain != leaderboard_chain_id {
+            let message = GameMessage::SeasonPassUpdated { player_chain: current_chain, progress };
+            self.runtime.send_message(leaderboard_chain_id, message.into());
+        } else {
+            let _ = self.state.season_passes.insert(&current_chain, progress);
+        }
+    }
+    
+    /// Unlock an achievement once, granting its XP bonus
+    async fn unlock_achievement(&mut self, achievement: Achievement, timestamp: u64) {
+        if let Ok(true) = self.state.my_achievements.contains_key(&achievement).await {
+            return;
+        }
+        
+        let _ = self.state.my_achievements.insert(&achievement, timestamp);
+        log_event!(self, Info, "ACHIEVEMENT", "Unlocked {:?}", achievement);
+        
+        if let Some(leaderboard_chain_id) = *self.state.leaderboard_chain_id.get() {
+            let message = GameMessage::AchievementUnlocked {
+                player_chain: self.runtime.chain_id(),
+                achievement,
+                unlocked_at: timestamp,
+            };
+            self.send_to_chain(leaderboard_chain_id, message).await;
+        }
+        
+        let achievement_xp = self.runtime.application_parameters().xp.per_achievement;
+        self.grant_xp(achievement_xp).await;
     }
     
     /// Rebuild the global leaderboard from all player stats
     async fn rebuild_global_leaderboard(&mut self) {
         // Collect all player stats
         let mut all_entries = Vec::new();
+        let mut inactive_entries = Vec::new();
+        let mut previous_tiers = Vec::new();
+        let mut most_improved = Vec::new();
+        let mut rising_stars = Vec::new();
+        let mut shadowed_entries = Vec::new();
+        
+        let now = self.runtime.system_time().micros();
+        let inactivity_window = self.runtime.application_parameters().inactivity_window_micros();
+        let shadow_flagged = self.runtime.application_parameters().shadow_flagged_players;
+        
+        // Players with a flag still waiting for a moderator
+        let mut under_review = BTreeSet::new();
+        for dispute_id in self.state.review_queue.indices().await.unwrap_or_default() {
+            if let Ok(Some(dispute)) = self.state.disputes.get(&dispute_id).await {
+                under_review.insert(dispute.player_chain);
+            }
+        }
 
         // Get all player chain IDs who have stats
         match self.state.player_stats.indices().await {
             Ok(player_chains) => {
-                eprintln!("[LEADERBOARD] Found {} players with stats", player_chains.len());
+                log_event!(self, Debug, "LEADERBOARD", "Found {} players with stats", player_chains.len());
+                self.increment_metric(metrics::LEADERBOARD_REBUILDS, 1).await;
+                self.increment_metric(metrics::LEADERBOARD_REBUILD_PLAYERS, player_chains.len() as u64).await;
 
                 for player_chain in player_chains {
+                    // Banned players, and chains without enough activity, keep their stats but are left off the board
+                    if self.is_banned(player_chain, now).await || !self.is_eligible(player_chain).await {
+                        continue;
+                    }
                     if let Ok(Some(stats)) = self.state.player_stats.get(&player_chain).await {
                         // Get player name if available
                         let player_name = match self.state.player_names.get(&player_chain).await {
                             Ok(Some(name)) => Some(name),
                             _ => None,
                         };
+                        let level = self.state.player_levels.get(&player_chain).await
+                            .ok()
+                            .flatten()
+                            .unwrap_or(1);
                         
-                        let entry = LeaderboardEntry {
-                            chain_id: stats.chain_id,
-                            highest_score: stats.highest_score,
-                            games_played: stats.games_played,
-                            total_candies: stats.total_candies,
-                            player_name: player_name.clone(),
+                        let verified = self.state.verified_players.contains(&player_chain).await.unwrap_or(false);
+                        let mut entry = stats.leaderboard_entry(player_name.clone(), level, verified);
+                        entry.dispute = if under_review.contains(&player_chain) {
+                            DisputeMarker::UnderReview
+                        } else if self.state.upheld_players.contains(&player_chain).await.unwrap_or(false) {
+                            DisputeMarker::Upheld
+                        } else {
+                            DisputeMarker::Clear
                         };
+                        entry.reactions = self.reaction_counts(player_chain).await;
+                        entry.country = self.state.player_countries.get(&player_chain).await.ok().flatten();
+                        
+                        if let Some((previous_week_average, this_week_average)) = stats.weekly_averages(now) {
+                            let improvement = this_week_average - previous_week_average;
+                            if improvement > 0.0 {
+                                most_improved.push(ImprovementEntry {
+                                    chain_id: stats.chain_id,
+                                    player_name: player_name.clone(),
+                                    previous_week_average,
+                                    this_week_average,
+                                    improvement,
+                                });
+                            }
+                        }
+                        if stats.is_newcomer(now) {
+                            rising_stars.push(RisingStarEntry {
+                                chain_id: stats.chain_id,
+                                player_name: player_name.clone(),
+                                first_game_timestamp: stats.first_game_timestamp,
+                                games_played: stats.games_played,
+                                highest_score: stats.highest_score,
+                            });
+                        }
+                        
+                        // Players idle for longer than the window are listed separately
+                        let last_active = self.state.player_last_active.get(&player_chain).await
+                            .ok()
+                            .flatten()
+                            .unwrap_or(stats.last_game_timestamp);
+                        entry.last_active = last_active;
+                        let is_inactive = inactivity_window
+                            .map(|window| now.saturating_sub(last_active) > window)
+                            .unwrap_or(false);
+                        if is_inactive {
+                            inactive_entries.push(entry);
+                            continue;
+                        }
+                        // Players a moderator upheld a flag against wait out the season on the shadow board,
+                        // keeping their tier; an open flag alone is not enough, or anyone could hide a rival
+                        if shadow_flagged && entry.dispute == DisputeMarker::Upheld {
+                            shadowed_entries.push(entry);
+                            continue;
+                        }
+                        
                         all_entries.push(entry);
-                        eprintln!("[LEADERBOARD] Added {:?} ({:?}) with {} highest score to rebuild list", 
+                        previous_tiers.push((stats.chain_id, stats.tier));
+                        log_event!(self, Debug, "LEADERBOARD", "Added {:?} ({:?}) with {} highest score to rebuild list", 
                             player_chain, player_name, stats.highest_score);
                     }
                 }
             }
             Err(_) => {
-                eprintln!("[LEADERBOARD] Failed to get player chains, returning");
+                log_event!(self, Error, "LEADERBOARD", "Failed to get player chains, returning");
                 return;
             }
         }
 
-        // Sort by highest score descending, then by total candies, then by games played
-        all_entries.sort_by(|a, b| {
-            b.highest_score.cmp(&a.highest_score)
-                .then_with(|| b.total_candies.cmp(&a.total_candies))
-                .then_with(|| b.games_played.cmp(&a.games_played))
-        });
-        eprintln!("[LEADERBOARD] Sorted {} entries", all_entries.len());
+        // Sort by highest score descending, then by the configured tie-breakers
+        let parameters = self.runtime.application_parameters();
+        all_entries.sort_by(|a, b| parameters.compare_entries(a, b));
+        inactive_entries.sort_by(|a, b| parameters.compare_entries(a, b));
+        log_event!(self, Debug, "LEADERBOARD", "Sorted {} entries ({} inactive)", all_entries.len(), inactive_entries.len());
+        let standing: Vec<&LeaderboardEntry> = all_entries.iter().chain(&inactive_entries).collect();
+        self.refresh_hall_of_fame(&standing);
+        self.state.inactive_leaderboard.set(inactive_entries);
+        
+        // Each shadowed player is placed where they would rank among the public entries
+        shadowed_entries.sort_by(|a, b| parameters.compare_entries(a, b));
+        let shadow_board: Vec<ShadowEntry> = shadowed_entries.into_iter()
+            .map(|entry| ShadowEntry {
+                position: all_entries.iter()
+                    .filter(|public| parameters.compare_entries(public, &entry) == std::cmp::Ordering::Less)
+                    .count() as u32 + 1,
+                entry,
+            })
+            .collect();
+        if !shadow_board.is_empty() {
+            log_event!(self, Info, "LEADERBOARD", "{} flagged players held on the shadow board", shadow_board.len());
+        }
+        self.state.shadow_leaderboard.set(shadow_board);
+        
+        // Week-based boards go stale with time alone, so they are refreshed on every rebuild
+        most_improved.sort_by(|a, b| b.improvement.total_cmp(&a.improvement)
+            .then(b.this_week_average.total_cmp(&a.this_week_average)));
+        most_improved.truncate(100);
+        self.state.most_improved.set(most_improved);
+        rising_stars.sort_by(|a, b| b.highest_score.cmp(&a.highest_score)
+            .then(a.first_game_timestamp.cmp(&b.first_game_timestamp)));
+        rising_stars.truncate(100);
+        self.state.rising_stars.set(rising_stars);
+
+        // Re-evaluate tiers now that positions are known
+        let thresholds = parameters.tier_thresholds;
+        for (position, entry) in all_entries.iter_mut().enumerate() {
+            let old_tier = previous_tiers.iter()
+                .find(|(chain_id, _)| *chain_id == entry.chain_id)
+                .map(|(_, tier)| *tier)
+                .unwrap_or_default();
+            let new_tier = thresholds.tier_for_rank(entry.highest_score, position);
+            entry.tier = new_tier;
+            if new_tier != old_tier {
+                self.apply_tier_change(entry.chain_id, old_tier, new_tier).await;
+            }
+        }
 
+        // Country boards keep the global order, so the first entry from each country is its #1
+        let mut country_boards: BTreeMap<String, Vec<LeaderboardEntry>> = BTreeMap::new();
+        let mut country_rankings: BTreeMap<String, CountryRanking> = BTreeMap::new();
+        for entry in all_entries.iter_mut() {
+            let Some(country) = entry.country.clone() else {
+                continue;
+            };
+            let board = country_boards.entry(country.clone()).or_default();
+            entry.country_leader = board.is_empty();
+            if board.len() < 100 {
+                board.push(entry.clone());
+            }
+            
+            let ranking = country_rankings.entry(country.clone()).or_insert_with(|| CountryRanking {
+                country,
+                players: 0,
+                games_played: 0,
+                total_candies: 0,
+                best_score: entry.highest_score,
+                average_best_score: 0.0,
+                leader: entry.chain_id,
+                leader_name: entry.player_name.clone(),
+            });
+            ranking.players += 1;
+            ranking.games_played += entry.games_played as u64;
+            ranking.total_candies += entry.total_candies;
+            // Running mean of the players' highest scores
+            ranking.average_best_score += (entry.highest_score as f64 - ranking.average_best_score) / ranking.players as f64;
+        }
+        self.state.country_leaderboards.clear();
+        for (country, board) in country_boards {
+            let _ = self.state.country_leaderboards.insert(&country, board);
+        }
+        let mut country_rankings: Vec<CountryRanking> = country_rankings.into_values().collect();
+        country_rankings.sort_by(|a, b| b.best_score.cmp(&a.best_score)
+            .then(b.total_candies.cmp(&a.total_candies)));
+        self.state.country_rankings.set(country_rankings);
+        
         // Take top 100
         let top_100: Vec<LeaderboardEntry> = all_entries.into_iter().take(100).collect();
-        eprintln!("[LEADERBOARD] Taking top {} entries for leaderboard", top_100.len());
+        log_event!(self, Debug, "LEADERBOARD", "Taking top {} entries for leaderboard", top_100.len());
 
+        // Tell indexers when the visible top of the board moves
+        let top: Vec<RankedScore> = top_100.iter().take(INDEXED_TOP_SIZE).map(RankedScore::from).collect();
+        let previous_top: Vec<RankedScore> = self.state.leaderboard_entries().await.iter().take(INDEXED_TOP_SIZE).map(RankedScore::from).collect();
+        if top != previous_top {
+            self.runtime.emit(LEADERBOARD_STREAM_NAME.into(), &SnakeGameEvent::LeaderboardChanged { version: EVENT_SCHEMA_VERSION, top });
+        }
+        
         // Update the global leaderboard
-        self.state.global_leaderboard.set(top_100.clone());
-        eprintln!("[LEADERBOARD] Global leaderboard updated with {} entries", top_100.len());
+        self.set_global_leaderboard(top_100.clone()).await;
+        log_event!(self, Debug, "LEADERBOARD", "Global leaderboard updated with {} entries", top_100.len());
         
         // Log final leaderboard state
-        eprintln!("[LEADERBOARD] Final leaderboard state:");
+        log_event!(self, Debug, "LEADERBOARD", "Final leaderboard state:");
         for (i, entry) in top_100.iter().take(10).enumerate() {
             let display_name = entry.player_name.as_ref().map(|s| s.as_str()).unwrap_or("Anonymous");
-            eprintln!("[LEADERBOARD] #{}: {} ({:?}) - {} highest score, {} total candies ({} games)", 
+            log_event!(self, Debug, "LEADERBOARD", "#{}: {} ({:?}) - {} highest score, {} total candies ({} games)", 
                 i + 1, display_name, entry.chain_id, entry.highest_score, entry.total_candies, entry.games_played);
         }
         
-        eprintln!("[LEADERBOARD] Rebuild completed successfully");
+        log_event!(self, Debug, "LEADERBOARD", "Rebuild completed successfully");
+    }
+    
+    /// Replace the global leaderboard, logging every row that was added, moved, updated or dropped.
+    /// Only positions whose entry changed are written.
+    async fn set_global_leaderboard(&mut self, entries: Vec<LeaderboardEntry>) {
+        let previous = self.state.leaderboard_entries().await;
+        let version = *self.state.leaderboard_version.get() + 1;
+        let mut changes = Vec::new();
+        
+        for (index, entry) in entries.iter().enumerate() {
+            let position = index as u32 + 1;
+            let old = previous.iter().enumerate().find(|(_, old)| old.chain_id == entry.chain_id);
+            let kind = match old {
+                None => LeaderboardChangeKind::Added,
+                Some((old_index, _)) if old_index as u32 + 1 != position => LeaderboardChangeKind::Moved,
+                Some((_, old)) if entry_stats_changed(old, entry) => LeaderboardChangeKind::Updated,
+                Some(_) => continue,
+            };
+            changes.push(LeaderboardChange {
+                version,
+                chain_id: entry.chain_id,
+                kind,
+                previous_position: old.map(|(old_index, _)| old_index as u32 + 1),
+                position: Some(position),
+                entry: Some(entry.clone()),
+            });
+        }
+        for (old_index, old) in previous.iter().enumerate() {
+            if !entries.iter().any(|entry| entry.chain_id == old.chain_id) {
+                changes.push(LeaderboardChange {
+                    version,
+                    chain_id: old.chain_id,
+                    kind: LeaderboardChangeKind::Dropped,
+                    previous_position: Some(old_index as u32 + 1),
+                    position: None,
+                    entry: None,
+                });
+            }
+        }
+        
+        self.state.write_leaderboard(&previous, &entries);
+        self.notify_overtaken_players(&changes).await;
+        let podium_changed = previous.iter().take(PODIUM_SIZE).ne(entries.iter().take(PODIUM_SIZE));
+        if changes.is_empty() {
+            return;
+        }
+        
+        log_event!(self, Info, "LEADERBOARD", "Version {} changed {} rows", version, changes.len());
+        self.state.leaderboard_version.set(version);
+        for change in changes {
+            self.state.leaderboard_changes.push_back(change);
+        }
+        
+        // Drop whole versions so a client is never handed half of one
+        while self.state.leaderboard_changes.count() > MAX_LEADERBOARD_CHANGES {
+            let Ok(Some(oldest)) = self.state.leaderboard_changes.front().await else {
+                break;
+            };
+            while let Ok(Some(change)) = self.state.leaderboard_changes.front().await {
+                if change.version != oldest.version {
+                    break;
+                }
+                self.state.leaderboard_changes.delete_front();
+            }
+        }
+        
+        if podium_changed {
+            self.record_podium_changes(&previous, &entries);
+            self.notify_leaderboard_subscribers().await;
+        }
+    }
+    
+    /// Log and emit every podium position that another player took over
+    fn record_podium_changes(&mut self, previous: &[LeaderboardEntry], entries: &[LeaderboardEntry]) {
+        let changed_at = self.runtime.system_time().micros();
+        for (index, entry) in entries.iter().take(PODIUM_SIZE).enumerate() {
+            let displaced = previous.get(index);
+            if displaced.is_some_and(|displaced| displaced.chain_id == entry.chain_id) {
+                continue;
+            }
+            
+            let change = PodiumChange {
+                position: index as u32 + 1,
+                new_holder: RankedScore::from(entry),
+                displaced: displaced.map(RankedScore::from),
+                changed_at,
+            };
+            log_event!(self, Info, "LEADERBOARD", "{:?} took position {} from {:?}", entry.chain_id, change.position,
+                change.displaced.as_ref().map(|displaced| displaced.chain_id));
+            self.runtime.emit(PODIUM_STREAM_NAME.into(), &SnakeGameEvent::PodiumChanged { version: EVENT_SCHEMA_VERSION, change: change.clone() });
+            self.state.podium_history.push_back(change);
+        }
+        while self.state.podium_history.count() > MAX_PODIUM_HISTORY {
+            self.state.podium_history.delete_front();
+        }
+    }
+    
+    /// Store a player's new tier and emit the matching promotion or demotion event
+    async fn apply_tier_change(&mut self, player_chain: ChainId, old_tier: RankTier, new_tier: RankTier) {
+        let mut highest_score = 0;
+        if let Ok(Some(mut stats)) = self.state.player_stats.get(&player_chain).await {
+            stats.tier = new_tier;
+            highest_score = stats.highest_score;
+            let _ = self.state.player_stats.insert(&player_chain, stats);
+        }
+        
+        // The first player to reach each tier earns a hall of fame entry
+        if new_tier > old_tier {
+            let player_name = self.state.player_names.get(&player_chain).await.ok().flatten();
+            let mut hall_of_fame = self.state.hall_of_fame.get().clone();
+            let is_first = hall_of_fame.record_first(HallOfFameRecord {
+                title: format!("First {:?} player", new_tier),
+                chain_id: player_chain,
+                player_name,
+                score: highest_score,
+                timestamp: self.runtime.system_time().micros(),
+            });
+            if is_first {
+                self.state.hall_of_fame.set(hall_of_fame);
+            }
+        }
+        
+        let event = if new_tier > old_tier {
+            SnakeGameEvent::TierPromoted { chain_id: player_chain, old_tier, new_tier }
+        } else {
+            SnakeGameEvent::TierDemoted { chain_id: player_chain, old_tier, new_tier }
+        };
+        self.runtime.emit(TIER_STREAM_NAME.into(), &event);
+        log_event!(self, Info, "LEADERBOARD", "Tier change for {:?}: {:?} -> {:?}", player_chain, old_tier, new_tier);
+    }
+}
+
+/// Keep a player's best score on a level or map board, highest first.
+/// Returns whether the board changed.
+fn record_best_score(scores: &mut Vec<LevelScore>, player_chain: ChainId, player_name: Option<String>, candies_collected: u32, timestamp: u64) -> bool {
+    match scores.iter_mut().find(|score| score.chain_id == player_chain) {
+        Some(score) if score.best_score >= candies_collected => return false,
+        Some(score) => {
+            score.best_score = candies_collected;
+            score.record_timestamp = timestamp;
+            score.player_name = player_name;
+        }
+        None => scores.push(LevelScore {
+            chain_id: player_chain,
+            player_name,
+            best_score: candies_collected,
+            record_timestamp: timestamp,
+        }),
+    }
+    
+    // Highest first; whoever got there first wins a tie
+    scores.sort_by(|a, b| b.best_score.cmp(&a.best_score).then(a.record_timestamp.cmp(&b.record_timestamp)));
+    true
+}
+
+/// The status reported to a queued player, before a match or ghost is known
+fn queue_status(ticket: &QueueTicket, state: MatchmakingState) -> MatchmakingStatus {
+    MatchmakingStatus {
+        state,
+        rating: ticket.rating,
+        queued_at: ticket.queued_at,
+        challenge_id: None,
+        opponent: None,
+        ghost: None,
     }
 }
 
+/// Whether a player's row shows different stats, ignoring its position
+fn entry_stats_changed(old: &LeaderboardEntry, new: &LeaderboardEntry) -> bool {
+    old.player_name != new.player_name
+        || old.highest_score != new.highest_score
+        || old.games_played != new.games_played
+        || old.total_candies != new.total_candies
+        || old.tier != new.tier
+        || old.level != new.level
+        || old.record_timestamp != new.record_timestamp
+        || old.last_active != new.last_active
+        || old.replay_verdict != new.replay_verdict
+        || old.best_combo != new.best_combo
+        || old.verified != new.verified
+        || old.dispute != new.dispute
+        || old.reactions != new.reactions
+        || old.country != new.country
+        || old.country_leader != new.country_leader
+        || old.score_history != new.score_history
+}
+
+#[ComplexObject]
+impl SnakeGameState {}
+
 #[ComplexObject]
-impl SnakeGameState {}
\ No newline at end of file
+impl PlayerStats {}
\ No newline at end of file
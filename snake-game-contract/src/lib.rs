@@ -3,15 +3,22 @@
 
 /*! ABI of the Snake Game Cross-Chain Application */
 
+pub mod snake_engine;
+
+use std::cmp::Ordering;
+
 use async_graphql::{Request, Response};
-use linera_sdk::linera_base_types::{ChainId, ContractAbi, ServiceAbi};
+use linera_sdk::linera_base_types::{Account, AccountOwner, Amount, BcsSignable, ChainId, ContractAbi, DataBlobHash, Ed25519PublicKey, Ed25519Signature, ServiceAbi};
 use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
 
 pub struct SnakeGameAbi;
 
 impl ContractAbi for SnakeGameAbi {
     type Operation = Operation;
-    type Response = ();
+    type Response = OperationResponse;
 }
 
 impl ServiceAbi for SnakeGameAbi {
@@ -27,8 +34,46 @@ pub enum GameState {
     Finished,
 }
 
+// Rules a session is played under
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Hash, async_graphql::Enum)]
+pub enum GameMode {
+    #[default]
+    Classic,
+    Timed, // Candies only count until the time limit runs out
+    Survival, // The snake respawns until it runs out of lives
+}
+
+impl GameMode {
+    /// The feature flag that switches this mode on and off, if it can be switched off
+    pub fn feature_flag(&self) -> Option<&'static str> {
+        match self {
+            GameMode::Classic => None,
+            GameMode::Timed => Some(FEATURE_TIMED_MODE),
+            GameMode::Survival => Some(FEATURE_SURVIVAL_MODE),
+        }
+    }
+}
+
+// Feature flags set on the leaderboard chain and broadcast to player chains
+pub const FEATURE_TIMED_MODE: &str = "timed_mode";
+pub const FEATURE_SURVIVAL_MODE: &str = "survival_mode";
+pub const FEATURE_BATTLE_ROYALE: &str = "battle_royale";
+
+/// Whether a feature is on before the admin has set its flag; unknown features stay off
+pub fn feature_default(key: &str) -> bool {
+    matches!(key, FEATURE_TIMED_MODE | FEATURE_SURVIVAL_MODE | FEATURE_BATTLE_ROYALE)
+}
+
+/// A feature flag and whether it is on
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct FeatureFlag {
+    pub key: String,
+    pub enabled: bool,
+}
+
 // Game session structure
 #[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(complex)]
 pub struct GameSession {
     pub session_id: String,
     pub player: ChainId,
@@ -38,84 +83,2914 @@ pub struct GameSession {
     pub candies_collected: u32,
     pub is_record: bool,
     pub state: GameState,
+    pub move_log_hash: Option<String>,
+    pub seed: u64,
+    pub level_id: u32,
+    pub map_hash: Option<String>, // Custom map played instead of a level
+    pub mode: GameMode,
+    pub ticks: u64, // Latest game tick reported by moves, heartbeats or candies
+    pub tick_limit: Option<u64>, // Timed mode: last tick at which candies still count
+    pub lives_remaining: Option<u32>, // Survival mode only
+    pub last_death_tick: Option<u64>, // Survival mode: game tick of the latest reported death
+    pub combo: snake_engine::Combo, // Candy chains and the points they earned
+    pub match_id: Option<u64>,
+    pub royale_id: Option<u64>, // Battle royale event this game counts for
+    pub royale_round: Option<u32>,
+    pub replay_blob: Option<DataBlobHash>, // Full move log, published as a data blob instead of stored in state
+    pub ghost: Option<GhostRace>, // Published replay this game races against
+    pub tournament_id: Option<u64>, // Tournament this game is an attempt at
+    pub bracket_round: Option<u32>, // Knockout round of the tournament this game is played for
+    pub owner: Option<AccountOwner>, // Signer who played the game, when the chain has several owners; the board still ranks the chain
+    pub guest: bool, // Played before a leaderboard was configured, and not yet claimed by an owner
+    pub leaderboard_status: LeaderboardStatus, // Whether the leaderboard chain counted this game's result
+    pub leaderboard_rejection: Option<String>, // Why the leaderboard chain refused the result, if it did
 }
 
-// Leaderboard entry for global statistics
+/// A published replay that a game can race against, played on its level and seed
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct Ghost {
+    pub session_id: String,
+    pub player_chain: ChainId,
+    pub player_name: Option<String>,
+    pub candies_collected: u32, // Score claimed for the replayed game
+    pub seed: u64,
+    pub level_id: u32,
+    pub replay_blob: DataBlobHash,
+}
+
+/// A ghost being raced, and how the race went once the game ended
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct GhostRace {
+    pub ghost: Ghost,
+    pub ghost_candies: Option<u32>, // Candies the ghost collected when simulated from its move log
+    pub beaten: bool, // More candies than the ghost, or as many with the last one eaten sooner
+}
+
+/// Image formats accepted for avatars
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Gif,
+    Webp,
+}
+
+impl ImageFormat {
+    /// The format of an image, recognized from its leading magic bytes
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(ImageFormat::Png)
+        } else if bytes.starts_with(&[0xff, 0xd8, 0xff]) {
+            Some(ImageFormat::Jpeg)
+        } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+            Some(ImageFormat::Gif)
+        } else if bytes.len() >= 12 && bytes.starts_with(b"RIFF") && &bytes[8..12] == b"WEBP" {
+            Some(ImageFormat::Webp)
+        } else {
+            None
+        }
+    }
+}
+
+/// A custom avatar the player published as a data blob
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct Avatar {
+    pub blob_hash: DataBlobHash,
+    pub format: ImageFormat,
+    pub size_bytes: u32,
+    pub set_at: u64,
+}
+
+/// The format of an image that can be used as an avatar, or why it cannot
+pub fn avatar_format(bytes: &[u8]) -> Result<ImageFormat, String> {
+    if bytes.len() > MAX_AVATAR_BYTES {
+        return Err(format!("Avatar is {} bytes, the limit is {}", bytes.len(), MAX_AVATAR_BYTES));
+    }
+    ImageFormat::detect(bytes).ok_or_else(|| "Avatar must be a PNG, JPEG, GIF or WebP image".to_string())
+}
+
+/// Built-in avatar shown for a player who has not set one, the same for a chain every time
+pub fn default_avatar(chain: ChainId) -> &'static str {
+    let index = chain.to_string().bytes().map(usize::from).sum::<usize>() % DEFAULT_AVATARS.len();
+    DEFAULT_AVATARS[index]
+}
+
+impl GameSession {
+    /// Whether a candy eaten at `tick` still counts
+    pub fn accepts_candy_at(&self, tick: u64) -> bool {
+        self.tick_limit.map(|limit| tick <= limit).unwrap_or(true)
+    }
+    
+    /// Whether the game's seed was known before it started, as in ghost races, battle royale
+    /// rounds and tournaments, so its score cannot set a record on a global board
+    pub fn has_known_seed(&self) -> bool {
+        self.ghost.is_some() || self.royale_id.is_some() || self.tournament_id.is_some()
+    }
+}
+
+#[async_graphql::ComplexObject]
+impl GameSession {
+    /// Wall-clock length of a finished game in seconds
+    async fn duration_seconds(&self) -> Option<f64> {
+        self.end_time.map(|end| end.saturating_sub(self.start_time) as f64 / 1_000_000.0)
+    }
+    
+    /// Candies eaten per minute over a finished game
+    async fn candies_per_minute(&self) -> Option<f64> {
+        let end = self.end_time?;
+        let minutes = end.saturating_sub(self.start_time) as f64 / 60_000_000.0;
+        (minutes > 0.0).then(|| self.candies_collected as f64 / minutes)
+    }
+    
+    /// Whether this game beat the player's best score when it finished
+    async fn was_personal_record_at_time(&self) -> bool {
+        self.state == GameState::Finished && self.is_record
+    }
+    
+    /// Start time as a UTC date and time, e.g. "2025-03-14 09:26:53 UTC"
+    async fn started_at(&self) -> String {
+        format_timestamp(self.start_time)
+    }
+}
+
+// Public summary of a finished session, synced to the leaderboard chain by players who opt in
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct SessionSummary {
+    pub session_id: String,
+    pub mode: GameMode,
+    pub level_id: u32,
+    pub map_hash: Option<String>,
+    pub candies_collected: u32,
+    pub is_record: bool,
+    pub start_time: u64,
+    pub end_time: Option<u64>,
+    pub ticks: u64,
+}
+
+impl From<&GameSession> for SessionSummary {
+    fn from(session: &GameSession) -> Self {
+        SessionSummary {
+            session_id: session.session_id.clone(),
+            mode: session.mode,
+            level_id: session.level_id,
+            map_hash: session.map_hash.clone(),
+            candies_collected: session.candies_collected,
+            is_record: session.is_record,
+            start_time: session.start_time,
+            end_time: session.end_time,
+            ticks: session.ticks,
+        }
+    }
+}
+
+// Validated position of an active session, rebuilt from its submitted moves
 #[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct LiveSession {
+    pub session_id: String,
+    pub player: ChainId,
+    pub player_name: Option<String>,
+    pub mode: GameMode,
+    pub level_id: u32,
+    pub snake: Vec<snake_engine::Cell>, // Head first
+    pub direction: snake_engine::Direction,
+    pub candy: Option<snake_engine::Cell>,
+    pub candies_collected: u32,
+    pub points: u64,
+    pub ticks: u64,
+    pub alive: bool,
+    pub elapsed_micros: u64, // Time since the session started, as of the latest moves
+}
+
+impl LiveSession {
+    /// Snapshot `engine` as the live state of `session` at block time `timestamp`
+    pub fn new(session: &GameSession, engine: &snake_engine::Engine, timestamp: u64) -> Self {
+        Self {
+            session_id: session.session_id.clone(),
+            player: session.player,
+            player_name: session.player_name.clone(),
+            mode: session.mode,
+            level_id: session.level_id,
+            snake: engine.snake.body.iter().copied().collect(),
+            direction: engine.snake.direction,
+            candy: engine.candy,
+            candies_collected: engine.candies_collected,
+            points: engine.combo.points,
+            ticks: engine.ticks,
+            alive: engine.alive,
+            elapsed_micros: timestamp.saturating_sub(session.start_time),
+        }
+    }
+}
+
+// A turn the snake took at the start of a tick
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DirectionChange {
+    pub tick: u64,
+    pub direction: snake_engine::Direction,
+}
+
+// A candy eaten during a tick and where the next one appeared
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CandyDelta {
+    pub tick: u64,
+    pub next_candy: Option<snake_engine::Cell>,
+}
+
+// What changed in a live session between two ticks; every other tick just moves the snake forward
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LiveDelta {
+    pub session_id: String,
+    pub player: ChainId, // Chain playing the session, which answers resync requests
+    pub from_tick: u64, // Tick the receiver must already be at
+    pub to_tick: u64,
+    pub turns: Vec<DirectionChange>,
+    pub candies: Vec<CandyDelta>,
+    pub died_at: Option<u64>,
+    pub points: u64, // Combo points after `to_tick`
+    pub elapsed_micros: u64,
+}
+
+impl LiveDelta {
+    pub fn is_empty(&self) -> bool {
+        self.from_tick == self.to_tick
+    }
+}
+
+impl LiveSession {
+    /// Replay a delta on top of this snapshot.
+    ///
+    /// Returns false, leaving the snapshot untouched, when the delta does not start
+    /// at this snapshot's tick and a resync is needed.
+    pub fn apply_delta(&mut self, delta: &LiveDelta) -> bool {
+        if delta.from_tick != self.ticks {
+            return false;
+        }
+        for tick in delta.from_tick + 1..=delta.to_tick {
+            if let Some(turn) = delta.turns.iter().find(|turn| turn.tick == tick) {
+                self.direction = turn.direction;
+            }
+            let candy = delta.candies.iter().find(|candy| candy.tick == tick);
+            if candy.is_none() {
+                self.snake.pop();
+            }
+            if delta.died_at == Some(tick) {
+                self.alive = false;
+                break;
+            }
+            let head = self.direction.step(self.snake[0]);
+            self.snake.insert(0, head);
+            if let Some(candy) = candy {
+                self.candies_collected += 1;
+                self.candy = candy.next_candy;
+            }
+        }
+        self.ticks = delta.to_tick;
+        self.points = delta.points;
+        self.elapsed_micros = delta.elapsed_micros;
+        true
+    }
+}
+
+// Move logs are anchored by their hex-encoded SHA3-256 hash
+pub const MOVE_LOG_HASH_LEN: usize = 64;
+
+pub fn is_valid_move_log_hash(hash: &str) -> bool {
+    hash.len() == MOVE_LOG_HASH_LEN && hash.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+// A country code as stored on profiles: two ASCII letters (ISO 3166-1 alpha-2), upper-cased
+pub fn country_code(code: &str) -> Option<String> {
+    let code = code.trim();
+    (code.len() == 2 && code.chars().all(|c| c.is_ascii_alphabetic())).then(|| code.to_ascii_uppercase())
+}
+
+// Unique handle behind a player name: compatibility-normalized (NFKC), lowercased, with runs of
+// whitespace collapsed, invisible characters dropped and common Cyrillic and Greek lookalikes folded
+// into the Latin letters they imitate. ASCII names keep the handle they always had.
+pub fn name_key(name: &str) -> String {
+    let folded: String = name.nfkc()
+        .flat_map(char::to_lowercase)
+        .filter(|&c| !is_invisible(c))
+        .map(fold_lookalike)
+        .collect();
+    folded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// How a name is stored and shown: canonically composed (NFC), trimmed, with runs of whitespace
+// collapsed, or why it cannot be used. Length is counted in user-perceived characters.
+pub fn display_name(name: &str) -> Result<String, String> {
+    let composed: String = name.nfc().filter(|&c| !is_invisible(c) || shapes_emoji(c)).collect();
+    // Tabs and newlines are whitespace, collapsed below like spaces
+    if composed.chars().any(|c| c.is_control() && !c.is_whitespace()) {
+        return Err("Names cannot contain control characters".to_string());
+    }
+    let display = composed.split_whitespace().collect::<Vec<_>>().join(" ");
+    let length = display.graphemes(true).count();
+    if length == 0 || name_key(&display).is_empty() {
+        return Err("Names cannot be empty".to_string());
+    }
+    if length > MAX_NAME_GRAPHEMES {
+        return Err(format!("Names are limited to {} characters", MAX_NAME_GRAPHEMES));
+    }
+    Ok(display)
+}
+
+// The zero-width joiner and variation selectors shape emoji, so display names keep them;
+// handles drop them with the other invisible characters
+fn shapes_emoji(c: char) -> bool {
+    matches!(c, '\u{200d}' | '\u{fe00}'..='\u{fe0f}')
+}
+
+// Characters that render as nothing and could make two names look the same
+fn is_invisible(c: char) -> bool {
+    matches!(c, '\u{00ad}' | '\u{034f}' | '\u{061c}' | '\u{180e}' | '\u{200b}'..='\u{200f}'
+        | '\u{202a}'..='\u{202e}' | '\u{2060}'..='\u{2064}' | '\u{2066}'..='\u{206f}' | '\u{fe00}'..='\u{fe0f}' | '\u{feff}')
+}
+
+// Lowercase Cyrillic and Greek letters drawn like a Latin one
+fn fold_lookalike(c: char) -> char {
+    match c {
+        'а' | 'α' => 'a',
+        'ь' | 'β' => 'b',
+        'с' | 'ϲ' => 'c',
+        'ԁ' => 'd',
+        'е' | 'ε' => 'e',
+        'һ' => 'h',
+        'і' | 'ι' | 'ӏ' => 'i',
+        'ј' => 'j',
+        'κ' | 'к' => 'k',
+        'ν' => 'v',
+        'о' | 'ο' | 'σ' => 'o',
+        'р' | 'ρ' => 'p',
+        'ԛ' => 'q',
+        'ѕ' => 's',
+        'τ' | 'т' => 't',
+        'υ' => 'u',
+        'ԝ' | 'ω' => 'w',
+        'х' | 'χ' => 'x',
+        'у' | 'γ' => 'y',
+        _ => c,
+    }
+}
+
+// Rank tiers, from lowest to highest
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, async_graphql::Enum)]
+pub enum RankTier {
+    #[default]
+    Bronze,
+    Silver,
+    Gold,
+    Platinum,
+    Diamond,
+    Master,
+}
+
+// Leaderboard entry for global statistics
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, async_graphql::SimpleObject)]
 pub struct LeaderboardEntry {
     pub chain_id: ChainId,
     pub player_name: Option<String>,
     pub highest_score: u32,
     pub games_played: u32,
     pub total_candies: u64,
+    pub tier: RankTier,
+    pub level: u32,
+    pub record_timestamp: u64, // When highest_score was first reached
+    pub last_active: u64, // When the leaderboard chain last heard from the player; unlike record_timestamp, play between records moves it too
+    pub score_breakdown: ScoreBreakdown,
+    pub replay_verdict: ReplayVerdict,
+    pub best_combo: u32,
+    pub record_ticks: u64, // Ticks the record game took; 0 when unknown
+    pub verified: bool, // Identity confirmed by the admin
+    pub dispute: DisputeMarker, // Moderation status shown next to the score
+    pub reactions: Vec<ReactionCount>, // Emotes other players left on the entry, most used first
+    pub country: Option<String>, // ISO 3166-1 alpha-2 code the player chose
+    pub country_leader: bool, // Best entry of its country
+    pub score_history: Vec<u32>, // Last SCORE_HISTORY_LEN reported scores, oldest first, for trend sparklines
 }
 
-// Application parameters for leaderboard configuration
-#[derive(Debug, Clone, Deserialize, Serialize, Default)]
-pub struct ApplicationParameters {
-    pub leaderboard_chain_id: Option<ChainId>,
+// Emotes players can leave on leaderboard entries and the records behind them
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, async_graphql::Enum)]
+pub enum Emote {
+    Fire,
+    Clap,
+    Wow,
+    Laugh,
+    Crown,
 }
 
-// Cross-chain messages
+// How many players left one emote on an entry
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct ReactionCount {
+    pub emote: Emote,
+    pub count: u32,
+}
+
+// Where a game's result stands with the leaderboard chain
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum)]
+pub enum LeaderboardStatus {
+    #[default]
+    NotApplicable, // Not a record or match game, or no leaderboard to report to
+    Sent,
+    Acknowledged,
+    Rejected,
+}
+
+// Outcome of re-simulating a record game from its published move log
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum)]
+pub enum ReplayVerdict {
+    #[default]
+    Unverified,
+    Confirmed,
+    Rejected,
+}
+
+// Whether other players have flagged a leaderboard entry as suspicious
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum)]
+pub enum DisputeMarker {
+    #[default]
+    Clear,
+    UnderReview, // At least one flag is waiting for a moderator
+    Upheld, // A moderator agreed that a flagged score is suspicious
+}
+
+/// An entry with an upheld flag, held off the public board, with the position it would take there
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, async_graphql::SimpleObject)]
+pub struct ShadowEntry {
+    pub position: u32,
+    pub entry: LeaderboardEntry,
+}
+
+/// Reported scores shown as a trend on each leaderboard entry
+pub const SCORE_HISTORY_LEN: usize = 10;
+
+/// Most leaderboard rows a player chain can ask to have pushed to it
+pub const MAX_LEADERBOARD_SYNC: u32 = 100;
+
+/// Most players one chain can follow
+pub const MAX_FOLLOWING: usize = 100;
+
+/// Longest chat message a player can send
+pub const MAX_CHAT_MESSAGE_LEN: usize = 280;
+
+/// Longest player name, in user-perceived characters (grapheme clusters)
+pub const MAX_NAME_GRAPHEMES: usize = 24;
+
+/// Longest reason a player can give when flagging a score
+pub const MAX_DISPUTE_REASON_LEN: usize = 280;
+
+/// Most flags one player can have waiting for a moderator at once
+pub const MAX_OPEN_DISPUTES_PER_REPORTER: usize = 5;
+
+/// Largest image a player can publish as their avatar
+pub const MAX_AVATAR_BYTES: usize = 256 * 1024;
+
+/// Avatars bundled with the frontend, used for players without a custom one
+pub const DEFAULT_AVATARS: [&str; 6] = ["green-snake", "blue-snake", "red-snake", "gold-snake", "purple-snake", "striped-snake"];
+
+// Weighted parts of a player's composite score
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct ScoreBreakdown {
+    pub highest_component: f64,
+    pub average_component: f64,
+    pub consistency_component: f64,
+    pub stability_component: f64,
+    pub trend_component: f64,
+    pub total: f64,
+}
+
+// Minimum highest score required for each tier above Bronze
 #[derive(Debug, Clone, Deserialize, Serialize)]
-pub enum GameMessage {
-    // Request to start a game on leaderboard chain
-    StartGame {
-        session_id: String,
-        player_chain: ChainId,
-        player_name: Option<String>,
-    },
-    // Game finished notification with score
-    GameFinished {
-        session_id: String,
-        player_chain: ChainId,
-        candies_collected: u32,
-        is_new_record: bool,
-    },
-    // Update leaderboard stats
-    UpdateLeaderboard {
-        player_chain: ChainId,
-        candies_collected: u32,
-        is_new_record: bool,
-    },
-    // Update player name on leaderboard chain
-    UpdatePlayerName {
-        player_chain: ChainId,
-        player_name: String,
-    },
-    // Notification that leaderboard has been reset
-    LeaderboardReset,
-    // Notification that a candy was collected
-    CandyCollected {
-        session_id: String,
-        player_chain: ChainId,
-    },
+pub struct TierThresholds {
+    pub silver: u32,
+    pub gold: u32,
+    pub platinum: u32,
+    pub diamond: u32,
+    pub master: u32,
+    // Only this many top-ranked players can hold Master at once
+    pub master_slots: u32,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub enum Operation {
-    // Setup operations
-    SetupLeaderboard {
-        leaderboard_chain_id: ChainId,
-    },
-    
-    // Player name operations
-    SetPlayerName {
-        name: String,
-    },
-    
-    // Game operations
-    StartGame,
-    CollectCandy, // New operation to collect a candy during gameplay
-    EndGame, // No longer needs candies_collected parameter
+impl Default for TierThresholds {
+    fn default() -> Self {
+        Self {
+            silver: 5,
+            gold: 20,
+            platinum: 35,
+            diamond: 50,
+            master: 100,
+            master_slots: 10,
+        }
+    }
+}
+
+impl TierThresholds {
+    /// Tier earned by a score alone, ignoring leaderboard position
+    pub fn tier_for_score(&self, highest_score: u32) -> RankTier {
+        if highest_score >= self.master {
+            RankTier::Master
+        } else if highest_score >= self.diamond {
+            RankTier::Diamond
+        } else if highest_score >= self.platinum {
+            RankTier::Platinum
+        } else if highest_score >= self.gold {
+            RankTier::Gold
+        } else if highest_score >= self.silver {
+            RankTier::Silver
+        } else {
+            RankTier::Bronze
+        }
+    }
+
+    /// Tier for a player at a zero-based leaderboard position; Master is
+    /// capped to the top `master_slots` positions, everyone else above the
+    /// Master threshold is held at Diamond
+    pub fn tier_for_rank(&self, highest_score: u32, position: usize) -> RankTier {
+        match self.tier_for_score(highest_score) {
+            RankTier::Master if position >= self.master_slots as usize => RankTier::Diamond,
+            tier => tier,
+        }
+    }
+}
+
+// What a quest asks the player to do
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum)]
+pub enum QuestKind {
+    // Collect `target` candies across any number of games
+    CollectCandies,
+    // Finish `target` games
+    FinishGames,
+    // Finish a single game with at least `target` candies
+    ReachScore,
+}
+
+// How long a quest stays open after it is published
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum)]
+pub enum QuestPeriod {
+    Daily,
+    Weekly,
+}
+
+impl QuestPeriod {
+    pub fn duration_micros(&self) -> u64 {
+        match self {
+            QuestPeriod::Daily => 86_400_000_000,
+            QuestPeriod::Weekly => 7 * 86_400_000_000,
+        }
+    }
+}
+
+// Quest definition published by the leaderboard chain
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct QuestDefinition {
+    pub quest_id: u64,
+    pub title: String,
+    pub kind: QuestKind,
+    pub period: QuestPeriod,
+    pub target: u32,
+    pub reward_coins: u64,
+    pub reward_xp: u64,
+    pub starts_at: u64,
+    pub expires_at: u64,
+}
+
+impl QuestDefinition {
+    pub fn is_active(&self, timestamp: u64) -> bool {
+        timestamp >= self.starts_at && timestamp < self.expires_at
+    }
+}
+
+// Milestones unlocked on a player's chain
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, async_graphql::Enum)]
+pub enum Achievement {
+    // Finished a first game
+    FirstGame,
+    // Collected 100 candies in total
+    CandyHoarder,
+    // Collected 50 candies in a single game
+    HalfCentury,
+    // Played on 7 consecutive days
+    WeeklyRegular,
+    // Completed a first quest
+    QuestSolver,
+    // Beat another player's ghost
+    GhostBuster,
+}
+
+// XP rewards and level-up thresholds
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct XpConfig {
+    pub per_game: u64,
+    pub per_candy: u64,
+    pub per_achievement: u64,
+    // Total XP needed to reach level 2, 3, ... in increasing order
+    pub level_thresholds: Vec<u64>,
+}
+
+impl Default for XpConfig {
+    fn default() -> Self {
+        Self {
+            per_game: 10,
+            per_candy: 2,
+            per_achievement: 50,
+            level_thresholds: vec![100, 250, 500, 1_000, 2_000, 4_000, 8_000, 16_000],
+        }
+    }
+}
+
+// What one side of a season pass tier hands out when claimed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize, async_graphql::SimpleObject)]
+pub struct SeasonReward {
+    pub coins: u64,
+    pub skin: Option<String>,
+    pub trophy: Option<String>,
+}
+
+// A step on the season pass, unlocked by XP earned during the season
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, async_graphql::SimpleObject)]
+pub struct SeasonPassTier {
+    pub xp: u64,
+    pub free: SeasonReward,
+    pub premium: SeasonReward, // Only claimable once the premium track is bought
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum)]
+pub enum PassTrack {
+    Free,
+    Premium,
+}
+
+// Season pass tiers and the price of its premium track
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SeasonPassConfig {
+    // In increasing XP order
+    pub tiers: Vec<SeasonPassTier>,
+    pub premium_price: Amount,
+}
+
+impl SeasonPassConfig {
+    /// Number of tiers unlocked by `xp` season XP
+    pub fn tiers_unlocked(&self, xp: u64) -> u32 {
+        self.tiers.iter().take_while(|tier| tier.xp <= xp).count() as u32
+    }
+}
+
+impl Default for SeasonPassConfig {
+    fn default() -> Self {
+        let coins = |coins| SeasonReward { coins, ..SeasonReward::default() };
+        let skin = |name: &str| SeasonReward { skin: Some(name.to_string()), ..SeasonReward::default() };
+        Self {
+            tiers: vec![
+                SeasonPassTier { xp: 100, free: coins(25), premium: coins(75) },
+                SeasonPassTier { xp: 300, free: coins(50), premium: skin("Ember") },
+                SeasonPassTier { xp: 600, free: skin("Moss"), premium: coins(200) },
+                SeasonPassTier { xp: 1_000, free: coins(100), premium: skin("Aurora") },
+                SeasonPassTier {
+                    xp: 2_000,
+                    free: SeasonReward { trophy: Some("Season Finisher".to_string()), ..SeasonReward::default() },
+                    premium: SeasonReward { coins: 500, trophy: Some("Season Champion".to_string()), ..SeasonReward::default() },
+                },
+            ],
+            premium_price: Amount::from_tokens(1),
+        }
+    }
+}
+
+// Things players can sell each other on the market
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum)]
+pub enum ItemKind {
+    Skin, // A skin claimed from a season pass, held on the player's chain
+    NameToken, // A reserved name, held on the leaderboard chain
+}
+
+// A player's season pass as shown on their profile
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize, async_graphql::SimpleObject)]
+pub struct SeasonPassProgress {
+    pub season: u32, // Resets this player chain has seen; the pass starts over after each
+    pub xp: u64, // Earned since the season started
+    pub tiers_unlocked: u32,
+    pub premium: bool,
+}
+
+// Bounds on the GraphQL queries the service will run
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct QueryLimits {
+    pub max_depth: usize,
+    pub max_complexity: usize,
+    pub max_query_bytes: usize,
+    // Field resolutions allowed per query, bounding the work done for a query that passed the other limits
+    pub max_resolved_fields: usize,
+}
+
+impl Default for QueryLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 12,
+            max_complexity: 2_000,
+            max_query_bytes: 16_384,
+            max_resolved_fields: 100_000,
+        }
+    }
+}
+
+// Messages a single player chain may send per window, for results and for chat
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SubmissionRateLimit {
+    pub max_messages: u32, // 0 disables the limit
+    pub window_secs: u64,
+}
+
+impl Default for SubmissionRateLimit {
+    fn default() -> Self {
+        Self {
+            max_messages: 300,
+            window_secs: 60,
+        }
+    }
+}
+
+// Rating bands of the matchmaking queue. A player's rating is their best reported score, and
+// the band around it widens the longer they wait
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MatchmakingConfig {
+    pub initial_band: u32, // Rating difference allowed as soon as a player joins
+    pub band_growth_per_min: u32,
+    pub max_band: u32,
+    pub timeout_secs: u64, // Wait after which an unmatched player races a ghost instead; 0 waits forever
+}
+
+impl Default for MatchmakingConfig {
+    fn default() -> Self {
+        Self {
+            initial_band: 10,
+            band_growth_per_min: 10,
+            max_band: 200,
+            timeout_secs: 300,
+        }
+    }
+}
+
+impl MatchmakingConfig {
+    /// Rating difference allowed at `now` for a player queued at `queued_at`
+    pub fn band_at(&self, queued_at: u64, now: u64) -> u32 {
+        let waited_mins = u32::try_from(now.saturating_sub(queued_at) / 60_000_000).unwrap_or(u32::MAX);
+        self.initial_band
+            .saturating_add(waited_mins.saturating_mul(self.band_growth_per_min))
+            .min(self.max_band.max(self.initial_band))
+    }
     
-    // Query operations
-    GetLeaderboard,
-    GetMyStats,
-    GetGameSession {
-        session_id: String,
-    },
+    /// Whether a player queued at `queued_at` has waited too long for a match at `now`
+    pub fn timed_out(&self, queued_at: u64, now: u64) -> bool {
+        self.timeout_secs > 0 && now.saturating_sub(queued_at) > self.timeout_secs.saturating_mul(1_000_000)
+    }
+}
+
+// Strike counts at which the automatic penalties escalate
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StrikePolicy {
+    pub temporary_ban_strikes: u32, // Below this, a strike only voids the standing record
+    pub permanent_ban_strikes: u32,
+    pub temporary_ban_secs: u64,
+}
+
+impl Default for StrikePolicy {
+    fn default() -> Self {
+        Self {
+            temporary_ban_strikes: 2,
+            permanent_ban_strikes: 3,
+            temporary_ban_secs: 604_800,
+        }
+    }
+}
+
+impl StrikePolicy {
+    /// The penalty for a player who has just received their `strikes`-th strike
+    pub fn penalty_for(&self, strikes: u32) -> PenaltyKind {
+        if strikes >= self.permanent_ban_strikes {
+            PenaltyKind::PermanentBan
+        } else if strikes >= self.temporary_ban_strikes {
+            PenaltyKind::TemporaryBan
+        } else {
+            PenaltyKind::ScoreVoided
+        }
+    }
+}
+
+// Penalty applied automatically when a player receives a strike
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum)]
+pub enum PenaltyKind {
+    ScoreVoided, // The standing record is removed from the board
+    TemporaryBan, // Record voided and results ignored until the ban expires
+    PermanentBan,
+}
+
+// One strike and the penalty it triggered
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct PenaltyRecord {
+    pub strike: u32, // The player's strike count after this strike
+    pub kind: PenaltyKind,
+    pub reason: String,
+    pub voided_score: u32, // Score of the penalized game removed from the board; 0 if there was none
+    pub applied_at: u64,
+    pub expires_at: Option<u64>, // End of a temporary ban
+}
+
+impl PenaltyRecord {
+    /// Whether this penalty keeps the player off the leaderboard at `now`
+    pub fn bans_at(&self, now: u64) -> bool {
+        match self.kind {
+            PenaltyKind::ScoreVoided => false,
+            PenaltyKind::TemporaryBan => self.expires_at.map(|expires_at| now < expires_at).unwrap_or(false),
+            PenaltyKind::PermanentBan => true,
+        }
+    }
+}
+
+impl XpConfig {
+    /// XP earned for finishing a game with the given number of candies
+    pub fn xp_for_game(&self, candies_collected: u32) -> u64 {
+        self.per_game + self.per_candy * candies_collected as u64
+    }
+
+    /// Level reached with the given total XP, starting at level 1
+    pub fn level_for_xp(&self, xp: u64) -> u32 {
+        1 + self.level_thresholds.iter().take_while(|threshold| xp >= **threshold).count() as u32
+    }
+}
+
+// Criteria used to order players with the same highest score
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum)]
+pub enum TieBreaker {
+    MostTotalCandies,
+    MostGamesPlayed,
+    FewestGamesPlayed,
+    // Whoever set the score first ranks higher
+    EarliestRecord,
+    // Whoever reached the score in fewer game ticks ranks higher
+    FewestRecordTicks,
+}
+
+impl TieBreaker {
+    fn compare(&self, a: &LeaderboardEntry, b: &LeaderboardEntry) -> Ordering {
+        match self {
+            TieBreaker::MostTotalCandies => b.total_candies.cmp(&a.total_candies),
+            TieBreaker::MostGamesPlayed => b.games_played.cmp(&a.games_played),
+            TieBreaker::FewestGamesPlayed => a.games_played.cmp(&b.games_played),
+            TieBreaker::EarliestRecord => a.record_timestamp.cmp(&b.record_timestamp),
+            TieBreaker::FewestRecordTicks => {
+                // Records from before ticks were reported sort last
+                let ticks = |entry: &LeaderboardEntry| if entry.record_ticks == 0 { u64::MAX } else { entry.record_ticks };
+                ticks(a).cmp(&ticks(b))
+            }
+        }
+    }
+}
+
+// What the leaderboard is primarily ordered by
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default, async_graphql::Enum)]
+pub enum RankingMode {
+    #[default]
+    HighestScore,
+    Composite,
+}
+
+// Weights of the composite score formula
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CompositeWeights {
+    pub highest: f64,
+    pub average: f64,
+    pub consistency: f64,
+    // Weight of how little recent scores spread around their mean. Player chains only report new
+    // records, so on the leaderboard chain these are the gaps between successive records
+    #[serde(default)]
+    pub stability: f64,
+    // Weight of the recent improvement trend, in candies per reported record rather than per game
+    #[serde(default)]
+    pub trend: f64,
+}
+
+impl Default for CompositeWeights {
+    fn default() -> Self {
+        Self {
+            highest: 0.7,
+            average: 0.2,
+            consistency: 0.1,
+            stability: 0.0,
+            trend: 0.0,
+        }
+    }
+}
+
+// Spread and direction of a player's most recent scores; for the composite score these are the
+// scores player chains reported, which are mostly new records
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RecentForm {
+    pub mean: f64,
+    pub std_deviation: f64,
+    pub trend: f64, // Least-squares slope, in candies per score
+}
+
+impl RecentForm {
+    /// Form over the given scores, oldest first
+    pub fn from_scores(scores: &[u32]) -> Self {
+        if scores.is_empty() {
+            return Self::default();
+        }
+        let count = scores.len() as f64;
+        let mean = scores.iter().map(|&score| score as f64).sum::<f64>() / count;
+        let variance = scores.iter().map(|&score| (score as f64 - mean).powi(2)).sum::<f64>() / count;
+        
+        let mean_index = (count - 1.0) / 2.0;
+        let (covariance, index_variance) = scores.iter().enumerate().fold((0.0, 0.0), |(covariance, index_variance), (index, &score)| {
+            let offset = index as f64 - mean_index;
+            (covariance + offset * (score as f64 - mean), index_variance + offset * offset)
+        });
+        let trend = if index_variance > 0.0 { covariance / index_variance } else { 0.0 };
+        
+        RecentForm {
+            mean,
+            std_deviation: variance.sqrt(),
+            trend,
+        }
+    }
     
-    // Admin operations (only on leaderboard chain)
-    ResetLeaderboard,
+    /// 100 when every recent score is the same, falling towards 0 as they spread out
+    pub fn stability(&self) -> f64 {
+        if self.mean > 0.0 {
+            (100.0 - self.std_deviation / self.mean * 100.0).max(0.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+impl CompositeWeights {
+    /// Weighted score from a player's best, average, consistency and recent form, where
+    /// consistency is the average expressed as a percentage of the best
+    pub fn breakdown(&self, highest_score: u32, average: f64, form: RecentForm) -> ScoreBreakdown {
+        let consistency = if highest_score > 0 {
+            average / highest_score as f64 * 100.0
+        } else {
+            0.0
+        };
+        let highest_component = self.highest * highest_score as f64;
+        let average_component = self.average * average;
+        let consistency_component = self.consistency * consistency;
+        let stability_component = self.stability * form.stability();
+        let trend_component = self.trend * form.trend;
+        ScoreBreakdown {
+            highest_component,
+            average_component,
+            consistency_component,
+            stability_component,
+            trend_component,
+            total: highest_component + average_component + consistency_component + stability_component + trend_component,
+        }
+    }
+}
+
+fn default_challenge_accept_window_secs() -> u64 {
+    86_400
+}
+
+fn default_challenge_commit_window_secs() -> u64 {
+    86_400
+}
+
+fn default_challenge_reveal_window_secs() -> u64 {
+    3_600
+}
+
+fn default_timed_mode_limit_secs() -> u64 {
+    120
+}
+
+fn default_survival_lives() -> u32 {
+    3
+}
+
+fn default_chat_rate_limit() -> SubmissionRateLimit {
+    SubmissionRateLimit {
+        max_messages: 10,
+        window_secs: 60,
+    }
+}
+
+fn default_leaderboard_push_interval_secs() -> u64 {
+    60
+}
+
+fn default_candy_rate_grace_ticks() -> u64 {
+    // Five seconds of play, for games submitted in a single block
+    50
+}
+
+fn default_name_change_cooldown_secs() -> u64 {
+    86_400
+}
+
+fn default_reset_vote_window_secs() -> u64 {
+    259_200
+}
+
+fn default_reset_quorum_percent() -> u32 {
+    50
+}
+
+fn default_team_match_accept_window_secs() -> u64 {
+    86_400
+}
+
+fn default_team_war_duration_secs() -> u64 {
+    // One week
+    604_800
+}
+
+fn default_royale_min_entrants() -> u32 {
+    4
+}
+
+fn default_market_fee_percent() -> u8 {
+    5
+}
+
+fn default_referral_games_required() -> u32 {
+    3
+}
+
+fn default_referral_reward_coins() -> u64 {
+    100
+}
+
+fn default_prize_split() -> Vec<u8> {
+    vec![50, 30, 20]
+}
+
+fn default_tie_breakers() -> Vec<TieBreaker> {
+    vec![TieBreaker::MostTotalCandies, TieBreaker::MostGamesPlayed]
+}
+
+// Obstacle layout selected by a session's level_id; level 0 is the open board
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct LevelLayout {
+    pub level_id: u32,
+    pub name: String,
+    pub board: snake_engine::Board,
+}
+
+impl LevelLayout {
+    pub const OPEN_LEVEL_ID: u32 = 0;
+    
+    /// The built-in level without obstacles
+    pub fn open() -> Self {
+        Self {
+            level_id: Self::OPEN_LEVEL_ID,
+            name: "Open field".to_string(),
+            board: snake_engine::Board::default(),
+        }
+    }
+}
+
+// Two player chains registered with the leaderboard chain as a team
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct Team {
+    pub team_id: u64,
+    pub name: String,
+    pub members: Vec<ChainId>, // The member who registered the team comes first and leads it
+    pub matches_played: u32,
+    pub wins: u32,
+    pub total_score: u64,
+    pub war_trophies: u32, // Team wars won
+    pub treasury: u64, // Coins contributed by members and not spent yet
+    pub perks: Vec<TeamPerk>, // Perks the leader bought for the whole team
+}
+
+impl Team {
+    /// The member who can spend the team's treasury
+    pub fn leader(&self) -> Option<ChainId> {
+        self.members.first().copied()
+    }
+}
+
+// Team-wide cosmetics bought from the team treasury
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum)]
+pub enum TeamPerk {
+    BannerSkin, // Custom banner shown next to the team name
+    NameGlow, // Highlighted member names on the leaderboard
+}
+
+impl TeamPerk {
+    /// Coins the perk costs from the team treasury
+    pub fn cost(self) -> u64 {
+        match self {
+            TeamPerk::BannerSkin => 500,
+            TeamPerk::NameGlow => 300,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum)]
+pub enum TeamMatchState {
+    Open,
+    Completed,
+    Proposed, // Waiting for a member of the other team to accept
+    Expired, // Not accepted in time
+}
+
+// One member's finished game in a team match
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct TeamMatchScore {
+    pub player_chain: ChainId,
+    pub candies_collected: u32,
+}
+
+// A 2v2 match; each team scores the sum of its members' games
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct TeamMatch {
+    pub match_id: u64,
+    pub team_a: u64,
+    pub team_b: u64,
+    pub players: Vec<ChainId>, // All four members, team A first
+    pub scores: Vec<TeamMatchScore>,
+    pub team_a_score: u64,
+    pub team_b_score: u64,
+    pub state: TeamMatchState,
+    pub winner: Option<u64>, // None on a draw
+    pub created_at: u64,
+    pub awaiting_team: Option<u64>, // Team that still has to accept the match
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum)]
+pub enum TeamWarState {
+    Open,
+    Settled,
+}
+
+// A contest between two teams over a scheduled window; every game a member finishes
+// in the window adds to their team's total, and the higher total wins a trophy
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct TeamWar {
+    pub war_id: u64,
+    pub team_a: u64,
+    pub team_b: u64,
+    pub starts_at: u64,
+    pub ends_at: u64,
+    pub team_a_score: u64,
+    pub team_b_score: u64,
+    pub state: TeamWarState,
+    pub winner: Option<u64>, // None on a draw or while open
+}
+
+impl TeamWar {
+    /// Whether games finished at `now` count towards the war
+    pub fn is_running(&self, now: u64) -> bool {
+        self.state == TeamWarState::Open && self.starts_at <= now && now < self.ends_at
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum)]
+pub enum TournamentState {
+    Open,
+    Finalized,
+}
+
+// An entrant's attempts at a tournament and the best of them
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct TournamentEntry {
+    pub tournament_id: u64,
+    pub player_chain: ChainId,
+    pub attempts: u32,
+    pub best_score: u32,
+    pub best_at: u64, // Block time the best score arrived, which breaks ties
+    pub rank: Option<u32>, // Set once the tournament is finalized
+    pub prize_coins: u64,
+}
+
+impl TournamentEntry {
+    pub fn new(tournament_id: u64, player_chain: ChainId) -> Self {
+        Self {
+            tournament_id,
+            player_chain,
+            attempts: 0,
+            best_score: 0,
+            best_at: 0,
+            rank: None,
+            prize_coins: 0,
+        }
+    }
+}
+
+// A tournament played without meeting: the leaderboard chain publishes a seed and a window,
+// every entrant gets the same number of attempts on it, and their best attempt counts
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct Tournament {
+    pub tournament_id: u64,
+    pub name: String,
+    pub level_id: u32,
+    pub seed: u64,
+    pub starts_at: u64,
+    pub ends_at: u64,
+    pub max_attempts: u32,
+    pub max_entrants: u32, // 0 lets everyone in
+    pub prize_coins: u64, // Split across the top entrants like the season prizes, or the two finalists
+    pub bracket_size: u32, // Qualifiers seeded into a knockout bracket at the close; 0 ends with the ranking
+    pub bracket_round_secs: u64,
+    pub state: TournamentState,
+    pub standings: Vec<TournamentEntry>, // Kept on the leaderboard chain; best first once finalized
+}
+
+impl Tournament {
+    /// Whether attempts can be played and scored at `now`
+    pub fn is_open(&self, now: u64) -> bool {
+        self.state == TournamentState::Open && self.starts_at <= now && now <= self.ends_at
+    }
+}
+
+// A player's longest Survival run
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct SurvivalEntry {
+    pub chain_id: ChainId,
+    pub player_name: Option<String>,
+    pub longest_survival_ticks: u64,
+    pub record_timestamp: u64,
+}
+
+// A player's change in average reported score from the previous week to this one. Player chains
+// only report new personal records (and team match games), so this compares the records set in
+// each week rather than every game played
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct ImprovementEntry {
+    pub chain_id: ChainId,
+    pub player_name: Option<String>,
+    pub previous_week_average: f64,
+    pub this_week_average: f64,
+    pub improvement: f64,
+}
+
+// A player who finished their first game within the last week. A first game is always a
+// record, so it always reaches the leaderboard chain
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct RisingStarEntry {
+    pub chain_id: ChainId,
+    pub player_name: Option<String>,
+    pub first_game_timestamp: u64,
+    pub games_played: u32, // Results the leaderboard chain received, not every game played
+    pub highest_score: u32,
+}
+
+// Community map published to the leaderboard chain, keyed by the hash of its layout
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct CustomMap {
+    pub map_hash: String,
+    pub name: String,
+    pub author: ChainId,
+    pub board: snake_engine::Board,
+    pub published_at: u64,
+    pub plays: u64,
+}
+
+// A player's best score on one level or custom map
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct LevelScore {
+    pub chain_id: ChainId,
+    pub player_name: Option<String>,
+    pub best_score: u32,
+    pub record_timestamp: u64,
+}
+
+// Announcement published by the leaderboard chain admin
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct Announcement {
+    pub announcement_id: u64,
+    pub title: String,
+    pub body: String,
+    pub published_at: u64,
+    pub expires: Option<u64>,
+}
+
+impl Announcement {
+    pub fn is_expired(&self, timestamp: u64) -> bool {
+        self.expires.map(|expires| timestamp >= expires).unwrap_or(false)
+    }
+}
+
+// Lifecycle of a head-to-head challenge on the leaderboard chain
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum)]
+pub enum ChallengeState {
+    // Waiting for the opponent to accept and stake
+    AwaitingOpponent,
+    // Both staked; each side commits a hash of its result
+    Committing,
+    // Both committed; each side reveals the result behind its hash
+    Revealing,
+    Settled,
+    Voided,
+}
+
+// One player's side of a challenge
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct ChallengeSide {
+    pub chain_id: ChainId,
+    pub commitment: Option<String>,
+    pub revealed_score: Option<u32>,
+}
+
+impl ChallengeSide {
+    pub fn new(chain_id: ChainId) -> Self {
+        Self {
+            chain_id,
+            commitment: None,
+            revealed_score: None,
+        }
+    }
+}
+
+// Head-to-head challenge with a coin wager, settled by commit-reveal
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct Challenge {
+    pub challenge_id: u64,
+    pub challenger: ChallengeSide,
+    pub opponent: ChallengeSide,
+    pub wager: u64,
+    pub state: ChallengeState,
+    pub created_at: u64,
+    pub accepted_at: Option<u64>, // Only games started after this can be revealed
+    // Block time at which the current stage times out; unset once settled or voided
+    pub deadline: Option<u64>,
+    pub winner: Option<ChainId>,
+}
+
+impl Challenge {
+    /// Whether the challenge is still open and its current stage ran out at `now`
+    pub fn is_expired(&self, now: u64) -> bool {
+        let open = matches!(
+            self.state,
+            ChallengeState::AwaitingOpponent | ChallengeState::Committing | ChallengeState::Revealing
+        );
+        open && self.deadline.map(|deadline| now > deadline).unwrap_or(false)
+    }
+    
+    /// Who wins by forfeit when the current stage times out: the only side that committed
+    /// or revealed in time. `None` means the match is void and the stakes go back.
+    pub fn forfeit_winner(&self) -> Option<ChainId> {
+        let (challenger_done, opponent_done) = match self.state {
+            ChallengeState::Committing => (self.challenger.commitment.is_some(), self.opponent.commitment.is_some()),
+            ChallengeState::Revealing => (self.challenger.revealed_score.is_some(), self.opponent.revealed_score.is_some()),
+            _ => return None,
+        };
+        match (challenger_done, opponent_done) {
+            (true, false) => Some(self.challenger.chain_id),
+            (false, true) => Some(self.opponent.chain_id),
+            _ => None,
+        }
+    }
+    
+    /// The side belonging to `chain_id`, if it takes part in this challenge
+    pub fn side_mut(&mut self, chain_id: ChainId) -> Option<&mut ChallengeSide> {
+        if self.challenger.chain_id == chain_id {
+            Some(&mut self.challenger)
+        } else if self.opponent.chain_id == chain_id {
+            Some(&mut self.opponent)
+        } else {
+            None
+        }
+    }
+}
+
+// Where a player stands in the matchmaking queue
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum)]
+pub enum MatchmakingState {
+    Queued,
+    // Paired with another player in a challenge without a wager
+    Matched,
+    // Waited too long; races a ghost, or plays a solo run when there is none
+    TimedOut,
+    Left,
+}
+
+// This player's latest matchmaking status, as reported by the leaderboard chain
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct MatchmakingStatus {
+    pub state: MatchmakingState,
+    pub rating: u32, // Best reported score the player was matched on
+    pub queued_at: u64,
+    pub challenge_id: Option<u64>,
+    pub opponent: Option<ChainId>,
+    pub ghost: Option<String>, // Session of the ghost sent to race after a timeout
+}
+
+/// Commitment to a challenge result: hex-encoded SHA3-256 of
+/// `"{challenge_id}:{player_chain}:{session_id}:{score}:{salt}"`
+pub fn challenge_commitment(challenge_id: u64, player_chain: ChainId, session_id: &str, score: u32, salt: &str) -> String {
+    let preimage = format!("{}:{}:{}:{}:{}", challenge_id, player_chain, session_id, score, salt);
+    sha3_hex(preimage.as_bytes())
+}
+
+/// How long the new chain has to present an account link token
+pub const ACCOUNT_LINK_TTL_SECS: u64 = 24 * 60 * 60;
+
+/// Token linking `old_chain` to `new_chain`: hex-encoded SHA3-256 of
+/// `"link:{old_chain}:{new_chain}:{issued_at}"`
+pub fn account_link_token(old_chain: ChainId, new_chain: ChainId, issued_at: u64) -> String {
+    let preimage = format!("link:{}:{}:{}", old_chain, new_chain, issued_at);
+    sha3_hex(preimage.as_bytes())
+}
+
+/// Hash of a client's move log, as anchored at `EndGame`
+pub fn move_log_hash(move_log: &[u8]) -> String {
+    sha3_hex(move_log)
+}
+
+/// What the game client signs when a player chain with a registered client key ends a game
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ScoreClaim {
+    pub chain_id: ChainId, // So a signature cannot be replayed on another chain with the same client key
+    pub session_id: String,
+    pub candies_collected: u32,
+    pub move_log_hash: Option<String>,
+}
+
+impl BcsSignable<'_> for ScoreClaim {}
+
+/// What the current client key signs to replace or remove itself
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ClientKeyChange {
+    pub chain_id: ChainId,
+    pub new_key: Option<Ed25519PublicKey>,
+}
+
+impl BcsSignable<'_> for ClientKeyChange {}
+
+/// Content hash of a map layout: hex-encoded SHA3-256 of
+/// `"{width}x{height}:{x},{y};..."` with obstacles sorted and deduplicated,
+/// so the same layout always hashes the same regardless of obstacle order
+pub fn map_hash(board: &snake_engine::Board) -> String {
+    let mut obstacles: Vec<(i32, i32)> = board.obstacles.iter().map(|cell| (cell.x, cell.y)).collect();
+    obstacles.sort_unstable();
+    obstacles.dedup();
+    let mut preimage = format!("{}x{}:", board.width, board.height);
+    for (x, y) in obstacles {
+        preimage.push_str(&format!("{},{};", x, y));
+    }
+    sha3_hex(preimage.as_bytes())
+}
+
+/// Why `candies` eaten by `tick`, `elapsed_micros` after the session started, are not humanly
+/// possible: the snake eats at most one candy per tick, and the game cannot run ahead of the clock
+/// by more than `grace_ticks`
+pub fn implausible_candy_rate(candies: u32, tick: u64, elapsed_micros: u64, grace_ticks: u64) -> Option<String> {
+    if candies as u64 > tick {
+        return Some(format!("{} candies in {} ticks", candies, tick));
+    }
+    let reachable_ticks = elapsed_micros.saturating_mul(snake_engine::TICKS_PER_SECOND) / 1_000_000 + grace_ticks;
+    if tick > reachable_ticks {
+        return Some(format!("tick {} reached {:.1}s after the start", tick, elapsed_micros as f64 / 1_000_000.0));
+    }
+    None
+}
+
+// What a `GameFinished` result counts towards on the leaderboard chain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResultAdmission {
+    PlayerStats, // The player's statistics, and its team match if it has one
+    MatchOnly, // Only its team match: team games reach the leaderboard chain even when they are not records
+    Implausible(String), // Nothing: the game's pace is impossible
+}
+
+/// What a `GameFinished` result received `elapsed_micros` after its session started counts
+/// towards, before the sending chain's standing (merges, bans, suspect sessions) is checked.
+/// Both the contract and the delivery simulator admit results through this
+pub fn game_finished_admission(
+    candies_collected: u32,
+    ticks: u64,
+    elapsed_micros: u64,
+    grace_ticks: u64,
+    is_new_record: bool,
+    match_id: Option<u64>,
+) -> ResultAdmission {
+    if let Some(reason) = implausible_candy_rate(candies_collected, ticks, elapsed_micros, grace_ticks) {
+        return ResultAdmission::Implausible(reason);
+    }
+    if match_id.is_some() && !is_new_record {
+        return ResultAdmission::MatchOnly;
+    }
+    ResultAdmission::PlayerStats
+}
+
+/// ID of the `index`-th session started in block `block_height` of `chain`: `session_` followed
+/// by the hex-encoded SHA3-256 of `"{chain}:{block_height}:{index}"`. Block heights only grow,
+/// so IDs never repeat on a chain, and anyone can recompute one from where the session started.
+pub fn session_id(chain: ChainId, block_height: u64, index: u32) -> String {
+    let preimage = format!("{}:{}:{}", chain, block_height, index);
+    format!("session_{}", sha3_hex(preimage.as_bytes()))
+}
+
+/// Seed for a session's candy schedule, fixed when the game starts
+pub fn session_seed(session_id: &str, start_time: u64) -> u64 {
+    let preimage = format!("{}:{}", session_id, start_time);
+    let digest = Sha3_256::digest(preimage.as_bytes());
+    let mut seed = [0u8; 8];
+    seed.copy_from_slice(&digest[..8]);
+    u64::from_le_bytes(seed)
+}
+
+/// Format a timestamp in microseconds since the Unix epoch as a UTC date and time
+pub fn format_timestamp(micros: u64) -> String {
+    let seconds = micros / 1_000_000;
+    let (hour, minute, second) = (seconds / 3600 % 24, seconds / 60 % 60, seconds % 60);
+    
+    // Civil date from days since 1970-01-01, counting in 400-year eras that start on March 1st
+    let days = seconds / 86_400 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC", year, month, day, hour, minute, second)
+}
+
+fn sha3_hex(bytes: &[u8]) -> String {
+    Sha3_256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+// Application parameters for leaderboard configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ApplicationParameters {
+    pub leaderboard_chain_id: Option<ChainId>,
+    // Signer allowed to run privileged operations on the leaderboard chain
+    #[serde(default)]
+    pub admin: Option<AccountOwner>,
+    #[serde(default)]
+    pub tier_thresholds: TierThresholds,
+    #[serde(default)]
+    pub xp: XpConfig,
+    // Days without activity before a player moves to the inactive section (0 disables decay)
+    #[serde(default)]
+    pub inactivity_window_days: u32,
+    // Applied in order after comparing highest scores
+    #[serde(default = "default_tie_breakers")]
+    pub tie_breakers: Vec<TieBreaker>,
+    #[serde(default)]
+    pub ranking_mode: RankingMode,
+    #[serde(default)]
+    pub composite_weights: CompositeWeights,
+    // Percentage of each prize paid to leaderboard positions 1, 2, 3, ...; at most 100 in total, the rest stays in the treasury
+    #[serde(default = "default_prize_split")]
+    pub prize_split_percent: Vec<u8>,
+    // Games a referred player must finish before both sides are rewarded
+    #[serde(default = "default_referral_games_required")]
+    pub referral_games_required: u32,
+    // Coins paid to both the referrer and the referred player
+    #[serde(default = "default_referral_reward_coins")]
+    pub referral_reward_coins: u64,
+    // Time the opponent has to accept before the challenger's stake is returned
+    #[serde(default = "default_challenge_accept_window_secs")]
+    pub challenge_accept_window_secs: u64,
+    // Time both sides have to commit once the challenge is accepted
+    #[serde(default = "default_challenge_commit_window_secs")]
+    pub challenge_commit_window_secs: u64,
+    // Time both sides have to reveal once the second commitment arrives
+    #[serde(default = "default_challenge_reveal_window_secs")]
+    pub challenge_reveal_window_secs: u64,
+    // Length of a Timed mode game, measured in block time from the start
+    #[serde(default = "default_timed_mode_limit_secs")]
+    pub timed_mode_limit_secs: u64,
+    // Lives a Survival game starts with
+    #[serde(default = "default_survival_lives")]
+    pub survival_lives: u32,
+    // Ticks a game may run ahead of block time on the leaderboard chain before it is suspect
+    #[serde(default = "default_candy_rate_grace_ticks")]
+    pub candy_rate_grace_ticks: u64,
+    // Shortest time between two podium updates pushed to the same subscriber
+    #[serde(default = "default_leaderboard_push_interval_secs")]
+    pub leaderboard_push_interval_secs: u64,
+    #[serde(default)]
+    pub query_limits: QueryLimits,
+    #[serde(default)]
+    pub strike_policy: StrikePolicy,
+    // Move players with an upheld flag to the shadow board for the rest of the season
+    #[serde(default)]
+    pub shadow_flagged_players: bool,
+    // GameFinished, CandyCollected, tournament and team war score messages each player chain may send the leaderboard chain
+    #[serde(default)]
+    pub submission_rate_limit: SubmissionRateLimit,
+    // Chat messages each player may send to one chain, or to their teams through the leaderboard chain
+    #[serde(default = "default_chat_rate_limit")]
+    pub chat_rate_limit: SubmissionRateLimit,
+    // Games with a confirmed replay a chain needs before its scores appear on the global leaderboard; 0 disables the gate
+    #[serde(default)]
+    pub min_verified_games: u32,
+    // Coins a chain can stake instead of playing the verified games; 0 means staking is not offered
+    #[serde(default)]
+    pub eligibility_stake_coins: u64,
+    // Minimum time between two name changes; the first name can always be set
+    #[serde(default = "default_name_change_cooldown_secs")]
+    pub name_change_cooldown_secs: u64,
+    // How long participants can vote on a reset proposal
+    #[serde(default = "default_reset_vote_window_secs")]
+    pub reset_vote_window_secs: u64,
+    // Share of the eligible participants that must approve a reset
+    #[serde(default = "default_reset_quorum_percent")]
+    pub reset_quorum_percent: u32,
+    // Time the other team has to accept a match before it expires
+    #[serde(default = "default_team_match_accept_window_secs")]
+    pub team_match_accept_window_secs: u64,
+    // How long a team war runs once it starts
+    #[serde(default = "default_team_war_duration_secs")]
+    pub team_war_duration_secs: u64,
+    // Battle royales opening with fewer players are filled up with bots; 0 never adds bots, at most 16
+    #[serde(default = "default_royale_min_entrants")]
+    pub royale_min_entrants: u32,
+    #[serde(default)]
+    pub season_pass: SeasonPassConfig,
+    // Time over which a prize, quest or referral reward becomes claimable, linearly; 0 vests at once
+    #[serde(default)]
+    pub reward_vesting_secs: u64,
+    // Time after vesting ends before unclaimed coins are forfeited; 0 keeps them forever
+    #[serde(default)]
+    pub reward_expiry_secs: u64,
+    // Share of each market sale kept by the treasury
+    #[serde(default = "default_market_fee_percent")]
+    pub market_fee_percent: u8,
+    #[serde(default)]
+    pub matchmaking: MatchmakingConfig,
+}
+
+impl Default for ApplicationParameters {
+    fn default() -> Self {
+        Self {
+            leaderboard_chain_id: None,
+            admin: None,
+            tier_thresholds: TierThresholds::default(),
+            xp: XpConfig::default(),
+            inactivity_window_days: 0,
+            tie_breakers: default_tie_breakers(),
+            ranking_mode: RankingMode::default(),
+            composite_weights: CompositeWeights::default(),
+            prize_split_percent: default_prize_split(),
+            referral_games_required: default_referral_games_required(),
+            referral_reward_coins: default_referral_reward_coins(),
+            challenge_accept_window_secs: default_challenge_accept_window_secs(),
+            challenge_commit_window_secs: default_challenge_commit_window_secs(),
+            challenge_reveal_window_secs: default_challenge_reveal_window_secs(),
+            timed_mode_limit_secs: default_timed_mode_limit_secs(),
+            survival_lives: default_survival_lives(),
+            candy_rate_grace_ticks: default_candy_rate_grace_ticks(),
+            leaderboard_push_interval_secs: default_leaderboard_push_interval_secs(),
+            query_limits: QueryLimits::default(),
+            strike_policy: StrikePolicy::default(),
+            shadow_flagged_players: false,
+            submission_rate_limit: SubmissionRateLimit::default(),
+            chat_rate_limit: default_chat_rate_limit(),
+            min_verified_games: 0,
+            eligibility_stake_coins: 0,
+            name_change_cooldown_secs: default_name_change_cooldown_secs(),
+            reset_vote_window_secs: default_reset_vote_window_secs(),
+            reset_quorum_percent: default_reset_quorum_percent(),
+            team_match_accept_window_secs: default_team_match_accept_window_secs(),
+            team_war_duration_secs: default_team_war_duration_secs(),
+            royale_min_entrants: default_royale_min_entrants(),
+            season_pass: SeasonPassConfig::default(),
+            reward_vesting_secs: 0,
+            reward_expiry_secs: 0,
+            market_fee_percent: default_market_fee_percent(),
+            matchmaking: MatchmakingConfig::default(),
+        }
+    }
+}
+
+impl ApplicationParameters {
+    /// Earliest time a name set at `changed_at` may be replaced
+    pub fn name_change_allowed_at(&self, changed_at: u64) -> u64 {
+        changed_at.saturating_add(self.name_change_cooldown_secs.saturating_mul(1_000_000))
+    }
+    
+    /// Ordering of two leaderboard entries, best first
+    pub fn compare_entries(&self, a: &LeaderboardEntry, b: &LeaderboardEntry) -> Ordering {
+        let primary = match self.ranking_mode {
+            RankingMode::HighestScore => Ordering::Equal,
+            RankingMode::Composite => b.score_breakdown.total.total_cmp(&a.score_breakdown.total),
+        };
+        self.tie_breakers.iter().fold(
+            primary.then_with(|| b.highest_score.cmp(&a.highest_score)),
+            |ordering, tie_breaker| ordering.then_with(|| tie_breaker.compare(a, b)),
+        )
+    }
+    
+    /// Inactivity window in microseconds, if decay is enabled
+    pub fn inactivity_window_micros(&self) -> Option<u64> {
+        if self.inactivity_window_days == 0 {
+            None
+        } else {
+            Some(self.inactivity_window_days as u64 * 86_400_000_000)
+        }
+    }
+}
+
+// Version of the stored state layout, recorded on each chain when the application is instantiated.
+// Version 2 keeps the metrics counters in a map rather than one register; counters written by
+// version 1 are not carried over. Version 3 keeps only the latest personal bests in a queue, and
+// the full history written by earlier versions is not carried over
+pub const STATE_SCHEMA_VERSION: u32 = 3;
+
+// Version of the indexer event payloads, bumped whenever their fields change
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+
+// A leaderboard position as reported to indexers
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, async_graphql::SimpleObject)]
+pub struct RankedScore {
+    pub chain_id: ChainId,
+    pub player_name: Option<String>,
+    pub highest_score: u32,
+}
+
+// A podium position taken over by another player
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, async_graphql::SimpleObject)]
+pub struct PodiumChange {
+    pub position: u32, // 1 to 3
+    pub new_holder: RankedScore,
+    pub displaced: Option<RankedScore>, // None when the position was empty
+    pub changed_at: u64,
+}
+
+impl From<&LeaderboardEntry> for RankedScore {
+    fn from(entry: &LeaderboardEntry) -> Self {
+        Self {
+            chain_id: entry.chain_id,
+            player_name: entry.player_name.clone(),
+            highest_score: entry.highest_score,
+        }
+    }
+}
+
+// Events emitted to the application's streams
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub enum SnakeGameEvent {
+    // A player moved up to a higher tier
+    TierPromoted {
+        chain_id: ChainId,
+        old_tier: RankTier,
+        new_tier: RankTier,
+    },
+    // A player dropped to a lower tier
+    TierDemoted {
+        chain_id: ChainId,
+        old_tier: RankTier,
+        new_tier: RankTier,
+    },
+    // A new quest is open for all players
+    QuestPublished(QuestDefinition),
+    // Patch notes or event info for all players
+    AnnouncementPublished(Announcement),
+    // A new obstacle layout players can start games on
+    LevelPublished(LevelLayout),
+    // A tournament opened for entries, with the seed every attempt plays
+    TournamentPublished(Tournament),
+    // The leaderboard moved to another chain; sent on the announcements stream so every player chain sees it
+    LeaderboardMoved {
+        new_chain: ChainId,
+    },
+    // An admin switched a feature on or off; sent on the announcements stream so every player chain sees it
+    FeatureFlagSet(FeatureFlag),
+    // Latest validated state of a game being played on the emitting chain
+    LiveSessionUpdated(LiveSession),
+    // A live game finished and is no longer watched
+    LiveSessionEnded {
+        session_id: String,
+    },
+    // Indexer events below carry EVENT_SCHEMA_VERSION so consumers can detect format changes
+    // A player chain started a game
+    GameStarted {
+        version: u32,
+        session_id: String,
+        player: ChainId,
+        mode: GameMode,
+        level_id: u32,
+        map_hash: Option<String>,
+        started_at: u64,
+    },
+    // Candies eaten during one batch of submitted moves
+    CandyBatch {
+        version: u32,
+        session_id: String,
+        player: ChainId,
+        from_tick: u64,
+        to_tick: u64,
+        candies: u32,
+        total_candies: u32,
+    },
+    // A player chain finished a game
+    GameFinished {
+        version: u32,
+        session_id: String,
+        player: ChainId,
+        candies_collected: u32,
+        points: u64,
+        best_combo: u32,
+        ticks: u64,
+        is_record: bool,
+        ended_at: u64,
+    },
+    // The top of the global leaderboard changed
+    LeaderboardChanged {
+        version: u32,
+        top: Vec<RankedScore>,
+    },
+    // The leaderboard was reset, closing a season
+    SeasonEnded {
+        version: u32,
+        season: u32,
+        ended_at: u64,
+        podium: Vec<RankedScore>,
+    },
+    // Another player took over one of the top three positions
+    PodiumChanged {
+        version: u32,
+        change: PodiumChange,
+    },
+}
+
+// Who a chat message is for
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ChatRecipient {
+    Player(ChainId),
+    Team(u64), // Every other member of a registered team, relayed by the leaderboard chain
+}
+
+// A chat message between players
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct ChatMessage {
+    pub from: ChainId,
+    pub from_name: Option<String>,
+    pub to: Option<ChainId>, // None for team messages
+    pub team_id: Option<u64>,
+    pub text: String,
+    pub sent_at: u64,
+}
+
+// What a player chain is waiting for the leaderboard chain to confirm
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum)]
+pub enum PendingUpdateKind {
+    GameResult,
+    PlayerName,
+}
+
+// An update sent to the leaderboard chain that has not been confirmed yet
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct PendingUpdate {
+    pub kind: PendingUpdateKind,
+    pub subject: String, // Session ID of a result, or the name
+    pub sent_at: u64,
+}
+
+// Cross-chain messages
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub enum GameMessage {
+    // Request to start a game on leaderboard chain
+    StartGame {
+        session_id: String,
+        player_chain: ChainId,
+        player_name: Option<String>,
+    },
+    // Game finished notification with score
+    GameFinished {
+        session_id: String,
+        player_chain: ChainId,
+        candies_collected: u32,
+        is_new_record: bool,
+        move_log_hash: Option<String>, // Missing when a Survival game ends on its last death
+        seed: u64,
+        level_id: u32,
+        map_hash: Option<String>,
+        points: u64,
+        best_combo: u32,
+        match_id: Option<u64>, // Team match this game was played for
+        ticks: u64, // Game length in ticks
+        started_at: u64, // Block time the session started, to check the game's pace
+    },
+    // A player's new best score on a level
+    LevelRecord {
+        level_id: u32,
+        player_chain: ChainId,
+        candies_collected: u32,
+    },
+    // A player's new best score in a mode with its own board
+    ModeRecord {
+        mode: GameMode,
+        player_chain: ChainId,
+        candies_collected: u32,
+    },
+    // Team messages sent to the leaderboard chain
+    RegisterTeam {
+        name: String,
+        members: Vec<ChainId>,
+    },
+    CreateTeamMatch {
+        requester: ChainId,
+        team_a: u64,
+        team_b: u64,
+    },
+    // Latest state of a team match, sent to all four players
+    TeamMatchUpdated {
+        team_match: TeamMatch,
+    },
+    // Battle royale messages
+    JoinBattleRoyale {
+        event_id: u64,
+        player_chain: ChainId,
+    },
+    BattleRoyaleRound {
+        event_id: u64,
+        round: u32,
+        round_seed: u64,
+        round_ends_at: u64,
+    },
+    SubmitRoyaleScore {
+        event_id: u64,
+        round: u32,
+        player_chain: ChainId,
+        session_id: String,
+        candies_collected: u32,
+        ticks: u64,
+        started_at: u64,
+    },
+    BattleRoyaleEliminated {
+        event_id: u64,
+        round: u32,
+    },
+    BattleRoyaleWon {
+        event_id: u64,
+    },
+    // A player's new longest Survival run
+    SurvivalRecord {
+        player_chain: ChainId,
+        survival_ticks: u64,
+    },
+    // Publish a community map on the leaderboard chain
+    PublishMap {
+        author: ChainId,
+        name: String,
+        board: snake_engine::Board,
+    },
+    // A game finished on a custom map, sent for every game to track popularity
+    MapPlayed {
+        map_hash: String,
+        player_chain: ChainId,
+        candies_collected: u32,
+    },
+    // Update leaderboard stats
+    UpdateLeaderboard {
+        player_chain: ChainId,
+        candies_collected: u32,
+        is_new_record: bool,
+    },
+    // Update player name on leaderboard chain
+    UpdatePlayerName {
+        player_chain: ChainId,
+        player_name: String,
+    },
+    // The leaderboard chain refused a name, sent back to the player chain
+    NameRejected {
+        name: String,
+        reason: String,
+    },
+    // Update player level on leaderboard chain
+    UpdatePlayerLevel {
+        player_chain: ChainId,
+        level: u32,
+    },
+    // An achievement unlocked on a player chain, shown on the public profile
+    AchievementUnlocked {
+        player_chain: ChainId,
+        achievement: Achievement,
+        unlocked_at: u64,
+    },
+    // Tokens donated to a named prize pool, already transferred to the leaderboard chain
+    PrizeDonation {
+        pool: String,
+        sponsor_chain: ChainId,
+        sponsor_name: Option<String>,
+        amount: Amount,
+    },
+    // Ask the leaderboard chain for this player's referral code
+    RequestReferralCode {
+        player_chain: ChainId,
+    },
+    // Referral code minted for the receiving player chain
+    ReferralCodeAssigned {
+        code: String,
+    },
+    // A new player wants to redeem a referral code
+    RedeemReferral {
+        code: String,
+        referee_chain: ChainId,
+    },
+    // Outcome of a redemption, sent back to the referee chain
+    ReferralAccepted {
+        code: String,
+    },
+    ReferralRejected {
+        code: String,
+        reason: String,
+    },
+    // The referee finished enough games for the referral to pay out
+    ReferralQualified {
+        referee_chain: ChainId,
+    },
+    // Coins credited to a referrer or referee
+    ReferralReward {
+        coins: u64,
+    },
+    // Coins a player locked with the leaderboard chain to become eligible for the board
+    EligibilityStaked {
+        player_chain: ChainId,
+        coins: u64,
+    },
+    // Challenge messages sent to the leaderboard chain; stakes are already debited
+    ChallengeCreated {
+        challenger: ChainId,
+        opponent: ChainId,
+        wager: u64,
+    },
+    ChallengeAccepted {
+        challenge_id: u64,
+        player_chain: ChainId,
+    },
+    ChallengeCommit {
+        challenge_id: u64,
+        player_chain: ChainId,
+        commitment: String,
+    },
+    ChallengeReveal {
+        challenge_id: u64,
+        player_chain: ChainId,
+        session_id: String,
+        started_at: u64,
+        score: u32,
+        salt: String,
+    },
+    ChallengeTimeoutClaim {
+        challenge_id: u64,
+        player_chain: ChainId,
+    },
+    // Challenge messages sent back to the participants
+    ChallengeUpdated {
+        challenge: Challenge,
+    },
+    ChallengePayout {
+        challenge_id: u64,
+        coins: u64,
+    },
+    // Notification that leaderboard has been reset
+    LeaderboardReset,
+    // A reset was proposed, sent to every participant allowed to vote
+    ResetProposed {
+        proposal_id: u64,
+        closes_at: u64,
+    },
+    ResetVote {
+        proposal_id: u64,
+        voter: ChainId,
+        approve: bool,
+    },
+    // The leaderboard chain tells a player about a strike and its penalty
+    PenaltyApplied {
+        penalty: PenaltyRecord,
+        session_id: String, // Game the penalty was for, so it reaches the owner who played it
+    },
+    // A participant flags another player's score for moderator review
+    ScoreFlagged {
+        reporter: ChainId,
+        player_chain: ChainId,
+        session_id: String,
+        reason: String,
+    },
+    // The current leaderboard chain hands its role to the receiving chain
+    LeaderboardHandover,
+    // The receiving chain took over as leaderboard chain, sent back to the previous one
+    LeaderboardHandoverAccepted,
+    // Notification that a candy was collected
+    CandyCollected {
+        session_id: String,
+        player_chain: ChainId,
+        tick: u64,
+        started_at: u64, // Block time the session started, to check the game's pace
+    },
+    // Spectator registration, sent to the watched player chain
+    SpectatorJoined {
+        spectator: ChainId,
+    },
+    SpectatorLeft {
+        spectator: ChainId,
+    },
+    // Ticks played since the previous delta, sent to each spectator
+    LiveDelta(LiveDelta),
+    // A finished game's move log was published as a data blob
+    ReplayPublished {
+        session_id: String,
+        player_chain: ChainId,
+        replay_blob: DataBlobHash,
+    },
+    // A spectator missed a delta and asks for the full state
+    ResyncRequest {
+        session_id: String,
+        spectator: ChainId,
+    },
+    // Full state of a live session, answering a resync or a new spectator
+    LiveResync(LiveSession),
+    // Finished sessions of a player who opted into public history, oldest first
+    HistorySync {
+        player_chain: ChainId,
+        summaries: Vec<SessionSummary>,
+    },
+    // The player opted out; their public history is deleted
+    HistoryOptOut {
+        player_chain: ChainId,
+    },
+    // A player chain asks for the top `limit` rows of the global leaderboard
+    LeaderboardSyncRequest {
+        player_chain: ChainId,
+        limit: u32,
+    },
+    // The top of the global leaderboard at `version`, pushed to a player chain
+    LeaderboardSnapshot {
+        version: u64,
+        entries: Vec<LeaderboardEntry>,
+    },
+    // A player chain wants the top `limit` rows pushed whenever the podium changes
+    LeaderboardSubscribe {
+        player_chain: ChainId,
+        limit: u32,
+    },
+    LeaderboardUnsubscribe {
+        player_chain: ChainId,
+    },
+    // A player chain starts or stops following another player's records
+    FollowPlayer {
+        follower: ChainId,
+        followed: ChainId,
+    },
+    UnfollowPlayer {
+        follower: ChainId,
+        followed: ChainId,
+    },
+    // A followed player set a new record, forwarded to each follower
+    FollowedRecord {
+        player_chain: ChainId,
+        player_name: Option<String>,
+        candies_collected: u32,
+    },
+    // A player's emote on another player's entry; None takes it back
+    React {
+        reactor: ChainId,
+        target: ChainId,
+        emote: Option<Emote>,
+    },
+    // A chat message for the leaderboard chain to relay to the sender's team
+    TeamChat {
+        message: ChatMessage,
+    },
+    // A chat message delivered to its recipient
+    Chat {
+        message: ChatMessage,
+    },
+    // Another player moved ahead, pushing this chain down from the top ten of the global leaderboard
+    Overtaken {
+        by: ChainId,
+        by_name: Option<String>,
+        old_rank: u32,
+        new_rank: u32,
+    },
+    // A member's finished game, counted towards every running war of their team
+    TeamWarScore {
+        war_id: u64,
+        player_chain: ChainId,
+        session_id: String,
+        candies_collected: u32,
+        ticks: u64,
+        started_at: u64, // With the ticks, lets the leaderboard chain check the game's pace
+    },
+    // Latest state of a team war, sent to the members of both teams
+    TeamWarUpdated {
+        war: TeamWar,
+    },
+    // Coins a member moves from their balance into their team's treasury
+    TeamContribution {
+        team_id: u64,
+        player_chain: ChainId,
+        coins: u64,
+    },
+    // A contribution the team could not accept, returned to the player
+    TeamContributionRefunded {
+        team_id: u64,
+        coins: u64,
+    },
+    // The team leader spends treasury coins on a perk
+    BuyTeamPerk {
+        team_id: u64,
+        leader: ChainId,
+        perk: TeamPerk,
+    },
+    // Ask the leaderboard chain for the published replay of an anchored game
+    GhostRequest {
+        session_id: String,
+        player_chain: ChainId,
+    },
+    // A ghost the requesting player can race
+    GhostReplay {
+        ghost: Ghost,
+    },
+    // A finished tournament attempt, sent to the leaderboard chain
+    TournamentScore {
+        tournament_id: u64,
+        player_chain: ChainId,
+        session_id: String,
+        candies_collected: u32,
+        move_log_hash: String, // Anchored so the attempt can be replayed if it is disputed
+        seed: u64,
+        level_id: u32,
+        map_hash: Option<String>,
+        ticks: u64,
+        started_at: u64,
+    },
+    // An entrant's final standing, with the coins it won
+    TournamentResult {
+        tournament_id: u64,
+        entry: TournamentEntry,
+    },
+    // A knockout round opened, sent to both players of each match
+    BracketRound {
+        tournament_id: u64,
+        round: u32,
+        round_seed: u64,
+        round_ends_at: u64,
+        opponent: ChainId,
+    },
+    // A knockout game, sent to the leaderboard chain
+    BracketScore {
+        tournament_id: u64,
+        round: u32,
+        player_chain: ChainId,
+        session_id: String,
+        candies_collected: u32,
+        move_log_hash: String, // Anchored so the game can be replayed if it is disputed
+        seed: u64,
+        level_id: u32,
+        map_hash: Option<String>,
+        ticks: u64,
+        started_at: u64,
+    },
+    BracketEliminated {
+        tournament_id: u64,
+        round: u32,
+        prize_coins: u64, // The runner-up's share of the prize
+    },
+    BracketWon {
+        tournament_id: u64,
+        prize_coins: u64,
+    },
+    // Sent when a tier unlocks or the premium track is bought, to show the pass on the profile
+    SeasonPassUpdated {
+        player_chain: ChainId,
+        progress: SeasonPassProgress,
+    },
+    // Tokens paid for the premium track, already transferred to the leaderboard chain
+    SeasonPremiumPurchased {
+        buyer: Account,
+        amount: Amount,
+    },
+    // Market messages; items and payments are escrowed on the leaderboard chain until a sale or cancellation
+    ListMarketItem {
+        seller: Account, // Receives the proceeds
+        kind: ItemKind,
+        item: String,
+        price: Amount,
+    },
+    CancelMarketListing {
+        listing_id: u64,
+        seller_chain: ChainId,
+    },
+    BuyMarketListing {
+        listing_id: u64,
+        buyer: Account,
+        paid: Amount, // Already transferred to the leaderboard chain
+    },
+    // An item bought from the market, or returned to its seller
+    MarketItemDelivered {
+        listing_id: u64,
+        kind: ItemKind,
+        item: String,
+    },
+    // Coins another player gave this chain, already taken from the sender's balance
+    GiftCoins {
+        from_chain: ChainId,
+        from_name: Option<String>,
+        amount: u64,
+    },
+    AvatarUpdated {
+        player_chain: ChainId,
+        avatar: Option<Avatar>, // None when the player went back to the default
+    },
+    CountryUpdated {
+        player_chain: ChainId,
+        country: Option<String>,
+    },
+    // Account linking: the old chain registers a link with the leaderboard chain and hands its
+    // token to the new chain, which presents it to have the histories merged
+    IssueAccountLink {
+        old_chain: ChainId,
+        new_chain: ChainId,
+        token: String,
+    },
+    AccountLinkToken {
+        old_chain: ChainId,
+        token: String,
+    },
+    MergeAccounts {
+        old_chain: ChainId,
+        new_chain: ChainId,
+        token: String,
+    },
+    // Sent to both chains once the old chain's history is part of the new one
+    AccountMerged {
+        old_chain: ChainId,
+        new_chain: ChainId,
+    },
+    // Sent back to a player chain once the leaderboard chain has processed its update
+    ResultReceived {
+        session_id: String,
+    },
+    NameAccepted {
+        name: String,
+    },
+    // The leaderboard chain refused a result, sent back to the player chain
+    ResultRejected {
+        session_id: String,
+        reason: String,
+    },
+    // Sent to the teammate a new team was registered with; the team exists once they accept
+    TeamInvite {
+        team: Team,
+    },
+    AcceptTeam {
+        team_id: u64,
+        player_chain: ChainId,
+    },
+    // A member of the team asked to play accepts the match
+    AcceptTeamMatch {
+        match_id: u64,
+        player_chain: ChainId,
+    },
+    // Join the matchmaking queue, or check on a ticket already in it
+    JoinMatchmaking {
+        player_chain: ChainId,
+    },
+    LeaveMatchmaking {
+        player_chain: ChainId,
+    },
+    // Sent to a queued player whenever their ticket changes
+    MatchmakingUpdate {
+        status: MatchmakingStatus,
+    },
+}
+
+// Version of the GameMessage encoding this bytecode sends. New variants go at the end of the
+// enum so older payloads still decode; bump this when an existing variant changes shape and
+// teach `MessageEnvelope::open` to read the previous layout.
+pub const MESSAGE_SCHEMA_VERSION: u32 = 3;
+
+// A GameMessage as it travels between chains. The payload is encoded separately, so a chain
+// running other bytecode can still read the version and turn away a message it cannot decode
+// instead of failing the block that delivers it.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MessageEnvelope {
+    pub version: u32,
+    pub payload: Vec<u8>,
+}
+
+impl From<GameMessage> for MessageEnvelope {
+    fn from(message: GameMessage) -> Self {
+        MessageEnvelope {
+            version: MESSAGE_SCHEMA_VERSION,
+            payload: linera_sdk::bcs::to_bytes(&message).expect("GameMessage should serialize"),
+        }
+    }
+}
+
+impl MessageEnvelope {
+    /// Decode the message, or say why it cannot be read by this version
+    pub fn open(&self) -> Result<GameMessage, String> {
+        // Version 2 reshaped ChallengeReveal, TeamWarScore, TournamentScore, PenaltyApplied and TeamMatchUpdated; version 1 payloads of those
+        // no longer decode and are dropped. Version 3 did the same to BracketScore and SubmitRoyaleScore. A newer sender may still use variants this bytecode knows,
+        // so those are accepted too
+        linera_sdk::bcs::from_bytes(&self.payload).map_err(|error| {
+            if self.version > MESSAGE_SCHEMA_VERSION {
+                format!("message version {} is newer than {} and could not be decoded: {}", self.version, MESSAGE_SCHEMA_VERSION, error)
+            } else {
+                format!("message version {} could not be decoded: {}", self.version, error)
+            }
+        })
+    }
+}
+
+// What an operation did, returned to the block proposer
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum OperationResponse {
+    Ok,
+    SessionStarted {
+        session_id: String,
+    },
+    SessionRestarted {
+        ended_session_id: Option<String>, // None when no game was in progress
+        session_id: String,
+    },
+    // The operation ran but had no effect
+    Rejected {
+        reason: String,
+    },
+    // One response per operation of a batch, in order
+    Batch(Vec<OperationResponse>),
+    // The operation is retired and did nothing; `replacement` says what to use instead
+    Deprecated {
+        operation: String,
+        replacement: String,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum Operation {
+    // Run several operations in one block; a failure in any of them reverts them all
+    Batch(Vec<Operation>),
+    
+    // Setup operations
+    SetupLeaderboard {
+        leaderboard_chain_id: ChainId,
+    },
+    
+    // Player name operations
+    SetPlayerName {
+        name: String,
+    },
+    // Show a country on this player's profile and entries; None removes it
+    SetCountry {
+        country: Option<String>,
+    },
+    // Require games on this chain to end with a score signed by the game client's key.
+    // Replacing or removing a registered key needs its signature of the ClientKeyChange
+    RegisterClientKey {
+        public_key: Option<Ed25519PublicKey>,
+        signature: Option<Ed25519Signature>,
+    },
+    // Let `new_chain` take over this chain's leaderboard history; the link expires after ACCOUNT_LINK_TTL_SECS
+    IssueAccountLink {
+        new_chain: ChainId,
+    },
+    // Present the link token `old_chain` sent, merging its history into this chain
+    MergeAccount {
+        old_chain: ChainId,
+    },
+    // Use a published image blob as this player's avatar; None goes back to the default one
+    SetAvatar {
+        blob_hash: Option<DataBlobHash>,
+    },
+    
+    // Answer an open reset proposal from the leaderboard chain
+    VoteOnReset {
+        proposal_id: u64,
+        approve: bool,
+    },
+    // Report a suspicious leaderboard entry to the moderators
+    FlagScore {
+        chain_id: ChainId,
+        session_id: String,
+        reason: String,
+    },
+    
+    // Public history operations
+    SetHistorySync {
+        enabled: bool, // Disabling also deletes the history held by the leaderboard chain
+    },
+    SyncHistory, // Send pending summaries now instead of waiting for a full batch
+    
+    // Game operations
+    StartGame {
+        level_id: u32,
+        map_hash: Option<String>, // Play a published custom map instead of a level
+        mode: GameMode,
+        match_id: Option<u64>, // Play this game for a scheduled team match
+        royale_id: Option<u64>, // Play the current round of a battle royale
+        ghost: Option<String>, // Race the published replay of this session, on its level and seed
+        tournament_id: Option<u64>, // Spend an attempt at an open tournament, on its level and seed
+    },
+    CollectCandy {
+        tick: u64, // Game tick the candy was eaten at, used to chain combos
+    },
+    EndGame {
+        move_log_hash: String, // Hash of the client's move log, so the score can be replayed in a dispute
+        signature: Option<Ed25519Signature>, // Client key's signature of the ScoreClaim, required once a key is registered
+    },
+    // End the current game, if any, and start a new one in the same block
+    RestartGame {
+        move_log_hash: Option<String>, // Move log of the game being ended; required when there is one
+        signature: Option<Ed25519Signature>, // Client key's signature of the ended game's ScoreClaim
+        level_id: u32,
+        map_hash: Option<String>,
+        mode: GameMode,
+        match_id: Option<u64>,
+        royale_id: Option<u64>,
+        ghost: Option<String>,
+        tournament_id: Option<u64>,
+    },
+    ReportDeath {
+        tick: u64, // Survival mode: game tick at which the snake died
+    },
+    SubmitMoves {
+        moves: Vec<u8>, // Direction bytes for the next ticks of the current session
+    },
+    Heartbeat {
+        tick: u64, // Advance the session tick while no moves or candies are submitted
+    },
+    // Merge the games played as a guest into the signer's stats and report their records
+    ClaimGuestHistory,
+    
+    // Spectator operations
+    Spectate {
+        player_chain: ChainId,
+    },
+    StopSpectating {
+        player_chain: ChainId,
+    },
+    
+    // Deprecated: these never changed state, and the service answers the same questions.
+    // They are still decoded so clients that submit them get OperationResponse::Deprecated
+    // instead of a failed block; see `Operation::replacement`.
+    GetLeaderboard,
+    GetMyStats,
+    GetGameSession {
+        session_id: String,
+    },
+    
+    // Admin operations (only on leaderboard chain)
+    ProposeReset, // Opens a vote; the leaderboard resets once enough participants approve
+    // Hand the leaderboard role to another chain and repoint every player chain to it
+    ReconfigureLeaderboard {
+        new_chain: ChainId,
+    },
+    DecayInactive,
+    PublishQuest {
+        title: String,
+        kind: QuestKind,
+        period: QuestPeriod,
+        target: u32,
+        reward_coins: u64,
+        reward_xp: u64,
+    },
+    PublishAnnouncement {
+        title: String,
+        body: String,
+        expires: Option<u64>,
+    },
+    DefineLevel {
+        name: String,
+        width: i32,
+        height: i32,
+        obstacles: Vec<snake_engine::Cell>,
+    },
+    ReserveName {
+        name: String,
+        reserved_for: Option<ChainId>, // The only chain allowed to use the name; None blocks it for everyone
+    },
+    ReleaseName {
+        name: String,
+    },
+    SetVerified {
+        player_chain: ChainId,
+        verified: bool,
+    },
+    SetFeatureFlag {
+        key: String,
+        enabled: bool,
+    },
+    
+    // Moderator review of flagged scores (only on leaderboard chain)
+    ResolveDispute {
+        dispute_id: u64, // Dismiss the flag; the score stands
+    },
+    UpholdDispute {
+        dispute_id: u64, // Agree the score is suspicious and mark it on the board
+    },
+    StrikeDispute {
+        dispute_id: u64, // Uphold the flag and record a strike against the player
+    },
+    
+    // Community map operations
+    PublishMap {
+        name: String,
+        layout: snake_engine::Board,
+    },
+    
+    // Treasury operations (only on leaderboard chain)
+    FundTreasury {
+        amount: Amount,
+    },
+    WithdrawTreasury {
+        amount: Amount,
+        to: Account,
+    },
+    
+    // Prize operations
+    DonateToPrizePool {
+        pool: String,
+        amount: Amount,
+        sponsor_name: Option<String>,
+    },
+    DistributePrizes {
+        treasury_amount: Amount,
+    },
+    
+    // Have the leaderboard chain push its top `limit` rows to this chain, so the board can be shown from local state
+    RequestLeaderboardSync {
+        limit: u32,
+    },
+    // Leave an emote on a player's leaderboard entry, replacing this chain's earlier one; None takes it back
+    ReactToEntry {
+        chain_id: ChainId,
+        emote: Option<Emote>,
+    },
+    
+    // Chat with another player, or with the members of a team the sender belongs to
+    SendChatMessage {
+        recipient: ChatRecipient,
+        text: String,
+    },
+    
+    // Get a notification whenever another player sets a new record
+    FollowPlayer {
+        chain_id: ChainId,
+    },
+    UnfollowPlayer {
+        chain_id: ChainId,
+    },
+    
+    // Keep getting the top `limit` rows pushed whenever the podium changes, until unsubscribed
+    SubscribeToLeaderboard {
+        limit: u32,
+    },
+    UnsubscribeFromLeaderboard,
+    
+    // Lock coins with the leaderboard chain to appear on the board before playing enough verified games
+    StakeForEligibility,
+    
+    // Move the vested part of every pending reward into this chain's coins, dropping expired ones
+    ClaimRewards,
+    // Send coins to another player chain; they come back if the recipient refuses them
+    GiftCoins {
+        to_chain: ChainId,
+        amount: u64,
+    },
+    
+    // Market operations
+    ListItem {
+        kind: ItemKind,
+        item: String,
+        price: Amount,
+    },
+    CancelListing {
+        listing_id: u64,
+    },
+    BuyListing {
+        listing_id: u64,
+        price: Amount, // Transferred up front; anything above the listing price is refunded
+    },
+    
+    // Referral operations
+    RequestReferralCode,
+    RegisterReferral {
+        code: String,
+    },
+    
+    // Battle royale operations
+    CreateBattleRoyale {
+        name: String,
+        starts_at: u64,
+        round_duration_secs: u64,
+        elimination_percent: u8,
+    },
+    JoinBattleRoyale {
+        event_id: u64,
+    },
+    AdvanceBattleRoyale {
+        event_id: u64,
+    },
+    
+    // Tournament operations (only on leaderboard chain)
+    CreateTournament {
+        name: String,
+        level_id: u32,
+        starts_at: u64,
+        duration_secs: u64,
+        max_attempts: u32,
+        max_entrants: u32, // 0 lets everyone in
+        prize_coins: u64,
+        bracket_size: u32, // Power of two: the top qualifiers play a knockout bracket; 0 for none
+        bracket_round_secs: u64, // How long each knockout round stays open
+    },
+    FinalizeTournament {
+        tournament_id: u64,
+    },
+    
+    // Season pass operations
+    BuySeasonPremium,
+    // Claiming a reward that was already claimed does nothing, so retries never pay twice
+    ClaimSeasonReward {
+        season: u32,
+        track: PassTrack,
+        tier: u32, // 0-based index into the configured tiers
+    },
+    
+    // Team operations
+    RegisterTeam {
+        name: String,
+        teammate: ChainId,
+    },
+    CreateTeamMatch {
+        team_a: u64,
+        team_b: u64,
+    },
+    // Move coins from this chain's balance into a team treasury
+    ContributeToTeam {
+        team_id: u64,
+        coins: u64,
+    },
+    // Team leader: spend treasury coins on a team-wide perk
+    BuyTeamPerk {
+        team_id: u64,
+        perk: TeamPerk,
+    },
+    // Admin: pit two teams against each other from `starts_at` (now when in the past) for the configured window
+    ScheduleTeamWar {
+        team_a: u64,
+        team_b: u64,
+        starts_at: u64,
+    },
+    
+    // Challenge operations
+    CreateChallenge {
+        opponent: ChainId,
+        wager: u64,
+    },
+    AcceptChallenge {
+        challenge_id: u64,
+    },
+    CommitChallengeResult {
+        challenge_id: u64,
+        commitment: String,
+    },
+    RevealChallengeResult {
+        challenge_id: u64,
+        session_id: String,
+        salt: String,
+    },
+    ClaimChallengeTimeout {
+        challenge_id: u64,
+    },
+    
+    // Replay operations
+    PublishReplay {
+        session_id: String,
+        move_log: Vec<u8>, // One direction byte per tick; must match the hash anchored at EndGame
+    },
+    // Fetch another player's published record from the leaderboard chain to race its ghost
+    RequestGhost {
+        session_id: String,
+    },
+    
+    // Dispute operations (only on leaderboard chain)
+    VerifyReplay {
+        session_id: String,
+        move_log: Option<DataBlobHash>, // Published move log; defaults to the replay the player published
+    },
+    
+    // Join the team a player registered with this chain as their teammate
+    AcceptTeam {
+        team_id: u64,
+    },
+    // Agree to play a match another team proposed against this chain's team
+    AcceptTeamMatch {
+        match_id: u64,
+    },
+    // Re-index every player name and reservation under the current name_key (admin, leaderboard chain)
+    RebuildNameHandles,
+    // Queue for a head-to-head match against a player with a similar best score; joining again
+    // while queued checks whether the ticket was matched or timed out
+    JoinMatchmaking,
+    LeaveMatchmaking,
+}
+
+impl Operation {
+    /// What to use instead of a deprecated operation, or `None` if the operation is current
+    pub fn replacement(&self) -> Option<&'static str> {
+        match self {
+            Operation::GetLeaderboard => Some("RequestLeaderboardSync, or the globalLeaderboard query"),
+            Operation::GetMyStats => Some("the myStats query"),
+            Operation::GetGameSession { .. } => Some("the session query"),
+            _ => None,
+        }
+    }
+    
+    /// The operation's variant name, used to count operations by type
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Operation::Batch(_) => "Batch",
+            Operation::SetupLeaderboard { .. } => "SetupLeaderboard",
+            Operation::SetPlayerName { .. } => "SetPlayerName",
+            Operation::VoteOnReset { .. } => "VoteOnReset",
+            Operation::FlagScore { .. } => "FlagScore",
+            Operation::SetHistorySync { .. } => "SetHistorySync",
+            Operation::SyncHistory => "SyncHistory",
+            Operation::StartGame { .. } => "StartGame",
+            Operation::CollectCandy { .. } => "CollectCandy",
+            Operation::EndGame { .. } => "EndGame",
+            Operation::RestartGame { .. } => "RestartGame",
+            Operation::ReportDeath { .. } => "ReportDeath",
+            Operation::SubmitMoves { .. } => "SubmitMoves",
+            Operation::Heartbeat { .. } => "Heartbeat",
+            Operation::Spectate { .. } => "Spectate",
+            Operation::StopSpectating { .. } => "StopSpectating",
+            Operation::GetLeaderboard => "GetLeaderboard",
+            Operation::GetMyStats => "GetMyStats",
+            Operation::GetGameSession { .. } => "GetGameSession",
+            Operation::ProposeReset => "ProposeReset",
+            Operation::ReconfigureLeaderboard { .. } => "ReconfigureLeaderboard",
+            Operation::DecayInactive => "DecayInactive",
+            Operation::PublishQuest { .. } => "PublishQuest",
+            Operation::PublishAnnouncement { .. } => "PublishAnnouncement",
+            Operation::DefineLevel { .. } => "DefineLevel",
+            Operation::ReserveName { .. } => "ReserveName",
+            Operation::ReleaseName { .. } => "ReleaseName",
+            Operation::SetVerified { .. } => "SetVerified",
+            Operation::SetFeatureFlag { .. } => "SetFeatureFlag",
+            Operation::ResolveDispute { .. } => "ResolveDispute",
+            Operation::UpholdDispute { .. } => "UpholdDispute",
+            Operation::StrikeDispute { .. } => "StrikeDispute",
+            Operation::PublishMap { .. } => "PublishMap",
+            Operation::FundTreasury { .. } => "FundTreasury",
+            Operation::WithdrawTreasury { .. } => "WithdrawTreasury",
+            Operation::DonateToPrizePool { .. } => "DonateToPrizePool",
+            Operation::DistributePrizes { .. } => "DistributePrizes",
+            Operation::StakeForEligibility => "StakeForEligibility",
+            Operation::ClaimRewards => "ClaimRewards",
+            Operation::ClaimGuestHistory => "ClaimGuestHistory",
+            Operation::GiftCoins { .. } => "GiftCoins",
+            Operation::RegisterClientKey { .. } => "RegisterClientKey",
+            Operation::IssueAccountLink { .. } => "IssueAccountLink",
+            Operation::MergeAccount { .. } => "MergeAccount",
+            Operation::SetAvatar { .. } => "SetAvatar",
+            Operation::SetCountry { .. } => "SetCountry",
+            Operation::ListItem { .. } => "ListItem",
+            Operation::CancelListing { .. } => "CancelListing",
+            Operation::BuyListing { .. } => "BuyListing",
+            Operation::RequestReferralCode => "RequestReferralCode",
+            Operation::RequestLeaderboardSync { .. } => "RequestLeaderboardSync",
+            Operation::SubscribeToLeaderboard { .. } => "SubscribeToLeaderboard",
+            Operation::SendChatMessage { .. } => "SendChatMessage",
+            Operation::ReactToEntry { .. } => "ReactToEntry",
+            Operation::FollowPlayer { .. } => "FollowPlayer",
+            Operation::UnfollowPlayer { .. } => "UnfollowPlayer",
+            Operation::UnsubscribeFromLeaderboard => "UnsubscribeFromLeaderboard",
+            Operation::RegisterReferral { .. } => "RegisterReferral",
+            Operation::CreateBattleRoyale { .. } => "CreateBattleRoyale",
+            Operation::JoinBattleRoyale { .. } => "JoinBattleRoyale",
+            Operation::AdvanceBattleRoyale { .. } => "AdvanceBattleRoyale",
+            Operation::CreateTournament { .. } => "CreateTournament",
+            Operation::FinalizeTournament { .. } => "FinalizeTournament",
+            Operation::BuySeasonPremium => "BuySeasonPremium",
+            Operation::ClaimSeasonReward { .. } => "ClaimSeasonReward",
+            Operation::RegisterTeam { .. } => "RegisterTeam",
+            Operation::CreateTeamMatch { .. } => "CreateTeamMatch",
+            Operation::ScheduleTeamWar { .. } => "ScheduleTeamWar",
+            Operation::ContributeToTeam { .. } => "ContributeToTeam",
+            Operation::BuyTeamPerk { .. } => "BuyTeamPerk",
+            Operation::CreateChallenge { .. } => "CreateChallenge",
+            Operation::AcceptChallenge { .. } => "AcceptChallenge",
+            Operation::CommitChallengeResult { .. } => "CommitChallengeResult",
+            Operation::RevealChallengeResult { .. } => "RevealChallengeResult",
+            Operation::ClaimChallengeTimeout { .. } => "ClaimChallengeTimeout",
+            Operation::PublishReplay { .. } => "PublishReplay",
+            Operation::RequestGhost { .. } => "RequestGhost",
+            Operation::VerifyReplay { .. } => "VerifyReplay",
+            Operation::AcceptTeam { .. } => "AcceptTeam",
+            Operation::AcceptTeamMatch { .. } => "AcceptTeamMatch",
+            Operation::RebuildNameHandles => "RebuildNameHandles",
+            Operation::JoinMatchmaking => "JoinMatchmaking",
+            Operation::LeaveMatchmaking => "LeaveMatchmaking",
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,48 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+/*! Keeps significant contract events in a bounded on-chain log, readable where stderr is not */
+
+use super::SnakeGameContract;
+use crate::state::{LogEntry, LogLevel};
+
+/// Log an event from inside the contract: `log_event!(self, Warn, "RESET", "Proposal {} is not open", id)`
+///
+/// The message is formatted before the contract is borrowed, so it may read from the runtime.
+macro_rules! log_event {
+    ($contract:expr, $level:ident, $code:literal, $($context:tt)+) => {{
+        let context = format!($($context)+);
+        $contract.log($crate::state::LogLevel::$level, $code, context)
+    }};
+}
+
+/// Log why an incoming message is ignored and count it as rejected
+macro_rules! reject_message {
+    ($contract:expr, $code:literal, $($context:tt)+) => {{
+        log_event!($contract, Warn, $code, $($context)+);
+        $contract.increment_metric($crate::metrics::MESSAGES_REJECTED, 1).await
+    }};
+}
+
+/// Maximum number of entries kept in the on-chain log
+const MAX_LOG_ENTRIES: usize = 500;
+
+impl SnakeGameContract {
+    /// Print an event to stderr and, unless it is debug output, append it to the on-chain log
+    pub(crate) fn log(&mut self, level: LogLevel, code: &str, context: String) {
+        eprintln!("[{}] {}", code, context);
+        if level == LogLevel::Debug {
+            return;
+        }
+        
+        self.state.debug_log.push_back(LogEntry {
+            level,
+            code: code.to_string(),
+            context,
+            timestamp: self.runtime.system_time().micros(),
+        });
+        while self.state.debug_log.count() > MAX_LOG_ENTRIES {
+            self.state.debug_log.delete_front();
+        }
+    }
+}
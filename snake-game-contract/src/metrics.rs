@@ -0,0 +1,42 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+/*! Monotonic counters describing what the contract has done, for dashboards */
+
+use super::SnakeGameContract;
+
+/// Messages handled, whether delivered from another chain or to this chain itself
+pub const MESSAGES_PROCESSED: &str = "messages.processed";
+
+/// Messages ignored because they were invalid or sent to the wrong chain
+pub const MESSAGES_REJECTED: &str = "messages.rejected";
+
+/// Messages that came back to this chain after their destination rejected them
+pub const MESSAGES_BOUNCED: &str = "messages.bounced";
+
+/// Results dropped because their player chain went over the submission rate limit
+pub const MESSAGES_RATE_LIMITED: &str = "messages.rate_limited";
+
+/// Messages dropped because they came from bytecode whose message format this chain cannot read
+pub const MESSAGES_UNDECODABLE: &str = "messages.undecodable";
+
+/// Global leaderboard rebuilds
+pub const LEADERBOARD_REBUILDS: &str = "leaderboard.rebuilds";
+
+/// Players scanned across all rebuilds; block time is fixed while a block executes,
+/// so this stands in for how long the rebuilds took
+pub const LEADERBOARD_REBUILD_PLAYERS: &str = "leaderboard.rebuild_players";
+
+/// Counter for operations of one type, e.g. `operations.StartGame`
+pub fn operation_metric(kind: &str) -> String {
+    format!("operations.{}", kind)
+}
+
+impl SnakeGameContract {
+    /// Add to a counter, starting it at zero the first time it is used
+    pub(crate) async fn increment_metric(&mut self, name: &str, amount: u64) {
+        if let Ok(counter) = self.state.metrics.get_mut_or_default(&name.to_string()).await {
+            *counter = counter.saturating_add(amount);
+        }
+    }
+}
@@ -0,0 +1,111 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+/*! Rejects GraphQL queries that are too large, too deep, too complex or too expensive to run */
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use async_graphql::{
+    extensions::{Extension, ExtensionContext, ExtensionFactory, NextParseQuery, NextResolve, NextValidation, ResolveInfo},
+    parser::types::ExecutableDocument,
+    ServerError, ServerResult, ValidationResult, Value, Variables,
+};
+use snake_game::QueryLimits;
+
+/// Error codes reported in the `code` extension of a rejected query
+const QUERY_TOO_LARGE: &str = "QUERY_TOO_LARGE";
+const QUERY_TOO_DEEP: &str = "QUERY_TOO_DEEP";
+const QUERY_TOO_COMPLEX: &str = "QUERY_TOO_COMPLEX";
+const QUERY_TOO_MANY_FIELDS: &str = "QUERY_TOO_MANY_FIELDS";
+
+/// Schema extension enforcing the configured query limits
+pub struct QueryGuard {
+    limits: QueryLimits,
+}
+
+impl QueryGuard {
+    pub fn new(limits: QueryLimits) -> Self {
+        QueryGuard { limits }
+    }
+}
+
+impl ExtensionFactory for QueryGuard {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(QueryGuardExtension {
+            limits: self.limits.clone(),
+            resolved_fields: AtomicUsize::new(0),
+        })
+    }
+}
+
+struct QueryGuardExtension {
+    limits: QueryLimits,
+    resolved_fields: AtomicUsize,
+}
+
+#[async_graphql::async_trait::async_trait]
+impl Extension for QueryGuardExtension {
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        if query.len() > self.limits.max_query_bytes {
+            return Err(rejection(
+                QUERY_TOO_LARGE,
+                format!("Query is {} bytes, the limit is {}", query.len(), self.limits.max_query_bytes),
+            ));
+        }
+        next.run(ctx, query, variables).await
+    }
+
+    async fn validation(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        next: NextValidation<'_>,
+    ) -> Result<ValidationResult, Vec<ServerError>> {
+        // Validation measures depth and complexity even when the schema sets no limits
+        let result = next.run(ctx).await?;
+        if result.depth > self.limits.max_depth {
+            return Err(vec![rejection(
+                QUERY_TOO_DEEP,
+                format!("Query is nested {} levels deep, the limit is {}", result.depth, self.limits.max_depth),
+            )]);
+        }
+        if result.complexity > self.limits.max_complexity {
+            return Err(vec![rejection(
+                QUERY_TOO_COMPLEX,
+                format!("Query complexity is {}, the limit is {}", result.complexity, self.limits.max_complexity),
+            )]);
+        }
+        Ok(result)
+    }
+
+    async fn resolve(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        info: ResolveInfo<'_>,
+        next: NextResolve<'_>,
+    ) -> ServerResult<Option<Value>> {
+        let resolved_fields = self.resolved_fields.fetch_add(1, Ordering::Relaxed) + 1;
+        if resolved_fields > self.limits.max_resolved_fields {
+            return Err(rejection(
+                QUERY_TOO_MANY_FIELDS,
+                format!("Query resolved more than {} fields", self.limits.max_resolved_fields),
+            ));
+        }
+        next.run(ctx, info).await
+    }
+}
+
+/// A query error carrying a machine-readable `code` extension
+fn rejection(code: &str, message: String) -> ServerError {
+    let mut error = ServerError::new(message, None);
+    error.extensions.get_or_insert_with(Default::default).set("code", code);
+    error
+}
@@ -3,20 +3,29 @@
 
 #![cfg_attr(target_arch = "wasm32", no_main)]
 
+mod query_guard;
 mod state;
 
 use std::sync::Arc;
 
-use async_graphql::{ComplexObject, EmptySubscription, Object, Request, Response, Schema};
-use linera_sdk::{linera_base_types::WithServiceAbi, views::View, Service, ServiceRuntime};
-use snake_game::{SnakeGameAbi, GameSession, LeaderboardEntry};
+use async_graphql::{ComplexObject, EmptySubscription, Json, Object, Request, Response, Schema};
+use linera_sdk::{linera_base_types::{Account, AccountOwner, Amount, ChainId, DataBlobHash, Ed25519PublicKey, Ed25519Signature, WithServiceAbi}, views::View, Service, ServiceRuntime};
+use serde::Serialize;
+use snake_game::{snake_engine::{Board, Cell, Direction}, SnakeGameAbi, Announcement, ApplicationParameters, Avatar, avatar_format, country_code, default_avatar, Challenge, ChallengeState, ChatMessage, ChatRecipient, Emote, GameState, CustomMap, FeatureFlag, GameMode, GameSession, Ghost, ImprovementEntry, LeaderboardEntry, LevelLayout, LevelScore, ItemKind, LiveSession, MatchmakingState, MatchmakingStatus, PassTrack, PenaltyRecord, PendingUpdate, PodiumChange, QuestDefinition, QuestKind, QuestPeriod, RankTier, RisingStarEntry, SeasonPassProgress, SeasonPassTier, SessionSummary, ShadowEntry, SurvivalEntry, Team, TeamMatch, TeamMatchState, TeamPerk, TeamWar, Tournament, TournamentEntry};
 
-use self::state::{SnakeGameState, PlayerStats};
+use self::query_guard::QueryGuard;
+use self::state::{SnakeGameState, AccountTombstone, LogEntry, LogLevel, Notification, TeamLedgerEntry, SESSION_BUCKET_MICROS, MAX_RECORD_HISTORY, AchievementUnlock, BattleRoyale, CountryRanking, Bracket, BracketSlot, HallOfFame, LeaderboardChange, Dispute, MarketListing, MarketSale, MoveLogAnchor, NameChange, ProposalState, ReservedName, ResetBallot, ResetProposal, PendingReward, PersonalBest, RewardClaim, SessionEvent, PlayerStats, PrizePayout, PrizePool, QuestProgress, ReferralStatus, RoyaleStatus, SeasonPass, SeasonTrophy, Sponsorship, SuspectSession, TreasuryEntry, QueueTicket};
 
 linera_sdk::service!(SnakeGameService);
 
+/// Layout version of the document produced by `export_my_data`
+const EXPORT_FORMAT_VERSION: u32 = 1;
+
+/// Maximum number of candies `collect_candies` schedules in one batch
+const MAX_CANDY_BATCH: usize = 100;
+
 pub struct SnakeGameService {
-    state: SnakeGameState,
+    state: Arc<SnakeGameState>,
     runtime: Arc<ServiceRuntime<Self>>,
 }
 
@@ -25,289 +34,2616 @@ impl WithServiceAbi for SnakeGameService {
 }
 
 impl Service for SnakeGameService {
-    type Parameters = ();
+    type Parameters = ApplicationParameters;
 
     async fn new(runtime: ServiceRuntime<Self>) -> Self {
         let state = SnakeGameState::load(runtime.root_view_storage_context())
             .await
             .expect("Failed to load state");
         SnakeGameService {
-            state,
+            state: Arc::new(state),
             runtime: Arc::new(runtime),
         }
     }
 
     async fn handle_query(&self, request: Request) -> Response {
-        // Collect all sessions
-        let mut all_sessions = Vec::new();
-        if let Ok(session_ids) = self.state.sessions.indices().await {
-            for session_id in session_ids {
-                if let Ok(Some(session)) = self.state.sessions.get(&session_id).await {
-                    all_sessions.push(session);
-                }
-            }
-        }
-        
-        // Get leaderboard data
-        let global_leaderboard = self.state.global_leaderboard.get().clone();
-        
-        // Get player stats
-        let mut all_player_stats = Vec::new();
-        if let Ok(player_chains) = self.state.player_stats.indices().await {
-            for player_chain in player_chains {
-                if let Ok(Some(stats)) = self.state.player_stats.get(&player_chain).await {
-                    all_player_stats.push(stats);
-                }
-            }
-        }
-        
-        // Get personal data
-        let my_sessions = self.state.my_sessions.get().clone();
-        let my_stats = self.state.my_stats.get().clone();
+        // Only registers and the current session are read here; collections are read by the fields
+        // that ask for them, once the query guard has accepted the query
         let my_current_session = self.state.my_current_session.get().clone();
-        let my_player_name = self.state.my_player_name.get().clone();
-        
-        // Get all player names
-        let mut all_player_names = Vec::new();
-        if let Ok(chain_ids) = self.state.player_names.indices().await {
-            for chain_id in chain_ids {
-                if let Ok(Some(name)) = self.state.player_names.get(&chain_id).await {
-                    all_player_names.push(PlayerNameEntry {
-                        chain_id: chain_id.to_string(),
-                        name,
-                    });
-                }
-            }
-        }
+        let (current_session, current_session_is_live) = match &my_current_session {
+            Some(session_id) => (
+                self.state.session(session_id).await,
+                self.state.live_engines.contains_key(session_id).await.unwrap_or(false),
+            ),
+            None => (None, false),
+        };
         
-        // Get configuration
-        let is_leaderboard_chain = *self.state.is_leaderboard_chain.get();
-        let leaderboard_chain_id = self.state.leaderboard_chain_id.get().clone();
-        let session_counter = *self.state.session_counter.get();
+        let mutation_root = MutationRoot {
+            runtime: self.runtime.clone(),
+            state: self.state.clone(),
+            is_leaderboard_chain: *self.state.is_leaderboard_chain.get(),
+            leaderboard_chain_id: *self.state.leaderboard_chain_id.get(),
+            current_session,
+            current_session_is_live,
+            games_played: self.state.my_stats.get().as_ref().map(|stats| stats.games_played).unwrap_or(0),
+            my_coins: *self.state.my_coins.get(),
+            my_referral_code: self.state.my_referral_code.get().clone(),
+            has_referral: self.state.my_referral.get().is_some(),
+            leaderboard_subscription: *self.state.my_leaderboard_subscription.get(),
+            my_eligibility_stake: *self.state.my_eligibility_stake.get(),
+            history_sync_enabled: *self.state.history_sync_enabled.get(),
+            name_changed_at: *self.state.my_name_changed_at.get(),
+            pending_leaderboard_move: *self.state.pending_leaderboard_move.get(),
+            reset_proposal: self.state.reset_proposal.get().clone(),
+            reset_ballot: self.state.reset_ballot.get().clone(),
+            season_pass: self.state.my_season_pass.get().clone(),
+            client_key: *self.state.my_client_key.get(),
+            guest_games: self.state.guest_sessions.get().len(),
+            merged_into: *self.state.merged_into.get(),
+        };
         
+        let limits = self.runtime.application_parameters().query_limits;
         let schema = Schema::build(
             QueryRoot {
-                all_sessions,
-                global_leaderboard,
-                all_player_stats,
-                my_sessions,
-                my_stats,
-                my_current_session,
-                is_leaderboard_chain,
-                leaderboard_chain_id,
-                session_counter,
-                my_player_name,
-                all_player_names,
-            },
-            MutationRoot {
                 runtime: self.runtime.clone(),
+                state: self.state.clone(),
             },
+            mutation_root,
             EmptySubscription,
         )
+        .extension(QueryGuard::new(limits))
         .finish();
         
         schema.execute(request).await
     }
 }
 
+/// Every feature flag that is set, plus the built-in features still at their defaults
+async fn load_feature_flags(state: &SnakeGameState) -> Vec<FeatureFlag> {
+    let mut feature_flags = Vec::new();
+    if let Ok(keys) = state.feature_flags.indices().await {
+        for key in keys {
+            if let Ok(Some(enabled)) = state.feature_flags.get(&key).await {
+                feature_flags.push(FeatureFlag { key, enabled });
+            }
+        }
+    }
+    for key in [snake_game::FEATURE_TIMED_MODE, snake_game::FEATURE_SURVIVAL_MODE, snake_game::FEATURE_BATTLE_ROYALE] {
+        if !feature_flags.iter().any(|flag| flag.key == key) {
+            feature_flags.push(FeatureFlag { key: key.to_string(), enabled: snake_game::feature_default(key) });
+        }
+    }
+    feature_flags
+}
+
 struct QueryRoot {
-    all_sessions: Vec<GameSession>,
-    global_leaderboard: Vec<LeaderboardEntry>,
-    all_player_stats: Vec<PlayerStats>,
-    my_sessions: Vec<String>,
-    my_stats: Option<PlayerStats>,
-    my_current_session: Option<String>,
-    is_leaderboard_chain: bool,
-    leaderboard_chain_id: Option<linera_sdk::linera_base_types::ChainId>,
-    session_counter: u64,
-    my_player_name: Option<String>,
-    all_player_names: Vec<PlayerNameEntry>,
+    runtime: Arc<ServiceRuntime<SnakeGameService>>,
+    state: Arc<SnakeGameState>,
+}
+
+impl QueryRoot {
+    /// Every session played on this chain, in the order they were started
+    async fn load_sessions(&self) -> Vec<GameSession> {
+        let mut sessions = Vec::new();
+        for session_id in self.state.sessions.indices().await.unwrap_or_default() {
+            if let Some(session) = self.state.session(&session_id).await {
+                sessions.push(session);
+            }
+        }
+        sessions.sort_by_key(|session| session.start_time);
+        sessions
+    }
+    
+    /// Achievements unlocked on this chain
+    async fn load_achievements(&self) -> Vec<AchievementUnlock> {
+        self.state.my_achievements.index_values().await.unwrap_or_default()
+            .into_iter()
+            .map(|(achievement, unlocked_at)| AchievementUnlock { achievement, unlocked_at })
+            .collect()
+    }
+    
+    /// Matchmaking tickets, oldest first, with the band each has widened to
+    async fn load_queue(&self) -> Vec<QueuePosition> {
+        let mut tickets: Vec<QueueTicket> = self.state.matchmaking_queue.index_values().await.unwrap_or_default()
+            .into_iter()
+            .map(|(_, ticket)| ticket)
+            .collect();
+        tickets.sort_by_key(|ticket| ticket.queued_at);
+        let now = self.runtime.system_time().micros();
+        let config = self.runtime.application_parameters().matchmaking;
+        tickets.into_iter()
+            .enumerate()
+            .map(|(index, ticket)| QueuePosition {
+                position: index as u32 + 1,
+                player_chain: ticket.player_chain,
+                rating: ticket.rating,
+                queued_at: ticket.queued_at,
+                band: config.band_at(ticket.queued_at, now),
+            })
+            .collect()
+    }
 }
 
 #[Object]
 impl QueryRoot {
     /// Get all game sessions
-    async fn all_sessions(&self) -> &Vec<GameSession> {
-        &self.all_sessions
+    async fn all_sessions(&self) -> Vec<GameSession> {
+        self.load_sessions().await
     }
     
     /// Get a specific game session by ID
-    async fn session(&self, session_id: String) -> Option<&GameSession> {
-        self.all_sessions.iter().find(|session| session.session_id == session_id)
+    async fn session(&self, session_id: String) -> Option<GameSession> {
+        self.state.session(&session_id).await
+    }
+    
+    /// Get what happened during a game session, oldest first
+    async fn session_events(&self, session_id: String) -> Vec<SessionEvent> {
+        let Ok(Some(entry)) = self.state.sessions.try_load_entry(&session_id).await else {
+            return Vec::new();
+        };
+        entry.events.read(0..entry.events.count()).await.unwrap_or_default()
+    }
+    
+    /// Get the global leaderboard; on a player chain, the copy last pushed by the leaderboard chain.
+    /// With a `viewer`, that player's own entry is included even while they are on the shadow board
+    async fn global_leaderboard(&self, viewer: Option<ChainId>) -> Vec<LeaderboardEntry> {
+        match viewer {
+            Some(viewer) => self.state.leaderboard_seen_by(viewer).await,
+            None => self.state.leaderboard_entries().await,
+        }
+    }
+    
+    /// Get the players with an upheld flag held off the public board, with the positions they would hold (leaderboard chain)
+    async fn shadow_leaderboard(&self) -> Vec<ShadowEntry> {
+        self.state.shadow_leaderboard.get().clone()
+    }
+    
+    /// Get the active entries from one country, best first (leaderboard chain)
+    async fn leaderboard(&self, country: String) -> Vec<LeaderboardEntry> {
+        let Some(country) = country_code(&country) else {
+            return Vec::new();
+        };
+        self.state.country_leaderboards.get(&country).await.ok().flatten().unwrap_or_default()
     }
     
-    /// Get the global leaderboard
-    async fn global_leaderboard(&self) -> &Vec<LeaderboardEntry> {
-        &self.global_leaderboard
+    /// Get every represented country with its players' combined results, best score first (leaderboard chain)
+    async fn country_rankings(&self) -> Vec<CountryRanking> {
+        self.state.country_rankings.get().clone()
+    }
+    
+    /// When the leaderboard chain last pushed its board to this player chain
+    async fn leaderboard_synced_at(&self) -> Option<u64> {
+        *self.state.leaderboard_synced_at.get()
+    }
+    
+    /// How many rows this chain has pushed to it when the podium changes, if it subscribed
+    async fn my_leaderboard_subscription(&self) -> Option<u32> {
+        *self.state.my_leaderboard_subscription.get()
+    }
+    
+    /// Get the global leaderboard rows that changed after `since_version`. When the change log no
+    /// longer reaches back that far, `complete` is false and the full leaderboard should be refetched
+    async fn leaderboard_changes(&self, since_version: u64) -> LeaderboardDiff {
+        let version = *self.state.leaderboard_version.get();
+        let changes = self.state.leaderboard_changes.elements().await.unwrap_or_default();
+        let complete = since_version >= version
+            || changes.first().map(|oldest| oldest.version <= since_version + 1).unwrap_or(false);
+        LeaderboardDiff {
+            version,
+            complete,
+            changes: changes.into_iter().filter(|change| change.version > since_version).collect(),
+        }
+    }
+    
+    /// Get the latest takeovers of the top three positions, newest first
+    async fn podium_history(&self, limit: Option<usize>) -> Vec<PodiumChange> {
+        let mut history = self.state.podium_history.elements().await.unwrap_or_default();
+        history.reverse();
+        history.truncate(limit.unwrap_or(history.len()));
+        history
+    }
+    
+    /// Get a player's 1-based position on the global leaderboard, as the player sees it
+    async fn player_rank(&self, chain_id: ChainId) -> Option<u32> {
+        // The stored board is already ordered by the configured tie-breakers
+        let shadow = self.state.shadow_leaderboard.get().iter()
+            .find(|shadow| shadow.entry.chain_id == chain_id)
+            .map(|shadow| shadow.position);
+        if shadow.is_some() {
+            return shadow;
+        }
+        self.state.leaderboard_entries().await.iter()
+            .position(|entry| entry.chain_id == chain_id)
+            .map(|position| position as u32 + 1)
+    }
+    
+    /// Get ranked players who have been inactive longer than the configured window
+    async fn inactive_leaderboard(&self) -> Vec<LeaderboardEntry> {
+        self.state.inactive_leaderboard.get().clone()
     }
     
     /// Get all player statistics
-    async fn all_player_stats(&self) -> &Vec<PlayerStats> {
-        &self.all_player_stats
+    async fn all_player_stats(&self) -> Vec<PlayerStats> {
+        self.state.player_stats.index_values().await.unwrap_or_default()
+            .into_iter()
+            .map(|(_, stats)| stats)
+            .collect()
     }
     
     /// Get player statistics for a specific chain
-    async fn player_stats(&self, chain_id: String) -> Option<&PlayerStats> {
-        // Parse chain_id string to ChainId if needed
-        self.all_player_stats.iter().find(|stats| {
-            format!("{:?}", stats.chain_id).contains(&chain_id)
+    async fn player_stats(&self, chain_id: ChainId) -> Option<PlayerStats> {
+        self.state.player_stats.get(&chain_id).await.ok().flatten()
+    }
+    
+    /// Get the IDs of the sessions played on this chain; with an `owner`, only the ones that owner played
+    async fn my_session_ids(&self, owner: Option<AccountOwner>) -> Vec<String> {
+        self.load_sessions().await.into_iter()
+            .filter(|session| owner.is_none() || session.owner == owner)
+            .map(|session| session.session_id)
+            .collect()
+    }
+    
+    /// Get this player's sessions, newest first, optionally filtered by the owner who played them
+    /// on a shared chain, state, start time (microseconds, inclusive) and minimum score
+    async fn my_sessions(
+        &self,
+        owner: Option<AccountOwner>,
+        state: Option<GameState>,
+        started_after: Option<u64>,
+        started_before: Option<u64>,
+        min_score: Option<u32>,
+        limit: Option<usize>,
+    ) -> Vec<GameSession> {
+        let mut sessions: Vec<GameSession> = self.load_sessions().await.into_iter()
+            .filter(|session| owner.is_none() || session.owner == owner)
+            .filter(|session| state.map(|state| session.state == state).unwrap_or(true))
+            .filter(|session| started_after.map(|after| session.start_time >= after).unwrap_or(true))
+            .filter(|session| started_before.map(|before| session.start_time <= before).unwrap_or(true))
+            .filter(|session| min_score.map(|min_score| session.candies_collected >= min_score).unwrap_or(true))
+            .collect();
+        sessions.sort_by(|a, b| b.start_time.cmp(&a.start_time));
+        sessions.truncate(limit.unwrap_or(sessions.len()));
+        sessions
+    }
+    
+    /// Get the sessions started between `from` and `to` (microseconds, inclusive), oldest first,
+    /// optionally filtered by minimum score
+    async fn sessions_in_range(
+        &self,
+        from: u64,
+        to: u64,
+        min_score: Option<u32>,
+        limit: Option<usize>,
+    ) -> Vec<GameSession> {
+        let limit = limit.unwrap_or(usize::MAX);
+        let mut sessions = Vec::new();
+        if from > to {
+            return sessions;
+        }
+        
+        // Visit only the buckets the range covers, skipping days nobody played
+        let first_bucket = from / SESSION_BUCKET_MICROS;
+        let last_bucket = to / SESSION_BUCKET_MICROS;
+        let mut buckets = self.state.sessions_by_start.indices().await.unwrap_or_default();
+        buckets.retain(|bucket| (first_bucket..=last_bucket).contains(bucket));
+        buckets.sort_unstable();
+        
+        for bucket in buckets {
+            let session_ids = self.state.sessions_by_start.get(&bucket).await.ok().flatten().unwrap_or_default();
+            for session_id in session_ids {
+                if sessions.len() >= limit {
+                    return sessions;
+                }
+                let Some(session) = self.state.session(&session_id).await else {
+                    continue;
+                };
+                if (from..=to).contains(&session.start_time)
+                    && min_score.map(|min_score| session.candies_collected >= min_score).unwrap_or(true)
+                {
+                    sessions.push(session);
+                }
+            }
+        }
+        sessions
+    }
+    
+    /// Get everything the leaderboard chain knows about a player in one object (leaderboard chain)
+    async fn player_profile(&self, chain_id: ChainId) -> Option<PlayerProfile> {
+        let name = self.state.player_names.get(&chain_id).await.ok().flatten();
+        let stats = self.state.player_stats.get(&chain_id).await.ok().flatten();
+        if name.is_none() && stats.is_none() {
+            return None;
+        }
+        
+        Some(PlayerProfile {
+            chain_id,
+            handle: name.as_deref().map(snake_game::name_key),
+            name,
+            tier: stats.as_ref().map(|stats| stats.tier),
+            level: self.state.player_levels.get(&chain_id).await.ok().flatten().unwrap_or(1),
+            rank: self.state.leaderboard_entries().await.iter()
+                .position(|entry| entry.chain_id == chain_id)
+                .map(|position| position as u32 + 1),
+            stats,
+            achievements: self.state.player_achievements.get(&chain_id).await.ok().flatten().unwrap_or_default(),
+            teams: self.state.teams.index_values().await.unwrap_or_default()
+                .into_iter()
+                .map(|(_, team)| team)
+                .filter(|team| team.members.contains(&chain_id))
+                .collect(),
+            last_seen: self.state.player_last_active.get(&chain_id).await.ok().flatten(),
+            previous_names: self.state.name_history.get(&chain_id).await.ok().flatten().unwrap_or_default(),
+            verified: self.state.verified_players.contains(&chain_id).await.unwrap_or(false),
+            verified_games: self.state.verified_games.get(&chain_id).await.ok().flatten().unwrap_or_default(),
+            eligibility_stake: self.state.eligibility_stakes.get(&chain_id).await.ok().flatten().unwrap_or_default(),
+            strikes: self.state.player_strikes.get(&chain_id).await.ok().flatten().unwrap_or_default(),
+            penalties: self.state.penalty_history.get(&chain_id).await.ok().flatten().unwrap_or_default(),
+            season_pass: self.state.season_passes.get(&chain_id).await.ok().flatten(),
+            avatar: self.state.player_avatars.get(&chain_id).await.ok().flatten(),
+            country: self.state.player_countries.get(&chain_id).await.ok().flatten(),
+            default_avatar: default_avatar(chain_id).to_string(),
         })
     }
     
-    /// Get sessions this player participated in
-    async fn my_sessions(&self) -> &Vec<String> {
-        &self.my_sessions
+    /// Get the names reserved by the admin; with `chain_id`, only those that chain may not use (leaderboard chain)
+    async fn reserved_names(&self, chain_id: Option<ChainId>) -> Vec<ReservedName> {
+        let mut reserved_names = Vec::new();
+        if let Ok(keys) = self.state.reserved_names.indices().await {
+            for key in keys {
+                if let Ok(Some(reservation)) = self.state.reserved_names.get(&key).await {
+                    if chain_id.is_none_or(|chain_id| reservation.blocks(chain_id)) {
+                        reserved_names.push(reservation);
+                    }
+                }
+            }
+        }
+        reserved_names
+    }
+    
+    /// Get a player's synced session history, newest first (leaderboard chain, players who opted in)
+    async fn player_history(&self, chain_id: ChainId, limit: Option<usize>) -> Vec<SessionSummary> {
+        let history = self.state.player_histories.get(&chain_id).await.ok().flatten().unwrap_or_default();
+        history.into_iter().rev().take(limit.unwrap_or(usize::MAX)).collect()
+    }
+    
+    /// Get this player's custom avatar, if they set one
+    async fn my_avatar(&self) -> Option<Avatar> {
+        self.state.my_avatar.get().clone()
+    }
+    
+    /// Get the chains that sent this chain a link token it has not presented yet
+    async fn pending_account_links(&self) -> Vec<ChainId> {
+        self.state.received_link_tokens.indices().await.unwrap_or_default()
+    }
+    
+    /// Get the chain that took over this chain's history, if it was merged
+    async fn merged_into(&self) -> Option<ChainId> {
+        *self.state.merged_into.get()
+    }
+    
+    /// Get where a merged chain's history went (leaderboard chain)
+    async fn account_tombstone(&self, chain_id: ChainId) -> Option<AccountTombstone> {
+        self.state.account_tombstones.get(&chain_id).await.ok().flatten()
+    }
+    
+    /// Get the key that must sign this chain's scores, if one is registered
+    async fn my_client_key(&self) -> Option<Ed25519PublicKey> {
+        *self.state.my_client_key.get()
+    }
+    
+    /// Check whether this player publishes finished sessions to the leaderboard chain
+    async fn history_sync_enabled(&self) -> bool {
+        *self.state.history_sync_enabled.get()
+    }
+    
+    /// Get the most recently finished sessions, newest first
+    async fn recent_games(&self, limit: Option<usize>) -> Vec<GameSession> {
+        let session_ids = self.state.recent_games.elements().await.unwrap_or_default();
+        let mut sessions = Vec::new();
+        for session_id in session_ids.iter().rev().take(limit.unwrap_or(session_ids.len())) {
+            if let Some(session) = self.state.session(session_id).await {
+                sessions.push(session);
+            }
+        }
+        sessions
+    }
+    
+    /// Get the chat messages exchanged with one player (`with`) or in one team, oldest first;
+    /// all of them when neither is given
+    async fn chat_messages(&self, with: Option<ChainId>, team_id: Option<u64>) -> Vec<ChatMessage> {
+        self.state.chat_inbox.elements().await.unwrap_or_default()
+            .into_iter()
+            .filter(|message| match (with, team_id) {
+                (Some(chain), _) => message.team_id.is_none() && (message.from == chain || message.to == Some(chain)),
+                (None, Some(team_id)) => message.team_id == Some(team_id),
+                (None, None) => true,
+            })
+            .collect()
+    }
+    
+    /// Get the players whose records this chain follows
+    async fn following(&self) -> Vec<ChainId> {
+        self.state.following.indices().await.unwrap_or_default()
+    }
+    
+    /// Get the chains following a player's records (leaderboard chain only)
+    async fn followers(&self, chain_id: ChainId) -> Vec<ChainId> {
+        self.state.followers.get(&chain_id).await.ok().flatten().unwrap_or_default()
+    }
+    
+    /// Get this player's notification inbox, newest first
+    async fn notifications(&self) -> Vec<Notification> {
+        let mut notifications = self.state.notifications.elements().await.unwrap_or_default();
+        notifications.reverse();
+        notifications
+    }
+    
+    /// Get personal statistics; on a shared chain, the given owner's, or else the owner who played last
+    async fn my_stats(&self, owner: Option<AccountOwner>) -> Option<PlayerStats> {
+        match owner {
+            Some(owner) => self.state.owner_profile(owner).await.stats,
+            None => self.state.my_stats.get().clone(),
+        }
+    }
+    
+    /// Get the stats of the games played as a guest that no owner has claimed yet
+    async fn guest_stats(&self) -> Option<PlayerStats> {
+        self.state.guest_stats.get().clone()
     }
     
-    /// Get personal statistics
-    async fn my_stats(&self) -> &Option<PlayerStats> {
-        &self.my_stats
+    /// Get the guest games waiting to be claimed, in the order they ended
+    async fn guest_sessions(&self) -> Vec<GameSession> {
+        let mut sessions = Vec::new();
+        for session_id in self.state.guest_sessions.get() {
+            if let Some(session) = self.state.session(session_id).await {
+                sessions.push(session);
+            }
+        }
+        sessions
     }
     
-    /// Get current active session
-    async fn my_current_session(&self) -> &Option<String> {
-        &self.my_current_session
+    /// Get current active session; on a shared chain, the given owner's, or else the owner who played last
+    async fn my_current_session(&self, owner: Option<AccountOwner>) -> Option<String> {
+        match owner {
+            Some(owner) => self.state.owner_profile(owner).await.current_session,
+            None => self.state.my_current_session.get().clone(),
+        }
     }
     
     /// Check if this chain is the leaderboard chain
     async fn is_leaderboard_chain(&self) -> bool {
-        self.is_leaderboard_chain
+        *self.state.is_leaderboard_chain.get()
     }
     
     /// Get the configured leaderboard chain ID
-    async fn leaderboard_chain_id(&self) -> Option<String> {
-        self.leaderboard_chain_id.map(|id| id.to_string())
+    async fn leaderboard_chain_id(&self) -> Option<ChainId> {
+        *self.state.leaderboard_chain_id.get()
+    }
+    
+    /// Get the chain asked to take over as leaderboard chain, until it accepts (leaderboard chain)
+    async fn pending_leaderboard_move(&self) -> Option<ChainId> {
+        *self.state.pending_leaderboard_move.get()
+    }
+    
+    /// Get the contract's counters, e.g. operations by type and messages rejected, sorted by name
+    async fn metrics(&self) -> Vec<MetricCounter> {
+        let mut counters = Vec::new();
+        for name in self.state.metrics.indices().await.unwrap_or_default() {
+            if let Ok(Some(value)) = self.state.metrics.get(&name).await {
+                counters.push(MetricCounter { name, value });
+            }
+        }
+        counters
+    }
+    
+    /// Get the results and name sent to the leaderboard chain that it has not confirmed yet, oldest first
+    async fn pending_updates(&self) -> Vec<PendingUpdate> {
+        let mut updates = Vec::new();
+        if let Ok(session_ids) = self.state.pending_results.indices().await {
+            for session_id in session_ids {
+                if let Ok(Some(update)) = self.state.pending_results.get(&session_id).await {
+                    updates.push(update);
+                }
+            }
+        }
+        updates.extend(self.state.pending_name_update.get().clone());
+        updates.sort_by_key(|update| update.sent_at);
+        updates
+    }
+    
+    /// Get this chain's configuration and backlog in one call
+    async fn status(&self) -> ChainStatus {
+        // The active owner's game, and those of owners whose profiles are parked
+        let mut active_sessions = u64::from(self.state.my_current_session.get().is_some());
+        for owner in self.state.owner_profiles.indices().await.unwrap_or_default() {
+            if let Ok(Some(profile)) = self.state.owner_profiles.get(&owner).await {
+                active_sessions += u64::from(profile.current_session.is_some());
+            }
+        }
+        ChainStatus {
+            leaderboard_configured: self.state.leaderboard_chain_id.get().is_some(),
+            is_leaderboard_chain: *self.state.is_leaderboard_chain.get(),
+            pending_updates: self.state.pending_results.count().await.unwrap_or(0) as u64
+                + u64::from(self.state.pending_name_update.get().is_some()),
+            active_sessions,
+            last_message_at: *self.state.last_message_at.get(),
+        }
+    }
+    
+    /// Get the version of the deployed bytecode
+    async fn contract_version(&self) -> &'static str {
+        env!("CARGO_PKG_VERSION")
+    }
+    
+    /// Get the schema versions of this chain's state and of the deployed bytecode
+    async fn schema_version(&self) -> SchemaVersion {
+        SchemaVersion {
+            state: *self.state.schema_version.get(),
+            compiled_state: snake_game::STATE_SCHEMA_VERSION,
+            events: snake_game::EVENT_SCHEMA_VERSION,
+            messages: snake_game::MESSAGE_SCHEMA_VERSION,
+        }
+    }
+    
+    /// Get the most recent logged events at or above a level (Info by default), newest first
+    async fn debug_log(&self, level: Option<LogLevel>, limit: Option<usize>) -> Vec<LogEntry> {
+        let level = level.unwrap_or(LogLevel::Info);
+        let entries = self.state.debug_log.elements().await.unwrap_or_default();
+        entries.into_iter()
+            .rev()
+            .filter(|entry| entry.level >= level)
+            .take(limit.unwrap_or(100))
+            .collect()
+    }
+    
+    /// Get the feature flags as this chain last heard them from the leaderboard chain
+    async fn feature_flags(&self) -> Vec<FeatureFlag> {
+        load_feature_flags(&self.state).await
+    }
+    
+    /// Check whether a feature is switched on
+    async fn feature_enabled(&self, key: String) -> bool {
+        self.state.feature_flags.get(&key).await.ok().flatten().unwrap_or_else(|| snake_game::feature_default(&key))
+    }
+    
+    /// Get the latest reset proposal and how the vote stands (leaderboard chain)
+    async fn reset_proposal(&self) -> Option<ResetProposal> {
+        let now = self.runtime.system_time().micros();
+        self.state.reset_proposal.get().clone().map(|mut proposal| {
+            proposal.state = proposal.state_at(now);
+            proposal
+        })
+    }
+    
+    /// Get the coins this chain staked for leaderboard eligibility
+    async fn my_eligibility_stake(&self) -> u64 {
+        *self.state.my_eligibility_stake.get()
+    }
+    
+    /// Get the strikes and penalties the leaderboard chain applied to this player, oldest first
+    async fn my_penalties(&self) -> Vec<PenaltyRecord> {
+        self.state.my_penalties.read(0..self.state.my_penalties.count()).await.unwrap_or_default()
+    }
+    
+    /// Get the flagged scores waiting for a moderator, oldest first (leaderboard chain)
+    async fn review_queue(&self) -> Vec<Dispute> {
+        let mut disputes = Vec::new();
+        for dispute_id in self.state.review_queue.indices().await.unwrap_or_default() {
+            if let Ok(Some(dispute)) = self.state.disputes.get(&dispute_id).await {
+                disputes.push(dispute);
+            }
+        }
+        disputes
+    }
+    
+    /// Get the sessions whose pace was rejected as humanly impossible (leaderboard chain)
+    async fn suspect_sessions(&self, player_chain: Option<ChainId>) -> Vec<SuspectSession> {
+        let mut sessions = Vec::new();
+        for session_id in self.state.suspect_sessions.indices().await.unwrap_or_default() {
+            if let Ok(Some(suspect)) = self.state.suspect_sessions.get(&session_id).await {
+                if player_chain.map(|chain_id| suspect.player_chain == chain_id).unwrap_or(true) {
+                    sessions.push(suspect);
+                }
+            }
+        }
+        sessions
+    }
+    
+    /// Get a dispute and its outcome by ID (leaderboard chain)
+    async fn dispute(&self, dispute_id: u64) -> Option<Dispute> {
+        self.state.disputes.get(&dispute_id).await.ok().flatten()
+    }
+    
+    /// Get how many strikes a player has received from disputes (leaderboard chain)
+    async fn player_strikes(&self, chain_id: ChainId) -> u32 {
+        self.state.player_strikes.get(&chain_id).await.ok().flatten().unwrap_or_default()
+    }
+    
+    /// Get the latest reset proposal this player was asked to vote on
+    async fn reset_ballot(&self) -> Option<ResetBallot> {
+        self.state.reset_ballot.get().clone()
     }
     
-    /// Get the current session counter
+    /// Get the number of sessions started on this chain
+    #[graphql(deprecation = "Session IDs are derived from the starting block; use restartGame's sessionId")]
     async fn session_counter(&self) -> u64 {
-        self.session_counter
+        self.state.sessions.count().await.unwrap_or(0) as u64
     }
     
-    /// Get my player name
-    async fn my_player_name(&self) -> &Option<String> {
-        &self.my_player_name
+    /// Get my player name; on a shared chain, the given owner's, or else the owner who played last
+    async fn my_player_name(&self, owner: Option<AccountOwner>) -> Option<String> {
+        match owner {
+            Some(owner) => self.state.owner_profile(owner).await.player_name,
+            None => self.state.my_player_name.get().clone(),
+        }
     }
     
     /// Get all player names
-    async fn all_player_names(&self) -> &Vec<PlayerNameEntry> {
-        &self.all_player_names
+    async fn all_player_names(&self) -> Vec<PlayerNameEntry> {
+        self.state.player_names.index_values().await.unwrap_or_default()
+            .into_iter()
+            .map(|(chain_id, name)| PlayerNameEntry { chain_id, name })
+            .collect()
     }
     
     /// Get player name by chain ID
-    async fn player_name(&self, chain_id: String) -> Option<String> {
-        self.all_player_names.iter()
-            .find(|entry| entry.chain_id == chain_id)
-            .map(|entry| entry.name.clone())
+    async fn player_name(&self, chain_id: ChainId) -> Option<String> {
+        self.state.player_names.get(&chain_id).await.ok().flatten()
     }
     
-    /// Get game statistics summary
-    async fn game_stats(&self) -> GameStats {
-        let total_sessions = self.all_sessions.len() as u64;
-        let finished_games = self.all_sessions.iter().filter(|session| session.state == snake_game::GameState::Finished).count() as u64;
-        let total_players = self.all_player_stats.len() as u64;
-        
-        GameStats {
-            total_sessions,
-            finished_games,
-            total_players,
-        }
+    /// Get this player's personal bests, oldest first
+    async fn my_record_history(&self, limit: Option<usize>) -> Vec<PersonalBest> {
+        let limit = limit.unwrap_or(MAX_RECORD_HISTORY);
+        let history = self.state.my_record_history.elements().await.unwrap_or_default();
+        let skip = history.len().saturating_sub(limit);
+        history[skip..].to_vec()
     }
-}
-
-#[derive(async_graphql::SimpleObject)]
-struct GameStats {
-    total_sessions: u64,
-    finished_games: u64,
-    total_players: u64,
-}
-
-#[derive(async_graphql::SimpleObject)]
-struct PlayerNameEntry {
-    chain_id: String,
-    name: String,
-}
-
-struct MutationRoot {
-    runtime: Arc<ServiceRuntime<SnakeGameService>>,
-}
-
-#[Object]
-impl MutationRoot {
-    /// Setup the leaderboard chain (admin operation)
-    async fn setup_leaderboard(&self, leaderboard_chain_id: String) -> String {
-        // Parse chain ID string
-        let chain_id = match leaderboard_chain_id.parse() {
-            Ok(id) => id,
-            Err(_) => return format!("Invalid chain ID format: {}", leaderboard_chain_id),
+    
+    /// Get this player's progress on received quests
+    async fn quests(&self) -> Vec<QuestProgress> {
+        self.state.quests.index_values().await.unwrap_or_default()
+            .into_iter()
+            .map(|(_, progress)| progress)
+            .collect()
+    }
+    
+    /// Get all quests published by this chain (leaderboard chain only)
+    async fn quest_catalog(&self) -> Vec<QuestDefinition> {
+        self.state.quest_catalog.index_values().await.unwrap_or_default()
+            .into_iter()
+            .map(|(_, quest)| quest)
+            .collect()
+    }
+    
+    /// Get this chain's coins, including claimed rewards
+    async fn my_coins(&self) -> u64 {
+        *self.state.my_coins.get()
+    }
+    
+    /// Get prizes, quest and referral rewards credited to this chain but not claimed yet
+    async fn my_rewards(&self) -> Vec<PendingReward> {
+        self.state.my_rewards.index_values().await.unwrap_or_default()
+            .into_iter()
+            .map(|(_, reward)| reward)
+            .collect()
+    }
+    
+    /// Get this chain's reward claims, oldest first
+    async fn reward_claims(&self) -> Vec<RewardClaim> {
+        let count = self.state.reward_claims.count();
+        self.state.reward_claims.read(0..count).await.unwrap_or_default()
+    }
+    
+    /// Get the referral code other players can redeem for this chain
+    async fn my_referral_code(&self) -> Option<String> {
+        self.state.my_referral_code.get().clone()
+    }
+    
+    /// Get the status of the referral code this player redeemed
+    async fn my_referral(&self) -> Option<ReferralStatus> {
+        self.state.my_referral.get().clone()
+    }
+    
+    /// Get challenges this chain has issued or received
+    async fn my_challenges(&self) -> Vec<Challenge> {
+        self.state.my_challenges.index_values().await.unwrap_or_default()
+            .into_iter()
+            .map(|(_, challenge)| challenge)
+            .collect()
+    }
+    
+    /// Get where this chain's matchmaking ticket stands, and the match or ghost it ended in
+    async fn my_matchmaking(&self) -> Option<MatchmakingStatus> {
+        self.state.my_matchmaking.get().clone()
+    }
+    
+    /// Get the players waiting for a match, oldest first (leaderboard chain)
+    async fn matchmaking_queue(&self) -> Vec<QueuePosition> {
+        self.load_queue().await
+    }
+    
+    /// Get a player's place in the matchmaking queue and how far their band has widened (leaderboard chain)
+    async fn queue_position(&self, chain_id: ChainId) -> Option<QueuePosition> {
+        self.load_queue().await.into_iter().find(|entry| entry.player_chain == chain_id)
+    }
+    
+    /// Get every head-to-head challenge (leaderboard chain)
+    async fn challenges(&self) -> Vec<Challenge> {
+        self.state.challenges.index_values().await.unwrap_or_default()
+            .into_iter()
+            .map(|(_, challenge)| challenge)
+            .collect()
+    }
+    
+    /// Get the move log hash anchored for each record game, optionally for one player (leaderboard chain)
+    async fn move_log_anchors(&self, chain_id: Option<ChainId>) -> Vec<MoveLogAnchor> {
+        self.state.move_log_anchors.index_values().await.unwrap_or_default()
+            .into_iter()
+            .map(|(_, anchor)| anchor)
+            .filter(|anchor| chain_id.map(|chain_id| anchor.player_chain == chain_id).unwrap_or(true))
+            .collect()
+    }
+    
+    /// Fetch the published move log of a game, from this chain's sessions or the anchored records
+    async fn replay(&self, session_id: String) -> Option<Vec<u8>> {
+        let replay_blob = match self.state.session(&session_id).await.and_then(|session| session.replay_blob) {
+            Some(replay_blob) => replay_blob,
+            None => self.state.move_log_anchors.get(&session_id).await.ok().flatten()?.replay_blob?,
         };
-        
-        self.runtime.schedule_operation(&snake_game::Operation::SetupLeaderboard { leaderboard_chain_id: chain_id });
-        format!("Setup leaderboard with chain ID: {}", leaderboard_chain_id)
+        Some(self.runtime.read_data_blob(replay_blob))
     }
     
-    /// Start a new game
-    async fn start_game(&self) -> String {
-        self.runtime.schedule_operation(&snake_game::Operation::StartGame);
-        "New game started successfully".to_string()
+    /// Get every playable level layout
+    async fn levels(&self) -> Vec<LevelLayout> {
+        // The built-in open level comes first
+        let mut levels = vec![LevelLayout::open()];
+        levels.extend(self.state.levels.index_values().await.unwrap_or_default()
+            .into_iter()
+            .map(|(_, level)| level));
+        levels
     }
     
-    /// Collect a candy during gameplay
-    async fn collect_candy(&self) -> String {
-        self.runtime.schedule_operation(&snake_game::Operation::CollectCandy);
-        "Candy collected successfully".to_string()
+    /// Get the best score of each player on one level, highest first (leaderboard chain)
+    async fn level_leaderboard(&self, level_id: u32) -> Vec<LevelScore> {
+        self.state.level_leaderboards.get(&level_id).await.ok().flatten().unwrap_or_default()
     }
     
-    /// End the current game
-    async fn end_game(&self) -> String {
-        self.runtime.schedule_operation(&snake_game::Operation::EndGame);
-        "Game ended successfully".to_string()
+    /// Get the validated live state of an active game on this chain or a spectated one
+    async fn live_session(&self, session_id: String) -> Option<LiveSession> {
+        self.state.live_sessions.get(&session_id).await.ok().flatten()
     }
     
-    /// Reset the leaderboard (admin operation, only on leaderboard chain)
-    async fn reset_leaderboard(&self) -> String {
-        self.runtime.schedule_operation(&snake_game::Operation::ResetLeaderboard);
-        "Leaderboard reset successfully".to_string()
+    /// Get every live game known to this chain
+    async fn live_sessions(&self) -> Vec<LiveSession> {
+        self.state.live_sessions.index_values().await.unwrap_or_default()
+            .into_iter()
+            .map(|(_, live)| live)
+            .collect()
     }
     
-    /// Set player name
-    async fn set_player_name(&self, name: String) -> String {
-        self.runtime.schedule_operation(&snake_game::Operation::SetPlayerName { name: name.clone() });
-        format!("Player name set to '{}' successfully", name)
+    /// Get a published community map by its hash (leaderboard chain)
+    async fn custom_map(&self, map_hash: String) -> Option<CustomMap> {
+        self.state.custom_maps.get(&map_hash).await.ok().flatten()
     }
-}
-
-#[ComplexObject]
-impl SnakeGameState {}
-
-#[ComplexObject]
-impl PlayerStats {
-    /// Get average candies per game as a formatted string
-    async fn average_candies_formatted(&self) -> String {
-        format!("{:.1}", self.average_candies())
+    
+    /// Get the most played community maps, optionally limited (leaderboard chain)
+    async fn popular_maps(&self, limit: Option<usize>) -> Vec<CustomMap> {
+        let mut maps: Vec<CustomMap> = self.state.custom_maps.index_values().await.unwrap_or_default()
+            .into_iter()
+            .map(|(_, custom_map)| custom_map)
+            .collect();
+        maps.sort_by(|a, b| b.plays.cmp(&a.plays).then(a.published_at.cmp(&b.published_at)));
+        maps.truncate(limit.unwrap_or(maps.len()));
+        maps
     }
     
-    /// Get current streak description
-    async fn streak_description(&self) -> String {
-        if self.current_streak == 0 {
-            "No current streak".to_string()
-        } else {
-            format!("{} game win streak", self.current_streak)
-        }
+    /// Get the best score of each player on one community map, highest first (leaderboard chain)
+    async fn map_leaderboard(&self, map_hash: String) -> Vec<LevelScore> {
+        self.state.map_leaderboards.get(&map_hash).await.ok().flatten().unwrap_or_default()
     }
     
-    /// Get player rank based on highest score
-    async fn estimated_rank(&self) -> String {
-        if self.highest_score >= 100 {
-            "Master".to_string()
-        } else if self.highest_score >= 50 {
-            "Expert".to_string()
-        } else if self.highest_score >= 20 {
-            "Advanced".to_string()
-        } else if self.highest_score >= 5 {
-            "Intermediate".to_string()
-        } else {
-            "Beginner".to_string()
-        }
+    /// Get the best score of each player in a non-Classic mode, highest first (leaderboard chain)
+    async fn mode_leaderboard(&self, mode: GameMode) -> Vec<LevelScore> {
+        self.state.mode_leaderboards.get(&mode).await.ok().flatten().unwrap_or_default()
+    }
+    
+    /// Get the longest Survival runs, longest first (leaderboard chain)
+    async fn survival_leaderboard(&self) -> Vec<SurvivalEntry> {
+        self.state.survival_leaderboard.get().clone()
+    }
+    
+    /// Get the players whose average reported score rose the most from last week to this week (leaderboard chain).
+    /// Only new personal records are reported, so this ranks record-to-record improvement
+    async fn most_improved(&self, limit: Option<usize>) -> Vec<ImprovementEntry> {
+        let most_improved = self.state.most_improved.get();
+        most_improved.iter().take(limit.unwrap_or(most_improved.len())).cloned().collect()
+    }
+    
+    /// Get the best players who started playing within the last week (leaderboard chain)
+    async fn rising_stars(&self, limit: Option<usize>) -> Vec<RisingStarEntry> {
+        let rising_stars = self.state.rising_stars.get();
+        rising_stars.iter().take(limit.unwrap_or(rising_stars.len())).cloned().collect()
+    }
+    
+    /// Get registered teams ranked by wins, then by total score (leaderboard chain)
+    async fn team_leaderboard(&self) -> Vec<Team> {
+        let mut teams: Vec<Team> = self.state.teams.index_values().await.unwrap_or_default()
+            .into_iter()
+            .map(|(_, team)| team)
+            .collect();
+        teams.sort_by(|a, b| b.wins.cmp(&a.wins).then(b.total_score.cmp(&a.total_score)));
+        teams
+    }
+    
+    /// Get every team match (leaderboard chain)
+    async fn team_matches(&self) -> Vec<TeamMatch> {
+        self.state.team_matches.index_values().await.unwrap_or_default()
+            .into_iter()
+            .map(|(_, team_match)| team_match)
+            .collect()
+    }
+    
+    /// Get team matches this chain plays in
+    async fn my_team_matches(&self) -> Vec<TeamMatch> {
+        self.state.my_team_matches.index_values().await.unwrap_or_default()
+            .into_iter()
+            .map(|(_, team_match)| team_match)
+            .collect()
+    }
+    
+    /// Get the teams other players registered with this chain, waiting for it to accept
+    async fn team_invites(&self) -> Vec<Team> {
+        self.state.team_invites.index_values().await.unwrap_or_default()
+            .into_iter()
+            .map(|(_, team)| team)
+            .collect()
+    }
+    
+    /// Get how much each member contributed to a team's treasury, most first (leaderboard chain)
+    async fn team_contributions(&self, team_id: u64) -> Vec<TeamContribution> {
+        let mut contributions: Vec<TeamContribution> = self.state.team_contributions.get(&team_id).await
+            .ok()
+            .flatten()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(player_chain, coins)| TeamContribution { player_chain, coins })
+            .collect();
+        contributions.sort_by(|a, b| b.coins.cmp(&a.coins));
+        contributions
+    }
+    
+    /// Get a team treasury's ledger, oldest first (leaderboard chain)
+    async fn team_ledger(&self, team_id: u64) -> Vec<TeamLedgerEntry> {
+        self.state.team_ledger.read(0..self.state.team_ledger.count()).await.unwrap_or_default()
+            .into_iter()
+            .filter(|entry| entry.team_id == team_id)
+            .collect()
+    }
+    
+    /// Get team wars, newest first (leaderboard chain)
+    async fn team_wars(&self) -> Vec<TeamWar> {
+        let mut wars: Vec<TeamWar> = self.state.team_wars.index_values().await.unwrap_or_default()
+            .into_iter()
+            .map(|(_, war)| war)
+            .collect();
+        wars.sort_by(|a, b| b.war_id.cmp(&a.war_id));
+        wars
+    }
+    
+    /// Get team wars this chain's teams fight, newest first
+    async fn my_team_wars(&self) -> Vec<TeamWar> {
+        let mut wars: Vec<TeamWar> = self.state.my_team_wars.index_values().await.unwrap_or_default()
+            .into_iter()
+            .map(|(_, war)| war)
+            .collect();
+        wars.sort_by(|a, b| b.war_id.cmp(&a.war_id));
+        wars
+    }
+    
+    /// Tournaments, newest first; standings are only kept on the leaderboard chain
+    async fn tournaments(&self) -> Vec<Tournament> {
+        let mut tournaments: Vec<Tournament> = self.state.tournaments.index_values().await.unwrap_or_default()
+            .into_iter()
+            .map(|(_, tournament)| tournament)
+            .collect();
+        tournaments.sort_by(|a, b| b.tournament_id.cmp(&a.tournament_id));
+        tournaments
+    }
+    
+    /// This chain's attempts at tournaments, and its results once they are finalized
+    async fn my_tournaments(&self) -> Vec<TournamentEntry> {
+        self.state.my_tournaments.index_values().await.unwrap_or_default()
+            .into_iter()
+            .map(|(_, entry)| entry)
+            .collect()
+    }
+    
+    /// Knockout finals of a tournament, every round so far (leaderboard chain)
+    async fn bracket(&self, tournament_id: u64) -> Option<Bracket> {
+        self.state.brackets.get(&tournament_id).await.ok().flatten()
+    }
+    
+    /// This chain's knockout matches, one per tournament it reached the finals of
+    async fn my_brackets(&self) -> Vec<BracketSlot> {
+        self.state.my_brackets.index_values().await.unwrap_or_default()
+            .into_iter()
+            .map(|(_, slot)| slot)
+            .collect()
+    }
+    
+    /// This chain's season pass: season XP, premium status and claimed tiers
+    async fn my_season_pass(&self) -> SeasonPass {
+        self.state.my_season_pass.get().clone()
+    }
+    
+    /// Tiers of the season pass and their rewards on both tracks
+    async fn season_pass_tiers(&self) -> Vec<SeasonPassTier> {
+        self.runtime.application_parameters().season_pass.tiers
+    }
+    
+    /// Skins claimed from season passes
+    async fn my_skins(&self) -> Vec<String> {
+        self.state.my_skins.indices().await.unwrap_or_default()
+    }
+    
+    /// Trophies claimed from season passes, oldest first
+    async fn my_trophies(&self) -> Vec<SeasonTrophy> {
+        let count = self.state.my_trophies.count();
+        self.state.my_trophies.read(0..count).await.unwrap_or_default()
+    }
+    
+    /// Items for sale on the market, optionally of one kind, cheapest first (leaderboard chain)
+    async fn market_listings(&self, kind: Option<ItemKind>) -> Vec<MarketListing> {
+        let mut listings: Vec<MarketListing> = self.state.market_listings.index_values().await.unwrap_or_default()
+            .into_iter()
+            .map(|(_, listing)| listing)
+            .filter(|listing| kind.is_none_or(|kind| listing.kind == kind))
+            .collect();
+        listings.sort_by_key(|listing| (listing.price, listing.listing_id));
+        listings
+    }
+    
+    /// Past market sales of one kind, optionally of a single item, newest first (leaderboard chain)
+    async fn price_history(&self, kind: ItemKind, item: Option<String>, limit: Option<usize>) -> Vec<MarketSale> {
+        let count = self.state.market_sales.count();
+        let sales = self.state.market_sales.read(0..count).await.unwrap_or_default();
+        sales.into_iter()
+            .rev()
+            .filter(|sale| sale.kind == kind && item.as_ref().is_none_or(|item| &sale.item == item))
+            .take(limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+    
+    /// Ghosts fetched from the leaderboard chain, ready to race
+    async fn ghosts(&self) -> Vec<Ghost> {
+        self.state.ghosts.index_values().await.unwrap_or_default()
+            .into_iter()
+            .map(|(_, ghost)| ghost)
+            .collect()
+    }
+    
+    /// Get battle royale events with their survivors and eliminations (leaderboard chain)
+    async fn battle_royales(&self) -> Vec<BattleRoyale> {
+        self.state.battle_royales.index_values().await.unwrap_or_default()
+            .into_iter()
+            .map(|(_, event)| event)
+            .collect()
+    }
+    
+    /// Get this chain's progress in the battle royales it joined
+    async fn my_battle_royales(&self) -> Vec<RoyaleStatus> {
+        self.state.my_battle_royales.index_values().await.unwrap_or_default()
+            .into_iter()
+            .map(|(_, status)| status)
+            .collect()
+    }
+    
+    /// Compute the commitment hash for a challenge result, to be revealed later
+    async fn challenge_commitment(&self, challenge_id: u64, player_chain_id: ChainId, session_id: String, score: u32, salt: String) -> String {
+        snake_game::challenge_commitment(challenge_id, player_chain_id, &session_id, score, &salt)
+    }
+    
+    /// Get achievements unlocked on this chain
+    async fn my_achievements(&self) -> Vec<AchievementUnlock> {
+        self.load_achievements().await
+    }
+    
+    /// Get season podiums, the all-time record and notable firsts (leaderboard chain)
+    async fn hall_of_fame(&self) -> HallOfFame {
+        self.state.hall_of_fame.get().clone()
+    }
+    
+    /// Get active announcements from the leaderboard chain, newest first
+    async fn announcements(&self) -> Vec<Announcement> {
+        let now = self.runtime.system_time().micros();
+        let mut announcements: Vec<Announcement> = self.state.announcements.elements().await
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|announcement| !announcement.is_expired(now))
+            .collect();
+        announcements.reverse();
+        announcements
+    }
+    
+    /// Get the treasury balance (leaderboard chain)
+    async fn treasury_balance(&self) -> Amount {
+        *self.state.treasury_balance.get()
+    }
+    
+    /// Get every treasury inflow and outflow, oldest first (leaderboard chain)
+    async fn treasury_ledger(&self) -> Vec<TreasuryEntry> {
+        let count = self.state.treasury_ledger.count();
+        self.state.treasury_ledger.read(0..count).await.unwrap_or_default()
+    }
+    
+    /// Get sponsored prize pools (leaderboard chain)
+    async fn prize_pools(&self) -> Vec<PrizePool> {
+        self.state.prize_pools.index_values().await.unwrap_or_default()
+            .into_iter()
+            .map(|(_, pool)| pool)
+            .collect()
+    }
+    
+    /// Get every sponsor donation, optionally for a single pool (leaderboard chain)
+    async fn sponsors(&self, pool: Option<String>) -> Vec<Sponsorship> {
+        let count = self.state.sponsorships.count();
+        self.state.sponsorships.read(0..count).await.unwrap_or_default()
+            .into_iter()
+            .filter(|sponsorship| pool.as_ref().map(|pool| &sponsorship.pool == pool).unwrap_or(true))
+            .collect()
+    }
+    
+    /// Get prizes paid out so far (leaderboard chain)
+    async fn prize_payouts(&self) -> Vec<PrizePayout> {
+        let count = self.state.prize_payouts.count();
+        self.state.prize_payouts.read(0..count).await.unwrap_or_default()
+    }
+    
+    /// Export this player's full history as a single JSON document, for backups and future imports
+    async fn export_my_data(&self) -> Json<PlayerExport> {
+        let sessions = self.load_sessions().await;
+        let replays = sessions.iter()
+            .filter(|session| session.move_log_hash.is_some() || session.replay_blob.is_some())
+            .map(|session| ReplayReference {
+                session_id: session.session_id.clone(),
+                move_log_hash: session.move_log_hash.clone(),
+                replay_blob: session.replay_blob,
+            })
+            .collect();
+        
+        Json(PlayerExport {
+            format_version: EXPORT_FORMAT_VERSION,
+            chain_id: self.runtime.chain_id(),
+            exported_at: self.runtime.system_time().micros(),
+            player_name: self.state.my_player_name.get().clone(),
+            stats: self.state.my_stats.get().clone(),
+            sessions,
+            record_history: self.state.my_record_history.elements().await.unwrap_or_default(),
+            achievements: self.load_achievements().await,
+            coins: *self.state.my_coins.get(),
+            referral_code: self.state.my_referral_code.get().clone(),
+            referral: self.state.my_referral.get().clone(),
+            replays,
+        })
+    }
+    
+    /// Get game statistics summary
+    async fn game_stats(&self) -> GameStats {
+        let sessions = self.load_sessions().await;
+        let total_sessions = sessions.len() as u64;
+        let finished_games = sessions.iter().filter(|session| session.state == snake_game::GameState::Finished).count() as u64;
+        let total_players = self.state.player_stats.count().await.unwrap_or(0) as u64;
+        
+        GameStats {
+            total_sessions,
+            finished_games,
+            total_players,
+        }
+    }
+}
+
+/// A ticket in the matchmaking queue, as returned by `matchmaking_queue`
+#[derive(async_graphql::SimpleObject)]
+struct QueuePosition {
+    position: u32, // 1-based, oldest ticket first
+    player_chain: ChainId,
+    rating: u32,
+    queued_at: u64,
+    band: u32, // Rating difference currently accepted for an opponent
+}
+
+#[derive(async_graphql::SimpleObject)]
+struct GameStats {
+    total_sessions: u64,
+    finished_games: u64,
+    total_players: u64,
+}
+
+#[derive(async_graphql::SimpleObject)]
+struct PlayerNameEntry {
+    chain_id: ChainId,
+    name: String,
+}
+
+/// A player's public profile, as returned by `player_profile`
+#[derive(async_graphql::SimpleObject)]
+struct PlayerProfile {
+    chain_id: ChainId,
+    name: Option<String>, // Display name, as the player wrote it
+    handle: Option<String>, // Unique lowercase form of the name, with lookalike letters folded
+    tier: Option<RankTier>,
+    level: u32,
+    rank: Option<u32>, // 1-based position on the global leaderboard
+    stats: Option<PlayerStats>,
+    achievements: Vec<AchievementUnlock>,
+    teams: Vec<Team>,
+    last_seen: Option<u64>,
+    previous_names: Vec<NameChange>, // Oldest first
+    verified: bool,
+    verified_games: u32, // Games with a confirmed replay, counted towards leaderboard eligibility
+    eligibility_stake: u64, // Coins staked in place of verified games
+    strikes: u32,
+    penalties: Vec<PenaltyRecord>, // Oldest first
+    season_pass: Option<SeasonPassProgress>, // None until a tier unlocks or premium is bought this season
+    avatar: Option<Avatar>,
+    default_avatar: String, // Built-in avatar to show when there is no custom one
+    country: Option<String>,
+}
+
+/// Leaderboard rows changed since a client's last known version
+#[derive(async_graphql::SimpleObject)]
+struct LeaderboardDiff {
+    version: u64,
+    complete: bool,
+    changes: Vec<LeaderboardChange>,
+}
+
+/// Health of this chain at a glance, for dashboards and frontend startup checks
+#[derive(async_graphql::SimpleObject)]
+struct ChainStatus {
+    leaderboard_configured: bool,
+    is_leaderboard_chain: bool,
+    pending_updates: u64, // Results and names sent to the leaderboard chain and not confirmed yet
+    active_sessions: u64, // Games being played on this chain, by any of its owners
+    last_message_at: Option<u64>,
+}
+
+/// Versions a frontend can compare against the API it was built for
+#[derive(async_graphql::SimpleObject)]
+struct SchemaVersion {
+    state: u32, // Layout of the state stored on this chain
+    compiled_state: u32, // Layout this bytecode expects
+    events: u32,
+    messages: u32,
+}
+
+/// A named contract counter
+#[derive(async_graphql::SimpleObject)]
+struct MetricCounter {
+    name: String,
+    value: u64,
+}
+
+/// One member's total contributions to their team's treasury
+#[derive(async_graphql::SimpleObject)]
+struct TeamContribution {
+    player_chain: ChainId,
+    coins: u64,
+}
+
+/// Session started by `start_game`
+#[derive(async_graphql::SimpleObject)]
+struct StartGameResult {
+    // Only a prediction: the session gets this ID if the operation is the first game started in
+    // the next block; `myCurrentSession` shows the ID it actually got
+    expected_session_id: String,
+    seed: Option<u64>, // Known in advance for battle royale rounds; otherwise set by the starting block
+}
+
+/// Outcome of `set_player_name`; a rejected name is not scheduled
+#[derive(async_graphql::SimpleObject)]
+struct SetNameResult {
+    accepted: bool,
+    name: Option<String>, // The name as it will be stored
+    reason: Option<String>,
+}
+
+impl SetNameResult {
+    fn rejected(reason: String) -> Self {
+        SetNameResult { accepted: false, name: None, reason: Some(reason) }
+    }
+}
+
+/// Sessions ended and started by `restart_game`
+#[derive(async_graphql::SimpleObject)]
+struct RestartedGame {
+    ended_session_id: Option<String>,
+    expected_session_id: String, // Predicted like `StartGameResult::expected_session_id`
+}
+
+/// Everything this chain knows about its player, as returned by `export_my_data`
+#[derive(Serialize)]
+struct PlayerExport {
+    format_version: u32,
+    chain_id: ChainId,
+    exported_at: u64,
+    player_name: Option<String>,
+    stats: Option<PlayerStats>,
+    sessions: Vec<GameSession>,
+    record_history: Vec<PersonalBest>,
+    achievements: Vec<AchievementUnlock>,
+    coins: u64,
+    referral_code: Option<String>,
+    referral: Option<ReferralStatus>,
+    replays: Vec<ReplayReference>,
+}
+
+/// Where the move log of one exported session can be found
+#[derive(Serialize)]
+struct ReplayReference {
+    session_id: String,
+    move_log_hash: Option<String>,
+    replay_blob: Option<DataBlobHash>,
+}
+
+struct MutationRoot {
+    runtime: Arc<ServiceRuntime<SnakeGameService>>,
+    state: Arc<SnakeGameState>, // Collections are only read by the mutations that check them
+    // State read before scheduling, so doomed operations fail with a GraphQL error instead
+    is_leaderboard_chain: bool,
+    leaderboard_chain_id: Option<ChainId>,
+    current_session: Option<GameSession>,
+    current_session_is_live: bool,
+    games_played: u32,
+    my_coins: u64,
+    my_referral_code: Option<String>,
+    has_referral: bool,
+    leaderboard_subscription: Option<u32>,
+    my_eligibility_stake: u64,
+    history_sync_enabled: bool,
+    name_changed_at: Option<u64>,
+    pending_leaderboard_move: Option<ChainId>,
+    reset_proposal: Option<ResetProposal>,
+    reset_ballot: Option<ResetBallot>,
+    season_pass: SeasonPass,
+    client_key: Option<Ed25519PublicKey>,
+    guest_games: usize,
+    merged_into: Option<ChainId>,
+}
+
+impl MutationRoot {
+    /// Fail unless this chain is the leaderboard chain
+    fn require_leaderboard_chain(&self) -> async_graphql::Result<()> {
+        if !self.is_leaderboard_chain {
+            return Err(async_graphql::Error::new("This operation can only be performed on the leaderboard chain"));
+        }
+        Ok(())
+    }
+    
+    /// Fail unless a leaderboard chain has been configured
+    fn require_leaderboard_configured(&self) -> async_graphql::Result<()> {
+        if self.leaderboard_chain_id.is_none() {
+            return Err(async_graphql::Error::new("No leaderboard chain configured. Please use setupLeaderboard first"));
+        }
+        Ok(())
+    }
+    
+    /// The session currently being played on this chain, which must be the one the client is playing
+    fn require_active_session(&self, session_id: &str) -> async_graphql::Result<&GameSession> {
+        let session = self.current_session.as_ref()
+            .filter(|session| session.state == GameState::Playing)
+            .ok_or_else(|| async_graphql::Error::new("No active game session found. Please start a game first"))?;
+        if session.session_id != session_id {
+            return Err(async_graphql::Error::new(format!("Session {} is not the active session", session_id)));
+        }
+        Ok(session)
+    }
+    
+    /// With a registered client key, the current session's score must come with the key's signature
+    fn require_signed_score(&self, move_log_hash: &Option<String>, signature: &Option<Ed25519Signature>) -> async_graphql::Result<()> {
+        let (Some(client_key), Some(session)) = (self.client_key, &self.current_session) else {
+            return Ok(());
+        };
+        let Some(signature) = signature else {
+            return Err(async_graphql::Error::new("This chain only accepts scores signed by its client key"));
+        };
+        let claim = snake_game::ScoreClaim {
+            chain_id: self.runtime.chain_id(),
+            session_id: session.session_id.clone(),
+            candies_collected: session.candies_collected,
+            move_log_hash: move_log_hash.clone(),
+        };
+        if signature.check(&claim, client_key).is_err() {
+            return Err(async_graphql::Error::new(format!("The score claim for session {} is not signed by the client key", claim.session_id)));
+        }
+        Ok(())
+    }
+    
+    /// A session played on this chain that has finished
+    async fn require_finished_session(&self, session_id: &str) -> async_graphql::Result<GameSession> {
+        let session = self.state.session(session_id).await
+            .ok_or_else(|| async_graphql::Error::new(format!("Unknown session: {}", session_id)))?;
+        if session.state != GameState::Finished {
+            return Err(async_graphql::Error::new(format!("Session {} has not finished yet", session_id)));
+        }
+        Ok(session)
+    }
+    
+    /// Fail unless this is the leaderboard chain and the dispute is waiting for a moderator
+    async fn require_pending_dispute(&self, dispute_id: u64) -> async_graphql::Result<()> {
+        self.require_leaderboard_chain()?;
+        if !self.state.review_queue.contains(&dispute_id).await.unwrap_or(false) {
+            return Err(async_graphql::Error::new(format!("Dispute {} is not waiting for review", dispute_id)));
+        }
+        Ok(())
+    }
+    
+    /// Fail unless a feature is switched on
+    async fn require_feature(&self, key: &str, description: &str) -> async_graphql::Result<()> {
+        let enabled = self.state.feature_flags.get(&key.to_string()).await.ok().flatten()
+            .unwrap_or_else(|| snake_game::feature_default(key));
+        if !enabled {
+            return Err(async_graphql::Error::new(format!("{} currently disabled", description)));
+        }
+        Ok(())
+    }
+    
+    /// Whether a level is the built-in open level or one published on this chain
+    async fn level_exists(&self, level_id: u32) -> bool {
+        level_id == LevelLayout::OPEN_LEVEL_ID || self.state.levels.contains_key(&level_id).await.unwrap_or(false)
+    }
+    
+    /// Check the settings of a game about to be started
+    async fn check_new_game(
+        &self,
+        level_id: u32,
+        map_hash: &Option<String>,
+        mode: GameMode,
+        match_id: Option<u64>,
+        royale_id: Option<u64>,
+        ghost: &Option<String>,
+        tournament_id: Option<u64>,
+    ) -> async_graphql::Result<()> {
+        if let Some(flag) = mode.feature_flag() {
+            self.require_feature(flag, &format!("Game mode {:?} is", mode)).await?;
+        }
+        if royale_id.is_some() {
+            self.require_feature(snake_game::FEATURE_BATTLE_ROYALE, "Battle royales are").await?;
+        }
+        // Ghost races and tournament attempts take their level from the ghost or the tournament,
+        // so there is no level to check here
+        if tournament_id.is_some() {
+            if map_hash.is_some() || match_id.is_some() || royale_id.is_some() || ghost.is_some() {
+                return Err(async_graphql::Error::new("Tournament attempts are played on their own, on the tournament's level"));
+            }
+        } else if ghost.is_some() {
+            if map_hash.is_some() || match_id.is_some() || royale_id.is_some() {
+                return Err(async_graphql::Error::new("Ghost races are played on their own, on the ghost's level"));
+            }
+        } else if !self.level_exists(level_id).await {
+            return Err(async_graphql::Error::new(format!("Unknown level {}", level_id)));
+        }
+        if map_hash.is_some() && level_id != LevelLayout::OPEN_LEVEL_ID {
+            return Err(async_graphql::Error::new("A custom map is played instead of a level, not on top of one"));
+        }
+        if let Some(match_id) = match_id {
+            let team_match = self.state.my_team_matches.get(&match_id).await.ok().flatten()
+                .ok_or_else(|| async_graphql::Error::new(format!("Unknown team match {}", match_id)))?;
+            if team_match.state != TeamMatchState::Open {
+                return Err(async_graphql::Error::new(format!("Team match {} is not open to play ({:?})", match_id, team_match.state)));
+            }
+        }
+        if let Some(event_id) = royale_id {
+            let now = self.runtime.system_time().micros();
+            let round_open = self.state.my_battle_royales.get(&event_id).await.ok().flatten()
+                .map(|status| status.eliminated_in_round.is_none() && !status.won && now <= status.round_ends_at)
+                .unwrap_or(false);
+            if !round_open {
+                return Err(async_graphql::Error::new(format!("No open round of battle royale {} for this chain", event_id)));
+            }
+        }
+        Ok(())
+    }
+    
+    fn require_coins(&self, amount: u64) -> async_graphql::Result<()> {
+        if self.my_coins < amount {
+            return Err(async_graphql::Error::new(format!("Insufficient coins: {} available, {} required", self.my_coins, amount)));
+        }
+        Ok(())
+    }
+}
+
+fn parse_amount(amount: &str) -> async_graphql::Result<Amount> {
+    amount.parse().map_err(|_| async_graphql::Error::new(format!("Invalid amount: {}", amount)))
+}
+
+#[Object]
+impl MutationRoot {
+    /// Run several JSON-encoded operations in one block; they apply in order and revert together if any fails
+    async fn batch(&self, operations: Json<Vec<snake_game::Operation>>) -> async_graphql::Result<String> {
+        if operations.0.is_empty() {
+            return Err(async_graphql::Error::new("A batch needs at least one operation"));
+        }
+        if operations.0.iter().any(|operation| matches!(operation, snake_game::Operation::Batch(_))) {
+            return Err(async_graphql::Error::new("Batches cannot be nested"));
+        }
+        let count = operations.0.len();
+        self.runtime.schedule_operation(&snake_game::Operation::Batch(operations.0));
+        Ok(format!("Scheduled a batch of {} operations", count))
+    }
+    
+    /// Setup the leaderboard chain (admin operation)
+    async fn setup_leaderboard(&self, leaderboard_chain_id: ChainId) -> async_graphql::Result<String> {
+        if self.leaderboard_chain_id.is_some() {
+            return Err(async_graphql::Error::new("Leaderboard already configured"));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::SetupLeaderboard { leaderboard_chain_id });
+        Ok(format!("Setup leaderboard with chain ID: {}", leaderboard_chain_id))
+    }
+    
+    /// Start a new game on a level, or on a published custom map. The returned session ID is a
+    /// prediction; confirm it with `myCurrentSession` once the block is executed
+    async fn start_game(
+        &self,
+        level_id: Option<u32>,
+        map_hash: Option<String>,
+        mode: Option<GameMode>,
+        match_id: Option<u64>,
+        royale_id: Option<u64>,
+        ghost: Option<String>,
+        tournament_id: Option<u64>,
+    ) -> async_graphql::Result<StartGameResult> {
+        let level_id = level_id.unwrap_or(LevelLayout::OPEN_LEVEL_ID);
+        let mode = mode.unwrap_or_default();
+        self.check_new_game(level_id, &map_hash, mode, match_id, royale_id, &ghost, tournament_id).await?;
+        let seed = match royale_id {
+            Some(event_id) => self.state.my_battle_royales.get(&event_id).await.ok().flatten().map(|status| status.round_seed),
+            None => None,
+        };
+        
+        self.runtime.schedule_operation(&snake_game::Operation::StartGame { level_id, map_hash, mode, match_id, royale_id, ghost, tournament_id });
+        // Predicted from the next block; another operation starting a game first changes the real ID
+        Ok(StartGameResult {
+            expected_session_id: snake_game::session_id(self.runtime.chain_id(), self.runtime.next_block_height().0, 0),
+            seed,
+        })
+    }
+    
+    /// End the current game, if any, and start a new one in the same block. `ended_session_id` must name the current game;
+    /// like `start_game`, the new session ID is only a prediction
+    async fn restart_game(
+        &self,
+        move_log_hash: Option<String>,
+        signature: Option<Ed25519Signature>,
+        level_id: Option<u32>,
+        map_hash: Option<String>,
+        mode: Option<GameMode>,
+        match_id: Option<u64>,
+        royale_id: Option<u64>,
+        ghost: Option<String>,
+        tournament_id: Option<u64>,
+        ended_session_id: Option<String>,
+    ) -> async_graphql::Result<RestartedGame> {
+        // The client names the game it means to end, so a stale client cannot end a newer one
+        match (&self.current_session, &ended_session_id) {
+            (Some(session), Some(session_id)) if session.session_id != *session_id => {
+                return Err(async_graphql::Error::new(format!("Session {} is not the current session", session_id)));
+            }
+            (Some(session), None) => {
+                return Err(async_graphql::Error::new(format!("Ending the current game needs its session ID ({})", session.session_id)));
+            }
+            (None, Some(session_id)) => {
+                return Err(async_graphql::Error::new(format!("Session {} is not the current session; there is no game to end", session_id)));
+            }
+            _ => {}
+        }
+        let level_id = level_id.unwrap_or(LevelLayout::OPEN_LEVEL_ID);
+        let mode = mode.unwrap_or_default();
+        match &move_log_hash {
+            Some(hash) if !snake_game::is_valid_move_log_hash(hash) => {
+                return Err(async_graphql::Error::new(format!("Move log hash must be {} hex characters", snake_game::MOVE_LOG_HASH_LEN)));
+            }
+            None if self.current_session.is_some() => {
+                return Err(async_graphql::Error::new("Ending the current game needs the hash of its move log"));
+            }
+            _ => {}
+        }
+        self.check_new_game(level_id, &map_hash, mode, match_id, royale_id, &ghost, tournament_id).await?;
+        self.require_signed_score(&move_log_hash, &signature)?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::RestartGame {
+            move_log_hash,
+            signature,
+            level_id,
+            map_hash,
+            mode,
+            match_id,
+            royale_id,
+            ghost,
+            tournament_id,
+        });
+        // Session IDs come from the block that starts them, so the new one can only be predicted,
+        // assuming the scheduled operation starts the first session of the next block
+        Ok(RestartedGame {
+            ended_session_id,
+            expected_session_id: snake_game::session_id(self.runtime.chain_id(), self.runtime.next_block_height().0, 0),
+        })
+    }
+    
+    /// Report a death in a Survival game; once no lives remain, the game waits for `endGame`
+    async fn report_death(&self, tick: u64, session_id: String) -> async_graphql::Result<String> {
+        let session = self.require_active_session(&session_id)?;
+        match session.lives_remaining {
+            None => return Err(async_graphql::Error::new("Deaths are only reported in Survival mode")),
+            Some(0) => return Err(async_graphql::Error::new(format!("Session {} is out of lives and waits for endGame", session_id))),
+            Some(_) => {}
+        }
+        if session.last_death_tick.map(|last| tick <= last).unwrap_or(false) {
+            return Err(async_graphql::Error::new(format!("Death at tick {} is not after the previous death", tick)));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::ReportDeath { tick });
+        Ok(format!("Death reported at tick {}", tick))
+    }
+    
+    /// Submit direction bytes (0 = up, 1 = right, 2 = down, 3 = left) for the next ticks of the current game
+    async fn submit_moves(&self, moves: Vec<u8>, session_id: String) -> async_graphql::Result<String> {
+        self.require_active_session(&session_id)?;
+        if !self.current_session_is_live {
+            return Err(async_graphql::Error::new("Live state is only tracked for games on levels"));
+        }
+        if let Some(byte) = moves.iter().find(|byte| Direction::from_byte(**byte).is_none()) {
+            return Err(async_graphql::Error::new(format!("Invalid move byte {}", byte)));
+        }
+        
+        let count = moves.len();
+        self.runtime.schedule_operation(&snake_game::Operation::SubmitMoves { moves });
+        Ok(format!("Submitted {} moves", count))
+    }
+    
+    /// Watch the live games played on another chain
+    async fn spectate(&self, player_chain_id: ChainId) -> async_graphql::Result<String> {
+        self.runtime.schedule_operation(&snake_game::Operation::Spectate { player_chain: player_chain_id });
+        Ok(format!("Spectating {}", player_chain_id))
+    }
+    
+    /// Stop watching the live games of another chain
+    async fn stop_spectating(&self, player_chain_id: ChainId) -> async_graphql::Result<String> {
+        self.runtime.schedule_operation(&snake_game::Operation::StopSpectating { player_chain: player_chain_id });
+        Ok(format!("Stopped spectating {}", player_chain_id))
+    }
+    
+    /// Advance the current game's tick counter while no moves or candies are submitted
+    async fn heartbeat(&self, tick: u64, session_id: String) -> async_graphql::Result<String> {
+        let session = self.require_active_session(&session_id)?;
+        if tick < session.ticks {
+            return Err(async_graphql::Error::new(format!("Heartbeat tick {} is behind the session at tick {}", tick, session.ticks)));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::Heartbeat { tick });
+        Ok(format!("Session advanced to tick {}", tick))
+    }
+    
+    /// Collect a candy during gameplay at the given game tick
+    async fn collect_candy(&self, tick: u64, session_id: String) -> async_graphql::Result<String> {
+        let session = self.require_active_session(&session_id)?;
+        if !session.accepts_candy_at(tick) {
+            return Err(async_graphql::Error::new(format!("Tick {} is past the time limit", tick)));
+        }
+        if session.lives_remaining == Some(0) {
+            return Err(async_graphql::Error::new("The snake is out of lives"));
+        }
+        if session.combo.last_candy_tick.map(|last| tick <= last).unwrap_or(false) {
+            return Err(async_graphql::Error::new(format!("Tick {} is not after the previous candy", tick)));
+        }
+        if tick < session.ticks {
+            return Err(async_graphql::Error::new(format!("Tick {} is before tick {} the game already reached", tick, session.ticks)));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::CollectCandy { tick });
+        Ok("Candy collected successfully".to_string())
+    }
+    
+    /// Collect several candies in one batch, given the tick each was eaten on in order
+    async fn collect_candies(&self, ticks: Vec<u64>, session_id: String) -> async_graphql::Result<String> {
+        let session = self.require_active_session(&session_id)?;
+        let (Some(&first_tick), Some(&last_tick)) = (ticks.first(), ticks.last()) else {
+            return Err(async_graphql::Error::new("At least one tick is needed"));
+        };
+        if ticks.len() > MAX_CANDY_BATCH {
+            return Err(async_graphql::Error::new(format!("At most {} candies can be collected at once", MAX_CANDY_BATCH)));
+        }
+        if ticks.windows(2).any(|pair| pair[1] <= pair[0]) {
+            return Err(async_graphql::Error::new("Ticks must be strictly increasing"));
+        }
+        if session.lives_remaining == Some(0) {
+            return Err(async_graphql::Error::new("The snake is out of lives"));
+        }
+        if session.combo.last_candy_tick.map(|last| first_tick <= last).unwrap_or(false) {
+            return Err(async_graphql::Error::new(format!("Tick {} is not after the previous candy", first_tick)));
+        }
+        if first_tick < session.ticks {
+            return Err(async_graphql::Error::new(format!("Tick {} is before tick {} the game already reached", first_tick, session.ticks)));
+        }
+        if !session.accepts_candy_at(last_tick) {
+            return Err(async_graphql::Error::new(format!("Tick {} is past the time limit", last_tick)));
+        }
+        
+        let count = ticks.len();
+        let operations = ticks.into_iter()
+            .map(|tick| snake_game::Operation::CollectCandy { tick })
+            .collect();
+        self.runtime.schedule_operation(&snake_game::Operation::Batch(operations));
+        Ok(format!("Collected {} candies", count))
+    }
+    
+    /// End the current game, anchoring the hash of the client's move log
+    async fn end_game(&self, move_log_hash: String, signature: Option<Ed25519Signature>, session_id: String) -> async_graphql::Result<String> {
+        self.require_active_session(&session_id)?;
+        if !snake_game::is_valid_move_log_hash(&move_log_hash) {
+            return Err(async_graphql::Error::new(format!("Move log hash must be {} hex characters", snake_game::MOVE_LOG_HASH_LEN)));
+        }
+        self.require_signed_score(&Some(move_log_hash.clone()), &signature)?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::EndGame { move_log_hash, signature });
+        Ok("Game ended successfully".to_string())
+    }
+    
+    /// Propose a leaderboard reset for the participants to vote on (admin operation, only on leaderboard chain)
+    async fn propose_reset(&self) -> async_graphql::Result<String> {
+        self.require_leaderboard_chain()?;
+        let now = self.runtime.system_time().micros();
+        if let Some(proposal) = &self.reset_proposal {
+            if proposal.state_at(now) == ProposalState::Open {
+                return Err(async_graphql::Error::new(format!("Reset proposal {} is still open", proposal.proposal_id)));
+            }
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::ProposeReset);
+        Ok("Leaderboard reset proposed".to_string())
+    }
+    
+    /// Vote on the open leaderboard reset proposal
+    async fn vote_on_reset(&self, proposal_id: u64, approve: bool) -> async_graphql::Result<String> {
+        let ballot = self.reset_ballot.as_ref()
+            .filter(|ballot| ballot.proposal_id == proposal_id)
+            .ok_or_else(|| async_graphql::Error::new(format!("No open reset proposal {} for this chain", proposal_id)))?;
+        if ballot.vote.is_some() {
+            return Err(async_graphql::Error::new(format!("Already voted on reset proposal {}", proposal_id)));
+        }
+        if self.runtime.system_time().micros() > ballot.closes_at {
+            return Err(async_graphql::Error::new(format!("Voting on reset proposal {} has closed", proposal_id)));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::VoteOnReset { proposal_id, approve });
+        Ok(format!("Vote on reset proposal {} submitted", proposal_id))
+    }
+    
+    /// Report a suspicious leaderboard entry to the moderators
+    async fn flag_score(&self, chain_id: ChainId, session_id: String, reason: String) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        if chain_id == self.runtime.chain_id() {
+            return Err(async_graphql::Error::new("Players cannot flag their own scores"));
+        }
+        let reason = reason.trim();
+        if reason.is_empty() {
+            return Err(async_graphql::Error::new("A reason is required to flag a score"));
+        }
+        if reason.len() > snake_game::MAX_DISPUTE_REASON_LEN {
+            return Err(async_graphql::Error::new(format!("Dispute reason cannot exceed {} bytes", snake_game::MAX_DISPUTE_REASON_LEN)));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::FlagScore {
+            chain_id,
+            session_id: session_id.clone(),
+            reason: reason.to_string(),
+        });
+        Ok(format!("Session {} flagged for review", session_id))
+    }
+    
+    /// Dismiss a flag, leaving the score as it is (admin operation, only on leaderboard chain)
+    async fn resolve_dispute(&self, dispute_id: u64) -> async_graphql::Result<String> {
+        self.require_pending_dispute(dispute_id).await?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::ResolveDispute { dispute_id });
+        Ok(format!("Dispute {} dismissed", dispute_id))
+    }
+    
+    /// Agree that a flagged score is suspicious and mark it on the board (admin operation, only on leaderboard chain)
+    async fn uphold_dispute(&self, dispute_id: u64) -> async_graphql::Result<String> {
+        self.require_pending_dispute(dispute_id).await?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::UpholdDispute { dispute_id });
+        Ok(format!("Dispute {} upheld", dispute_id))
+    }
+    
+    /// Uphold a flag and give the player a strike (admin operation, only on leaderboard chain)
+    async fn strike_dispute(&self, dispute_id: u64) -> async_graphql::Result<String> {
+        self.require_pending_dispute(dispute_id).await?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::StrikeDispute { dispute_id });
+        Ok(format!("Dispute {} upheld with a strike", dispute_id))
+    }
+    
+    /// Switch a feature on or off for the whole network (admin operation, only on leaderboard chain)
+    async fn set_feature_flag(&self, key: String, enabled: bool) -> async_graphql::Result<String> {
+        self.require_leaderboard_chain()?;
+        if key.trim().is_empty() {
+            return Err(async_graphql::Error::new("Feature flag key cannot be empty"));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::SetFeatureFlag { key: key.clone(), enabled });
+        Ok(format!("Feature '{}' {}", key, if enabled { "enabled" } else { "disabled" }))
+    }
+    
+    /// Hand the leaderboard role to another chain (admin operation, only on leaderboard chain)
+    async fn reconfigure_leaderboard(&self, new_chain_id: ChainId) -> async_graphql::Result<String> {
+        self.require_leaderboard_chain()?;
+        if let Some(pending_chain) = self.pending_leaderboard_move {
+            return Err(async_graphql::Error::new(format!("A move to leaderboard chain {} is still pending", pending_chain)));
+        }
+        if new_chain_id == self.runtime.chain_id() {
+            return Err(async_graphql::Error::new("This chain is already the leaderboard chain"));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::ReconfigureLeaderboard { new_chain: new_chain_id });
+        Ok(format!("Leaderboard handover to {} requested", new_chain_id))
+    }
+    
+    /// Set player name
+    async fn set_player_name(&self, name: String) -> SetNameResult {
+        let name = match snake_game::display_name(&name) {
+            Ok(name) => name,
+            Err(reason) => return SetNameResult::rejected(reason),
+        };
+        if let Some(changed_at) = self.name_changed_at {
+            let allowed_at = self.runtime.application_parameters().name_change_allowed_at(changed_at);
+            let now = self.runtime.system_time().micros();
+            if now < allowed_at {
+                return SetNameResult::rejected(format!(
+                    "Player name can be changed again in {} seconds", (allowed_at - now).div_ceil(1_000_000)));
+            }
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::SetPlayerName { name: name.clone() });
+        SetNameResult { accepted: true, name: Some(name), reason: None }
+    }
+    
+    /// Show a two-letter country code on this player's profile and entries; no code removes it
+    async fn set_country(&self, country: Option<String>) -> async_graphql::Result<String> {
+        let country = match country {
+            Some(code) => Some(country_code(&code)
+                .ok_or_else(|| async_graphql::Error::new(format!("'{}' is not a two-letter country code", code)))?),
+            None => None,
+        };
+        
+        self.runtime.schedule_operation(&snake_game::Operation::SetCountry { country: country.clone() });
+        Ok(match country {
+            Some(country) => format!("Country set to {}", country),
+            None => "Country removed".to_string(),
+        })
+    }
+    
+    /// Let another chain take over this chain's leaderboard history once it presents the link
+    async fn issue_account_link(&self, new_chain_id: ChainId) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        if new_chain_id == self.runtime.chain_id() {
+            return Err(async_graphql::Error::new("A chain cannot be linked to itself"));
+        }
+        if let Some(merged_into) = self.merged_into {
+            return Err(async_graphql::Error::new(format!("This chain's history was already merged into {}", merged_into)));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::IssueAccountLink { new_chain: new_chain_id });
+        Ok(format!("Account link issued to {}", new_chain_id))
+    }
+    
+    /// Present the link token another chain sent, merging its history into this chain
+    async fn merge_account(&self, old_chain_id: ChainId) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        if !self.state.received_link_tokens.contains_key(&old_chain_id).await.unwrap_or(false) {
+            return Err(async_graphql::Error::new(format!("No link token was received from {}", old_chain_id)));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::MergeAccount { old_chain: old_chain_id });
+        Ok(format!("Merging the history of {}", old_chain_id))
+    }
+    
+    /// Register the game client's key, so scores from this chain must be signed by it. A registered key
+    /// can only be replaced or removed with its signature of the change
+    async fn register_client_key(&self, public_key: Option<Ed25519PublicKey>, signature: Option<Ed25519Signature>) -> async_graphql::Result<String> {
+        if let Some(current_key) = self.client_key {
+            let change = snake_game::ClientKeyChange { chain_id: self.runtime.chain_id(), new_key: public_key };
+            let Some(signature) = &signature else {
+                return Err(async_graphql::Error::new("Replacing the client key requires a signature by the current key"));
+            };
+            if signature.check(&change, current_key).is_err() {
+                return Err(async_graphql::Error::new("The key change is not signed by the current client key"));
+            }
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::RegisterClientKey { public_key, signature });
+        Ok(match public_key {
+            Some(_) => "Client key registered".to_string(),
+            None => "Client key removed".to_string(),
+        })
+    }
+    
+    /// Use an image published as a data blob as this player's avatar; no hash restores the default
+    async fn set_avatar(&self, blob_hash: Option<String>) -> async_graphql::Result<String> {
+        let blob_hash = match blob_hash {
+            Some(hash) => match hash.parse() {
+                Ok(hash) => Some(DataBlobHash(hash)),
+                Err(_) => return Err(async_graphql::Error::new(format!("Invalid blob hash format: {}", hash))),
+            },
+            None => None,
+        };
+        if let Some(blob_hash) = blob_hash {
+            avatar_format(&self.runtime.read_data_blob(blob_hash)).map_err(async_graphql::Error::new)?;
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::SetAvatar { blob_hash });
+        Ok(if blob_hash.is_some() { "Avatar updated" } else { "Avatar reset to the default" }.to_string())
+    }
+    
+    /// Publish finished sessions to the leaderboard chain, or stop and delete the published history
+    async fn set_history_sync(&self, enabled: bool) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::SetHistorySync { enabled });
+        Ok(if enabled { "Public history enabled" } else { "Public history disabled" }.to_string())
+    }
+    
+    /// Send finished sessions still waiting for a full batch to the leaderboard chain
+    async fn sync_history(&self) -> async_graphql::Result<String> {
+        if !self.history_sync_enabled {
+            return Err(async_graphql::Error::new("Public history sync is not enabled"));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::SyncHistory);
+        Ok("History sync scheduled".to_string())
+    }
+    
+    /// Move players past the inactivity window off the main board (admin operation, only on leaderboard chain)
+    async fn decay_inactive(&self) -> async_graphql::Result<String> {
+        self.require_leaderboard_chain()?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::DecayInactive);
+        Ok("Inactive players decayed successfully".to_string())
+    }
+    
+    /// Publish a quest to all player chains (admin operation, only on leaderboard chain)
+    async fn publish_quest(
+        &self,
+        title: String,
+        kind: QuestKind,
+        period: QuestPeriod,
+        target: u32,
+        reward_coins: u64,
+        reward_xp: u64,
+    ) -> async_graphql::Result<String> {
+        self.require_leaderboard_chain()?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::PublishQuest {
+            title: title.clone(),
+            kind,
+            period,
+            target,
+            reward_coins,
+            reward_xp,
+        });
+        Ok(format!("Quest '{}' published successfully", title))
+    }
+    
+    /// Publish an announcement to all player chains (admin operation, only on leaderboard chain)
+    async fn publish_announcement(&self, title: String, body: String, expires: Option<u64>) -> async_graphql::Result<String> {
+        self.require_leaderboard_chain()?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::PublishAnnouncement {
+            title: title.clone(),
+            body,
+            expires,
+        });
+        Ok(format!("Announcement '{}' published successfully", title))
+    }
+    
+    /// Move tokens from the signer into the treasury (only on leaderboard chain)
+    async fn fund_treasury(&self, amount: String) -> async_graphql::Result<String> {
+        let amount = parse_amount(&amount)?;
+        self.require_leaderboard_chain()?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::FundTreasury { amount });
+        Ok(format!("Treasury funded with {}", amount))
+    }
+    
+    /// Withdraw tokens from the treasury (admin operation, only on leaderboard chain)
+    async fn withdraw_treasury(&self, amount: String, to_chain_id: ChainId, to_owner: String) -> async_graphql::Result<String> {
+        let amount = parse_amount(&amount)?;
+        let owner = to_owner.parse()
+            .map_err(|_| async_graphql::Error::new(format!("Invalid owner format: {}", to_owner)))?;
+        self.require_leaderboard_chain()?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::WithdrawTreasury {
+            amount,
+            to: Account { chain_id: to_chain_id, owner },
+        });
+        Ok(format!("Withdrawal of {} scheduled", amount))
+    }
+    
+    /// Donate tokens to a named prize pool on the leaderboard chain
+    async fn donate_to_prize_pool(&self, pool: String, amount: String, sponsor_name: Option<String>) -> async_graphql::Result<String> {
+        let amount = parse_amount(&amount)?;
+        self.require_leaderboard_configured()?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::DonateToPrizePool {
+            pool: pool.clone(),
+            amount,
+            sponsor_name,
+        });
+        Ok(format!("Donated {} to prize pool '{}'", amount, pool))
+    }
+    
+    /// Move the vested part of every pending reward into this chain's coins
+    async fn claim_rewards(&self) -> async_graphql::Result<String> {
+        if self.state.my_rewards.count().await.unwrap_or(0) == 0 {
+            return Err(async_graphql::Error::new("No rewards to claim"));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::ClaimRewards);
+        Ok("Rewards claimed".to_string())
+    }
+    
+    /// Merge the games played as a guest into the signer's stats and report their records
+    async fn claim_guest_history(&self) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        if self.guest_games == 0 {
+            return Err(async_graphql::Error::new("There are no guest games to claim"));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::ClaimGuestHistory);
+        Ok(format!("Claiming {} guest games", self.guest_games))
+    }
+    
+    /// Send coins to another player chain; they are restored here if the recipient refuses them
+    async fn gift_coins(&self, to_chain_id: ChainId, amount: u64) -> async_graphql::Result<String> {
+        if to_chain_id == self.runtime.chain_id() {
+            return Err(async_graphql::Error::new("Cannot gift coins to this chain"));
+        }
+        if amount == 0 {
+            return Err(async_graphql::Error::new("Gift amount must be greater than zero"));
+        }
+        self.require_coins(amount)?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::GiftCoins { to_chain: to_chain_id, amount });
+        Ok(format!("Gifted {} coins to {}", amount, to_chain_id))
+    }
+    
+    /// Lock coins with the leaderboard chain to be ranked before playing enough verified games
+    async fn stake_for_eligibility(&self) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        let coins = self.runtime.application_parameters().eligibility_stake_coins;
+        if coins == 0 {
+            return Err(async_graphql::Error::new("Staking for leaderboard eligibility is not offered"));
+        }
+        if self.my_eligibility_stake > 0 {
+            return Err(async_graphql::Error::new("This chain has already staked for leaderboard eligibility"));
+        }
+        self.require_coins(coins)?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::StakeForEligibility);
+        Ok(format!("Staked {} coins for leaderboard eligibility", coins))
+    }
+    
+    /// Ask the leaderboard chain to push its top `limit` rows (all of them by default) to this chain
+    async fn request_leaderboard_sync(&self, limit: Option<u32>) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        if self.is_leaderboard_chain {
+            return Err(async_graphql::Error::new("The leaderboard chain already holds the global leaderboard"));
+        }
+        let limit = limit.unwrap_or(snake_game::MAX_LEADERBOARD_SYNC);
+        if limit == 0 || limit > snake_game::MAX_LEADERBOARD_SYNC {
+            return Err(async_graphql::Error::new(format!("Limit must be between 1 and {}", snake_game::MAX_LEADERBOARD_SYNC)));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::RequestLeaderboardSync { limit });
+        Ok(format!("Requested the top {} from the leaderboard chain", limit))
+    }
+    
+    /// Leave an emote on another player's leaderboard entry, replacing any earlier one; no emote takes it back
+    async fn react_to_entry(&self, chain_id: ChainId, emote: Option<Emote>) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        if chain_id == self.runtime.chain_id() {
+            return Err(async_graphql::Error::new("Players cannot react to their own entry"));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::ReactToEntry { chain_id, emote });
+        Ok(match emote {
+            Some(emote) => format!("Reacted with {:?}", emote),
+            None => "Reaction removed".to_string(),
+        })
+    }
+    
+    /// Send a chat message to another player (`to_chain`) or to the other members of a team
+    async fn send_chat_message(&self, to_chain: Option<ChainId>, team_id: Option<u64>, text: String) -> async_graphql::Result<String> {
+        let recipient = match (to_chain, team_id) {
+            (Some(chain), None) if chain == self.runtime.chain_id() => {
+                return Err(async_graphql::Error::new("Players cannot chat with themselves"));
+            }
+            (Some(chain), None) => ChatRecipient::Player(chain),
+            (None, Some(team_id)) => {
+                self.require_leaderboard_configured()?;
+                ChatRecipient::Team(team_id)
+            }
+            _ => return Err(async_graphql::Error::new("Give exactly one of toChain and teamId")),
+        };
+        let length = text.trim().chars().count();
+        if length == 0 || length > snake_game::MAX_CHAT_MESSAGE_LEN {
+            return Err(async_graphql::Error::new(format!("Chat messages must be between 1 and {} characters", snake_game::MAX_CHAT_MESSAGE_LEN)));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::SendChatMessage { recipient, text });
+        Ok("Chat message sent".to_string())
+    }
+    
+    /// Get a notification whenever another player sets a new record
+    async fn follow_player(&self, chain_id: ChainId) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        if chain_id == self.runtime.chain_id() {
+            return Err(async_graphql::Error::new("Players cannot follow themselves"));
+        }
+        if self.state.following.contains(&chain_id).await.unwrap_or(false) {
+            return Err(async_graphql::Error::new("Already following this player"));
+        }
+        if self.state.following.count().await.unwrap_or(0) >= snake_game::MAX_FOLLOWING {
+            return Err(async_graphql::Error::new(format!("A chain can follow at most {} players", snake_game::MAX_FOLLOWING)));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::FollowPlayer { chain_id });
+        Ok(format!("Following {}", chain_id))
+    }
+    
+    /// Stop notifications about another player's records
+    async fn unfollow_player(&self, chain_id: ChainId) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        if !self.state.following.contains(&chain_id).await.unwrap_or(false) {
+            return Err(async_graphql::Error::new("Not following this player"));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::UnfollowPlayer { chain_id });
+        Ok(format!("Stopped following {}", chain_id))
+    }
+    
+    /// Have the top `limit` rows (all of them by default) pushed to this chain whenever the podium changes
+    async fn subscribe_to_leaderboard(&self, limit: Option<u32>) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        if self.is_leaderboard_chain {
+            return Err(async_graphql::Error::new("The leaderboard chain already holds the global leaderboard"));
+        }
+        let limit = limit.unwrap_or(snake_game::MAX_LEADERBOARD_SYNC);
+        if limit == 0 || limit > snake_game::MAX_LEADERBOARD_SYNC {
+            return Err(async_graphql::Error::new(format!("Limit must be between 1 and {}", snake_game::MAX_LEADERBOARD_SYNC)));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::SubscribeToLeaderboard { limit });
+        Ok(format!("Subscribed to the top {} of the leaderboard", limit))
+    }
+    
+    /// Stop podium updates from the leaderboard chain
+    async fn unsubscribe_from_leaderboard(&self) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        if self.leaderboard_subscription.is_none() {
+            return Err(async_graphql::Error::new("This chain is not subscribed to the leaderboard"));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::UnsubscribeFromLeaderboard);
+        Ok("Unsubscribed from the leaderboard".to_string())
+    }
+    
+    /// Request this chain's referral code from the leaderboard chain
+    async fn request_referral_code(&self) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::RequestReferralCode);
+        Ok("Referral code requested successfully".to_string())
+    }
+    
+    /// Redeem another player's referral code (new players only)
+    async fn register_referral(&self, code: String) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        if self.has_referral {
+            return Err(async_graphql::Error::new("A referral code has already been registered on this chain"));
+        }
+        if self.games_played > 0 {
+            return Err(async_graphql::Error::new("Referral codes can only be redeemed before the first game"));
+        }
+        if self.my_referral_code.as_ref() == Some(&code) {
+            return Err(async_graphql::Error::new("Players cannot redeem their own referral code"));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::RegisterReferral { code: code.clone() });
+        Ok(format!("Referral code '{}' registered successfully", code))
+    }
+    
+    /// Reserve a name for one player chain, or for nobody (admin operation, only on leaderboard chain)
+    async fn reserve_name(&self, name: String, reserved_for: Option<ChainId>) -> async_graphql::Result<String> {
+        self.require_leaderboard_chain()?;
+        if snake_game::name_key(&name).is_empty() {
+            return Err(async_graphql::Error::new("Cannot reserve an empty name"));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::ReserveName { name: name.clone(), reserved_for });
+        Ok(format!("Name '{}' reserved", name))
+    }
+    
+    /// Make a reserved name available again (admin operation, only on leaderboard chain)
+    async fn release_name(&self, name: String) -> async_graphql::Result<String> {
+        self.require_leaderboard_chain()?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::ReleaseName { name: name.clone() });
+        Ok(format!("Name '{}' released", name))
+    }
+    
+    /// Re-index player names and reservations stored before names were normalized (admin operation, only on leaderboard chain)
+    async fn rebuild_name_handles(&self) -> async_graphql::Result<String> {
+        self.require_leaderboard_chain()?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::RebuildNameHandles);
+        Ok("Name handles rebuilt".to_string())
+    }
+    
+    /// Show or hide a player's verification badge (admin operation, only on leaderboard chain)
+    async fn set_verified(&self, chain_id: ChainId, verified: bool) -> async_graphql::Result<String> {
+        self.require_leaderboard_chain()?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::SetVerified { player_chain: chain_id, verified });
+        Ok(if verified { "Player verified" } else { "Player verification removed" }.to_string())
+    }
+    
+    /// Define a new obstacle layout (admin operation, only on leaderboard chain)
+    async fn define_level(&self, name: String, width: i32, height: i32, obstacles: Vec<Cell>) -> async_graphql::Result<String> {
+        self.require_leaderboard_chain()?;
+        if let Err(error) = Board::new(width, height, obstacles.clone()).validate() {
+            return Err(async_graphql::Error::new(format!("Invalid level layout: {:?}", error)));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::DefineLevel {
+            name: name.clone(),
+            width,
+            height,
+            obstacles,
+        });
+        Ok(format!("Level '{}' defined successfully", name))
+    }
+    
+    /// Publish a community map to the leaderboard chain
+    async fn publish_map(&self, name: String, width: i32, height: i32, obstacles: Vec<Cell>) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        let layout = Board::new(width, height, obstacles);
+        if let Err(error) = layout.validate() {
+            return Err(async_graphql::Error::new(format!("Invalid map layout: {:?}", error)));
+        }
+        
+        let hash = snake_game::map_hash(&layout);
+        self.runtime.schedule_operation(&snake_game::Operation::PublishMap { name: name.clone(), layout });
+        Ok(format!("Map '{}' published with hash {}", name, hash))
+    }
+    
+    /// Schedule a battle royale starting at the given time in microseconds (admin operation, only on leaderboard chain)
+    async fn create_battle_royale(&self, name: String, starts_at: u64, round_duration_secs: u64, elimination_percent: u8) -> async_graphql::Result<String> {
+        self.require_leaderboard_chain()?;
+        self.require_feature(snake_game::FEATURE_BATTLE_ROYALE, "Battle royales are").await?;
+        if !(1..=99).contains(&elimination_percent) {
+            return Err(async_graphql::Error::new("Elimination percent must be between 1 and 99"));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::CreateBattleRoyale {
+            name: name.clone(),
+            starts_at,
+            round_duration_secs,
+            elimination_percent,
+        });
+        Ok(format!("Battle royale '{}' scheduled", name))
+    }
+    
+    /// Register this chain for a battle royale before it starts
+    async fn join_battle_royale(&self, event_id: u64) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::JoinBattleRoyale { event_id });
+        Ok(format!("Joined battle royale {}", event_id))
+    }
+    
+    /// Start the next round of a battle royale once the current one is over (only on leaderboard chain)
+    async fn advance_battle_royale(&self, event_id: u64) -> async_graphql::Result<String> {
+        self.require_leaderboard_chain()?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::AdvanceBattleRoyale { event_id });
+        Ok(format!("Battle royale {} advanced", event_id))
+    }
+    
+    /// Open a tournament on a level for a window, with a number of attempts per entrant; with a
+    /// bracket size, the top qualifiers then play knockout rounds (admin, leaderboard chain)
+    async fn create_tournament(
+        &self,
+        name: String,
+        level_id: Option<u32>,
+        starts_at: Option<u64>,
+        duration_secs: u64,
+        max_attempts: u32,
+        max_entrants: Option<u32>,
+        prize_coins: Option<u64>,
+        bracket_size: Option<u32>,
+        bracket_round_secs: Option<u64>,
+    ) -> async_graphql::Result<String> {
+        self.require_leaderboard_chain()?;
+        let level_id = level_id.unwrap_or(LevelLayout::OPEN_LEVEL_ID);
+        if !self.level_exists(level_id).await {
+            return Err(async_graphql::Error::new(format!("Unknown level {}", level_id)));
+        }
+        if max_attempts == 0 {
+            return Err(async_graphql::Error::new("A tournament needs at least one attempt per entrant"));
+        }
+        let bracket_size = bracket_size.unwrap_or(0);
+        if bracket_size != 0 && (bracket_size < 2 || !bracket_size.is_power_of_two()) {
+            return Err(async_graphql::Error::new("A knockout bracket needs a power of two of at least 2 players"));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::CreateTournament {
+            name: name.clone(),
+            level_id,
+            starts_at: starts_at.unwrap_or(0),
+            duration_secs,
+            max_attempts,
+            max_entrants: max_entrants.unwrap_or(0),
+            prize_coins: prize_coins.unwrap_or(0),
+            bracket_size,
+            // A day per knockout round unless told otherwise
+            bracket_round_secs: bracket_round_secs.unwrap_or(86_400),
+        });
+        Ok(format!("Tournament '{}' opened", name))
+    }
+    
+    /// Rank a tournament whose window has closed, if no message has done it yet (leaderboard chain)
+    async fn finalize_tournament(&self, tournament_id: u64) -> async_graphql::Result<String> {
+        self.require_leaderboard_chain()?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::FinalizeTournament { tournament_id });
+        Ok(format!("Tournament {} finalized", tournament_id))
+    }
+    
+    /// Pay tokens to unlock the premium track of this season's pass
+    async fn buy_season_premium(&self) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        if self.season_pass.premium {
+            return Err(async_graphql::Error::new("The premium track is already unlocked this season"));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::BuySeasonPremium);
+        let price = self.runtime.application_parameters().season_pass.premium_price;
+        Ok(format!("Premium season pass unlocked for {}", price))
+    }
+    
+    /// Offer a skin or name token on the market; `price` is in tokens
+    async fn list_item(&self, kind: ItemKind, item: String, price: String) -> async_graphql::Result<String> {
+        let price = parse_amount(&price)?;
+        self.require_leaderboard_configured()?;
+        if price == Amount::ZERO {
+            return Err(async_graphql::Error::new("Listings need a price above zero"));
+        }
+        if kind == ItemKind::Skin && !self.state.my_skins.contains(&item).await.unwrap_or(false) {
+            return Err(async_graphql::Error::new(format!("This chain does not own the skin '{}'", item)));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::ListItem { kind, item: item.clone(), price });
+        Ok(format!("Listed '{}' for {}", item, price))
+    }
+    
+    /// Take one of this chain's listings off the market and get the item back
+    async fn cancel_listing(&self, listing_id: u64) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::CancelListing { listing_id });
+        Ok(format!("Listing {} cancelled", listing_id))
+    }
+    
+    /// Pay `price` tokens for a listing; the payment is refunded if the item is gone or costs more
+    async fn buy_listing(&self, listing_id: u64, price: String) -> async_graphql::Result<String> {
+        let price = parse_amount(&price)?;
+        self.require_leaderboard_configured()?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::BuyListing { listing_id, price });
+        Ok(format!("Bought listing {} for {}", listing_id, price))
+    }
+    
+    /// Claim the reward of an unlocked season pass tier
+    async fn claim_season_reward(&self, season: u32, track: PassTrack, tier: u32) -> async_graphql::Result<String> {
+        let pass = &self.season_pass;
+        if season != pass.season {
+            return Err(async_graphql::Error::new(format!("Season {} is over; the pass is on season {}", season, pass.season)));
+        }
+        let tiers = self.runtime.application_parameters().season_pass.tiers;
+        let pass_tier = tiers.get(tier as usize)
+            .ok_or_else(|| async_graphql::Error::new(format!("Unknown season pass tier {}", tier)))?;
+        if pass.xp < pass_tier.xp {
+            return Err(async_graphql::Error::new(format!("Tier {} needs {} season XP, only {} earned", tier, pass_tier.xp, pass.xp)));
+        }
+        let claimed = match track {
+            PassTrack::Free => &pass.claimed_free,
+            PassTrack::Premium => {
+                if !pass.premium {
+                    return Err(async_graphql::Error::new("The premium track is locked. Please buy it first"));
+                }
+                &pass.claimed_premium
+            }
+        };
+        if claimed.contains(&tier) {
+            return Err(async_graphql::Error::new(format!("{:?} reward of tier {} was already claimed", track, tier)));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::ClaimSeasonReward { season, track, tier });
+        Ok(format!("Claimed {:?} reward of tier {}", track, tier))
+    }
+    
+    /// Register a 2v2 team made of this chain and a teammate
+    async fn register_team(&self, name: String, teammate_chain_id: ChainId) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        if teammate_chain_id == self.runtime.chain_id() {
+            return Err(async_graphql::Error::new("A team needs two different player chains"));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::RegisterTeam { name: name.clone(), teammate: teammate_chain_id });
+        Ok(format!("Team '{}' registered; waiting for the teammate to accept", name))
+    }
+    
+    /// Join a team another player registered with this chain as their teammate
+    async fn accept_team(&self, team_id: u64) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::AcceptTeam { team_id });
+        Ok(format!("Accepted the invitation to team {}", team_id))
+    }
+    
+    /// Propose a match between two registered teams (must be a member of either); the other team has to accept it
+    async fn create_team_match(&self, team_a: u64, team_b: u64) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::CreateTeamMatch { team_a, team_b });
+        Ok(format!("Match between teams {} and {} requested", team_a, team_b))
+    }
+    
+    /// Accept a match another team proposed against this chain's team
+    async fn accept_team_match(&self, match_id: u64) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::AcceptTeamMatch { match_id });
+        Ok(format!("Accepted team match {}", match_id))
+    }
+    
+    /// Move coins from this chain's balance into a team's treasury
+    async fn contribute_to_team(&self, team_id: u64, coins: u64) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        if coins == 0 {
+            return Err(async_graphql::Error::new("A contribution must be at least one coin"));
+        }
+        self.require_coins(coins)?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::ContributeToTeam { team_id, coins });
+        Ok(format!("Contributed {} coins to team {}", coins, team_id))
+    }
+    
+    /// Spend team treasury coins on a team-wide perk (team leader only)
+    async fn buy_team_perk(&self, team_id: u64, perk: TeamPerk) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::BuyTeamPerk { team_id, perk });
+        Ok(format!("Requested {:?} for team {} ({} coins)", perk, team_id, perk.cost()))
+    }
+    
+    /// Pit two teams against each other for the configured window, from `starts_at` or now (admin only)
+    async fn schedule_team_war(&self, team_a: u64, team_b: u64, starts_at: Option<u64>) -> async_graphql::Result<String> {
+        self.require_leaderboard_chain()?;
+        if team_a == team_b {
+            return Err(async_graphql::Error::new("A team war needs two different teams"));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::ScheduleTeamWar { team_a, team_b, starts_at: starts_at.unwrap_or(0) });
+        Ok(format!("War between teams {} and {} scheduled", team_a, team_b))
+    }
+    
+    /// Challenge another player to a head-to-head match, staking coins
+    async fn create_challenge(&self, opponent_chain_id: ChainId, wager: u64) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        if opponent_chain_id == self.runtime.chain_id() {
+            return Err(async_graphql::Error::new("Players cannot challenge themselves"));
+        }
+        self.require_coins(wager)?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::CreateChallenge { opponent: opponent_chain_id, wager });
+        Ok(format!("Challenged {} for {} coins", opponent_chain_id, wager))
+    }
+    
+    /// Accept a challenge, staking the same wager
+    async fn accept_challenge(&self, challenge_id: u64) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        let challenge = self.state.my_challenges.get(&challenge_id).await.ok().flatten()
+            .ok_or_else(|| async_graphql::Error::new(format!("Unknown challenge {}", challenge_id)))?;
+        if challenge.opponent.chain_id != self.runtime.chain_id() || challenge.state != ChallengeState::AwaitingOpponent {
+            return Err(async_graphql::Error::new("This challenge cannot be accepted by this chain"));
+        }
+        if challenge.is_expired(self.runtime.system_time().micros()) {
+            return Err(async_graphql::Error::new("This challenge has expired"));
+        }
+        self.require_coins(challenge.wager)?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::AcceptChallenge { challenge_id });
+        Ok(format!("Challenge {} accepted", challenge_id))
+    }
+    
+    /// Lock in a hidden result for a challenge (see `challengeCommitment` for the hash format)
+    async fn commit_challenge_result(&self, challenge_id: u64, commitment: String) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::CommitChallengeResult { challenge_id, commitment });
+        Ok(format!("Result committed for challenge {}", challenge_id))
+    }
+    
+    /// Reveal the finished session and salt behind a previous commitment
+    async fn reveal_challenge_result(&self, challenge_id: u64, session_id: String, salt: String) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        let session = self.require_finished_session(&session_id).await?;
+        let accepted_at = self.state.my_challenges.get(&challenge_id).await.ok().flatten()
+            .and_then(|challenge| challenge.accepted_at);
+        if accepted_at.is_some_and(|accepted_at| session.start_time < accepted_at) {
+            return Err(async_graphql::Error::new(format!("Session {} was started before challenge {} was accepted", session_id, challenge_id)));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::RevealChallengeResult {
+            challenge_id,
+            session_id: session_id.clone(),
+            salt,
+        });
+        Ok(format!("Revealed session {} for challenge {}", session_id, challenge_id))
+    }
+    
+    /// Queue for a head-to-head match against a player of similar rating, or re-check a ticket
+    async fn join_matchmaking(&self) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        let queued = self.state.my_matchmaking.get().as_ref()
+            .is_some_and(|status| status.state == MatchmakingState::Queued);
+        
+        self.runtime.schedule_operation(&snake_game::Operation::JoinMatchmaking);
+        if queued {
+            Ok("Already queued, checking the ticket with the leaderboard chain".to_string())
+        } else {
+            Ok("Joined the matchmaking queue".to_string())
+        }
+    }
+    
+    /// Leave the matchmaking queue before a match is found
+    async fn leave_matchmaking(&self) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        let queued = self.state.my_matchmaking.get().as_ref()
+            .is_some_and(|status| status.state == MatchmakingState::Queued);
+        if !queued {
+            return Err(async_graphql::Error::new("This chain is not in the matchmaking queue"));
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::LeaveMatchmaking);
+        Ok("Left the matchmaking queue".to_string())
+    }
+    
+    /// Settle a challenge whose current stage has passed its deadline
+    async fn claim_challenge_timeout(&self, challenge_id: u64) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        // Our copy may lag behind the leaderboard chain, so only refuse claims it shows are early
+        let now = self.runtime.system_time().micros();
+        if let Some(challenge) = self.state.my_challenges.get(&challenge_id).await.ok().flatten() {
+            if !challenge.is_expired(now) {
+                return Err(async_graphql::Error::new(format!("Challenge {} has not timed out", challenge_id)));
+            }
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::ClaimChallengeTimeout { challenge_id });
+        Ok(format!("Timeout claimed for challenge {}", challenge_id))
+    }
+    
+    /// Publish the move log of a finished game as a data blob
+    async fn publish_replay(&self, session_id: String, move_log: Vec<u8>) -> async_graphql::Result<String> {
+        let session = self.require_finished_session(&session_id).await?;
+        let anchored_hash = session.move_log_hash.as_ref()
+            .ok_or_else(|| async_graphql::Error::new("No move log hash was anchored for this session, so its replay cannot be published"))?;
+        if snake_game::move_log_hash(&move_log) != *anchored_hash {
+            return Err(async_graphql::Error::new("Replay does not match the move log hash anchored at EndGame"));
+        }
+        if session.is_record {
+            self.require_leaderboard_configured()?;
+        }
+        
+        self.runtime.schedule_operation(&snake_game::Operation::PublishReplay {
+            session_id: session_id.clone(),
+            move_log,
+        });
+        Ok(format!("Replay of session {} published", session_id))
+    }
+    
+    /// Fetch the published replay of another player's record so its ghost can be raced
+    async fn request_ghost(&self, session_id: String) -> async_graphql::Result<String> {
+        self.require_leaderboard_configured()?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::RequestGhost { session_id: session_id.clone() });
+        Ok(format!("Requested the ghost of session {}", session_id))
+    }
+    
+    /// Replay a published move log against the record it was anchored to, defaulting to
+    /// the replay the player published (only on leaderboard chain)
+    async fn verify_replay(&self, session_id: String, move_log_blob_hash: Option<String>) -> async_graphql::Result<String> {
+        let move_log = match move_log_blob_hash {
+            Some(hash) => match hash.parse() {
+                Ok(hash) => Some(DataBlobHash(hash)),
+                Err(_) => return Err(async_graphql::Error::new(format!("Invalid blob hash format: {}", hash))),
+            },
+            None => None,
+        };
+        self.require_leaderboard_chain()?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::VerifyReplay {
+            session_id: session_id.clone(),
+            move_log,
+        });
+        Ok(format!("Replay verification scheduled for session {}", session_id))
+    }
+    
+    /// Pay all prize pools plus a treasury contribution to the podium (admin operation, only on leaderboard chain)
+    async fn distribute_prizes(&self, treasury_amount: String) -> async_graphql::Result<String> {
+        let treasury_amount = parse_amount(&treasury_amount)?;
+        self.require_leaderboard_chain()?;
+        
+        self.runtime.schedule_operation(&snake_game::Operation::DistributePrizes { treasury_amount });
+        Ok("Prize distribution scheduled".to_string())
+    }
+}
+
+#[ComplexObject]
+impl SnakeGameState {}
+
+#[ComplexObject]
+impl PlayerStats {
+    /// Get average candies per game as a formatted string
+    async fn average_candies_formatted(&self) -> String {
+        format!("{:.1}", self.average_candies())
+    }
+    
+    /// Get current streak description
+    async fn streak_description(&self) -> String {
+        if self.current_streak == 0 {
+            "No current streak".to_string()
+        } else {
+            format!("{} game win streak", self.current_streak)
+        }
+    }
+    
+    /// Get daily play streak description
+    async fn daily_streak_description(&self) -> String {
+        match self.daily_streak {
+            0 => "No daily streak".to_string(),
+            1 => "Played today".to_string(),
+            days => format!("{} days in a row", days),
+        }
+    }
+    
+    /// Get the standard deviation of the recent scores; lower is more consistent
+    async fn consistency(&self) -> f64 {
+        self.recent_form().std_deviation
+    }
+    
+    /// Get the median of the recent scores
+    async fn median_score(&self) -> f64 {
+        self.recent_median()
+    }
+    
+    /// Get how the recent scores are moving, in candies per game
+    async fn improvement_trend(&self) -> f64 {
+        self.recent_form().trend
     }
 }
\ No newline at end of file
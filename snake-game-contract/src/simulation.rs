@@ -0,0 +1,241 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+/*! Deterministic simulator for the order in which results reach the leaderboard chain
+
+Linera delivers the messages one chain sends to another in order, but messages from different
+player chains interleave arbitrarily, and a block that is retried can deliver a message again.
+The simulator feeds generated interleavings of player outboxes, optionally with redeliveries,
+through the leaderboard chain's handling of the messages that change player statistics, and
+reports the first interleaving whose leaderboard differs from in-order delivery.
+*/
+
+// Driven from tests and debugging sessions rather than from the contract itself
+#![allow(dead_code)]
+
+use std::collections::BTreeMap;
+
+use linera_sdk::linera_base_types::ChainId;
+use snake_game::{game_finished_admission, ApplicationParameters, GameMessage, LeaderboardEntry, ResultAdmission};
+
+use crate::state::PlayerStats;
+
+/// Time between two simulated blocks on the leaderboard chain
+const BLOCK_MICROS: u64 = 1_000_000;
+
+/// Small deterministic generator (xorshift64*), so that a failing seed can be replayed
+pub struct Interleaver {
+    state: u64,
+}
+
+impl Interleaver {
+    pub fn new(seed: u64) -> Self {
+        // xorshift gets stuck on zero
+        Interleaver { state: seed.max(1) }
+    }
+    
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+    
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+    
+    /// One delivery order for the outboxes of several player chains. Each outbox stays in order;
+    /// `duplicate_percent` of the messages are delivered a second time before the sender's next one.
+    pub fn interleave(&mut self, outboxes: &[Vec<GameMessage>], duplicate_percent: usize) -> Vec<GameMessage> {
+        let mut positions = vec![0; outboxes.len()];
+        let mut deliveries = Vec::new();
+        loop {
+            let pending: Vec<usize> = (0..outboxes.len())
+                .filter(|&sender| positions[sender] < outboxes[sender].len())
+                .collect();
+            if pending.is_empty() {
+                return deliveries;
+            }
+            
+            let sender = pending[self.below(pending.len())];
+            let message = outboxes[sender][positions[sender]].clone();
+            // A redelivered message is not consumed, so it comes out of the outbox again
+            if self.below(100) >= duplicate_percent {
+                positions[sender] += 1;
+            }
+            deliveries.push(message);
+        }
+    }
+}
+
+/// The part of the leaderboard chain's state that player results change
+pub struct SimulatedLeaderboard {
+    parameters: ApplicationParameters,
+    stats: BTreeMap<ChainId, PlayerStats>,
+    clock: u64,
+}
+
+impl SimulatedLeaderboard {
+    pub fn new(parameters: ApplicationParameters) -> Self {
+        SimulatedLeaderboard {
+            parameters,
+            stats: BTreeMap::new(),
+            clock: 0,
+        }
+    }
+    
+    /// Handle one message in its own block, the way `handle_message` updates player statistics
+    pub fn deliver(&mut self, message: &GameMessage) {
+        self.clock += BLOCK_MICROS;
+        let (player_chain, candies_collected, best_combo, ticks) = match message {
+            GameMessage::GameFinished { player_chain, candies_collected, is_new_record, best_combo, match_id, ticks, started_at, .. } => {
+                let elapsed_micros = self.clock.saturating_sub(*started_at);
+                let grace_ticks = self.parameters.candy_rate_grace_ticks;
+                if game_finished_admission(*candies_collected, *ticks, elapsed_micros, grace_ticks, *is_new_record, *match_id) != ResultAdmission::PlayerStats {
+                    return;
+                }
+                (*player_chain, *candies_collected, *best_combo, *ticks)
+            }
+            GameMessage::UpdateLeaderboard { player_chain, candies_collected, .. } => (*player_chain, *candies_collected, 0, 0),
+            _ => return,
+        };
+
+        let stats = self.stats.entry(player_chain).or_insert_with(|| PlayerStats::new(player_chain));
+        stats.record_reported_game(candies_collected, best_combo, ticks, self.clock, &self.parameters.composite_weights);
+    }
+    
+    /// The global leaderboard as a rebuild would order it
+    pub fn leaderboard(&self) -> Vec<LeaderboardEntry> {
+        let mut entries: Vec<LeaderboardEntry> = self.stats.values()
+            .map(|stats| stats.leaderboard_entry(None, 1, false))
+            .collect();
+        entries.sort_by(|a, b| self.parameters.compare_entries(a, b));
+        entries
+    }
+}
+
+/// What players see of a leaderboard: who is where, with which totals
+pub type Standing = (ChainId, u32, u32, u64);
+
+fn standings(entries: &[LeaderboardEntry]) -> Vec<Standing> {
+    entries.iter()
+        .map(|entry| (entry.chain_id, entry.highest_score, entry.games_played, entry.total_candies))
+        .collect()
+}
+
+/// An interleaving whose leaderboard differs from in-order delivery
+#[derive(Debug)]
+pub struct Divergence {
+    pub run: usize,
+    pub deliveries: Vec<GameMessage>,
+    pub expected: Vec<Standing>,
+    pub actual: Vec<Standing>,
+}
+
+/// Deliver the outboxes `runs` times in different orders and compare every resulting
+/// leaderboard with the one from delivering each outbox in turn, exactly once
+pub fn check_convergence(
+    parameters: &ApplicationParameters,
+    outboxes: &[Vec<GameMessage>],
+    runs: usize,
+    seed: u64,
+    duplicate_percent: usize,
+) -> Result<Vec<Standing>, Box<Divergence>> {
+    let mut reference = SimulatedLeaderboard::new(parameters.clone());
+    for message in outboxes.iter().flatten() {
+        reference.deliver(message);
+    }
+    let expected = standings(&reference.leaderboard());
+    
+    let mut interleaver = Interleaver::new(seed);
+    for run in 0..runs {
+        let deliveries = interleaver.interleave(outboxes, duplicate_percent);
+        let mut leaderboard = SimulatedLeaderboard::new(parameters.clone());
+        for message in &deliveries {
+            leaderboard.deliver(message);
+        }
+        
+        let actual = standings(&leaderboard.leaderboard());
+        if actual != expected {
+            return Err(Box::new(Divergence { run, deliveries, expected, actual }));
+        }
+    }
+    Ok(expected)
+}
+
+#[cfg(test)]
+mod tests {
+    use snake_game::{RankingMode, TieBreaker};
+    
+    use super::*;
+    
+    fn chain(index: u64) -> ChainId {
+        format!("{:064x}", index).parse().expect("valid chain ID")
+    }
+    
+    fn game_finished(player: u64, candies_collected: u32) -> GameMessage {
+        GameMessage::GameFinished {
+            session_id: format!("session_{}_{}", player, candies_collected),
+            player_chain: chain(player),
+            candies_collected,
+            is_new_record: true,
+            move_log_hash: None,
+            seed: 0,
+            level_id: 0,
+            map_hash: None,
+            points: candies_collected as u64,
+            best_combo: 1,
+            match_id: None,
+            ticks: candies_collected as u64,
+            started_at: 0,
+        }
+    }
+    
+    /// Records sent by five players, three of whom tie on their best score
+    fn outboxes() -> Vec<Vec<GameMessage>> {
+        let records: [&[u32]; 5] = [&[3, 5, 8], &[2, 6, 8], &[4], &[3, 8], &[1, 9]];
+        records
+            .iter()
+            .enumerate()
+            .map(|(player, scores)| scores.iter().map(|&score| game_finished(player as u64, score)).collect())
+            .collect()
+    }
+    
+    #[test]
+    fn reordered_results_converge() {
+        let standings = check_convergence(&ApplicationParameters::default(), &outboxes(), 500, 7, 0)
+            .unwrap_or_else(|divergence| panic!("{:#?}", divergence));
+        assert_eq!(standings[0], (chain(4), 9, 2, 10));
+    }
+    
+    #[test]
+    fn redelivered_results_are_counted_twice() {
+        // Nothing deduplicates messages yet, so a retried delivery adds the game again
+        let divergence = check_convergence(&ApplicationParameters::default(), &outboxes(), 500, 7, 20)
+            .expect_err("redeliveries should change the totals");
+        let games: u32 = divergence.actual.iter().map(|standing| standing.2).sum();
+        assert!(games > 11);
+    }
+    
+    #[test]
+    fn earliest_record_tie_breaker_depends_on_delivery_order() {
+        // Block time decides which of two equal records came first
+        let parameters = ApplicationParameters {
+            tie_breakers: vec![TieBreaker::EarliestRecord],
+            ..ApplicationParameters::default()
+        };
+        assert!(check_convergence(&parameters, &outboxes(), 500, 7, 0).is_err());
+    }
+    
+    #[test]
+    fn composite_ranking_converges() {
+        // Recent form is computed from scores in the order they arrive from each player,
+        // which Linera preserves, so reordering across players does not matter
+        let parameters = ApplicationParameters {
+            ranking_mode: RankingMode::Composite,
+            ..ApplicationParameters::default()
+        };
+        assert!(check_convergence(&parameters, &outboxes(), 500, 7, 0).is_ok());
+    }
+}
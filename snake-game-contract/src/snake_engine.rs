@@ -0,0 +1,464 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+/*! Deterministic snake simulation shared by the contract and the frontend
+
+The engine has no chain dependencies beyond plain data derives, so the same
+code validates games on-chain and drives the client once compiled to
+WebAssembly. Given the same layout, seed and sequence of directions, every
+build produces the same board, candy schedule and score.
+*/
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+/// Board size in cells, matching the client
+pub const BOARD_WIDTH: i32 = 30;
+pub const BOARD_HEIGHT: i32 = 30;
+
+/// Largest width or height a custom board may have
+pub const MAX_BOARD_DIMENSION: i32 = 200;
+
+/// Snake length when a game starts
+pub const INITIAL_LENGTH: usize = 3;
+
+/// Candies eaten at most this many ticks apart extend the current combo
+pub const COMBO_WINDOW_TICKS: u64 = 10;
+
+/// Highest points multiplier a combo can reach
+pub const MAX_COMBO_MULTIPLIER: u32 = 5;
+
+/// Game ticks played per second of game time
+pub const TICKS_PER_SECOND: u64 = 10;
+
+/// A cell on the board, with (0, 0) in the top-left corner
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, async_graphql::SimpleObject, async_graphql::InputObject)]
+#[graphql(input_name = "CellInput")]
+pub struct Cell {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Cell {
+    pub fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::Enum)]
+pub enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [Direction::Up, Direction::Right, Direction::Down, Direction::Left];
+
+    /// Decode one byte of a move log (0 = up, 1 = right, 2 = down, 3 = left)
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Direction::Up),
+            1 => Some(Direction::Right),
+            2 => Some(Direction::Down),
+            3 => Some(Direction::Left),
+            _ => None,
+        }
+    }
+
+    /// Encode this direction as one byte of a move log
+    pub fn to_byte(self) -> u8 {
+        match self {
+            Direction::Up => 0,
+            Direction::Right => 1,
+            Direction::Down => 2,
+            Direction::Left => 3,
+        }
+    }
+
+    pub fn is_opposite(self, other: Direction) -> bool {
+        matches!(
+            (self, other),
+            (Direction::Up, Direction::Down)
+                | (Direction::Down, Direction::Up)
+                | (Direction::Left, Direction::Right)
+                | (Direction::Right, Direction::Left)
+        )
+    }
+
+    /// The neighbour of `cell` one step in this direction
+    pub fn step(self, cell: Cell) -> Cell {
+        match self {
+            Direction::Up => Cell::new(cell.x, cell.y - 1),
+            Direction::Right => Cell::new(cell.x + 1, cell.y),
+            Direction::Down => Cell::new(cell.x, cell.y + 1),
+            Direction::Left => Cell::new(cell.x - 1, cell.y),
+        }
+    }
+}
+
+/// Why a layout cannot be played
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LayoutError {
+    TooSmall { width: i32, height: i32 },
+    TooLarge { width: i32, height: i32 },
+    ObstacleOutOfBounds(Cell),
+    ObstacleOnStart(Cell),
+}
+
+/// The playing field and the walls placed on it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct Board {
+    pub width: i32,
+    pub height: i32,
+    pub obstacles: Vec<Cell>,
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self {
+            width: BOARD_WIDTH,
+            height: BOARD_HEIGHT,
+            obstacles: Vec::new(),
+        }
+    }
+}
+
+impl Board {
+    pub fn new(width: i32, height: i32, obstacles: Vec<Cell>) -> Self {
+        Self { width, height, obstacles }
+    }
+
+    /// Check that the snake can spawn and that every obstacle is on the board
+    pub fn validate(&self) -> Result<(), LayoutError> {
+        if self.width < 2 * INITIAL_LENGTH as i32 || self.height < 2 * INITIAL_LENGTH as i32 {
+            return Err(LayoutError::TooSmall { width: self.width, height: self.height });
+        }
+        if self.width > MAX_BOARD_DIMENSION || self.height > MAX_BOARD_DIMENSION {
+            return Err(LayoutError::TooLarge { width: self.width, height: self.height });
+        }
+        let start = Snake::new(self);
+        for &obstacle in &self.obstacles {
+            if !self.contains(obstacle) {
+                return Err(LayoutError::ObstacleOutOfBounds(obstacle));
+            }
+            // The first move goes right, so the cell ahead of the head must be open too
+            if start.occupies(obstacle) || obstacle == Direction::Right.step(start.head()) {
+                return Err(LayoutError::ObstacleOnStart(obstacle));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, cell: Cell) -> bool {
+        cell.x >= 0 && cell.y >= 0 && cell.x < self.width && cell.y < self.height
+    }
+
+    /// Whether moving into `cell` ends the game
+    pub fn is_blocked(&self, cell: Cell) -> bool {
+        !self.contains(cell) || self.obstacles.contains(&cell)
+    }
+
+    pub fn cell_count(&self) -> u64 {
+        self.width as u64 * self.height as u64
+    }
+
+    fn cell_at(&self, index: u64) -> Cell {
+        Cell::new((index % self.width as u64) as i32, (index / self.width as u64) as i32)
+    }
+}
+
+/// The snake's body, head first, and its current heading
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snake {
+    pub body: VecDeque<Cell>,
+    pub direction: Direction,
+}
+
+impl Snake {
+    /// A snake of `INITIAL_LENGTH` cells in the middle of the board, heading right
+    pub fn new(board: &Board) -> Self {
+        let start = Cell::new(board.width / 2, board.height / 2);
+        let body = (0..INITIAL_LENGTH as i32)
+            .map(|offset| Cell::new(start.x - offset, start.y))
+            .collect();
+        Self {
+            body,
+            direction: Direction::Right,
+        }
+    }
+
+    pub fn head(&self) -> Cell {
+        self.body[0]
+    }
+
+    pub fn occupies(&self, cell: Cell) -> bool {
+        self.body.contains(&cell)
+    }
+
+    /// Turn towards `direction`, unless that would reverse into the snake's own neck
+    pub fn turn(&mut self, direction: Direction) {
+        if !direction.is_opposite(self.direction) {
+            self.direction = direction;
+        }
+    }
+}
+
+/// Seeded xorshift generator deciding where each candy appears
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CandySchedule {
+    state: u64,
+}
+
+impl CandySchedule {
+    pub fn new(seed: u64) -> Self {
+        // Xorshift is stuck at zero, so replace a zero seed with a fixed constant
+        let state = if seed == 0 { 0x9E37_79B9_7F4A_7C15 } else { seed };
+        Self { state }
+    }
+
+    /// The next free cell for a candy, or `None` when the snake fills the board
+    pub fn next_candy(&mut self, board: &Board, snake: &Snake) -> Option<Cell> {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+
+        let cells = board.cell_count();
+        let first = self.state % cells;
+        (0..cells)
+            .map(|offset| board.cell_at((first + offset) % cells))
+            .find(|cell| !snake.occupies(*cell) && !board.is_blocked(*cell))
+    }
+}
+
+/// Chains of quickly eaten candies and the points they are worth
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct Combo {
+    pub current: u32,
+    pub best: u32,
+    pub last_candy_tick: Option<u64>,
+    pub points: u64,
+}
+
+impl Combo {
+    /// Count a candy eaten at `tick` and return the multiplier applied to it
+    pub fn register_candy(&mut self, tick: u64) -> u32 {
+        let chained = self.last_candy_tick
+            .map(|last| tick >= last && tick - last <= COMBO_WINDOW_TICKS)
+            .unwrap_or(false);
+        self.current = if chained { self.current + 1 } else { 1 };
+        self.best = self.best.max(self.current);
+        self.last_candy_tick = Some(tick);
+
+        let multiplier = self.current.min(MAX_COMBO_MULTIPLIER);
+        self.points += multiplier as u64;
+        multiplier
+    }
+}
+
+/// What happened during one tick
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TickOutcome {
+    Moved,
+    AteCandy,
+    Died,
+}
+
+/// A game in progress
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Engine {
+    pub board: Board,
+    pub snake: Snake,
+    pub candy: Option<Cell>,
+    pub candies_collected: u32,
+    pub combo: Combo,
+    pub ticks: u64,
+    pub alive: bool,
+    schedule: CandySchedule,
+}
+
+impl Engine {
+    pub fn new(seed: u64) -> Self {
+        Self::with_board(Board::default(), seed)
+    }
+
+    pub fn with_board(board: Board, seed: u64) -> Self {
+        let snake = Snake::new(&board);
+        let mut schedule = CandySchedule::new(seed);
+        let candy = schedule.next_candy(&board, &snake);
+        Self {
+            board,
+            snake,
+            candy,
+            candies_collected: 0,
+            combo: Combo::default(),
+            ticks: 0,
+            alive: true,
+            schedule,
+        }
+    }
+
+    /// Advance the game by one tick, steering towards `direction`
+    pub fn tick(&mut self, direction: Direction) -> TickOutcome {
+        if !self.alive {
+            return TickOutcome::Died;
+        }
+        self.ticks += 1;
+        self.snake.turn(direction);
+
+        let head = self.snake.direction.step(self.snake.head());
+        let grows = Some(head) == self.candy;
+        if !grows {
+            self.snake.body.pop_back();
+        }
+        if self.board.is_blocked(head) || self.snake.occupies(head) {
+            self.alive = false;
+            return TickOutcome::Died;
+        }
+        self.snake.body.push_front(head);
+
+        if grows {
+            self.candies_collected += 1;
+            self.combo.register_candy(self.ticks);
+            self.candy = self.schedule.next_candy(&self.board, &self.snake);
+            TickOutcome::AteCandy
+        } else {
+            TickOutcome::Moved
+        }
+    }
+}
+
+/// Why a move log could not be replayed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayError {
+    InvalidMove { tick: usize, byte: u8 },
+}
+
+/// Replay a move log on `board` from the session seed and return the candies it collects.
+///
+/// Each byte of the log is the direction for one tick. The game ends as soon
+/// as the snake hits a wall, an obstacle or itself; any moves after that are ignored.
+pub fn replay_candies(board: Board, seed: u64, move_log: &[u8]) -> Result<u32, ReplayError> {
+    replay(board, seed, move_log).map(|engine| engine.candies_collected)
+}
+
+/// Replay a move log tick by tick and return the engine as the game ended, for callers
+/// that need more than the candy count, such as ghost races
+pub fn replay(board: Board, seed: u64, move_log: &[u8]) -> Result<Engine, ReplayError> {
+    let mut engine = Engine::with_board(board, seed);
+    for (tick, &byte) in move_log.iter().enumerate() {
+        let direction = Direction::from_byte(byte).ok_or(ReplayError::InvalidMove { tick, byte })?;
+        if engine.tick(direction) == TickOutcome::Died {
+            break;
+        }
+    }
+    Ok(engine)
+}
+
+/// How a bot snake picks its next direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::Enum)]
+pub enum BotPolicy {
+    Greedy, // Heads for the candy, only avoiding cells that kill it on the next tick
+    Cautious, // Prefers cells with room around them, then the candy
+}
+
+/// A seeded bot steering an engine. Its choices depend only on the game state and its
+/// seed, so a bot game can be replayed from the two seeds like any player's
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bot {
+    policy: BotPolicy,
+    state: u64,
+}
+
+impl Bot {
+    pub fn new(policy: BotPolicy, seed: u64) -> Self {
+        // Same zero guard as the candy schedule
+        let state = if seed == 0 { 0x9E37_79B9_7F4A_7C15 } else { seed };
+        Self { policy, state }
+    }
+
+    fn next_random(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// The direction to play on the next tick; equally good moves are picked at random
+    pub fn choose(&mut self, engine: &Engine) -> Direction {
+        let head = engine.snake.head();
+        let free = |cell: Cell| !engine.board.is_blocked(cell) && !engine.snake.occupies(cell);
+
+        let mut best = Vec::new();
+        let mut best_rating = None;
+        for direction in Direction::ALL {
+            if direction.is_opposite(engine.snake.direction) {
+                continue;
+            }
+            let next = direction.step(head);
+            let room = match self.policy {
+                BotPolicy::Greedy => 0,
+                BotPolicy::Cautious => Direction::ALL.iter().filter(|around| free(around.step(next))).count(),
+            };
+            let distance = engine.candy
+                .map(|candy| (candy.x - next.x).abs() + (candy.y - next.y).abs())
+                .unwrap_or(0);
+            let rating = Some((free(next), room, -distance));
+            if rating > best_rating {
+                best_rating = rating;
+                best.clear();
+            }
+            if rating == best_rating {
+                best.push(direction);
+            }
+        }
+        best[(self.next_random() % best.len() as u64) as usize]
+    }
+}
+
+/// A finished bot game and the move log that reproduces it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BotRun {
+    pub candies_collected: u32,
+    pub points: u64,
+    pub ticks: u64,
+    pub move_log: Vec<u8>,
+}
+
+/// Let a bot play `board` with the candy schedule of `seed` until it dies or `max_ticks` pass.
+///
+/// The move log replays to the same candies with `replay_candies`, so a bot's score can
+/// be checked the same way as a player's.
+pub fn play_bot(board: Board, seed: u64, policy: BotPolicy, bot_seed: u64, max_ticks: u64) -> BotRun {
+    let mut engine = Engine::with_board(board, seed);
+    let mut bot = Bot::new(policy, bot_seed);
+    let mut move_log = Vec::new();
+    while engine.alive && engine.ticks < max_ticks {
+        let direction = bot.choose(&engine);
+        move_log.push(direction.to_byte());
+        engine.tick(direction);
+    }
+    BotRun {
+        candies_collected: engine.candies_collected,
+        points: engine.combo.points,
+        ticks: engine.ticks,
+        move_log,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bot_games_replay_to_the_same_score() {
+        for policy in [BotPolicy::Greedy, BotPolicy::Cautious] {
+            let run = play_bot(Board::default(), 42, policy, 7, 2_000);
+            assert_eq!(run, play_bot(Board::default(), 42, policy, 7, 2_000));
+            assert_eq!(replay_candies(Board::default(), 42, &run.move_log), Ok(run.candies_collected));
+            assert!(run.candies_collected > 0);
+        }
+    }
+}
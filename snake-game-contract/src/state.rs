@@ -1,14 +1,40 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use linera_sdk::views::{linera_views, MapView, RegisterView, RootView, ViewStorageContext, SetView};
-use linera_sdk::linera_base_types::ChainId;
+use std::{cmp::Reverse, collections::BTreeMap};
+
+use linera_sdk::views::{linera_views, CollectionView, LogView, MapView, QueueView, RegisterView, RootView, View, ViewStorageContext, SetView};
+use linera_sdk::linera_base_types::{Account, AccountOwner, Amount, ChainId, DataBlobHash, Ed25519PublicKey};
 use serde::{Deserialize, Serialize};
 use async_graphql::SimpleObject;
-use snake_game::{GameSession, LeaderboardEntry};
+use snake_game::{Achievement, Announcement, Avatar, Challenge, ChatMessage, CompositeWeights, CustomMap, DisputeMarker, Emote, GameMode, GameSession, GameState, Ghost, ImprovementEntry, ItemKind, LeaderboardEntry, LevelLayout, LevelScore, LiveSession, MatchmakingStatus, PassTrack, PenaltyRecord, PendingUpdate, PodiumChange, QuestDefinition, QuestKind, RankTier, RecentForm, ReplayVerdict, RisingStarEntry, ScoreBreakdown, SCORE_HISTORY_LEN, SeasonPassConfig, SeasonPassProgress, SessionSummary, ShadowEntry, SubmissionRateLimit, SurvivalEntry, Team, TeamMatch, TeamPerk, TeamWar, Tournament, TournamentEntry};
+use snake_game::snake_engine::{self, BotPolicy, Engine};
+
+/// Microseconds in one UTC day, used to bucket timestamps into days
+const MICROS_PER_DAY: u64 = 86_400_000_000;
+
+/// Microseconds in one week, the period compared by the improvement and newcomer boards
+const MICROS_PER_WEEK: u64 = 7 * MICROS_PER_DAY;
+
+/// Width of the start time buckets in the session time index
+pub const SESSION_BUCKET_MICROS: u64 = MICROS_PER_DAY;
+
+/// Number of latest scores kept for consistency and trend metrics
+pub const RECENT_SCORES_WINDOW: usize = 20;
+
+/// Number of personal bests kept in the record history, oldest dropped first
+pub const MAX_RECORD_HISTORY: usize = 50;
+
+/// One of a player's latest scores
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct RecentScore {
+    pub score: u32,
+    pub timestamp: u64,
+}
 
 /// Player statistics for tracking personal game history
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+#[graphql(complex)]
 pub struct PlayerStats {
     pub chain_id: ChainId,
     pub games_played: u32,
@@ -17,6 +43,19 @@ pub struct PlayerStats {
     pub current_streak: u32,
     pub best_streak: u32,
     pub last_game_timestamp: u64,
+    pub tier: RankTier,
+    pub daily_streak: u32, // Consecutive UTC days with at least one finished game
+    pub best_daily_streak: u32,
+    pub xp: u64,
+    pub level: u32,
+    pub record_timestamp: u64, // When highest_score was first reached
+    pub score_breakdown: ScoreBreakdown, // Composite score, refreshed whenever these stats change
+    pub replay_verdict: ReplayVerdict, // Result of replaying the move log behind highest_score
+    pub longest_survival_ticks: u64, // Longest Survival mode run, in game ticks
+    pub best_combo: u32, // Longest chain of quickly eaten candies
+    pub record_ticks: u64, // Game ticks the highest_score game took; 0 when unknown
+    pub recent_scores: Vec<RecentScore>, // Last RECENT_SCORES_WINDOW scores, oldest first
+    pub first_game_timestamp: u64,
 }
 
 impl PlayerStats {
@@ -30,18 +69,47 @@ impl PlayerStats {
             current_streak: 0,
             best_streak: 0,
             last_game_timestamp: 0,
+            tier: RankTier::Bronze,
+            daily_streak: 0,
+            best_daily_streak: 0,
+            xp: 0,
+            level: 1,
+            record_timestamp: 0,
+            score_breakdown: ScoreBreakdown::default(),
+            replay_verdict: ReplayVerdict::Unverified,
+            longest_survival_ticks: 0,
+            best_combo: 0,
+            record_ticks: 0,
+            recent_scores: Vec::new(),
+            first_game_timestamp: 0,
         }
     }
     
+    /// Start over after a leaderboard reset; XP, levels and the first game are kept
+    #[allow(dead_code)]
+    pub fn clear_for_reset(&mut self) {
+        self.highest_score = 0;
+        self.games_played = 0;
+        self.total_candies = 0;
+        self.current_streak = 0;
+        self.best_streak = 0;
+        self.daily_streak = 0;
+        self.best_daily_streak = 0;
+        self.tier = RankTier::Bronze;
+        self.longest_survival_ticks = 0;
+        self.best_combo = 0;
+        self.record_ticks = 0;
+        self.recent_scores.clear();
+    }
+    
     #[allow(dead_code)]
     pub fn add_game(&mut self, candies_collected: u32, timestamp: u64) -> bool {
-        self.games_played += 1;
-        self.total_candies += candies_collected as u64;
-        self.last_game_timestamp = timestamp;
+        self.add_unranked_game(candies_collected, timestamp);
         
         let is_record = candies_collected > self.highest_score;
         if is_record {
             self.highest_score = candies_collected;
+            self.record_timestamp = timestamp;
             self.current_streak += 1;
             if self.current_streak > self.best_streak {
                 self.best_streak = self.current_streak;
@@ -53,6 +121,170 @@ impl PlayerStats {
         is_record
     }
     
+    /// Count a game that cannot set a record, such as one played on a seed known in advance
+    #[allow(dead_code)]
+    pub fn add_unranked_game(&mut self, candies_collected: u32, timestamp: u64) {
+        self.update_daily_streak(timestamp);
+        if self.games_played == 0 {
+            self.first_game_timestamp = timestamp;
+        }
+        self.games_played += 1;
+        self.total_candies += candies_collected as u64;
+        self.last_game_timestamp = timestamp;
+        self.recent_scores.push(RecentScore { score: candies_collected, timestamp });
+        if self.recent_scores.len() > RECENT_SCORES_WINDOW {
+            self.recent_scores.remove(0);
+        }
+    }
+    
+    /// Apply a game reported to the leaderboard chain, returning whether it set a new record
+    #[allow(dead_code)]
+    pub fn record_reported_game(&mut self, candies_collected: u32, best_combo: u32, ticks: u64, timestamp: u64, weights: &CompositeWeights) -> bool {
+        let was_record = self.add_game(candies_collected, timestamp);
+        if was_record {
+            // A new record has not been replayed yet
+            self.replay_verdict = ReplayVerdict::Unverified;
+            self.record_ticks = ticks;
+        }
+        self.best_combo = self.best_combo.max(best_combo);
+        
+        // Only this player's composite score changes, so refresh it here rather than in the rebuild
+        self.score_breakdown = weights.breakdown(self.highest_score, self.average_candies(), self.recent_form());
+        was_record
+    }
+    
+    /// Remove the score of a penalized game, falling back to the best remaining recent score
+    /// when it was the standing record, and return the voided score; 0 if nothing was removed
+    #[allow(dead_code)]
+    pub fn void_record(&mut self, score: u32, weights: &CompositeWeights) -> u32 {
+        if score == 0 {
+            return 0;
+        }
+        let removed = match self.recent_scores.iter().rposition(|recent| recent.score == score) {
+            Some(position) => {
+                self.recent_scores.remove(position);
+                true
+            }
+            None => false,
+        };
+        if score != self.highest_score {
+            if !removed {
+                return 0;
+            }
+            self.score_breakdown = weights.breakdown(self.highest_score, self.average_candies(), self.recent_form());
+            return score;
+        }
+        
+        // Earliest of the best remaining scores, as add_game would have kept it
+        let fallback = self.recent_scores.iter()
+            .min_by_key(|recent| (Reverse(recent.score), recent.timestamp))
+            .map(|recent| (recent.score, recent.timestamp))
+            .unwrap_or((0, 0));
+        (self.highest_score, self.record_timestamp) = fallback;
+        self.record_ticks = 0;
+        self.replay_verdict = ReplayVerdict::Unverified;
+        self.current_streak = 0;
+        self.score_breakdown = weights.breakdown(self.highest_score, self.average_candies(), self.recent_form());
+        score
+    }
+    
+    /// Fold in the statistics of a chain this player moved away from. Totals add up, bests and the
+    /// record with its verdict come from whichever chain set them
+    #[allow(dead_code)]
+    pub fn merge(&mut self, other: PlayerStats, weights: &CompositeWeights) {
+        if other.highest_score > self.highest_score
+            || (other.highest_score == self.highest_score && other.record_timestamp < self.record_timestamp) {
+            self.highest_score = other.highest_score;
+            self.record_timestamp = other.record_timestamp;
+            self.record_ticks = other.record_ticks;
+            self.replay_verdict = other.replay_verdict;
+        }
+        if self.games_played == 0 || (other.games_played > 0 && other.first_game_timestamp < self.first_game_timestamp) {
+            self.first_game_timestamp = other.first_game_timestamp;
+        }
+        self.games_played += other.games_played;
+        self.total_candies += other.total_candies;
+        self.last_game_timestamp = self.last_game_timestamp.max(other.last_game_timestamp);
+        self.best_streak = self.best_streak.max(other.best_streak);
+        self.best_daily_streak = self.best_daily_streak.max(other.best_daily_streak);
+        self.xp += other.xp;
+        self.level = self.level.max(other.level);
+        self.longest_survival_ticks = self.longest_survival_ticks.max(other.longest_survival_ticks);
+        self.best_combo = self.best_combo.max(other.best_combo);
+        
+        self.recent_scores.extend(other.recent_scores);
+        self.recent_scores.sort_by_key(|recent| recent.timestamp);
+        let excess = self.recent_scores.len().saturating_sub(RECENT_SCORES_WINDOW);
+        self.recent_scores.drain(..excess);
+        self.score_breakdown = weights.breakdown(self.highest_score, self.average_candies(), self.recent_form());
+    }
+    
+    /// This player's row on the global leaderboard
+    #[allow(dead_code)]
+    pub fn leaderboard_entry(&self, player_name: Option<String>, level: u32, verified: bool) -> LeaderboardEntry {
+        LeaderboardEntry {
+            chain_id: self.chain_id,
+            highest_score: self.highest_score,
+            games_played: self.games_played,
+            total_candies: self.total_candies,
+            player_name,
+            tier: self.tier,
+            level,
+            record_timestamp: self.record_timestamp,
+            last_active: self.last_game_timestamp,
+            score_breakdown: self.score_breakdown.clone(),
+            replay_verdict: self.replay_verdict,
+            best_combo: self.best_combo,
+            record_ticks: self.record_ticks,
+            verified,
+            dispute: DisputeMarker::Clear,
+            reactions: Vec::new(),
+            country: None,
+            country_leader: false,
+            score_history: self.recent_scores.iter()
+                .skip(self.recent_scores.len().saturating_sub(SCORE_HISTORY_LEN))
+                .map(|recent| recent.score)
+                .collect(),
+        }
+    }
+    
+    /// Extend, keep or restart the daily-play streak for a game finished at `timestamp`
+    fn update_daily_streak(&mut self, timestamp: u64) {
+        let today = timestamp / MICROS_PER_DAY;
+        let last_day = self.last_game_timestamp / MICROS_PER_DAY;
+        
+        if self.games_played == 0 || self.daily_streak == 0 {
+            self.daily_streak = 1;
+        } else if today == last_day + 1 {
+            self.daily_streak += 1;
+        } else if today != last_day {
+            self.daily_streak = 1;
+        }
+        
+        if self.daily_streak > self.best_daily_streak {
+            self.best_daily_streak = self.daily_streak;
+        }
+    }
+    
+    /// Achievements these statistics qualify for, whether or not already unlocked
+    #[allow(dead_code)]
+    pub fn earned_achievements(&self, last_score: u32) -> Vec<Achievement> {
+        let mut earned = Vec::new();
+        if self.games_played > 0 {
+            earned.push(Achievement::FirstGame);
+        }
+        if self.total_candies >= 100 {
+            earned.push(Achievement::CandyHoarder);
+        }
+        if last_score >= 50 {
+            earned.push(Achievement::HalfCentury);
+        }
+        if self.daily_streak >= 7 {
+            earned.push(Achievement::WeeklyRegular);
+        }
+        earned
+    }
+    
     pub fn average_candies(&self) -> f64 {
         if self.games_played > 0 {
             (self.total_candies as f64) / (self.games_played as f64)
@@ -60,6 +292,798 @@ impl PlayerStats {
             0.0
         }
     }
+    
+    /// Spread and trend of the recent scores
+    pub fn recent_form(&self) -> RecentForm {
+        let scores: Vec<u32> = self.recent_scores.iter().map(|recent| recent.score).collect();
+        RecentForm::from_scores(&scores)
+    }
+    
+    /// Middle value of the recent scores
+    #[allow(dead_code)]
+    pub fn recent_median(&self) -> f64 {
+        let mut scores: Vec<u32> = self.recent_scores.iter().map(|recent| recent.score).collect();
+        scores.sort_unstable();
+        match scores.len() {
+            0 => 0.0,
+            len if len % 2 == 0 => (scores[len / 2 - 1] + scores[len / 2]) as f64 / 2.0,
+            len => scores[len / 2] as f64,
+        }
+    }
+    
+    /// Average recent score over the previous week and over the week up to `now`,
+    /// when the player has scores in both. On the leaderboard chain these are reported
+    /// records, so this is record-to-record improvement
+    #[allow(dead_code)]
+    pub fn weekly_averages(&self, now: u64) -> Option<(f64, f64)> {
+        let week_start = now.saturating_sub(MICROS_PER_WEEK);
+        let previous_week_start = week_start.saturating_sub(MICROS_PER_WEEK);
+        let average = |from: u64, to: u64| {
+            let scores: Vec<u32> = self.recent_scores.iter()
+                .filter(|recent| recent.timestamp > from && recent.timestamp <= to)
+                .map(|recent| recent.score)
+                .collect();
+            (!scores.is_empty()).then(|| scores.iter().sum::<u32>() as f64 / scores.len() as f64)
+        };
+        Some((average(previous_week_start, week_start)?, average(week_start, now)?))
+    }
+    
+    /// Whether the player's first game was within the week up to `now`
+    #[allow(dead_code)]
+    pub fn is_newcomer(&self, now: u64) -> bool {
+        self.games_played > 0 && now.saturating_sub(self.first_game_timestamp) <= MICROS_PER_WEEK
+    }
+}
+
+/// A link the old chain issued so another chain can take over its leaderboard history
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct AccountLink {
+    pub old_chain: ChainId,
+    pub new_chain: ChainId,
+    pub token: String,
+    pub expires_at: u64,
+}
+
+/// What is left of a chain whose history was merged into another one
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct AccountTombstone {
+    pub old_chain: ChainId,
+    pub merged_into: ChainId,
+    pub merged_at: u64,
+}
+
+/// Personal data of one owner of a chain shared by several owners. It is local to the chain: the
+/// leaderboard chain ranks the chain as one player, whose record, name and coins the owners share
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OwnerProfile {
+    pub stats: Option<PlayerStats>,
+    pub current_session: Option<String>,
+    pub player_name: Option<String>,
+    pub name_changed_at: Option<u64>,
+}
+
+/// A personal best set on this player's chain
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct PersonalBest {
+    pub score: u32,
+    pub timestamp: u64,
+    pub session_id: String,
+}
+
+/// A record score anchored to the hash of the move log that produced it
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct MoveLogAnchor {
+    pub player_chain: ChainId,
+    pub session_id: String,
+    pub candies_collected: u32,
+    pub move_log_hash: String,
+    pub seed: u64,
+    pub level_id: u32,
+    pub map_hash: Option<String>,
+    pub anchored_at: u64,
+    pub verdict: ReplayVerdict,
+    pub replayed_candies: Option<u32>, // Candies counted by the last replay of the published log
+    pub replay_blob: Option<DataBlobHash>, // Move log the player published for this session
+}
+
+/// A name a player used before renaming, kept for impersonation audits
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct NameChange {
+    pub previous_name: String,
+    pub changed_at: u64,
+}
+
+/// Messages counted against a player chain's rate limit in the current window
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SubmissionWindow {
+    pub started_at: u64,
+    pub messages: u32,
+}
+
+impl SubmissionWindow {
+    /// Count one message at `now`, starting a new window when the last one is over,
+    /// and return whether it is within `limit`
+    #[allow(dead_code)]
+    pub fn admit(&mut self, now: u64, limit: &SubmissionRateLimit) -> bool {
+        if now.saturating_sub(self.started_at) >= limit.window_secs * 1_000_000 {
+            *self = SubmissionWindow { started_at: now, messages: 0 };
+        }
+        self.messages += 1;
+        limit.max_messages == 0 || self.messages <= limit.max_messages
+    }
+}
+
+/// Candies the leaderboard chain has seen from a player's latest session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CandyTally {
+    pub session_id: String,
+    pub candies: u32,
+    pub last_tick: u64,
+}
+
+/// What a notification in the player's inbox is about
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum)]
+pub enum NotificationKind {
+    Overtaken, // Another player pushed this one down the global leaderboard
+    FollowedRecord, // A followed player set a new record
+    GiftReceived, // Another player sent this one coins
+}
+
+/// An entry in the player's notification inbox
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct Notification {
+    pub kind: NotificationKind,
+    pub message: String,
+    pub other_player: Option<ChainId>, // The player who caused it, if any
+    pub old_rank: Option<u32>, // 1-based
+    pub new_rank: Option<u32>, // 1-based
+    pub received_at: u64,
+}
+
+/// A player chain that gets the top of the leaderboard pushed when the podium changes
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct LeaderboardSubscription {
+    pub limit: u32,
+    pub last_pushed_at: Option<u64>,
+}
+
+/// A session whose reported pace is not humanly possible; its result is ignored
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct SuspectSession {
+    pub session_id: String,
+    pub player_chain: ChainId,
+    pub reason: String,
+    pub flagged_at: u64,
+}
+
+/// Where a flagged score stands in the moderator review queue
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum)]
+pub enum DisputeState {
+    Pending,
+    Dismissed,
+    Upheld,
+    Struck, // Upheld, with a strike recorded against the player
+}
+
+/// A score another participant flagged as suspicious
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct Dispute {
+    pub dispute_id: u64,
+    pub player_chain: ChainId,
+    pub session_id: String,
+    pub reporter: ChainId,
+    pub reason: String,
+    pub flagged_score: Option<u32>, // Candies claimed by the anchored session
+    pub state: DisputeState,
+    pub flagged_at: u64,
+    pub resolved_at: Option<u64>,
+}
+
+/// Where a reset proposal stands
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum)]
+pub enum ProposalState {
+    Open,
+    Executed,
+    Rejected, // Too many participants voted against it
+    Expired, // The window closed before the quorum was reached
+}
+
+/// A leaderboard reset waiting for the participants' approval
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct ResetProposal {
+    pub proposal_id: u64,
+    pub proposed_at: u64,
+    pub closes_at: u64,
+    pub eligible_voters: Vec<ChainId>, // Participants when the proposal opened
+    pub approvals: Vec<ChainId>,
+    pub rejections: Vec<ChainId>,
+    pub state: ProposalState,
+}
+
+impl ResetProposal {
+    /// The proposal's state at `now`, accounting for a window that closed while it was open
+    pub fn state_at(&self, now: u64) -> ProposalState {
+        if self.state == ProposalState::Open && now > self.closes_at {
+            ProposalState::Expired
+        } else {
+            self.state
+        }
+    }
+    
+    #[allow(dead_code)]
+    pub fn has_voted(&self, voter: &ChainId) -> bool {
+        self.approvals.contains(voter) || self.rejections.contains(voter)
+    }
+    
+    /// Whether enough participants approved
+    #[allow(dead_code)]
+    pub fn quorum_reached(&self, quorum_percent: u32) -> bool {
+        self.approvals.len() as u64 * 100 >= quorum_percent as u64 * self.eligible_voters.len() as u64
+    }
+    
+    /// Whether enough participants rejected that the quorum can no longer be reached
+    #[allow(dead_code)]
+    pub fn quorum_unreachable(&self, quorum_percent: u32) -> bool {
+        let remaining = self.eligible_voters.len() - self.rejections.len();
+        (remaining as u64) * 100 < quorum_percent as u64 * self.eligible_voters.len() as u64
+    }
+}
+
+/// A reset proposal this player may vote on
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct ResetBallot {
+    pub proposal_id: u64,
+    pub closes_at: u64,
+    pub vote: Option<bool>, // true to approve; None until voted
+}
+
+/// How serious a logged event is; debug output only goes to stderr
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, async_graphql::Enum)]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+/// An event kept in the on-chain log
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct LogEntry {
+    pub level: LogLevel,
+    pub code: String, // Area of the contract the event comes from, e.g. RESET or MESSAGE
+    pub context: String,
+    pub timestamp: u64,
+}
+
+/// A name only its holder may use
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct ReservedName {
+    pub name: String,
+    pub reserved_for: Option<ChainId>, // None when nobody may use it
+    pub reserved_at: u64,
+}
+
+impl ReservedName {
+    /// Whether `player_chain` may not use this name
+    pub fn blocks(&self, player_chain: ChainId) -> bool {
+        self.reserved_for != Some(player_chain)
+    }
+}
+
+/// Combined results of the active ranked players from one country
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct CountryRanking {
+    pub country: String,
+    pub players: u32,
+    pub games_played: u64,
+    pub total_candies: u64,
+    pub best_score: u32,
+    pub average_best_score: f64, // Mean of the players' highest scores
+    pub leader: ChainId,
+    pub leader_name: Option<String>,
+}
+
+/// An achievement unlocked on a player's chain
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct AchievementUnlock {
+    pub achievement: Achievement,
+    pub unlocked_at: u64,
+}
+
+/// Top finishers of a completed season
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct SeasonPodium {
+    pub season: u32,
+    pub ended_at: u64,
+    pub podium: Vec<LeaderboardEntry>,
+}
+
+/// A score or milestone credited to a player in the hall of fame
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct HallOfFameRecord {
+    pub title: String,
+    pub chain_id: ChainId,
+    pub player_name: Option<String>,
+    pub score: u32,
+    pub timestamp: u64,
+}
+
+/// Permanent records kept on the leaderboard chain across resets
+#[derive(Debug, Clone, Default, Serialize, Deserialize, SimpleObject)]
+pub struct HallOfFame {
+    pub seasons: Vec<SeasonPodium>,
+    pub all_time_record: Option<HallOfFameRecord>,
+    pub notable_firsts: Vec<HallOfFameRecord>,
+    pub battle_royale_winners: Vec<HallOfFameRecord>,
+    pub tournament_winners: Vec<HallOfFameRecord>,
+}
+
+impl HallOfFame {
+    /// Record a first-time milestone unless someone already holds it
+    #[allow(dead_code)]
+    pub fn record_first(&mut self, record: HallOfFameRecord) -> bool {
+        if self.notable_firsts.iter().any(|first| first.title == record.title) {
+            return false;
+        }
+        self.notable_firsts.push(record);
+        true
+    }
+}
+
+/// Phase of a battle royale event
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum)]
+pub enum BattleRoyaleState {
+    Registration,
+    Running,
+    Finished,
+}
+
+/// A survivor's best game in the current round
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct RoyaleScore {
+    pub player_chain: ChainId,
+    pub candies_collected: u32,
+}
+
+/// A player knocked out of a battle royale
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct RoyaleElimination {
+    pub player_chain: ChainId,
+    pub round: u32,
+    pub candies_collected: u32,
+}
+
+/// A bot filling a battle royale short of players. Bots live apart from the survivors,
+/// so their scores never reach the hall of fame or any player's stats
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct RoyaleBot {
+    pub bot_id: u32,
+    pub name: String,
+    pub policy: BotPolicy,
+    pub candies_collected: u32, // Score in the last round it played
+    pub eliminated_in_round: Option<u32>,
+}
+
+impl RoyaleBot {
+    #[allow(dead_code)]
+    pub fn new(bot_id: u32) -> Self {
+        let policy = if bot_id % 2 == 0 { BotPolicy::Greedy } else { BotPolicy::Cautious };
+        Self {
+            bot_id,
+            name: format!("Bot {}", bot_id + 1),
+            policy,
+            candies_collected: 0,
+            eliminated_in_round: None,
+        }
+    }
+    
+    /// Play a round on the open board. The candy schedule is the survivors' and the bot's
+    /// own moves are seeded from it, so anyone can replay the score with `play_bot`
+    #[allow(dead_code)]
+    pub fn play_round(&mut self, round_seed: u64, max_ticks: u64) {
+        let bot_seed = round_seed.wrapping_add(self.bot_id as u64 + 1);
+        let run = snake_engine::play_bot(snake_engine::Board::default(), round_seed, self.policy, bot_seed, max_ticks);
+        self.candies_collected = run.candies_collected;
+    }
+}
+
+/// A scheduled elimination event run by the leaderboard chain
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct BattleRoyale {
+    pub event_id: u64,
+    pub name: String,
+    pub starts_at: u64,
+    pub round_duration_secs: u64,
+    pub elimination_percent: u8,
+    pub state: BattleRoyaleState,
+    pub round: u32,
+    pub round_seed: u64, // Every survivor plays the same candy schedule in a round
+    pub round_ends_at: u64,
+    pub survivors: Vec<ChainId>, // In registration order, which breaks ties
+    pub round_scores: Vec<RoyaleScore>,
+    pub eliminated: Vec<RoyaleElimination>,
+    pub winner: Option<ChainId>,
+    pub bots: Vec<RoyaleBot>, // Added when the first round opens with too few players
+    pub winning_bot: Option<u32>, // Set instead of `winner` when a bot outlasts every player
+}
+
+impl BattleRoyale {
+    /// Players and bots still in the event
+    #[allow(dead_code)]
+    pub fn entrants_left(&self) -> usize {
+        self.survivors.len() + self.bots.iter().filter(|bot| bot.eliminated_in_round.is_none()).count()
+    }
+    
+    /// Entrants knocked out at the end of a round: the bottom `elimination_percent`,
+    /// at least one, but never everyone
+    #[allow(dead_code)]
+    pub fn elimination_count(&self) -> usize {
+        let survivors = self.entrants_left();
+        if survivors < 2 {
+            return 0;
+        }
+        let count = (survivors * self.elimination_percent as usize).div_ceil(100);
+        count.clamp(1, survivors - 1)
+    }
+}
+
+/// This player's view of a battle royale it joined
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct RoyaleStatus {
+    pub event_id: u64,
+    pub round: u32,
+    pub round_seed: u64,
+    pub round_ends_at: u64,
+    pub eliminated_in_round: Option<u32>,
+    pub won: bool,
+}
+
+/// One knockout match; a missing player is a bye
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct BracketMatch {
+    pub round: u32,
+    pub player_a: Option<ChainId>, // The higher seed
+    pub player_b: Option<ChainId>,
+    pub score_a: Option<u32>,
+    pub score_b: Option<u32>,
+    pub winner: Option<ChainId>,
+}
+
+impl BracketMatch {
+    /// The winner once both scores are in, or once the round is over, when a missing score
+    /// loses. The higher seed goes through on a tie or when neither side played
+    #[allow(dead_code)]
+    pub fn decide(&self, round_over: bool) -> Option<ChainId> {
+        match (self.player_a, self.player_b) {
+            (Some(a), Some(b)) => match (self.score_a, self.score_b) {
+                (Some(score_a), Some(score_b)) => Some(if score_b > score_a { b } else { a }),
+                (Some(_), None) | (None, None) if round_over => Some(a),
+                (None, Some(_)) if round_over => Some(b),
+                _ => None,
+            },
+            (a, b) => a.or(b),
+        }
+    }
+    
+    /// The player knocked out by this match, if it was played
+    #[allow(dead_code)]
+    pub fn loser(&self) -> Option<ChainId> {
+        let winner = self.winner?;
+        [self.player_a, self.player_b].into_iter().flatten().find(|player| *player != winner)
+    }
+}
+
+/// The knockout finals of a tournament, run by the leaderboard chain
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct Bracket {
+    pub tournament_id: u64,
+    pub round: u32,
+    pub round_seed: u64, // Both players of a match play the same candy schedule
+    pub round_ends_at: u64,
+    pub matches: Vec<BracketMatch>, // Every round so far, in bracket order
+    pub champion: Option<ChainId>,
+}
+
+/// This player's place in a tournament's knockout bracket
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct BracketSlot {
+    pub tournament_id: u64,
+    pub round: u32,
+    pub round_seed: u64,
+    pub round_ends_at: u64,
+    pub opponent: ChainId,
+    pub played: bool, // Each round is a single game
+    pub eliminated_in_round: Option<u32>,
+    pub champion: bool,
+}
+
+/// This player's season pass: XP earned since the last reset and the rewards already claimed
+#[derive(Debug, Clone, Default, Serialize, Deserialize, SimpleObject)]
+pub struct SeasonPass {
+    pub season: u32,
+    pub xp: u64,
+    pub premium: bool,
+    pub claimed_free: Vec<u32>, // Tier indices, in claim order
+    pub claimed_premium: Vec<u32>,
+}
+
+impl SeasonPass {
+    /// Tiers already claimed on one track
+    #[allow(dead_code)]
+    pub fn claimed(&mut self, track: PassTrack) -> &mut Vec<u32> {
+        match track {
+            PassTrack::Free => &mut self.claimed_free,
+            PassTrack::Premium => &mut self.claimed_premium,
+        }
+    }
+    
+    #[allow(dead_code)]
+    pub fn progress(&self, config: &SeasonPassConfig) -> SeasonPassProgress {
+        SeasonPassProgress {
+            season: self.season,
+            xp: self.xp,
+            tiers_unlocked: config.tiers_unlocked(self.xp),
+            premium: self.premium,
+        }
+    }
+}
+
+/// A trophy claimed from a season pass
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct SeasonTrophy {
+    pub season: u32,
+    pub name: String,
+    pub claimed_at: u64,
+}
+
+/// Direction of a treasury movement
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum)]
+pub enum TreasuryFlow {
+    Inflow,
+    Outflow,
+}
+
+/// One line of a team treasury's ledger
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct TeamLedgerEntry {
+    pub team_id: u64,
+    pub flow: TreasuryFlow,
+    pub coins: u64,
+    pub player_chain: ChainId, // The contributor, or the leader who spent
+    pub perk: Option<TeamPerk>, // What an outflow bought
+    pub balance_after: u64,
+    pub timestamp: u64,
+}
+
+/// One line of the treasury audit trail
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct TreasuryEntry {
+    pub flow: TreasuryFlow,
+    pub amount: Amount,
+    pub counterparty: Account,
+    pub memo: String,
+    pub timestamp: u64,
+    pub balance_after: Amount,
+}
+
+/// A single donation to a prize pool
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct Sponsorship {
+    pub pool: String,
+    pub sponsor_chain: ChainId,
+    pub sponsor_name: Option<String>,
+    pub amount: Amount,
+    pub timestamp: u64,
+}
+
+/// A named pool of sponsored prize money awaiting distribution
+#[derive(Debug, Clone, Default, Serialize, Deserialize, SimpleObject)]
+pub struct PrizePool {
+    pub name: String,
+    pub balance: Amount,
+    pub total_donated: Amount,
+}
+
+/// How a player's global leaderboard row changed
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum)]
+pub enum LeaderboardChangeKind {
+    Added,
+    Moved,
+    Updated, // Same position, different stats
+    Dropped,
+}
+
+/// One row change recorded when the global leaderboard is rebuilt
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct LeaderboardChange {
+    pub version: u64, // Leaderboard version that introduced the change
+    pub chain_id: ChainId,
+    pub kind: LeaderboardChangeKind,
+    pub previous_position: Option<u32>, // 1-based; None when added
+    pub position: Option<u32>, // 1-based; None when dropped
+    pub entry: Option<LeaderboardEntry>, // The new row; None when dropped
+}
+
+/// A prize paid out to a ranked player
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct PrizePayout {
+    pub chain_id: ChainId,
+    pub position: u32,
+    pub amount: Amount,
+    pub timestamp: u64,
+}
+
+/// Where a redeemed referral stands, as seen from the referee chain
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum)]
+pub enum ReferralState {
+    Pending,
+    Accepted,
+    Rejected,
+    Qualified,
+}
+
+/// The referral code this player redeemed
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct ReferralStatus {
+    pub code: String,
+    pub state: ReferralState,
+    pub games_finished: u32,
+    pub rejection_reason: Option<String>,
+}
+
+/// A referral tracked on the leaderboard chain
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct ReferralRecord {
+    pub code: String,
+    pub referrer: ChainId,
+    pub referee: ChainId,
+    pub rewarded: bool,
+}
+
+/// A player waiting in the matchmaking queue (leaderboard chain)
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct QueueTicket {
+    pub player_chain: ChainId,
+    pub rating: u32, // Best reported score when the player joined
+    pub queued_at: u64,
+}
+
+/// An item offered on the market (leaderboard chain)
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct MarketListing {
+    pub listing_id: u64,
+    pub seller: Account,
+    pub kind: ItemKind,
+    pub item: String,
+    pub price: Amount,
+    pub listed_at: u64,
+}
+
+/// A completed market sale, kept for price history
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct MarketSale {
+    pub listing_id: u64,
+    pub kind: ItemKind,
+    pub item: String,
+    pub price: Amount,
+    pub fee: Amount, // Part of the price kept by the treasury
+    pub seller: ChainId,
+    pub buyer: ChainId,
+    pub sold_at: u64,
+}
+
+/// What earned a reward in a player's ledger
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum)]
+pub enum RewardSource {
+    TournamentPrize,
+    BracketPrize,
+    Quest,
+    Referral,
+}
+
+/// Coins credited to this player that are not in their balance yet
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct PendingReward {
+    pub reward_id: u64,
+    pub source: RewardSource,
+    pub memo: String,
+    pub coins: u64,
+    pub claimed_coins: u64,
+    pub credited_at: u64,
+    pub vests_until: u64, // Same as credited_at when there is no vesting
+    pub expires_at: Option<u64>,
+}
+
+impl PendingReward {
+    /// Coins unlocked by `now`, vesting linearly between crediting and `vests_until`
+    #[allow(dead_code)]
+    pub fn vested(&self, now: u64) -> u64 {
+        if now >= self.vests_until {
+            return self.coins;
+        }
+        let elapsed = now.saturating_sub(self.credited_at) as u128;
+        let duration = (self.vests_until - self.credited_at) as u128;
+        (self.coins as u128 * elapsed / duration) as u64
+    }
+    
+    #[allow(dead_code)]
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|expires_at| now > expires_at)
+    }
+}
+
+/// One `ClaimRewards` run on a player chain
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct RewardClaim {
+    pub coins: u64,
+    pub reward_ids: Vec<u64>, // Rewards the coins came from
+    pub expired_coins: u64, // Unclaimed coins forfeited by this run
+    pub timestamp: u64,
+}
+
+/// A player's progress towards one published quest
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct QuestProgress {
+    pub quest: QuestDefinition,
+    pub progress: u32,
+    pub completed: bool,
+}
+
+impl QuestProgress {
+    #[allow(dead_code)]
+    pub fn new(quest: QuestDefinition) -> Self {
+        Self {
+            quest,
+            progress: 0,
+            completed: false,
+        }
+    }
+    
+    /// Apply an activity of the given kind; returns true if this completed the quest
+    #[allow(dead_code)]
+    pub fn record(&mut self, kind: QuestKind, amount: u32) -> bool {
+        if self.completed || self.quest.kind != kind {
+            return false;
+        }
+        
+        match kind {
+            QuestKind::CollectCandies | QuestKind::FinishGames => {
+                self.progress = self.progress.saturating_add(amount);
+            }
+            QuestKind::ReachScore => {
+                self.progress = self.progress.max(amount);
+            }
+        }
+        
+        self.completed = self.progress >= self.quest.target;
+        self.completed
+    }
+}
+
+/// What happened at one point of a game
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum)]
+pub enum SessionEventKind {
+    Started,
+    CandyEaten,
+    Died, // Survival mode only
+    Finished,
+}
+
+/// One entry of a session's event log
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct SessionEvent {
+    pub kind: SessionEventKind,
+    pub tick: u64,
+    pub timestamp: u64,
+}
+
+/// Everything stored about one game session, so that it can be read or pruned as a unit.
+/// The record carries the reference to the published replay.
+#[derive(View)]
+#[view(context = ViewStorageContext)]
+pub struct SessionView {
+    pub record: RegisterView<Option<GameSession>>,
+    pub events: LogView<SessionEvent>, // Oldest first
 }
 
 /// The application state for Snake Game
@@ -67,22 +1091,461 @@ impl PlayerStats {
 #[view(context = ViewStorageContext)]
 pub struct SnakeGameState {
     // Game state
-    pub sessions: MapView<String, GameSession>, // session_id -> GameSession
-    pub session_counter: RegisterView<u64>, // Counter for generating unique session IDs
+    pub sessions: CollectionView<String, SessionView>, // session_id -> record and event log of a game played on this chain
+    pub session_block: RegisterView<(u64, u32)>, // Height of the last block that started a session, and how many it started
+    pub sessions_by_start: MapView<u64, Vec<String>>, // start_time / SESSION_BUCKET_MICROS -> session IDs, in start order
     
     // Player names
     pub player_names: MapView<ChainId, String>, // chain_id -> player_name
+    pub name_handles: MapView<String, ChainId>, // name_key -> chain using that name (only on leaderboard chain)
+    pub player_levels: MapView<ChainId, u32>, // chain_id -> level (only on leaderboard chain)
+    pub player_avatars: MapView<ChainId, Avatar>, // chain_id -> custom avatar (only on leaderboard chain)
+    pub player_countries: MapView<ChainId, String>, // chain_id -> country code (only on leaderboard chain)
+    pub season_passes: MapView<ChainId, SeasonPassProgress>, // chain_id -> this season's pass (only on leaderboard chain)
     pub my_player_name: RegisterView<Option<String>>, // This player's name
+    pub my_name_changed_at: RegisterView<Option<u64>>, // When my_player_name was last set
+    pub my_avatar: RegisterView<Option<Avatar>>, // This player's custom avatar, if any
+    pub my_client_key: RegisterView<Option<Ed25519PublicKey>>, // Key that must sign this chain's score claims, if registered
+    pub my_country: RegisterView<Option<String>>, // This player's country code, if set
+    pub pending_results: MapView<String, PendingUpdate>, // session_id -> result sent to the leaderboard chain, until it confirms
+    pub pending_name_update: RegisterView<Option<PendingUpdate>>, // Name sent to the leaderboard chain, until it accepts or rejects it
+    pub name_history: MapView<ChainId, Vec<NameChange>>, // chain_id -> previous names, oldest first (only on leaderboard chain)
+    pub reserved_names: MapView<String, ReservedName>, // name_key -> reservation (only on leaderboard chain)
+    pub verified_players: SetView<ChainId>, // Chains the admin has verified (only on leaderboard chain)
     
     // Leaderboard state (only on leaderboard chain)
-    pub global_leaderboard: RegisterView<Vec<LeaderboardEntry>>, // Top players globally
+    pub global_leaderboard: MapView<u32, LeaderboardEntry>, // position (from 1) -> entry for the top players globally
+    pub leaderboard_version: RegisterView<u64>, // Bumped whenever the global leaderboard changes
+    pub leaderboard_synced_at: RegisterView<Option<u64>>, // When the leaderboard chain last pushed its board (only on player chains)
+    pub leaderboard_subscribers: MapView<ChainId, LeaderboardSubscription>, // chain_id -> podium push subscription (only on leaderboard chain)
+    pub pending_leaderboard_pushes: SetView<ChainId>, // Subscribers whose podium update waits for their throttling window
+    pub my_leaderboard_subscription: RegisterView<Option<u32>>, // Rows this chain subscribed to, if it did
+    pub leaderboard_changes: QueueView<LeaderboardChange>, // Recent row changes, oldest first
+    pub podium_history: QueueView<PodiumChange>, // Recent takeovers of the top three positions, oldest first
+    pub inactive_leaderboard: RegisterView<Vec<LeaderboardEntry>>, // Ranked players past the inactivity window
+    pub shadow_leaderboard: RegisterView<Vec<ShadowEntry>>, // Players with an upheld flag held off the public board, best first
+    pub player_last_active: MapView<ChainId, u64>, // chain_id -> timestamp of last message received
     pub player_stats: MapView<ChainId, PlayerStats>, // chain_id -> detailed stats
     pub leaderboard_participants: SetView<ChainId>, // Tracks which chains have been in the leaderboard
     pub is_leaderboard_chain: RegisterView<bool>, // Flag to identify if this is the leaderboard chain
     pub leaderboard_chain_id: RegisterView<Option<ChainId>>, // Store the leaderboard chain ID
+    pub pending_leaderboard_move: RegisterView<Option<ChainId>>, // Chain asked to take over as leaderboard, until it accepts
+    pub reset_proposal: RegisterView<Option<ResetProposal>>, // Latest reset proposal (only on leaderboard chain)
+    pub reset_proposal_counter: RegisterView<u64>, // Counter for generating proposal IDs
+    pub reset_ballot: RegisterView<Option<ResetBallot>>, // Latest reset proposal this player was asked to vote on
+    pub feature_flags: MapView<String, bool>, // Feature flags set by the admin; unset flags use feature_default
+    pub debug_log: QueueView<LogEntry>, // Most recent significant events, oldest dropped first
+    pub metrics: MapView<String, u64>, // Monotonic counters by name, see metrics.rs
+    pub last_message_at: RegisterView<Option<u64>>, // Block time of the last message this chain executed
+    pub schema_version: RegisterView<u32>, // STATE_SCHEMA_VERSION of the bytecode that laid out this state; 0 predates the register
+    pub hall_of_fame: RegisterView<HallOfFame>, // Season podiums and all-time records, never reset
+    pub treasury_balance: RegisterView<Amount>, // Tokens held on behalf of the game
+    pub treasury_ledger: LogView<TreasuryEntry>, // Every inflow and outflow, oldest first
+    pub prize_pools: MapView<String, PrizePool>, // pool name -> sponsored balance
+    pub sponsorships: LogView<Sponsorship>, // Every donation, oldest first
+    pub prize_payouts: LogView<PrizePayout>, // Every prize paid by DistributePrizes
+    pub referral_codes: MapView<String, ChainId>, // code -> referrer chain
+    pub referral_code_of: MapView<ChainId, String>, // referrer chain -> code
+    pub referrals: MapView<ChainId, ReferralRecord>, // referee chain -> referral
+    pub referral_counter: RegisterView<u64>, // Counter for minting referral codes
+    pub challenges: MapView<u64, Challenge>, // challenge_id -> authoritative challenge state
+    pub challenge_counter: RegisterView<u64>, // Counter for generating challenge IDs
+    pub open_challenges: SetView<u64>, // Challenges not yet settled or voided, checked for timeouts
+    pub matchmaking_queue: MapView<ChainId, QueueTicket>, // Players waiting for a head-to-head match
+    pub disputes: MapView<u64, Dispute>, // dispute_id -> flagged score and its outcome
+    pub dispute_counter: RegisterView<u64>, // Counter for generating dispute IDs
+    pub review_queue: SetView<u64>, // Disputes waiting for a moderator
+    pub upheld_players: SetView<ChainId>, // Players with an upheld dispute this season
+    pub player_strikes: MapView<ChainId, u32>, // player chain -> strikes from disputes and failed replays
+    pub penalty_history: MapView<ChainId, Vec<PenaltyRecord>>, // player chain -> penalties, oldest first
+    pub active_bans: MapView<ChainId, PenaltyRecord>, // player chain -> latest ban, checked against its expiry
+    pub verified_games: MapView<ChainId, u32>, // player chain -> games whose replay confirmed their score
+    pub eligibility_stakes: MapView<ChainId, u64>, // player chain -> coins locked in place of verified games
+    pub submission_windows: MapView<ChainId, SubmissionWindow>, // origin chain -> results received in the current window
+    pub candy_tallies: MapView<ChainId, CandyTally>, // player chain -> candies seen from its latest session
+    pub suspect_sessions: MapView<String, SuspectSession>, // session_id -> why its pace was rejected
+    pub move_log_anchors: MapView<String, MoveLogAnchor>, // session_id -> move log hash of a record game
+    pub level_counter: RegisterView<u32>, // Last level ID handed out; 0 is the built-in open level
+    pub level_leaderboards: MapView<u32, Vec<LevelScore>>, // level_id -> best score per player, highest first
+    pub custom_maps: MapView<String, CustomMap>, // map hash -> community map
+    pub map_leaderboards: MapView<String, Vec<LevelScore>>, // map hash -> best score per player, highest first
+    pub mode_leaderboards: MapView<GameMode, Vec<LevelScore>>, // non-Classic mode -> best score per player, highest first
+    pub survival_leaderboard: RegisterView<Vec<SurvivalEntry>>, // Longest Survival runs, longest first
+    pub player_histories: MapView<ChainId, Vec<SessionSummary>>, // chain_id -> synced sessions, oldest first
+    pub player_achievements: MapView<ChainId, Vec<AchievementUnlock>>, // chain_id -> unlocked achievements, oldest first
+    pub account_links: MapView<ChainId, AccountLink>, // old chain -> link it issued, until presented (only on leaderboard chain)
+    pub account_tombstones: MapView<ChainId, AccountTombstone>, // old chain -> where its history went (only on leaderboard chain)
+    pub received_link_tokens: MapView<ChainId, String>, // old chain -> link token it sent this chain
+    pub merged_into: RegisterView<Option<ChainId>>, // Chain that took over this chain's history, if any
+    pub most_improved: RegisterView<Vec<ImprovementEntry>>, // Biggest week-over-week gains in reported records, largest first
+    pub country_leaderboards: MapView<String, Vec<LeaderboardEntry>>, // country code -> top 100 active entries from that country
+    pub country_rankings: RegisterView<Vec<CountryRanking>>, // Countries by their best score, best first
+    pub rising_stars: RegisterView<Vec<RisingStarEntry>>, // Players in their first week, best first
+    pub teams: MapView<u64, Team>, // team_id -> registered team
+    pub pending_teams: MapView<u64, Team>, // team_id -> team waiting for the teammate to accept
+    pub team_counter: RegisterView<u64>, // Counter for generating team IDs
+    pub team_matches: MapView<u64, TeamMatch>, // match_id -> authoritative match state
+    pub team_match_counter: RegisterView<u64>, // Counter for generating match IDs
+    pub proposed_team_matches: SetView<u64>, // Matches the other team has not accepted yet, checked for expiry
+    pub team_wars: MapView<u64, TeamWar>, // war_id -> authoritative war state
+    pub team_war_counter: RegisterView<u64>, // Counter for generating war IDs
+    pub open_team_wars: SetView<u64>, // Wars not settled yet, checked as messages arrive
+    pub team_contributions: MapView<u64, BTreeMap<ChainId, u64>>, // team_id -> member -> coins contributed
+    pub team_ledger: LogView<TeamLedgerEntry>, // Every team treasury inflow and outflow, oldest first
+    pub battle_royales: MapView<u64, BattleRoyale>, // event_id -> battle royale event
+    pub battle_royale_counter: RegisterView<u64>, // Counter for generating event IDs
+    pub tournament_counter: RegisterView<u64>, // Counter for generating tournament IDs
+    pub open_tournaments: SetView<u64>, // Tournaments not finalized yet, checked as messages arrive
+    pub brackets: MapView<u64, Bracket>, // tournament_id -> knockout finals
+    pub open_brackets: SetView<u64>, // Brackets without a champion, checked as messages arrive
     
     // Player-specific state (on each player's chain)
-    pub my_sessions: RegisterView<Vec<String>>, // Sessions this player participated in
+    pub recent_games: QueueView<String>, // Most recently finished session IDs, oldest first
+    pub notifications: QueueView<Notification>, // This player's inbox, oldest first
+    pub chat_inbox: QueueView<ChatMessage>, // Chat messages sent and received by this player, oldest first
+    pub chat_windows: MapView<ChainId, SubmissionWindow>, // sender chain -> chat messages counted in its current window
+    pub reactions: MapView<ChainId, BTreeMap<ChainId, Emote>>, // entry chain_id -> reactor -> emote (only on leaderboard chain)
+    pub following: SetView<ChainId>, // Players whose records this chain is notified about
+    pub followers: MapView<ChainId, Vec<ChainId>>, // chain_id -> chains following its records (only on leaderboard chain)
+    pub history_sync_enabled: RegisterView<bool>, // Whether finished sessions are published to the leaderboard chain
+    pub pending_history: QueueView<SessionSummary>, // Summaries waiting for the next batch
     pub my_stats: RegisterView<Option<PlayerStats>>, // Personal statistics
     pub my_current_session: RegisterView<Option<String>>, // Currently active session
-}
\ No newline at end of file
+    pub active_owner: RegisterView<Option<AccountOwner>>, // Owner whose stats, name and session are in the my_* registers
+    pub owner_profiles: MapView<AccountOwner, OwnerProfile>, // owner -> personal data parked while another owner plays
+    pub guest_stats: RegisterView<Option<PlayerStats>>, // Stats of the guest games waiting to be claimed
+    pub guest_sessions: RegisterView<Vec<String>>, // Finished guest games waiting to be claimed, in the order they ended
+    pub my_record_history: QueueView<PersonalBest>, // Latest MAX_RECORD_HISTORY personal bests, oldest first
+    pub quests: MapView<u64, QuestProgress>, // quest_id -> progress on this player's chain
+    pub my_coins: RegisterView<u64>, // Coins earned from quests, prizes and referrals once claimed
+    pub my_rewards: MapView<u64, PendingReward>, // reward_id -> coins waiting to be claimed
+    pub reward_counter: RegisterView<u64>, // Counter for generating reward IDs
+    pub reward_claims: LogView<RewardClaim>, // Claims made on this chain, oldest first
+    pub my_achievements: MapView<Achievement, u64>, // achievement -> unlock timestamp
+    pub my_referral_code: RegisterView<Option<String>>, // Code other players can redeem
+    pub my_referral: RegisterView<Option<ReferralStatus>>, // Code this player redeemed
+    pub my_challenges: MapView<u64, Challenge>, // challenge_id -> latest copy from the leaderboard chain
+    pub my_matchmaking: RegisterView<Option<MatchmakingStatus>>, // Latest status of this chain's matchmaking ticket
+    pub my_penalties: LogView<PenaltyRecord>, // Penalties the leaderboard chain applied to this player, oldest first
+    pub my_eligibility_stake: RegisterView<u64>, // Coins this chain locked with the leaderboard chain
+    pub my_level_bests: MapView<u32, u32>, // level_id -> best score on this chain
+    pub my_mode_bests: MapView<GameMode, u32>, // non-Classic mode -> best score on this chain
+    pub my_team_matches: MapView<u64, TeamMatch>, // match_id -> latest copy from the leaderboard chain
+    pub team_invites: MapView<u64, Team>, // team_id -> team another player registered with this chain
+    pub my_team_wars: MapView<u64, TeamWar>, // war_id -> latest copy of a war this chain's team fights
+    pub my_battle_royales: MapView<u64, RoyaleStatus>, // event_id -> this player's progress
+    pub ghosts: MapView<String, Ghost>, // session_id -> another player's replay fetched for racing
+    pub live_engines: MapView<String, Engine>, // session_id -> engine replaying the submitted moves
+    pub spectators: SetView<ChainId>, // Chains receiving live deltas of our games
+    
+    // Live games, both our own and those on chains we spectate
+    pub live_sessions: MapView<String, LiveSession>, // session_id -> latest validated state
+    
+    // Level catalog (defined on the leaderboard chain, mirrored to player chains)
+    pub levels: MapView<u32, LevelLayout>, // level_id -> obstacle layout
+    
+    // Tournaments (standings kept on the leaderboard chain, definitions mirrored to player chains)
+    pub tournaments: MapView<u64, Tournament>, // tournament_id -> tournament
+    pub my_tournaments: MapView<u64, TournamentEntry>, // tournament_id -> this player's attempts and result
+    pub my_brackets: MapView<u64, BracketSlot>, // tournament_id -> this player's knockout match
+    
+    // Season pass (kept on the player's chain, progress mirrored to the leaderboard chain)
+    pub my_season_pass: RegisterView<SeasonPass>,
+    pub my_skins: SetView<String>, // Skins claimed from any season
+    pub my_trophies: LogView<SeasonTrophy>, // Trophies claimed, oldest first
+    
+    // Market (only on leaderboard chain)
+    pub market_listings: MapView<u64, MarketListing>, // listing_id -> item for sale
+    pub listing_counter: RegisterView<u64>, // Counter for generating listing IDs
+    pub market_sales: LogView<MarketSale>, // Completed sales, oldest first
+    
+    // Quest catalog (only on leaderboard chain)
+    pub quest_catalog: MapView<u64, QuestDefinition>, // quest_id -> published definition
+    pub quest_counter: RegisterView<u64>, // Counter for generating quest IDs
+    pub announcement_counter: RegisterView<u64>, // Counter for generating announcement IDs
+    
+    // Announcements received from the leaderboard chain, oldest first
+    pub announcements: QueueView<Announcement>,
+}
+
+impl SnakeGameState {
+    /// The record of a session played on this chain
+    pub async fn session(&self, session_id: &str) -> Option<GameSession> {
+        let entry = self.sessions.try_load_entry(&session_id.to_string()).await.ok().flatten()?;
+        entry.record.get().clone()
+    }
+    
+    /// A replay this chain can race: one of its own finished level games with a published
+    /// move log, or a ghost fetched from the leaderboard chain
+    #[allow(dead_code)]
+    pub async fn ghost(&self, session_id: &str) -> Option<Ghost> {
+        if let Some(session) = self.session(session_id).await {
+            let replay_blob = session.replay_blob.filter(|_| session.state == GameState::Finished && session.map_hash.is_none())?;
+            return Some(Ghost {
+                session_id: session.session_id,
+                player_chain: session.player,
+                player_name: session.player_name,
+                candies_collected: session.candies_collected,
+                seed: session.seed,
+                level_id: session.level_id,
+                replay_blob,
+            });
+        }
+        self.ghosts.get(&session_id.to_string()).await.ok().flatten()
+    }
+    
+    /// Store a session's record, creating its entry for a new session
+    #[allow(dead_code)]
+    pub async fn save_session(&mut self, session: GameSession) {
+        let entry = self.sessions.load_entry_mut(&session.session_id).await.expect("Failed to load session");
+        entry.record.set(Some(session));
+    }
+    
+    /// Remember a new personal best, dropping the oldest once the history is full
+    #[allow(dead_code)]
+    pub fn push_record_history(&mut self, record: PersonalBest) {
+        self.my_record_history.push_back(record);
+        while self.my_record_history.count() > MAX_RECORD_HISTORY {
+            self.my_record_history.delete_front();
+        }
+    }
+    
+    /// Append to the event log of a stored session
+    #[allow(dead_code)]
+    pub async fn log_session_event(&mut self, session_id: &str, kind: SessionEventKind, tick: u64, timestamp: u64) {
+        let entry = self.sessions.load_entry_mut(&session_id.to_string()).await.expect("Failed to load session");
+        entry.events.push(SessionEvent { kind, tick, timestamp });
+    }
+    
+    /// The stats, name and current session of one owner of this chain
+    #[allow(dead_code)]
+    pub async fn owner_profile(&self, owner: AccountOwner) -> OwnerProfile {
+        if *self.active_owner.get() == Some(owner) {
+            return OwnerProfile {
+                stats: self.my_stats.get().clone(),
+                current_session: self.my_current_session.get().clone(),
+                player_name: self.my_player_name.get().clone(),
+                name_changed_at: *self.my_name_changed_at.get(),
+            };
+        }
+        self.owner_profiles.get(&owner).await.ok().flatten().unwrap_or_default()
+    }
+    
+    /// Replace the stats of the owner who played a game, whether or not they are the active owner
+    #[allow(dead_code)]
+    pub async fn set_owner_stats(&mut self, owner: Option<AccountOwner>, stats: Option<PlayerStats>) {
+        match owner {
+            Some(owner) if *self.active_owner.get() != Some(owner) => {
+                let mut profile = self.owner_profiles.get(&owner).await.ok().flatten().unwrap_or_default();
+                profile.stats = stats;
+                let _ = self.owner_profiles.insert(&owner, profile);
+            }
+            _ => self.my_stats.set(stats),
+        }
+    }
+    
+    /// Make `owner` the active owner: the previous owner's personal data is parked and `owner`'s
+    /// is loaded into the my_* registers. The first owner to sign keeps the data already there
+    #[allow(dead_code)]
+    pub async fn switch_owner(&mut self, owner: AccountOwner) {
+        let previous = *self.active_owner.get();
+        if previous == Some(owner) {
+            return;
+        }
+        self.active_owner.set(Some(owner));
+        let Some(previous) = previous else {
+            return;
+        };
+        
+        let parked = OwnerProfile {
+            stats: self.my_stats.get().clone(),
+            current_session: self.my_current_session.get().clone(),
+            player_name: self.my_player_name.get().clone(),
+            name_changed_at: *self.my_name_changed_at.get(),
+        };
+        let _ = self.owner_profiles.insert(&previous, parked);
+        let profile = self.owner_profiles.get(&owner).await.ok().flatten().unwrap_or_default();
+        let _ = self.owner_profiles.remove(&owner);
+        self.my_stats.set(profile.stats);
+        self.my_current_session.set(profile.current_session);
+        self.my_player_name.set(profile.player_name);
+        self.my_name_changed_at.set(profile.name_changed_at);
+    }
+    
+    /// The global leaderboard, best first
+    pub async fn leaderboard_entries(&self) -> Vec<LeaderboardEntry> {
+        let mut rows = self.global_leaderboard.index_values().await.unwrap_or_default();
+        rows.sort_by_key(|(position, _)| *position);
+        rows.into_iter().map(|(_, entry)| entry).collect()
+    }
+    
+    /// The global leaderboard as `viewer` sees it: a shadowed player still finds their own entry
+    /// at the position it would take, while everyone else sees the public board
+    pub async fn leaderboard_seen_by(&self, viewer: ChainId) -> Vec<LeaderboardEntry> {
+        let mut entries = self.leaderboard_entries().await;
+        if let Some(shadow) = self.shadow_leaderboard.get().iter().find(|shadow| shadow.entry.chain_id == viewer) {
+            let index = (shadow.position as usize).saturating_sub(1).min(entries.len());
+            entries.insert(index, shadow.entry.clone());
+        }
+        entries
+    }
+    
+    /// Replace the global leaderboard `previous`, writing only the positions that changed
+    #[allow(dead_code)]
+    pub fn write_leaderboard(&mut self, previous: &[LeaderboardEntry], entries: &[LeaderboardEntry]) {
+        for (index, entry) in entries.iter().enumerate() {
+            if previous.get(index) != Some(entry) {
+                let _ = self.global_leaderboard.insert(&(index as u32 + 1), entry.clone());
+            }
+        }
+        for position in entries.len() + 1..=previous.len() {
+            let _ = self.global_leaderboard.remove(&(position as u32));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    
+    use super::*;
+    
+    fn chain(index: u64) -> ChainId {
+        format!("{:064x}", index).parse().expect("valid chain ID")
+    }
+    
+    /// Scores paired with strictly increasing timestamps up to a few days apart
+    fn games() -> impl Strategy<Value = Vec<(u32, u64)>> {
+        prop::collection::vec((0u32..200, 1u64..3 * MICROS_PER_DAY), 1..60).prop_map(|games| {
+            let mut timestamp = 0;
+            games.into_iter()
+                .map(|(score, gap)| {
+                    timestamp += gap;
+                    (score, timestamp)
+                })
+                .collect()
+        })
+    }
+    
+    proptest! {
+        #[test]
+        fn totals_match_the_games_played(games in games()) {
+            let mut stats = PlayerStats::new(chain(1));
+            for &(score, timestamp) in &games {
+                stats.add_game(score, timestamp);
+            }
+            
+            prop_assert_eq!(stats.games_played as usize, games.len());
+            prop_assert_eq!(stats.total_candies, games.iter().map(|&(score, _)| score as u64).sum::<u64>());
+            prop_assert_eq!(stats.highest_score, games.iter().map(|&(score, _)| score).max().unwrap());
+            prop_assert_eq!(stats.first_game_timestamp, games[0].1);
+            prop_assert_eq!(stats.last_game_timestamp, games[games.len() - 1].1);
+            prop_assert!(stats.recent_scores.len() <= RECENT_SCORES_WINDOW);
+        }
+        
+        #[test]
+        fn records_only_ever_raise_the_highest_score(games in games()) {
+            let mut stats = PlayerStats::new(chain(1));
+            for (score, timestamp) in games {
+                let previous = stats.clone();
+                let is_record = stats.add_game(score, timestamp);
+                
+                prop_assert_eq!(is_record, score > previous.highest_score);
+                prop_assert!(stats.highest_score >= previous.highest_score);
+                if is_record {
+                    prop_assert_eq!(stats.record_timestamp, timestamp);
+                    prop_assert_eq!(stats.current_streak, previous.current_streak + 1);
+                } else {
+                    prop_assert_eq!(stats.highest_score, previous.highest_score);
+                    prop_assert_eq!(stats.record_timestamp, previous.record_timestamp);
+                    prop_assert_eq!(stats.current_streak, 0);
+                }
+                prop_assert!(stats.current_streak <= stats.best_streak);
+                prop_assert!(stats.best_streak >= previous.best_streak);
+            }
+        }
+        
+        #[test]
+        fn repeating_the_highest_score_is_not_a_record(games in games(), later in 1u64..MICROS_PER_DAY) {
+            let mut stats = PlayerStats::new(chain(1));
+            for &(score, timestamp) in &games {
+                stats.add_game(score, timestamp);
+            }
+            let before = stats.clone();
+            
+            prop_assert!(!stats.add_game(before.highest_score, before.last_game_timestamp + later));
+            prop_assert_eq!(stats.highest_score, before.highest_score);
+            prop_assert_eq!(stats.record_timestamp, before.record_timestamp);
+            prop_assert_eq!(stats.best_streak, before.best_streak);
+        }
+        
+        #[test]
+        fn daily_streak_counts_consecutive_days(days in 1u64..30, games_per_day in 1usize..4, start_day in 0u64..1000) {
+            let mut stats = PlayerStats::new(chain(1));
+            for day in start_day..start_day + days {
+                for game in 0..games_per_day {
+                    stats.add_game(1, day * MICROS_PER_DAY + game as u64);
+                }
+            }
+            prop_assert_eq!(stats.daily_streak as u64, days);
+            prop_assert_eq!(stats.best_daily_streak as u64, days);
+            
+            // Skipping a day starts over but keeps the best streak
+            stats.add_game(1, (start_day + days + 1) * MICROS_PER_DAY);
+            prop_assert_eq!(stats.daily_streak, 1);
+            prop_assert_eq!(stats.best_daily_streak as u64, days);
+        }
+        
+        #[test]
+        fn voiding_the_record_falls_back_to_the_best_remaining_score(games in games()) {
+            let mut stats = PlayerStats::new(chain(1));
+            for &(score, timestamp) in &games {
+                stats.add_game(score, timestamp);
+            }
+            let record = stats.highest_score;
+            
+            let voided = stats.void_record(record, &CompositeWeights::default());
+            prop_assert_eq!(voided, record);
+            prop_assert!(stats.highest_score <= record);
+            let best_remaining = stats.recent_scores.iter().map(|recent| recent.score).max().unwrap_or(0);
+            prop_assert_eq!(stats.highest_score, best_remaining);
+        }
+        
+        #[test]
+        fn voiding_an_older_score_keeps_the_record(games in games()) {
+            let mut stats = PlayerStats::new(chain(1));
+            for &(score, timestamp) in &games {
+                stats.add_game(score, timestamp);
+            }
+            let record = stats.highest_score;
+            let Some(older) = stats.recent_scores.iter().map(|recent| recent.score).find(|&score| score > 0 && score < record) else {
+                return Ok(());
+            };
+            
+            prop_assert_eq!(stats.void_record(older, &CompositeWeights::default()), older);
+            prop_assert_eq!(stats.highest_score, record);
+        }
+        
+        #[test]
+        fn merged_stats_add_up_both_chains(old_games in games(), new_games in games()) {
+            let mut old = PlayerStats::new(chain(1));
+            for &(score, timestamp) in &old_games {
+                old.add_game(score, timestamp);
+            }
+            let mut merged = PlayerStats::new(chain(2));
+            for &(score, timestamp) in &new_games {
+                merged.add_game(score, timestamp);
+            }
+            let new = merged.clone();
+            
+            merged.merge(old.clone(), &CompositeWeights::default());
+            prop_assert_eq!(merged.chain_id, new.chain_id);
+            prop_assert_eq!(merged.games_played, old.games_played + new.games_played);
+            prop_assert_eq!(merged.total_candies, old.total_candies + new.total_candies);
+            prop_assert_eq!(merged.highest_score, old.highest_score.max(new.highest_score));
+            prop_assert_eq!(merged.first_game_timestamp, old.first_game_timestamp.min(new.first_game_timestamp));
+            prop_assert!(merged.recent_scores.len() <= RECENT_SCORES_WINDOW);
+            prop_assert!(merged.recent_scores.windows(2).all(|pair| pair[0].timestamp <= pair[1].timestamp));
+        }
+    }
+}
@@ -0,0 +1,349 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Integration tests driving a leaderboard chain and several player chains through games and resets
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use linera_sdk::{
+    linera_base_types::{AccountOwner, ApplicationId, ChainId},
+    test::{ActiveChain, QueryOutcome, TestValidator},
+};
+use serde_json::Value;
+use snake_game::{ApplicationParameters, GameMode, LevelLayout, Operation, SnakeGameAbi};
+
+/// A move log hash that passes validation; these tests do not publish replays
+const MOVE_LOG_HASH: &str = "abababababababababababababababababababababababababababababababab";
+
+/// A leaderboard chain and its application, ready for player chains to join
+async fn leaderboard_setup() -> (TestValidator, ApplicationId<SnakeGameAbi>, ActiveChain) {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<SnakeGameAbi, ApplicationParameters, ()>().await;
+    let mut leaderboard = validator.new_chain().await;
+    // The leaderboard chain's owner administers the application, so it can propose resets
+    let parameters = ApplicationParameters {
+        admin: Some(AccountOwner::from(leaderboard.public_key())),
+        ..ApplicationParameters::default()
+    };
+    let application_id = leaderboard
+        .create_application(module_id, parameters, (), vec![])
+        .await;
+
+    let leaderboard_chain_id = leaderboard.id();
+    leaderboard
+        .add_block(|block| {
+            block.with_operation(application_id, Operation::SetupLeaderboard { leaderboard_chain_id });
+        })
+        .await;
+
+    (validator, application_id, leaderboard)
+}
+
+/// A player chain that sends its results to `leaderboard_chain_id`
+async fn player_chain(
+    validator: &TestValidator,
+    application_id: ApplicationId<SnakeGameAbi>,
+    leaderboard_chain_id: ChainId,
+) -> ActiveChain {
+    let player = validator.new_chain().await;
+    player
+        .add_block(|block| {
+            block.with_operation(application_id, Operation::SetupLeaderboard { leaderboard_chain_id });
+        })
+        .await;
+    player
+}
+
+/// Play one classic game on the open level, eating a candy on each of the first `candies` ticks
+async fn play_game(player: &ActiveChain, application_id: ApplicationId<SnakeGameAbi>, candies: u64) {
+    player
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::StartGame {
+                    level_id: LevelLayout::OPEN_LEVEL_ID,
+                    map_hash: None,
+                    mode: GameMode::Classic,
+                    match_id: None,
+                    royale_id: None,
+                    ghost: None,
+                    tournament_id: None,
+                },
+            );
+            for tick in 1..=candies {
+                block.with_operation(application_id, Operation::CollectCandy { tick });
+            }
+            block.with_operation(
+                application_id,
+                Operation::EndGame { move_log_hash: MOVE_LOG_HASH.to_string(), signature: None },
+            );
+        })
+        .await;
+}
+
+async fn query(chain: &ActiveChain, application_id: ApplicationId<SnakeGameAbi>, query: &str) -> Value {
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, query).await;
+    response
+}
+
+/// The chains and highest scores on the global leaderboard, best first
+async fn leaderboard_rows(chain: &ActiveChain, application_id: ApplicationId<SnakeGameAbi>) -> Vec<(String, u64)> {
+    let response = query(chain, application_id, "query { globalLeaderboard { chainId highestScore } }").await;
+    response["globalLeaderboard"]
+        .as_array()
+        .expect("globalLeaderboard should be a list")
+        .iter()
+        .map(|row| {
+            (
+                row["chainId"].as_str().unwrap().to_string(),
+                row["highestScore"].as_u64().unwrap(),
+            )
+        })
+        .collect()
+}
+
+/// A counter from the `metrics` query, zero if it was never incremented
+async fn metric(chain: &ActiveChain, application_id: ApplicationId<SnakeGameAbi>, name: &str) -> u64 {
+    let response = query(chain, application_id, "query { metrics { name value } }").await;
+    response["metrics"]
+        .as_array()
+        .expect("metrics should be a list")
+        .iter()
+        .find(|counter| counter["name"] == name)
+        .map(|counter| counter["value"].as_u64().unwrap())
+        .unwrap_or(0)
+}
+
+/// Results from several players end up on the leaderboard chain, ordered by highest score
+#[tokio::test(flavor = "multi_thread")]
+async fn leaderboard_converges_across_player_chains() {
+    let (validator, application_id, leaderboard) = leaderboard_setup().await;
+    let alice = player_chain(&validator, application_id, leaderboard.id()).await;
+    let bob = player_chain(&validator, application_id, leaderboard.id()).await;
+
+    play_game(&alice, application_id, 3).await;
+    play_game(&bob, application_id, 5).await;
+    leaderboard.handle_received_messages().await;
+
+    assert_eq!(
+        leaderboard_rows(&leaderboard, application_id).await,
+        vec![(bob.id().to_string(), 5), (alice.id().to_string(), 3)],
+    );
+
+    // A new record moves Alice ahead; a worse game does not move Bob back
+    play_game(&alice, application_id, 7).await;
+    play_game(&bob, application_id, 2).await;
+    leaderboard.handle_received_messages().await;
+
+    assert_eq!(
+        leaderboard_rows(&leaderboard, application_id).await,
+        vec![(alice.id().to_string(), 7), (bob.id().to_string(), 5)],
+    );
+
+    let response = query(&alice, application_id, "query { myStats { highestScore gamesPlayed } }").await;
+    assert_eq!(response["myStats"]["highestScore"], 7);
+    assert_eq!(response["myStats"]["gamesPlayed"], 2);
+}
+
+/// Results sent to a chain that is not the leaderboard are ignored there and never bounce back
+#[tokio::test(flavor = "multi_thread")]
+async fn misrouted_results_are_rejected_without_bouncing() {
+    let (validator, application_id, leaderboard) = leaderboard_setup().await;
+    // Bob never sets up a leaderboard, so he is not one
+    let bob = validator.new_chain().await;
+    let alice = player_chain(&validator, application_id, bob.id()).await;
+
+    play_game(&alice, application_id, 2).await;
+    bob.handle_received_messages().await;
+    alice.handle_received_messages().await;
+
+    assert!(leaderboard_rows(&bob, application_id).await.is_empty());
+    assert!(metric(&bob, application_id, "messages.rejected").await > 0);
+    assert_eq!(metric(&alice, application_id, "messages.bounced").await, 0);
+    assert!(leaderboard_rows(&leaderboard, application_id).await.is_empty());
+
+    // The game still counts locally
+    let response = query(&alice, application_id, "query { myStats { highestScore } }").await;
+    assert_eq!(response["myStats"]["highestScore"], 2);
+}
+
+/// A reset only runs once the participants approve it, and clears the players' local stats
+#[tokio::test(flavor = "multi_thread")]
+async fn reset_runs_after_participants_approve() {
+    let (validator, application_id, leaderboard) = leaderboard_setup().await;
+    let alice = player_chain(&validator, application_id, leaderboard.id()).await;
+    let bob = player_chain(&validator, application_id, leaderboard.id()).await;
+    let carol = player_chain(&validator, application_id, leaderboard.id()).await;
+
+    for (player, candies) in [(&alice, 4), (&bob, 6), (&carol, 1)] {
+        play_game(player, application_id, candies).await;
+    }
+    leaderboard.handle_received_messages().await;
+    assert_eq!(leaderboard_rows(&leaderboard, application_id).await.len(), 3);
+
+    leaderboard
+        .add_block(|block| {
+            block.with_operation(application_id, Operation::ProposeReset);
+        })
+        .await;
+    for player in [&alice, &bob, &carol] {
+        player.handle_received_messages().await;
+    }
+
+    let response = query(&alice, application_id, "query { resetBallot { proposalId vote } }").await;
+    assert_eq!(response["resetBallot"]["proposalId"], 1);
+    assert_eq!(response["resetBallot"]["vote"], Value::Null);
+
+    // One approval out of three is short of the default 50% quorum
+    alice
+        .add_block(|block| {
+            block.with_operation(application_id, Operation::VoteOnReset { proposal_id: 1, approve: true });
+        })
+        .await;
+    leaderboard.handle_received_messages().await;
+
+    let response = query(&leaderboard, application_id, "query { resetProposal { state approvals } }").await;
+    assert_eq!(response["resetProposal"]["state"], "OPEN");
+    assert_eq!(leaderboard_rows(&leaderboard, application_id).await.len(), 3);
+
+    bob
+        .add_block(|block| {
+            block.with_operation(application_id, Operation::VoteOnReset { proposal_id: 1, approve: true });
+        })
+        .await;
+    leaderboard.handle_received_messages().await;
+
+    let response = query(&leaderboard, application_id, "query { resetProposal { state } }").await;
+    assert_eq!(response["resetProposal"]["state"], "EXECUTED");
+    assert!(leaderboard_rows(&leaderboard, application_id).await.is_empty());
+
+    for player in [&alice, &bob, &carol] {
+        player.handle_received_messages().await;
+        let response = query(player, application_id, "query { myStats { highestScore gamesPlayed } }").await;
+        assert_eq!(response["myStats"]["highestScore"], 0);
+        assert_eq!(response["myStats"]["gamesPlayed"], 0);
+    }
+}
+
+/// Enough rejections close a proposal without resetting anything
+#[tokio::test(flavor = "multi_thread")]
+async fn reset_is_rejected_when_quorum_becomes_unreachable() {
+    let (validator, application_id, leaderboard) = leaderboard_setup().await;
+    let alice = player_chain(&validator, application_id, leaderboard.id()).await;
+    let bob = player_chain(&validator, application_id, leaderboard.id()).await;
+
+    play_game(&alice, application_id, 2).await;
+    play_game(&bob, application_id, 3).await;
+    leaderboard.handle_received_messages().await;
+
+    leaderboard
+        .add_block(|block| {
+            block.with_operation(application_id, Operation::ProposeReset);
+        })
+        .await;
+    for player in [&alice, &bob] {
+        player.handle_received_messages().await;
+        player
+            .add_block(|block| {
+                block.with_operation(application_id, Operation::VoteOnReset { proposal_id: 1, approve: false });
+            })
+            .await;
+    }
+    leaderboard.handle_received_messages().await;
+
+    let response = query(&leaderboard, application_id, "query { resetProposal { state rejections } }").await;
+    assert_eq!(response["resetProposal"]["state"], "REJECTED");
+    assert_eq!(leaderboard_rows(&leaderboard, application_id).await.len(), 2);
+}
+
+/// A player chain can pull the top of the board and show it from its own state
+#[tokio::test(flavor = "multi_thread")]
+async fn leaderboard_sync_copies_the_top_rows() {
+    let (validator, application_id, leaderboard) = leaderboard_setup().await;
+    let alice = player_chain(&validator, application_id, leaderboard.id()).await;
+    let bob = player_chain(&validator, application_id, leaderboard.id()).await;
+
+    play_game(&alice, application_id, 3).await;
+    play_game(&bob, application_id, 5).await;
+    leaderboard.handle_received_messages().await;
+    assert!(leaderboard_rows(&alice, application_id).await.is_empty());
+
+    alice
+        .add_block(|block| {
+            block.with_operation(application_id, Operation::RequestLeaderboardSync { limit: 1 });
+        })
+        .await;
+    leaderboard.handle_received_messages().await;
+    alice.handle_received_messages().await;
+
+    assert_eq!(leaderboard_rows(&alice, application_id).await, vec![(bob.id().to_string(), 5)]);
+}
+
+/// A player pushed down the board hears who overtook them
+#[tokio::test(flavor = "multi_thread")]
+async fn overtaken_players_are_notified() {
+    let (validator, application_id, leaderboard) = leaderboard_setup().await;
+    let alice = player_chain(&validator, application_id, leaderboard.id()).await;
+    let bob = player_chain(&validator, application_id, leaderboard.id()).await;
+
+    play_game(&bob, application_id, 5).await;
+    leaderboard.handle_received_messages().await;
+    play_game(&alice, application_id, 7).await;
+    leaderboard.handle_received_messages().await;
+    bob.handle_received_messages().await;
+
+    let response = query(&bob, application_id, "query { notifications { kind otherPlayer oldRank newRank } }").await;
+    assert_eq!(response["notifications"][0]["kind"], "OVERTAKEN");
+    assert_eq!(response["notifications"][0]["otherPlayer"], alice.id().to_string());
+    assert_eq!(response["notifications"][0]["oldRank"], 1);
+    assert_eq!(response["notifications"][0]["newRank"], 2);
+}
+
+/// Taking the top spot is logged as a podium change naming who was displaced
+#[tokio::test(flavor = "multi_thread")]
+async fn podium_takeovers_are_recorded() {
+    let (validator, application_id, leaderboard) = leaderboard_setup().await;
+    let alice = player_chain(&validator, application_id, leaderboard.id()).await;
+    let bob = player_chain(&validator, application_id, leaderboard.id()).await;
+
+    play_game(&bob, application_id, 5).await;
+    leaderboard.handle_received_messages().await;
+    play_game(&alice, application_id, 7).await;
+    leaderboard.handle_received_messages().await;
+
+    let response = query(
+        &leaderboard,
+        application_id,
+        "query { podiumHistory { position newHolder { chainId highestScore } displaced { chainId highestScore } } }",
+    )
+    .await;
+    // Newest first: Bob dropping into the empty second place is logged after Alice took the first
+    let history = response["podiumHistory"].as_array().expect("podiumHistory should be a list");
+    assert_eq!(history.len(), 3);
+    assert_eq!(history[0]["position"], 2);
+    assert_eq!(history[0]["newHolder"]["chainId"], bob.id().to_string());
+    assert_eq!(history[0]["displaced"], Value::Null);
+    assert_eq!(history[1]["position"], 1);
+    assert_eq!(history[1]["newHolder"]["chainId"], alice.id().to_string());
+    assert_eq!(history[1]["newHolder"]["highestScore"], 7);
+    assert_eq!(history[1]["displaced"]["chainId"], bob.id().to_string());
+    assert_eq!(history[1]["displaced"]["highestScore"], 5);
+}
+
+/// A record stays pending on the player chain until the leaderboard chain confirms it
+#[tokio::test(flavor = "multi_thread")]
+async fn results_stay_pending_until_received() {
+    let (validator, application_id, leaderboard) = leaderboard_setup().await;
+    let alice = player_chain(&validator, application_id, leaderboard.id()).await;
+
+    play_game(&alice, application_id, 3).await;
+    let response = query(&alice, application_id, "query { pendingUpdates { kind } mySessions { leaderboardStatus } }").await;
+    assert_eq!(response["pendingUpdates"][0]["kind"], "GAME_RESULT");
+    assert_eq!(response["mySessions"][0]["leaderboardStatus"], "SENT");
+
+    leaderboard.handle_received_messages().await;
+    alice.handle_received_messages().await;
+    let response = query(&alice, application_id, "query { pendingUpdates { kind } mySessions { leaderboardStatus } }").await;
+    assert_eq!(response["pendingUpdates"].as_array().map(Vec::len), Some(0));
+    assert_eq!(response["mySessions"][0]["leaderboardStatus"], "ACKNOWLEDGED");
+}
@@ -0,0 +1,30 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Property tests for the rating bands of the matchmaking queue
+
+use proptest::prelude::*;
+use snake_game::MatchmakingConfig;
+
+fn configs() -> impl Strategy<Value = MatchmakingConfig> {
+    (0u32..100, 0u32..50, 0u32..500, 0u64..3_600).prop_map(|(initial_band, band_growth_per_min, max_band, timeout_secs)| {
+        MatchmakingConfig { initial_band, band_growth_per_min, max_band, timeout_secs }
+    })
+}
+
+proptest! {
+    #[test]
+    fn bands_only_widen_while_waiting(config in configs(), queued_at in 0u64..1 << 40, waited in 0u64..1 << 36, more in 0u64..1 << 36) {
+        let earlier = config.band_at(queued_at, queued_at + waited);
+        let later = config.band_at(queued_at, queued_at + waited + more);
+        prop_assert!(earlier <= later);
+        prop_assert!(earlier >= config.initial_band);
+        prop_assert!(later <= config.max_band.max(config.initial_band));
+    }
+
+    #[test]
+    fn zero_timeout_waits_forever(config in configs(), queued_at in 0u64..1 << 40, waited in 0u64..1 << 50) {
+        let config = MatchmakingConfig { timeout_secs: 0, ..config };
+        prop_assert!(!config.timed_out(queued_at, queued_at + waited));
+    }
+}
@@ -0,0 +1,35 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for how player names are normalized, measured and compared
+
+use snake_game::{display_name, name_key, MAX_NAME_GRAPHEMES};
+
+/// The same name typed with a precomposed or a combining accent is stored the same way
+#[test]
+fn display_names_are_composed_and_trimmed() {
+    assert_eq!(display_name("  Zoe\u{0301}   the   Snake ").unwrap(), "Zoé the Snake");
+    assert_eq!(display_name("Zoé the Snake").unwrap(), "Zoé the Snake");
+}
+
+/// Limits count what players see, so scripts with multi-byte characters get the same length
+#[test]
+fn name_length_counts_grapheme_clusters() {
+    let family = "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}";
+    assert_eq!(display_name(family).unwrap(), family);
+    assert!(display_name(&"ж".repeat(MAX_NAME_GRAPHEMES)).is_ok());
+    assert!(display_name(&"ж".repeat(MAX_NAME_GRAPHEMES + 1)).is_err());
+    assert!(display_name("\u{200b}\u{200b}").is_err());
+    assert!(display_name("tab\there").is_ok());
+    assert!(display_name("bell\u{7}").is_err());
+}
+
+/// Lookalike letters, full-width forms and invisible characters all land on the same handle
+#[test]
+fn lookalike_names_share_a_handle() {
+    assert_eq!(name_key("Alice"), "alice");
+    assert_eq!(name_key("Аlice"), "alice"); // Cyrillic capital A
+    assert_eq!(name_key("Ａｌｉｃｅ"), "alice");
+    assert_eq!(name_key("Al\u{200b}ice"), "alice");
+    assert_ne!(name_key("Alice"), name_key("Alicia"));
+}
@@ -0,0 +1,151 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Property tests for the order of leaderboard entries
+
+use std::cmp::Ordering;
+
+use linera_sdk::linera_base_types::ChainId;
+use proptest::prelude::*;
+use snake_game::{ApplicationParameters, DisputeMarker, LeaderboardEntry, RankTier, ReplayVerdict, ScoreBreakdown, TieBreaker};
+
+fn chain(index: u64) -> ChainId {
+    format!("{:064x}", index).parse().expect("valid chain ID")
+}
+
+fn entry(index: u64, highest_score: u32, total_candies: u64, games_played: u32, record_timestamp: u64, record_ticks: u64) -> LeaderboardEntry {
+    LeaderboardEntry {
+        chain_id: chain(index),
+        player_name: None,
+        highest_score,
+        games_played,
+        total_candies,
+        tier: RankTier::Bronze,
+        level: 1,
+        record_timestamp,
+        last_active: record_timestamp,
+        score_breakdown: ScoreBreakdown::default(),
+        replay_verdict: ReplayVerdict::Unverified,
+        best_combo: 0,
+        record_ticks,
+        verified: false,
+        dispute: DisputeMarker::Clear,
+        reactions: Vec::new(),
+        country: None,
+        country_leader: false,
+        score_history: Vec::new(),
+    }
+}
+
+/// Entries with few distinct values, so that ties are common
+fn entries() -> impl Strategy<Value = Vec<LeaderboardEntry>> {
+    prop::collection::vec((0u32..5, 0u64..5, 1u32..5, 0u64..5, 0u64..5), 0..40).prop_map(|rows| {
+        rows.into_iter()
+            .enumerate()
+            .map(|(index, (score, candies, games, timestamp, ticks))| entry(index as u64, score, candies, games, timestamp, ticks))
+            .collect()
+    })
+}
+
+fn tie_breakers() -> impl Strategy<Value = Vec<TieBreaker>> {
+    prop::collection::vec(
+        prop::sample::select(vec![
+            TieBreaker::MostTotalCandies,
+            TieBreaker::MostGamesPlayed,
+            TieBreaker::FewestGamesPlayed,
+            TieBreaker::EarliestRecord,
+            TieBreaker::FewestRecordTicks,
+        ]),
+        0..5,
+    )
+}
+
+fn sorted(parameters: &ApplicationParameters, mut entries: Vec<LeaderboardEntry>) -> Vec<LeaderboardEntry> {
+    entries.sort_by(|a, b| parameters.compare_entries(a, b));
+    entries
+}
+
+fn chains(entries: &[LeaderboardEntry]) -> Vec<ChainId> {
+    entries.iter().map(|entry| entry.chain_id).collect()
+}
+
+proptest! {
+    #[test]
+    fn highest_scores_rank_first(entries in entries(), tie_breakers in tie_breakers()) {
+        let parameters = ApplicationParameters { tie_breakers, ..ApplicationParameters::default() };
+        let ranked = sorted(&parameters, entries);
+        
+        prop_assert!(ranked.windows(2).all(|pair| pair[0].highest_score >= pair[1].highest_score));
+    }
+    
+    #[test]
+    fn comparison_is_antisymmetric(entries in entries(), tie_breakers in tie_breakers()) {
+        let parameters = ApplicationParameters { tie_breakers, ..ApplicationParameters::default() };
+        for a in &entries {
+            for b in &entries {
+                prop_assert_eq!(parameters.compare_entries(a, b), parameters.compare_entries(b, a).reverse());
+            }
+        }
+    }
+    
+    #[test]
+    fn sorting_is_idempotent(entries in entries(), tie_breakers in tie_breakers()) {
+        let parameters = ApplicationParameters { tie_breakers, ..ApplicationParameters::default() };
+        let once = sorted(&parameters, entries);
+        let twice = sorted(&parameters, once.clone());
+        
+        prop_assert_eq!(chains(&once), chains(&twice));
+    }
+    
+    #[test]
+    fn reinserting_an_entry_ranks_it_next_to_itself(entries in entries(), pick in any::<prop::sample::Index>()) {
+        prop_assume!(!entries.is_empty());
+        let parameters = ApplicationParameters::default();
+        let copy = entries[pick.index(entries.len())].clone();
+        
+        let mut with_copy = entries.clone();
+        with_copy.push(copy.clone());
+        let ranked = sorted(&parameters, with_copy);
+        
+        let positions: Vec<usize> = ranked.iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.chain_id == copy.chain_id)
+            .map(|(position, _)| position)
+            .collect();
+        prop_assert_eq!(positions.len(), 2);
+        // Anything ranked between the two copies must tie with them
+        for between in &ranked[positions[0]..=positions[1]] {
+            prop_assert_eq!(parameters.compare_entries(between, &copy), Ordering::Equal);
+        }
+    }
+    
+    #[test]
+    fn default_tie_breakers_prefer_more_candies_then_more_games(entries in entries()) {
+        let parameters = ApplicationParameters::default();
+        let ranked = sorted(&parameters, entries);
+        
+        for pair in ranked.windows(2) {
+            if pair[0].highest_score == pair[1].highest_score {
+                prop_assert!(pair[0].total_candies >= pair[1].total_candies);
+                if pair[0].total_candies == pair[1].total_candies {
+                    prop_assert!(pair[0].games_played >= pair[1].games_played);
+                }
+            }
+        }
+    }
+    
+    #[test]
+    fn unknown_record_ticks_rank_last_among_ties(entries in entries()) {
+        let parameters = ApplicationParameters {
+            tie_breakers: vec![TieBreaker::FewestRecordTicks],
+            ..ApplicationParameters::default()
+        };
+        let ranked = sorted(&parameters, entries);
+        
+        for pair in ranked.windows(2) {
+            if pair[0].highest_score == pair[1].highest_score && pair[0].record_ticks == 0 {
+                prop_assert_eq!(pair[1].record_ticks, 0);
+            }
+        }
+    }
+}